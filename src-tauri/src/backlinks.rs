@@ -0,0 +1,287 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::markdown::images::normalize_path;
+use crate::markdown::kbd::KBD_PREFIX;
+use crate::markdown::links::resolve_local_link;
+use crate::markdown::lines::source_lines;
+use crate::vault::markdown_files;
+
+/// Inline links `[text](target)`; the first group catches the `!` of images
+static INLINE_LINK_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(!?)\[[^\]]*\]\(\s*(<[^>]*>|[^\s)]+)").unwrap());
+
+/// Reference definitions `[label]: target`
+static REFERENCE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s{0,3}\[[^\]]+\]:\s*(<[^>]*>|\S+)").unwrap());
+
+/// Wikilinks `[[target]]`, `[[target#heading]]`, `[[target|alias]]`
+static WIKILINK_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\[\[([^\]|#]+)(?:#[^\]|]*)?(?:\|[^\]]*)?\]\]").unwrap());
+
+/// Inline code spans, which never contain links
+static CODE_SPAN_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"`[^`]*`").unwrap());
+
+/// Parsed links per file, keyed by path and invalidated by modification time
+/// or `evict_cached_links`, with the tick it was last used at
+static LINK_CACHE: Lazy<Mutex<HashMap<PathBuf, CachedLinks>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Use counter for evicting the least recently used cache entry
+static LINK_CACHE_TICK: AtomicU64 = AtomicU64::new(0);
+
+/// Most files kept in `LINK_CACHE`
+const LINK_CACHE_LIMIT: usize = 4096;
+
+struct CachedLinks {
+    modified: SystemTime,
+    used: u64,
+    links: Vec<LinkRef>,
+}
+
+/// Kind of link syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkKind {
+    Inline,
+    Reference,
+    Wikilink,
+}
+
+/// A link found in a markdown file
+#[derive(Debug, Clone)]
+struct LinkRef {
+    kind: LinkKind,
+    target: String,
+    line: usize,
+    context: String,
+}
+
+/// A note that links to the target file
+#[derive(Debug, Clone, Serialize)]
+pub struct Backlink {
+    /// The file containing the link
+    pub source: String,
+    /// 1-based line of the link
+    pub line: usize,
+    /// The (trimmed) line containing the link
+    pub context: String,
+    pub kind: LinkKind,
+}
+
+/// Finds every markdown file under `root` linking to `target`.
+///
+/// Inline and reference links are resolved relative to the linking file;
+/// wikilinks match by note name (with or without `.md`) or by path suffix.
+/// Parsed links are cached per file and only re-read when its mtime changes.
+pub fn collect_backlinks(root: &Path, target: &Path) -> Vec<Backlink> {
+    let target = normalize_path(target);
+    let mut backlinks = Vec::new();
+
+    for source in markdown_files(root) {
+        if normalize_path(&source) == target {
+            continue;
+        }
+        let source_dir = source.parent().unwrap_or(root);
+
+        for link in cached_links(&source) {
+            let matches = match link.kind {
+                LinkKind::Wikilink => wikilink_matches(&link.target, root, &target),
+                _ => resolve_local_link(&link.target, source_dir)
+                    .is_some_and(|path| path == target),
+            };
+            if matches {
+                backlinks.push(Backlink {
+                    source: source.display().to_string(),
+                    line: link.line,
+                    context: link.context,
+                    kind: link.kind,
+                });
+            }
+        }
+    }
+
+    backlinks
+}
+
+fn cached_links(path: &Path) -> Vec<LinkRef> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+
+    let tick = LINK_CACHE_TICK.fetch_add(1, Ordering::Relaxed);
+
+    if let (Some(modified), Ok(mut cache)) = (modified, LINK_CACHE.lock()) {
+        if let Some(cached) = cache.get_mut(path) {
+            if cached.modified == modified {
+                cached.used = tick;
+                return cached.links.clone();
+            }
+        }
+    }
+
+    let links = fs::read_to_string(path)
+        .map(|content| extract_links(&content))
+        .unwrap_or_default();
+
+    if let (Some(modified), Ok(mut cache)) = (modified, LINK_CACHE.lock()) {
+        if cache.len() >= LINK_CACHE_LIMIT && !cache.contains_key(path) {
+            let oldest = cache
+                .iter()
+                .min_by_key(|(_, cached)| cached.used)
+                .map(|(path, _)| path.clone());
+            if let Some(oldest) = oldest {
+                cache.remove(&oldest);
+            }
+        }
+        cache.insert(
+            path.to_path_buf(),
+            CachedLinks {
+                modified,
+                used: tick,
+                links: links.clone(),
+            },
+        );
+    }
+
+    links
+}
+
+/// Drops a file's cached links, e.g. when the file watcher reports a change.
+/// The mtime check misses edits within the filesystem's timestamp resolution.
+pub fn evict_cached_links(path: &Path) {
+    if let Ok(mut cache) = LINK_CACHE.lock() {
+        cache.remove(path);
+    }
+}
+
+/// Extracts inline, reference and wikilinks, skipping fenced code and code spans
+fn extract_links(markdown: &str) -> Vec<LinkRef> {
+    let mut links = Vec::new();
+
+    for line in source_lines(markdown).into_iter().filter(|l| !l.in_code) {
+        let text = CODE_SPAN_REGEX.replace_all(line.text, "");
+        let mut push = |kind: LinkKind, target: &str| {
+            links.push(LinkRef {
+                kind,
+                target: target.trim_start_matches('<').trim_end_matches('>').to_string(),
+                line: line.number,
+                context: line.text.trim().to_string(),
+            });
+        };
+
+        for caps in INLINE_LINK_REGEX.captures_iter(&text) {
+            if caps[1].is_empty() {
+                push(LinkKind::Inline, &caps[2]);
+            }
+        }
+        if let Some(caps) = REFERENCE_REGEX.captures(&text) {
+            push(LinkKind::Reference, &caps[1]);
+        }
+        for caps in WIKILINK_REGEX.captures_iter(&text) {
+            // `[[kbd:...]]` is a key combination, not a note
+            if !caps[1].starts_with(KBD_PREFIX) {
+                push(LinkKind::Wikilink, caps[1].trim());
+            }
+        }
+    }
+
+    links
+}
+
+/// True if a wikilink name refers to `target` (by note name or path suffix)
+fn wikilink_matches(name: &str, root: &Path, target: &Path) -> bool {
+    let name = name.trim().to_lowercase();
+    let name = name
+        .strip_suffix(".md")
+        .or_else(|| name.strip_suffix(".markdown"))
+        .unwrap_or(&name);
+
+    if name.contains('/') {
+        let relative = target.strip_prefix(root).unwrap_or(target).with_extension("");
+        let relative = relative.to_string_lossy().replace('\\', "/").to_lowercase();
+        relative == name || relative.ends_with(&format!("/{}", name))
+    } else {
+        target
+            .file_stem()
+            .is_some_and(|stem| stem.to_string_lossy().to_lowercase() == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_links_skips_code() {
+        let md = "[a](a.md) ![img](i.png)\n[ref]: <b c.md>\n```\n[x](x.md)\n```\n[[Note|a]] [[dir/Other#h]] [[kbd:Cmd+S]]";
+        let links = extract_links(md);
+        let targets: Vec<&str> = links.iter().map(|l| l.target.as_str()).collect();
+        assert_eq!(targets, vec!["a.md", "b c.md", "Note", "dir/Other"]);
+        assert_eq!(links[0].kind, LinkKind::Inline);
+        assert_eq!(links[1].kind, LinkKind::Reference);
+        assert_eq!(links[2].kind, LinkKind::Wikilink);
+        assert_eq!(links[2].line, 6);
+    }
+
+    #[test]
+    fn test_find_backlinks() {
+        let root = std::env::temp_dir().join("markviewer-backlinks-test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("projects")).unwrap();
+        let target = root.join("projects/Target Note.md");
+        fs::write(&target, "# Target").unwrap();
+        fs::write(root.join("inline.md"), "Intro\nSee [it](projects/Target%20Note.md#top).")
+            .unwrap();
+        fs::write(root.join("projects/ref.md"), "[t]: <Target Note.md>").unwrap();
+        fs::write(root.join("wiki.md"), "[[target note]] and [[projects/Target Note.md]]").unwrap();
+        fs::write(root.join("none.md"), "Just `[[Target Note]]`\n```\n[[Target Note]]\n```")
+            .unwrap();
+
+        let backlinks = collect_backlinks(&root, &target);
+        let mut sources: Vec<String> = backlinks
+            .iter()
+            .map(|b| Path::new(&b.source).file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        sources.sort();
+
+        assert_eq!(sources, vec!["inline.md", "ref.md", "wiki.md", "wiki.md"]);
+        let inline = backlinks.iter().find(|b| b.kind == LinkKind::Inline).unwrap();
+        assert_eq!(inline.line, 2);
+        assert_eq!(inline.context, "See [it](projects/Target%20Note.md#top).");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_evict_cached_links() {
+        let root = std::env::temp_dir().join("markviewer-backlinks-evict");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let target = root.join("target.md");
+        let source = root.join("source.md");
+        fs::write(&target, "# Target").unwrap();
+        fs::write(&source, "[[target]]").unwrap();
+        assert_eq!(collect_backlinks(&root, &target).len(), 1);
+
+        // An edit keeping the same mtime is only seen after eviction
+        let modified = fs::metadata(&source).unwrap().modified().unwrap();
+        fs::write(&source, "no link").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&source)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        assert_eq!(collect_backlinks(&root, &target).len(), 1);
+        evict_cached_links(&source);
+        assert!(collect_backlinks(&root, &target).is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
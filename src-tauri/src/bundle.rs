@@ -0,0 +1,176 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::markdown::images::rewrite_local_image_paths;
+use crate::markdown::{media_references, MediaKind};
+
+/// Folder inside the bundle that holds the images
+const ASSETS_DIR: &str = "assets";
+
+/// Outcome of a bundle export
+#[derive(Debug, Default, Serialize)]
+pub struct BundleReport {
+    /// Paths of the bundled images inside the archive
+    pub images: Vec<String>,
+    /// Referenced local images that don't exist; their links are left as-is
+    pub missing: Vec<String>,
+}
+
+/// Writes a document and its local images to a zip archive.
+///
+/// The markdown is stored under the document's file name at the archive root
+/// and each image under `assets/`, with links rewritten to match so the
+/// bundle works on its own. Images are found with [`media_references`];
+/// remote images and data URIs are skipped.
+pub fn write_bundle(
+    markdown: &str,
+    base_path: &str,
+    out_zip: &Path,
+) -> Result<BundleReport, String> {
+    let mut report = BundleReport::default();
+    let mut bundled: HashMap<PathBuf, String> = HashMap::new();
+    let mut used_names = HashSet::new();
+
+    let images = media_references(markdown, base_path)
+        .into_iter()
+        .filter(|media| media.kind == MediaKind::Image);
+    for image in images {
+        if !image.exists {
+            report.missing.push(image.path);
+            continue;
+        }
+        let path = PathBuf::from(image.path);
+        let name = unique_asset_name(&path, &mut used_names);
+        bundled.insert(path, name);
+    }
+
+    let rewritten =
+        rewrite_local_image_paths(markdown, base_path, |path| bundled.get(path).cloned());
+    let doc_name = Path::new(base_path)
+        .file_name()
+        .map_or("document.md".into(), |name| name.to_string_lossy());
+
+    let file = File::create(out_zip).map_err(|e| format!("Failed to create bundle: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file(doc_name.as_ref(), options)
+        .map_err(|e| format!("Failed to write bundle: {}", e))?;
+    zip.write_all(rewritten.as_bytes())
+        .map_err(|e| format!("Failed to write bundle: {}", e))?;
+
+    let mut images: Vec<(&PathBuf, &String)> = bundled.iter().collect();
+    images.sort_by(|a, b| a.1.cmp(b.1));
+    for (path, name) in images {
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read image: {}", e))?;
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| format!("Failed to write bundle: {}", e))?;
+        zip.write_all(&bytes)
+            .map_err(|e| format!("Failed to write bundle: {}", e))?;
+        report.images.push(name.clone());
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to write bundle: {}", e))?;
+    Ok(report)
+}
+
+/// `assets/<file name>`, with spaces replaced and a numeric suffix when the
+/// name is already taken by another image
+fn unique_asset_name(path: &Path, used: &mut HashSet<String>) -> String {
+    let stem = path
+        .file_stem()
+        .map_or("image".into(), |s| s.to_string_lossy().replace(' ', "-"));
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut name = format!("{}/{}{}", ASSETS_DIR, stem, extension);
+    let mut suffix = 0;
+    while used.contains(&name) {
+        suffix += 1;
+        name = format!("{}/{}-{}{}", ASSETS_DIR, stem, suffix, extension);
+    }
+    used.insert(name.clone());
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use zip::ZipArchive;
+
+    #[test]
+    fn test_bundle_contains_document_and_images() {
+        let dir = std::env::temp_dir().join("markviewer-bundle-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("notes/img")).unwrap();
+        fs::create_dir_all(dir.join("shared")).unwrap();
+        fs::write(dir.join("notes/img/chart.png"), b"chart").unwrap();
+        fs::write(dir.join("shared/chart.png"), b"other chart").unwrap();
+        fs::write(dir.join("shared/my photo.jpg"), b"photo").unwrap();
+
+        let doc = dir.join("notes/trip.md").display().to_string();
+        let md = "![a](img/chart.png)\n![b](../shared/chart.png \"B\")\n\
+                  <img src=\"../shared/my photo.jpg\">\n![again](img/chart.png)\n\
+                  ![web](https://x.dev/a.png) ![gone](missing.png)";
+        let out = dir.join("trip.zip");
+
+        let report = write_bundle(md, &doc, &out).unwrap();
+        assert_eq!(
+            report.images,
+            vec![
+                "assets/chart-1.png",
+                "assets/chart.png",
+                "assets/my-photo.jpg"
+            ]
+        );
+        assert_eq!(
+            report.missing,
+            vec![dir.join("notes/missing.png").display().to_string()]
+        );
+
+        let mut archive = ZipArchive::new(File::open(&out).unwrap()).unwrap();
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "assets/chart-1.png",
+                "assets/chart.png",
+                "assets/my-photo.jpg",
+                "trip.md"
+            ]
+        );
+
+        let mut bundled_md = String::new();
+        archive
+            .by_name("trip.md")
+            .unwrap()
+            .read_to_string(&mut bundled_md)
+            .unwrap();
+        assert_eq!(
+            bundled_md,
+            "![a](assets/chart.png)\n![b](assets/chart-1.png \"B\")\n\
+             <img src=\"assets/my-photo.jpg\">\n![again](assets/chart.png)\n\
+             ![web](https://x.dev/a.png) ![gone](missing.png)"
+        );
+
+        let mut bytes = Vec::new();
+        archive
+            .by_name("assets/chart-1.png")
+            .unwrap()
+            .read_to_end(&mut bytes)
+            .unwrap();
+        assert_eq!(bytes, b"other chart");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,351 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{ImageFormat, RgbImage};
+use plotters::prelude::*;
+use serde_json::Value;
+use std::f64::consts::PI;
+use std::io::Cursor;
+
+/// Largest chart edge accepted, in pixels
+pub const MAX_CHART_SIZE: u32 = 4096;
+
+/// Chart.js' default dataset colors
+const PALETTE: [RGBColor; 6] = [
+    RGBColor(54, 162, 235),
+    RGBColor(255, 99, 132),
+    RGBColor(75, 192, 192),
+    RGBColor(255, 159, 64),
+    RGBColor(153, 102, 255),
+    RGBColor(255, 205, 86),
+];
+
+const FONT: &str = "sans-serif";
+
+/// Chart types that can be drawn without a browser
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ChartKind {
+    Bar,
+    Line,
+    Pie,
+}
+
+/// The parts of a Chart.js config that are drawn
+#[derive(Debug)]
+struct ChartSpec {
+    kind: ChartKind,
+    title: Option<String>,
+    labels: Vec<String>,
+    /// (label, values) per dataset
+    datasets: Vec<(String, Vec<f64>)>,
+}
+
+/// Draws a Chart.js config to a PNG, returned as base64.
+///
+/// Supports `bar`, `line` and `pie` charts: `data.labels`, each dataset's
+/// `label` and `data` (numbers or `{x, y}` points) and the
+/// `options.plugins.title.text` caption. Other chart types are an error so
+/// callers can fall back to the data table.
+pub fn render_chart_png(spec: &str, width: u32, height: u32) -> Result<String, String> {
+    if width == 0 || height == 0 || width > MAX_CHART_SIZE || height > MAX_CHART_SIZE {
+        return Err(format!(
+            "Chart size must be between 1 and {}",
+            MAX_CHART_SIZE
+        ));
+    }
+    let spec = parse_spec(spec)?;
+
+    let mut buffer = vec![0u8; width as usize * height as usize * 3];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, (width, height)).into_drawing_area();
+        root.fill(&WHITE).map_err(draw_error)?;
+        match spec.kind {
+            ChartKind::Pie => draw_pie(&root, &spec)?,
+            ChartKind::Bar | ChartKind::Line => draw_cartesian(&root, &spec)?,
+        }
+        root.present().map_err(draw_error)?;
+    }
+
+    let image = RgbImage::from_raw(width, height, buffer)
+        .ok_or_else(|| "Failed to build chart image".to_string())?;
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode chart: {}", e))?;
+    Ok(STANDARD.encode(png))
+}
+
+fn parse_spec(spec: &str) -> Result<ChartSpec, String> {
+    let config: Value =
+        serde_json::from_str(spec).map_err(|e| format!("Invalid chart spec: {}", e))?;
+    let kind = match config.get("type").and_then(Value::as_str) {
+        Some("bar") => ChartKind::Bar,
+        Some("line") => ChartKind::Line,
+        Some("pie") => ChartKind::Pie,
+        Some(other) => return Err(format!("Unsupported chart type '{}'", other)),
+        None => return Err("Chart spec has no type".to_string()),
+    };
+
+    let data = config.get("data");
+    let labels = data
+        .and_then(|d| d.get("labels"))
+        .and_then(Value::as_array)
+        .map(|labels| labels.iter().map(label_text).collect())
+        .unwrap_or_default();
+    let datasets: Vec<(String, Vec<f64>)> = data
+        .and_then(|d| d.get("datasets"))
+        .and_then(Value::as_array)
+        .map(|datasets| {
+            datasets
+                .iter()
+                .enumerate()
+                .map(|(i, dataset)| {
+                    let name = dataset
+                        .get("label")
+                        .and_then(Value::as_str)
+                        .map(str::to_string)
+                        .unwrap_or_else(|| format!("Series {}", i + 1));
+                    let values = dataset
+                        .get("data")
+                        .and_then(Value::as_array)
+                        .map(|points| points.iter().map(point_value).collect())
+                        .unwrap_or_default();
+                    (name, values)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if datasets.iter().all(|(_, values)| values.is_empty()) {
+        return Err("Chart spec has no data".to_string());
+    }
+
+    let title = config
+        .pointer("/options/plugins/title/text")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    Ok(ChartSpec {
+        kind,
+        title,
+        labels,
+        datasets,
+    })
+}
+
+/// The y value of a data point ({x, y} objects or plain values); 0 if unreadable
+fn point_value(point: &Value) -> f64 {
+    let value = point.get("y").unwrap_or(point);
+    match value {
+        Value::Number(n) => n.as_f64().unwrap_or(0.0),
+        Value::String(s) => s.trim().parse().unwrap_or(0.0),
+        _ => 0.0,
+    }
+}
+
+fn label_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn draw_error<E: std::fmt::Display>(e: E) -> String {
+    format!("Failed to draw chart: {}", e)
+}
+
+/// Bars grouped per label, or one line per dataset
+fn draw_cartesian(
+    root: &DrawingArea<BitMapBackend, plotters::coord::Shift>,
+    spec: &ChartSpec,
+) -> Result<(), String> {
+    let points = spec
+        .datasets
+        .iter()
+        .map(|(_, values)| values.len())
+        .max()
+        .unwrap_or(0)
+        .max(spec.labels.len());
+    let values = spec
+        .datasets
+        .iter()
+        .flat_map(|(_, values)| values.iter().copied());
+    let (low, high) = values.fold((0.0f64, 0.0f64), |(low, high), v| (low.min(v), high.max(v)));
+    let high = if high == low {
+        low + 1.0
+    } else {
+        high + (high - low) * 0.1
+    };
+
+    let mut builder = ChartBuilder::on(root);
+    builder
+        .margin(12)
+        .x_label_area_size(30)
+        .y_label_area_size(50);
+    if let Some(title) = &spec.title {
+        builder.caption(title, (FONT, 20));
+    }
+    let mut chart = builder
+        .build_cartesian_2d(-0.5..points as f64 - 0.5, low..high)
+        .map_err(draw_error)?;
+
+    // Labels sit under whole x positions, one per data point
+    let label_at = |x: &f64| {
+        let index = x.round();
+        if (x - index).abs() > 1e-6 || index < 0.0 {
+            return String::new();
+        }
+        spec.labels
+            .get(index as usize)
+            .cloned()
+            .unwrap_or_else(|| (index as usize + 1).to_string())
+    };
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .x_labels(points)
+        .x_label_formatter(&label_at)
+        .draw()
+        .map_err(draw_error)?;
+
+    let count = spec.datasets.len() as f64;
+    for (i, (name, values)) in spec.datasets.iter().enumerate() {
+        let color = PALETTE[i % PALETTE.len()];
+        let series = match spec.kind {
+            ChartKind::Bar => {
+                let width = 0.8 / count;
+                let left = -0.4 + width * i as f64;
+                chart.draw_series(values.iter().enumerate().map(|(x, &y)| {
+                    let x = x as f64 + left;
+                    Rectangle::new([(x, 0.0), (x + width, y)], color.filled())
+                }))
+            }
+            _ => chart.draw_series(LineSeries::new(
+                values.iter().enumerate().map(|(x, &y)| (x as f64, y)),
+                color.stroke_width(2),
+            )),
+        }
+        .map_err(draw_error)?;
+        series
+            .label(name.as_str())
+            .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled()));
+    }
+
+    if spec.datasets.len() > 1 {
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()
+            .map_err(draw_error)?;
+    }
+    Ok(())
+}
+
+/// Slices of the first dataset, labelled with their share
+fn draw_pie(
+    root: &DrawingArea<BitMapBackend, plotters::coord::Shift>,
+    spec: &ChartSpec,
+) -> Result<(), String> {
+    let values: Vec<f64> = spec.datasets[0].1.iter().map(|v| v.max(0.0)).collect();
+    let total: f64 = values.iter().sum();
+    if total <= 0.0 {
+        return Err("Pie chart has no positive values".to_string());
+    }
+
+    let area = match &spec.title {
+        Some(title) => root.titled(title, (FONT, 20)).map_err(draw_error)?,
+        None => root.clone(),
+    };
+    let (width, height) = area.dim_in_pixel();
+    let center = (width as f64 / 2.0, height as f64 / 2.0);
+    let radius = width.min(height) as f64 * 0.35;
+    let at = |angle: f64, distance: f64| {
+        (
+            (center.0 + distance * angle.cos()) as i32,
+            (center.1 + distance * angle.sin()) as i32,
+        )
+    };
+
+    // Clockwise from twelve o'clock, like Chart.js
+    let mut start = -PI / 2.0;
+    for (i, &value) in values.iter().enumerate() {
+        if value == 0.0 {
+            continue;
+        }
+        let sweep = value / total * 2.0 * PI;
+        let steps = ((sweep * radius / 4.0).ceil() as usize).max(2);
+        let mut outline = vec![at(0.0, 0.0)];
+        outline
+            .extend((0..=steps).map(|step| at(start + sweep * step as f64 / steps as f64, radius)));
+        area.draw(&Polygon::new(outline, PALETTE[i % PALETTE.len()].filled()))
+            .map_err(draw_error)?;
+
+        let middle = start + sweep / 2.0;
+        let label = format!(
+            "{} ({:.0}%)",
+            spec.labels
+                .get(i)
+                .cloned()
+                .unwrap_or_else(|| (i + 1).to_string()),
+            value / total * 100.0
+        );
+        let (mut x, y) = at(middle, radius * 1.12);
+        if middle.cos() < 0.0 {
+            // Right-align labels on the left half
+            x -= label.chars().count() as i32 * 7;
+        }
+        area.draw(&Text::new(label, (x, y - 7), (FONT, 14).into_font()))
+            .map_err(draw_error)?;
+
+        start += sweep;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar_chart_png() {
+        let spec = r#"{
+            "type": "bar",
+            "data": {
+                "labels": ["Q1", "Q2", "Q3"],
+                "datasets": [{"label": "Sales", "data": [3, 7, 5]}]
+            }
+        }"#;
+        let png = STANDARD
+            .decode(render_chart_png(spec, 320, 200).unwrap())
+            .unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+
+        let image = image::load_from_memory(&png).unwrap();
+        assert_eq!((image.width(), image.height()), (320, 200));
+    }
+
+    #[test]
+    fn test_unsupported_and_invalid_specs() {
+        let radar = r#"{"type": "radar", "data": {"datasets": [{"data": [1]}]}}"#;
+        assert_eq!(
+            render_chart_png(radar, 100, 100).unwrap_err(),
+            "Unsupported chart type 'radar'"
+        );
+        assert!(render_chart_png("not json", 100, 100).is_err());
+        assert!(render_chart_png(r#"{"type": "bar", "data": {}}"#, 100, 100).is_err());
+        assert!(render_chart_png(r#"{"type": "bar"}"#, 0, 100).is_err());
+    }
+
+    #[test]
+    fn test_parse_spec_points() {
+        let spec = parse_spec(
+            r#"{"type": "line", "data": {"datasets": [{"data": [{"x": 1, "y": 2}, "4.5", null]}]},
+                "options": {"plugins": {"title": {"text": "Trend"}}}}"#,
+        )
+        .unwrap();
+        assert_eq!(spec.kind, ChartKind::Line);
+        assert_eq!(spec.title.as_deref(), Some("Trend"));
+        assert_eq!(
+            spec.datasets,
+            vec![("Series 1".to_string(), vec![2.0, 4.5, 0.0])]
+        );
+    }
+}
@@ -1,12 +1,47 @@
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
-use tauri::command;
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Emitter};
+use tauri_plugin_store::StoreExt;
 
+use crate::backlinks::{collect_backlinks, evict_cached_links, Backlink};
+use crate::bundle::{write_bundle, BundleReport};
+use crate::chart;
+use crate::daily_notes::{self, offset_date, DailyNotes};
+use crate::epub::{write_epub, EpubMeta, EpubReport};
+use crate::export::{self, export_folder, ExportReport, ExportedDoc};
+use crate::file_index::{self, document_aliases, find_files, index_files, FileMeta};
+use crate::image_fixes::{suggest_fixes, ImageFix};
 use crate::markdown::{
-    extract_special_blocks, highlight_code, render_markdown_html, resolve_image_paths,
-    SpecialBlock,
+    add_heading_task_counts, audit_image_alt_text, client_block_types, constrain_images,
+    default_stopwords, detect_language, diff_rendered_blocks, document_style_block,
+    duplicate_headings, expand_code_includes, extract_front_matter, extract_special_blocks_labeled,
+    extract_special_blocks_with, filter_sections, find_broken_anchors, find_fold_regions,
+    front_matter_html, heading_anchor_map, highlight_code, highlight_tokens, html_to_markdown,
+    inherited_front_matter, insert_color_swatches, link_github_refs, lint_fenced_code,
+    lint_markdown, list_themes, map_blocks_to_source, mark_local_links, mark_search_hits,
+    markdown_to_rtf, media_references, merge_front_matter, normalize_heading_levels,
+    normalize_tables, notebook_to_markdown, number_headings, offset_placeholder_ids, parse_tasks,
+    placeholder_number, preview_theme_html, rebase_markdown_image_paths, relative_time,
+    render_markdown_html_with, replace_placeholder, resolve_image_paths_with, resolve_remote_paths,
+    sanitize_html, search_snippets, shift_heading_levels, shorten_autolinks,
+    special_block_fallback, split_slides, style_heading_anchors, summarize_sections,
+    summarize_tasks, tex_to_mathml, top_keywords, validate_front_matter_schema, AltTextIssue,
+    AnchorIssue, AnchorPlacement, BlockPatch, CodeLint, DuplicateHeading, EmojiPolicy, FoldRegion,
+    FrontMatterMode, HtmlOptions, LintReport, MediaRef, NormalizedTables, SchemaError, Snippet,
+    SourceMapEntry, SpecialBlock, Task, TaskProgress, TocEntry, Token, DEFAULT_ANCHOR_SYMBOL,
+    DEFAULT_SLIDE_SEPARATOR, SYNTAX_SAMPLE,
 };
+use crate::palette::document_palette;
+use crate::plantuml::render_plantuml_svg;
+use crate::reading::{ReadingPositions, POSITIONS_KEY, READING_STORE};
+use crate::save::{save_document, LineEnding};
+use crate::settings::{Settings, SETTINGS_KEY, SETTINGS_STORE};
+use crate::thumbnail::document_thumbnail_uri;
+use crate::vault::{breadcrumb_segments, BreadcrumbSegment};
 
 /// Result of rendering markdown
 #[derive(Debug, Serialize)]
@@ -15,193 +50,1754 @@ pub struct RenderResult {
     pub html: String,
     /// Special blocks (mermaid, charts) that need JS rendering
     pub special_blocks: Vec<SpecialBlock>,
+    /// Parsed front matter (YAML, TOML or JSON), if present
+    pub front_matter: Option<serde_json::Value>,
+    /// Non-fatal problems encountered while rendering
+    pub warnings: Vec<String>,
+    /// `data-block-id` of each top-level block, in document order
+    pub block_ids: Vec<String>,
+    /// Every heading with its anchor id, for an outline sidebar
+    pub toc: Vec<TocEntry>,
+    /// Theme to display the document in: the requested one unless its front
+    /// matter asks for another
+    pub theme: String,
+    /// The markdown handed to the renderer, for `source_render_map`
+    #[serde(skip)]
+    rendered_source: String,
+    /// Source lines of each tagged block in `rendered_source`
+    #[serde(skip)]
+    block_lines: Vec<(usize, usize)>,
+}
+
+/// The blocks that changed between two renders of a document
+#[derive(Debug, Serialize)]
+pub struct RenderPatch {
+    /// Blocks to add, remove or replace, or the full HTML
+    #[serde(flatten)]
+    pub blocks: BlockPatch,
+    /// Special blocks of the new render
+    pub special_blocks: Vec<SpecialBlock>,
+    /// Parsed front matter of the new render
+    pub front_matter: Option<serde_json::Value>,
+    pub warnings: Vec<String>,
+    pub theme: String,
+}
+
+/// Milliseconds spent in each stage of a render, from `render_with_timing`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RenderTimings {
+    /// Front matter split and special block extraction (including MathML)
+    pub special_blocks_ms: f64,
+    /// comrak parsing, including preprocessing such as inline footnotes
+    pub parse_ms: f64,
+    /// HTML output and post-processing, excluding highlighting
+    pub html_ms: f64,
+    /// Syntax highlighting of all code blocks
+    pub highlight_ms: f64,
+    /// Number of code blocks highlighted
+    pub code_blocks: usize,
+    /// Image path and local link resolution
+    pub images_ms: f64,
+    /// Sanitizing the output
+    pub sanitize_ms: f64,
+    /// The whole render
+    pub total_ms: f64,
 }
 
-/// Options for rendering markdown
-#[derive(Debug, Deserialize)]
+/// Options for rendering markdown.
+///
+/// A document can set its own under a front matter `markviewer:` key (see
+/// `DOCUMENT_OPTION_KEYS`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RenderOptions {
     /// Theme: "light" or "dark"
     pub theme: String,
     /// Base path for resolving relative image paths (path to the .md file)
     pub base_path: Option<String>,
+    /// URL of the folder the document is published from (e.g. a GitHub raw
+    /// content URL). When set, relative image and link targets resolve
+    /// against it instead of `base_path`, and links aren't marked as local.
+    pub remote_base_url: Option<String>,
+    /// Extract special blocks (mermaid, chart) for JS rendering (default: true).
+    /// When false, those fences are highlighted as ordinary code.
+    pub extract_special_blocks: Option<bool>,
+    /// Fence languages handled by the block extractor (default: those with a
+    /// registered frontend handler, initially mermaid and chart).
+    /// Add "csv"/"tsv" to render delimited data as tables.
+    pub special_block_types: Option<Vec<String>>,
+    /// Screen reader labels for special block placeholders by block type, for
+    /// localizing (default: "Mermaid diagram", "Chart", ...)
+    pub special_block_labels: Option<HashMap<String, String>>,
+    /// Math handling for ```math fences and `$$` blocks: "client" (placeholder
+    /// rendered by the frontend) or "mathml" (converted during render).
+    /// Math is left as regular content when unset.
+    pub math_mode: Option<String>,
+    /// Strip scripts, event handlers and dangerous URLs from the output while
+    /// keeping raw HTML (default: false)
+    pub sanitize: Option<bool>,
+    /// Emoji handling in heading ids: "strip" (default), "keep" or "shortcode"
+    pub emoji_policy: Option<EmojiPolicy>,
+    /// Give headings the ids GitHub does (`## My Heading` gets `my-heading`,
+    /// repeats `-1`, `-2`), without the `heading-` prefix, so `#my-heading`
+    /// links work in both. Overrides `emoji_policy` (default: false)
+    pub github_compatible_ids: Option<bool>,
+    /// Front matter display: "strip" (default), "render" (key/value table) or
+    /// "collapse" (raw text in a `<details>` block)
+    pub front_matter_mode: Option<FrontMatterMode>,
+    /// Folders searched for images linked by bare file name that aren't next
+    /// to the document (requires base_path); relative folders are relative to
+    /// the document. Images found nowhere get `data-unresolved="true"`
+    pub attachment_folders: Option<Vec<String>>,
+    /// Render image embeds `![[chart.png]]` as images, looked up like bare
+    /// file names (default: true when attachment_folders are set)
+    pub image_embeds: Option<bool>,
+    /// Tag links to local files (requires base_path) with `data-internal-link`
+    /// (markdown, opened in-app) or `data-open-external` (default: true)
+    pub mark_local_links: Option<bool>,
+    /// Tag top-level blocks with content-derived `data-block-id`s for
+    /// restoring scroll position across re-renders (default: true)
+    pub block_ids: Option<bool>,
+    /// Give list items and tables content-derived `block-...` ids so they can
+    /// be deep-linked with `#block-...` (default: false)
+    pub block_anchors: Option<bool>,
+    /// Render literal `\n` inside table cells as line breaks (default: false)
+    pub table_line_breaks: Option<bool>,
+    /// Curly quotes and en/em dashes for `"`, `'`, `--` and `---`; code is
+    /// never changed (default: false)
+    pub smart_punctuation: Option<bool>,
+    /// Render `[[kbd:Cmd+S]]` as `<kbd>Cmd</kbd>+<kbd>S</kbd>` outside code
+    /// (default: true)
+    pub kbd_shortcuts: Option<bool>,
+    /// Render Pandoc-style inline footnotes `^[note text]` as numbered
+    /// footnotes alongside `[^label]` ones (default: false)
+    pub inline_footnotes: Option<bool>,
+    /// Expand PHP Markdown Extra abbreviations: `*[HTML]: HyperText Markup
+    /// Language` lines are removed and whole-word `HTML` outside code and
+    /// links becomes `<abbr title="...">` (default: false)
+    pub abbreviations: Option<bool>,
+    /// Code blocks longer than this many lines get a `collapsible` class on
+    /// their `<pre>` so the frontend can fold them behind "show more". Every
+    /// highlighted `<pre>` carries its `data-lines` count (default: no folding)
+    pub collapse_code_lines: Option<usize>,
+    /// Move every heading this many levels deeper (negative: shallower),
+    /// clamped to H1–H6, so an embedded document nests under its include
+    /// point. Heading ids follow the shifted levels (default: 0)
+    pub heading_shift: Option<i8>,
+    /// Line that separates slides in `render_slides`, matched at column 0
+    /// outside code (default: "---")
+    pub slide_separator: Option<String>,
+    /// Show a `<span class="color-swatch">` after hex color codes (`#RGB`,
+    /// `#RRGGBB`, `#RRGGBBAA`) in prose and inline code (default: false)
+    pub color_swatches: Option<bool>,
+    /// GitHub repository (`owner/name`) for autolinking `#123` and `GH-123`
+    /// to its issues, `owner/repo#123` to other repos and `@user` to
+    /// profiles, outside code and links. No autolinks when unset
+    pub github_repo: Option<String>,
+    /// Label long autolinked URLs with their domain and the start of the
+    /// path plus an ellipsis; the `href` and hover `title` keep the full URL.
+    /// `[text](url)` links are unaffected (default: false)
+    pub shorten_urls: Option<bool>,
+    /// Prefix headings with outline numbers (`1`, `1.1`, `1.2`, `2`…) in a
+    /// `<span class="heading-number">` (default: false)
+    pub number_headings: Option<bool>,
+    /// Append a `(done/total)` counter in a `<span class="task-count">` to
+    /// headings whose section has tasklist items (default: false)
+    pub heading_task_counts: Option<bool>,
+    /// Show heading anchors with this symbol, e.g. `#` or `¶` (default: hidden
+    /// anchors without text)
+    pub anchor_symbol: Option<String>,
+    /// Put visible heading anchors before or after the heading text (default:
+    /// before); setting it alone shows the `#` symbol
+    pub anchor_placement: Option<AnchorPlacement>,
+    /// Render every newline inside a paragraph as a line break (default: false)
+    pub hard_line_breaks: Option<bool>,
+    /// Render CriticMarkup edits outside code: `{++ins++}` and `{--del--}` as
+    /// `<ins>`/`<del>`, `{~~old~>new~~}` as both, `{==text==}` as `<mark>` and
+    /// `{>>comment<<}` as `<span class="critic-comment">` (default: false)
+    pub criticmarkup: Option<bool>,
+    /// Show angle-bracket text that isn't an HTML tag, like `<placeholder>`,
+    /// as written instead of as an unknown element the browser hides.
+    /// Autolinks, custom elements and code are unaffected (default: true)
+    pub escape_unknown_tags: Option<bool>,
+    /// Whether the document's front matter `markviewer:` settings override
+    /// these options (default: true). When false they only fill in options
+    /// left unset
+    pub front_matter_overrides: Option<bool>,
+    /// Turn the front matter `style:` mapping into CSS custom properties
+    /// (`accent: "#f50"` sets `--accent`) scoped to the rendered content
+    /// (default: false). Only colors, lengths and font families are allowed
+    pub allow_document_style: Option<bool>,
+    /// Fit images without a set width to the reading column with
+    /// `class="constrained"` and `max-width:100%` (default: true)
+    pub constrain_images: Option<bool>,
+    /// Replace `<!-- include: src/main.rs#L10-L20 -->` lines with those lines
+    /// of the file as a code block; needs `base_path` (default: false)
+    pub code_includes: Option<bool>,
+}
+
+/// Render options a document may set under its front matter `markviewer:`
+/// key. Paths, sanitizing and options applied before the front matter is
+/// read can only be set by the caller.
+const DOCUMENT_OPTION_KEYS: &[&str] = &[
+    "theme",
+    "extract_special_blocks",
+    "special_block_types",
+    "math_mode",
+    "emoji_policy",
+    "github_compatible_ids",
+    "front_matter_mode",
+    "block_anchors",
+    "table_line_breaks",
+    "smart_punctuation",
+    "kbd_shortcuts",
+    "image_embeds",
+    "inline_footnotes",
+    "abbreviations",
+    "collapse_code_lines",
+    "color_swatches",
+    "github_repo",
+    "shorten_urls",
+    "number_headings",
+    "heading_task_counts",
+    "anchor_symbol",
+    "anchor_placement",
+    "constrain_images",
+    "hard_line_breaks",
+    "criticmarkup",
+    "escape_unknown_tags",
+];
+
+/// Merges a document's front matter `markviewer:` settings into `options`.
+///
+/// Settings override the passed options unless `front_matter_overrides` is
+/// false, in which case they only fill in unset ones. Unknown keys and
+/// values of the wrong type are skipped with a warning.
+fn apply_document_options(
+    options: &mut RenderOptions,
+    settings: &serde_json::Value,
+    warnings: &mut Vec<String>,
+) {
+    let Some(settings) = settings.as_object() else {
+        warnings.push("Front matter 'markviewer' must be a mapping of render options".to_string());
+        return;
+    };
+    let Ok(serde_json::Value::Object(mut merged)) = serde_json::to_value(&*options) else {
+        return;
+    };
+
+    let overrides = options.front_matter_overrides.unwrap_or(true);
+    for (key, value) in settings {
+        if !DOCUMENT_OPTION_KEYS.contains(&key.as_str()) {
+            warnings.push(format!("Ignoring unknown front matter option '{}'", key));
+            continue;
+        }
+        let unset = match merged.get(key) {
+            None | Some(serde_json::Value::Null) => true,
+            Some(serde_json::Value::String(s)) => s.is_empty(),
+            Some(_) => false,
+        };
+        if !overrides && !unset {
+            continue;
+        }
+
+        let mut candidate = merged.clone();
+        candidate.insert(key.clone(), value.clone());
+        let checked = serde_json::Value::Object(candidate.clone());
+        match serde_json::from_value::<RenderOptions>(checked) {
+            Ok(_) => merged = candidate,
+            Err(e) => warnings.push(format!("Ignoring front matter option '{}': {}", key, e)),
+        }
+    }
+
+    if let Ok(merged) = serde_json::from_value(serde_json::Value::Object(merged)) {
+        *options = merged;
+    }
 }
 
 /// Renders markdown to HTML with syntax highlighting and special block extraction.
 ///
 /// # Arguments
 /// * `markdown` - The markdown content to render
-/// * `options` - Rendering options (theme, base_path for images)
+/// * `options` - Rendering options (theme, base_path for images). Without
+///   `attachment_folders`, the attachment folders setting is used
 ///
 /// # Returns
 /// * `RenderResult` containing HTML and special blocks for JS rendering
 #[command]
-pub fn render_markdown(markdown: String, options: RenderOptions) -> Result<RenderResult, String> {
-    // 1. Extract special blocks (mermaid, chart) before parsing
-    let (processed_md, special_blocks) = extract_special_blocks(&markdown);
+pub fn render_markdown(
+    app: AppHandle,
+    markdown: String,
+    mut options: RenderOptions,
+) -> Result<RenderResult, String> {
+    if options.attachment_folders.is_none() {
+        let store = app
+            .store(SETTINGS_STORE)
+            .map_err(|e| format!("Failed to open settings store: {}", e))?;
+        let settings = Settings::from_stored(store.get(SETTINGS_KEY));
+        options.attachment_folders = Some(settings.attachment_folders);
+    }
+    render_markdown_with(markdown, options)
+}
+
+/// Renders markdown like `render_markdown`, with only the given options
+/// (the attachment folders setting isn't read).
+pub fn render_markdown_with(
+    markdown: String,
+    options: RenderOptions,
+) -> Result<RenderResult, String> {
+    render_document(&markdown, options, None)
+}
+
+/// Renders markdown like `render_markdown`, reporting where the time went.
+///
+/// # Arguments
+/// * `markdown` - The markdown content to render
+/// * `options` - Rendering options, as for `render_markdown`
+///
+/// # Returns
+/// * The render result and milliseconds per stage, with the number of
+///   highlighted code blocks
+#[command]
+pub fn render_with_timing(
+    markdown: String,
+    options: RenderOptions,
+) -> Result<(RenderResult, RenderTimings), String> {
+    let mut timings = RenderTimings::default();
+    let result = render_document(&markdown, options, Some(&mut timings))?;
+    Ok((result, timings))
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Milliseconds since `started`, or zero when untimed
+fn millis_since(started: Option<Instant>) -> f64 {
+    started.map_or(0.0, |started| millis(started.elapsed()))
+}
+
+/// The render pipeline behind `render_markdown`, recording stage timings into
+/// `timings` when given (the clock isn't read otherwise)
+fn render_document(
+    markdown: &str,
+    mut options: RenderOptions,
+    timings: Option<&mut RenderTimings>,
+) -> Result<RenderResult, String> {
+    let timed = timings.is_some();
+    let now = || timed.then(Instant::now);
+    let mut stages = RenderTimings::default();
+    let started = now();
+
+    let shifted;
+    let markdown = match options.heading_shift {
+        Some(shift) if shift != 0 => {
+            shifted = shift_heading_levels(markdown, shift);
+            shifted.as_str()
+        }
+        _ => markdown,
+    };
+
+    // 1. Split off front matter (YAML ---, TOML +++ or JSON), applying any
+    // render options the document sets for itself
+    let (front_matter, body) = extract_front_matter(markdown);
+    let mut warnings = Vec::new();
+    let document_options = front_matter
+        .as_ref()
+        .and_then(|fm| fm.value.as_ref())
+        .and_then(|value| value.get("markviewer"));
+    if let Some(settings) = document_options {
+        apply_document_options(&mut options, settings, &mut warnings);
+    }
+
+    // Quote source files named by `<!-- include: path#L1-L9 -->` lines
+    let included;
+    let body = match options.base_path {
+        Some(ref base_path) if options.code_includes.unwrap_or(false) => {
+            included = expand_code_includes(body, base_path, &mut warnings);
+            included.as_str()
+        }
+        _ => body,
+    };
+
+    // 2. Extract special blocks (mermaid, chart) before parsing
+    let (processed_md, special_blocks) = if options.extract_special_blocks.unwrap_or(true) {
+        let mut block_types: Vec<String> = match options.special_block_types {
+            Some(ref types) => types.clone(),
+            None => client_block_types(),
+        };
+        if options.math_mode.is_some() {
+            block_types.push("math".to_string());
+        }
+        let block_types: Vec<&str> = block_types.iter().map(String::as_str).collect();
+        match options.special_block_labels {
+            Some(ref labels) => extract_special_blocks_labeled(body, &block_types, labels),
+            None => extract_special_blocks_with(body, &block_types),
+        }
+    } else {
+        (body.to_string(), Vec::new())
+    };
+
+    let (processed_md, special_blocks) = if options.math_mode.as_deref() == Some("mathml") {
+        render_math_blocks(processed_md, special_blocks, &mut warnings)
+    } else {
+        (processed_md, special_blocks)
+    };
+    stages.special_blocks_ms = millis_since(started);
+
+    // 3. Render markdown to HTML with comrak
+    let has_attachment_folders = options
+        .attachment_folders
+        .as_ref()
+        .is_some_and(|folders| !folders.is_empty());
+    let html_options = HtmlOptions {
+        emoji_policy: options.emoji_policy.unwrap_or_default(),
+        github_ids: options.github_compatible_ids.unwrap_or(false),
+        block_ids: options.block_ids.unwrap_or(true),
+        block_anchors: options.block_anchors.unwrap_or(false),
+        table_line_breaks: options.table_line_breaks.unwrap_or(false),
+        smart_punctuation: options.smart_punctuation.unwrap_or(false),
+        kbd_shortcuts: options.kbd_shortcuts.unwrap_or(true),
+        image_embeds: options.image_embeds.unwrap_or(has_attachment_folders),
+        inline_footnotes: options.inline_footnotes.unwrap_or(false),
+        abbreviations: options.abbreviations.unwrap_or(false),
+        collapse_code_lines: options.collapse_code_lines,
+        dark_theme: options.theme == "dark",
+        hard_line_breaks: options.hard_line_breaks.unwrap_or(false),
+        criticmarkup: options.criticmarkup.unwrap_or(false),
+        escape_unknown_tags: options.escape_unknown_tags.unwrap_or(true),
+        timings: timed,
+    };
+    let rendered = render_markdown_html_with(&processed_md, &html_options);
+    let toc = rendered.toc;
+    let mut html = rendered.html;
+    stages.parse_ms = millis(rendered.timings.parse);
+    stages.html_ms = millis(rendered.timings.format);
+    stages.highlight_ms = millis(rendered.timings.highlight);
+    stages.code_blocks = rendered.timings.code_blocks;
+    if options.number_headings.unwrap_or(false) {
+        html = number_headings(&html);
+    }
+    if options.anchor_symbol.is_some() || options.anchor_placement.is_some() {
+        let symbol = options.anchor_symbol.as_deref().unwrap_or(DEFAULT_ANCHOR_SYMBOL);
+        html = style_heading_anchors(&html, symbol, options.anchor_placement.unwrap_or_default());
+    }
+    if options.heading_task_counts.unwrap_or(false) {
+        html = add_heading_task_counts(&html);
+    }
+
+    // Only this document's leading block is shown; later `---` blocks stay body
+    let front_matter_mode = options.front_matter_mode.unwrap_or_default();
+    let front_matter_block = front_matter
+        .as_ref()
+        .and_then(|fm| front_matter_html(fm, front_matter_mode));
+    if let Some(block) = front_matter_block {
+        html.insert_str(0, &block);
+    }
+    if options.shorten_urls.unwrap_or(false) {
+        html = shorten_autolinks(&html);
+    }
+    if let Some(ref repo) = options.github_repo {
+        html = link_github_refs(&html, repo);
+    }
+    if options.color_swatches.unwrap_or(false) {
+        html = insert_color_swatches(&html);
+    }
+
+    // 4. Resolve image paths and local link targets against the remote base
+    // URL, or base_path if provided
+    let resolving = now();
+    if let Some(ref base_url) = options.remote_base_url {
+        html = resolve_remote_paths(&html, base_url);
+    } else if let Some(ref base_path) = options.base_path {
+        let attachment_folders = options.attachment_folders.as_deref().unwrap_or_default();
+        html = resolve_image_paths_with(&html, base_path, attachment_folders);
+        if options.mark_local_links.unwrap_or(true) {
+            html = mark_local_links(&html, base_path);
+        }
+    }
+    if options.constrain_images.unwrap_or(true) {
+        html = constrain_images(&html);
+    }
+    stages.images_ms = millis_since(resolving);
 
-    // 2. Render markdown to HTML with comrak
-    let mut html = render_markdown_html(&processed_md);
+    // 5. Sanitize the final HTML if requested
+    let sanitizing = now();
+    if options.sanitize.unwrap_or(false) {
+        html = sanitize_html(&html);
+    }
+    stages.sanitize_ms = millis_since(sanitizing);
 
-    // 3. Resolve image paths if base_path is provided
-    if let Some(ref base_path) = options.base_path {
-        html = resolve_image_paths(&html, base_path);
+    // Added after sanitizing, which drops <style>; its values are validated instead
+    if options.allow_document_style.unwrap_or(false) {
+        let style = front_matter
+            .as_ref()
+            .and_then(|fm| fm.value.as_ref())
+            .and_then(|value| value.get("style"));
+        if let Some(block) = style.and_then(|style| document_style_block(style, &mut warnings)) {
+            html.insert_str(0, &block);
+        }
+    }
+    if let Some(timings) = timings {
+        stages.total_ms = millis_since(started);
+        *timings = stages;
     }
 
     Ok(RenderResult {
         html,
         special_blocks,
+        front_matter: front_matter.and_then(|fm| fm.value),
+        warnings,
+        block_ids: rendered.block_ids,
+        toc,
+        theme: options.theme,
+        rendered_source: processed_md,
+        block_lines: rendered.block_lines,
     })
 }
 
-/// Highlights a code block using syntect.
+/// Maps rendered blocks to the source lines they came from, for scroll sync
+/// between the editor and the preview.
 ///
 /// # Arguments
-/// * `code` - The code to highlight
-/// * `lang` - The language identifier (e.g., "rust", "javascript", "python")
+/// * `markdown` - The markdown content
+/// * `options` - Rendering options, as for `render_markdown` (block ids are
+///   always on)
 ///
 /// # Returns
-/// * The highlighted HTML string with syntax highlighting classes
+/// * One entry per top-level block: its `data-block-id` and 1-based source
+///   line range. Lines are approximate around special blocks, which render
+///   from a placeholder
 #[command]
-pub fn highlight_code_block(code: String, lang: String) -> String {
-    highlight_code(&code, &lang)
+pub fn source_render_map(
+    markdown: String,
+    options: RenderOptions,
+) -> Result<Vec<SourceMapEntry>, String> {
+    let source = match options.heading_shift {
+        Some(shift) if shift != 0 => shift_heading_levels(&markdown, shift),
+        _ => markdown,
+    };
+    let options = RenderOptions {
+        heading_shift: None,
+        block_ids: Some(true),
+        ..options
+    };
+    let result = render_document(&source, options, None)?;
+    Ok(map_blocks_to_source(
+        &source,
+        &result.rendered_source,
+        &result.block_ids,
+        &result.block_lines,
+    ))
 }
 
-/// Opens a path in the system file manager (Finder on macOS)
+/// Renders a document and returns only the top-level blocks that differ from
+/// the previous render, so the preview can patch its DOM while typing.
 ///
 /// # Arguments
-/// * `path` - The path to open (file or directory)
+/// * `prev_markdown` - The markdown of the previous render
+/// * `new_markdown` - The current markdown
+/// * `options` - Rendering options, as for `render_markdown` (block ids are
+///   always on)
+///
+/// # Returns
+/// * Changed, added and removed blocks by `data-block-id`; the full HTML
+///   instead when content outside blocks (front matter, special block
+///   placeholders) changed
 #[command]
-pub fn open_path(path: String) -> Result<(), String> {
-    #[cfg(target_os = "macos")]
-    {
-        std::process::Command::new("open")
-            .arg(&path)
-            .spawn()
-            .map_err(|e| format!("Failed to open path: {}", e))?;
-    }
-    #[cfg(target_os = "windows")]
-    {
-        std::process::Command::new("explorer")
-            .arg(&path)
-            .spawn()
-            .map_err(|e| format!("Failed to open path: {}", e))?;
+pub fn render_patch(
+    prev_markdown: String,
+    new_markdown: String,
+    options: RenderOptions,
+) -> Result<RenderPatch, String> {
+    let options = RenderOptions {
+        block_ids: Some(true),
+        ..options
+    };
+    let previous = render_document(&prev_markdown, options.clone(), None)?;
+    let result = render_document(&new_markdown, options, None)?;
+
+    Ok(RenderPatch {
+        blocks: diff_rendered_blocks(&previous.html, &result.html),
+        special_blocks: result.special_blocks,
+        front_matter: result.front_matter,
+        warnings: result.warnings,
+        theme: result.theme,
+    })
+}
+
+/// Renders a presentation, one result per slide.
+///
+/// Slides are separated by a line matching `options.slide_separator` (default
+/// `---` at column 0); the front matter is never a separator and is rendered
+/// with the first slide. Special block ids are unique across all slides.
+///
+/// # Arguments
+/// * `markdown` - The presentation markdown
+/// * `options` - Rendering options, as for `render_markdown`
+///
+/// # Returns
+/// * The render result of each slide, in order
+#[command]
+pub fn render_slides(
+    markdown: String,
+    options: RenderOptions,
+) -> Result<Vec<RenderResult>, String> {
+    let separator = options
+        .slide_separator
+        .as_deref()
+        .unwrap_or(DEFAULT_SLIDE_SEPARATOR);
+
+    let mut slides = Vec::new();
+    let mut next_id = 0;
+    for slide in split_slides(&markdown, separator) {
+        let mut result = render_document(&slide, options.clone(), None)?;
+        offset_placeholder_ids(&mut result.html, &mut result.special_blocks, next_id);
+        next_id = result
+            .special_blocks
+            .iter()
+            .filter_map(|block| placeholder_number(&block.placeholder_id))
+            .map(|number| number + 1)
+            .fold(next_id, usize::max);
+        slides.push(result);
     }
-    #[cfg(target_os = "linux")]
-    {
-        std::process::Command::new("xdg-open")
-            .arg(&path)
-            .spawn()
-            .map_err(|e| format!("Failed to open path: {}", e))?;
+    Ok(slides)
+}
+
+/// Renders only the heading sections that mention a query (case-insensitive).
+///
+/// Each kept section includes its heading; non-matching sections are omitted.
+/// Special blocks are extracted from the filtered document, so their ids are
+/// sequential within the result. The document's front matter `markviewer:`
+/// settings apply as they would to the whole document.
+///
+/// # Arguments
+/// * `markdown` - The markdown content to search
+/// * `query` - Text to look for in each section
+/// * `options` - Rendering options, as for `render_markdown`
+#[command]
+pub fn render_matching_sections(
+    markdown: String,
+    query: String,
+    mut options: RenderOptions,
+) -> Result<RenderResult, String> {
+    let (front_matter, body) = extract_front_matter(&markdown);
+    let filtered = filter_sections(body, &query);
+
+    let front_matter = front_matter.and_then(|fm| fm.value);
+    let mut warnings = Vec::new();
+    if let Some(settings) = front_matter.as_ref().and_then(|value| value.get("markviewer")) {
+        apply_document_options(&mut options, settings, &mut warnings);
     }
-    Ok(())
+
+    let mut result = render_markdown_with(filtered, options)?;
+    result.front_matter = front_matter;
+    result.warnings.splice(0..0, warnings);
+    Ok(result)
 }
 
-/// Opens a file in an external editor
+/// Renders markdown with search terms wrapped in `<mark class="search-hit">`.
+///
+/// Matches are case-insensitive and only in rendered text, never inside tags or
+/// attributes.
 ///
 /// # Arguments
-/// * `command` - The command to run (e.g., "code", "cursor", "neovide")
-/// * `args` - Arguments to pass to the command (usually the file path)
+/// * `markdown` - The markdown content to render
+/// * `options` - Rendering options, as for `render_markdown`
+/// * `terms` - Terms to highlight; blank terms are ignored
+/// * `include_code` - Also highlight inside code spans and blocks (default: false)
 #[command]
-pub fn open_in_editor(command: String, args: Vec<String>) -> Result<(), String> {
-    std::process::Command::new(&command)
-        .args(&args)
-        .spawn()
-        .map_err(|e| format!("Failed to open in editor '{}': {}", command, e))?;
-    Ok(())
+pub fn render_with_highlight(
+    markdown: String,
+    options: RenderOptions,
+    terms: Vec<String>,
+    include_code: Option<bool>,
+) -> Result<RenderResult, String> {
+    let mut result = render_markdown_with(markdown, options)?;
+    result.html = mark_search_hits(&result.html, &terms, include_code.unwrap_or(false));
+    Ok(result)
 }
 
-/// Saves image data (base64) to a file and returns the path.
+/// Finds a query in a file and renders each match with the lines around it,
+/// for search results that show formatting.
+///
+/// Matching is case-insensitive against the source lines; matches close
+/// enough to share context are returned as one snippet. A match inside a
+/// code block renders inside that block.
 ///
 /// # Arguments
-/// * `base64_data` - The base64 encoded image data
-/// * `base_path` - The path to the .md file (images will be saved in same directory)
-/// * `filename` - Optional filename, will generate one if not provided
+/// * `file_path` - The markdown file to search
+/// * `query` - Text to look for
+/// * `context_lines` - Lines to include before and after each match
 ///
 /// # Returns
-/// * The relative path to the saved image
-#[command]
-pub fn save_pasted_image(
-    base64_data: String,
-    base_path: String,
-    filename: Option<String>,
-) -> Result<String, String> {
-    // Decode base64
-    use base64::{engine::general_purpose::STANDARD, Engine};
+/// * One snippet per match, in document order, with matches in
+///   `<mark class="search-hit">`
+#[command(async)]
+pub fn search_snippet(
+    file_path: String,
+    query: String,
+    context_lines: usize,
+) -> Result<Vec<Snippet>, String> {
+    let markdown =
+        fs::read_to_string(&file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(search_snippets(&markdown, &query, context_lines))
+}
 
-    // Remove data URL prefix if present
-    let data = if base64_data.contains(",") {
-        base64_data.split(",").last().unwrap_or(&base64_data)
-    } else {
-        &base64_data
+/// Renders markdown as Rich Text Format, for pasting into word processors
+/// that lose formatting from HTML.
+///
+/// Headings, emphasis, lists, links, code (in a monospace font) and tables
+/// are kept; special blocks stay as their source text and raw HTML is dropped.
+///
+/// # Arguments
+/// * `markdown` - The markdown content to convert
+/// * `options` - Rendering options; smart punctuation, line breaks and inline
+///   footnotes apply
+#[command]
+pub fn render_rtf(markdown: String, options: RenderOptions) -> String {
+    let html_options = HtmlOptions {
+        smart_punctuation: options.smart_punctuation.unwrap_or(false),
+        inline_footnotes: options.inline_footnotes.unwrap_or(false),
+        hard_line_breaks: options.hard_line_breaks.unwrap_or(false),
+        ..Default::default()
     };
+    markdown_to_rtf(&markdown, &html_options)
+}
 
-    let bytes = STANDARD
-        .decode(data)
-        .map_err(|e| format!("Failed to decode base64: {}", e))?;
+/// Renders a non-JS fallback for a special block (for screen readers and exports).
+///
+/// Mermaid becomes its source in a code block, charts a data table and math the
+/// raw TeX. Other blocks return their raw content in a code block.
+#[command]
+pub fn render_special_block_fallback(block: SpecialBlock) -> String {
+    special_block_fallback(&block)
+}
 
-    // Determine file path
-    let base = Path::new(&base_path);
-    let base_dir = base.parent().unwrap_or(base);
+/// Converts math special blocks to inline MathML.
+///
+/// Blocks that fail to convert keep their placeholder (rendered on the
+/// frontend) and are reported as warnings.
+fn render_math_blocks(
+    markdown: String,
+    blocks: Vec<SpecialBlock>,
+    warnings: &mut Vec<String>,
+) -> (String, Vec<SpecialBlock>) {
+    let mut markdown = markdown;
+    let mut remaining = Vec::new();
 
-    // Create images directory if it doesn't exist
-    let images_dir = base_dir.join("images");
-    if !images_dir.exists() {
-        fs::create_dir_all(&images_dir).map_err(|e| format!("Failed to create images dir: {}", e))?;
+    for block in blocks {
+        if block.block_type != "math" {
+            remaining.push(block);
+            continue;
+        }
+        match tex_to_mathml(&block.content) {
+            Ok(mathml) => {
+                let html = format!("<div class=\"math-block\">{}</div>", mathml);
+                markdown = replace_placeholder(&markdown, &block.placeholder_id, &html);
+            }
+            Err(e) => {
+                warnings.push(format!("{}: {}", block.placeholder_id, e));
+                remaining.push(block);
+            }
+        }
     }
 
-    // Generate filename
-    let file_name = filename.unwrap_or_else(|| {
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map(|d| d.as_millis())
-            .unwrap_or(0);
-        format!("pasted-image-{}.png", timestamp)
-    });
+    (markdown, remaining)
+}
 
-    let file_path = images_dir.join(&file_name);
+/// Renders a Jupyter notebook (.ipynb) through the markdown pipeline.
+///
+/// Markdown cells are rendered as markdown, code cells as highlighted code
+/// blocks in the notebook's language, followed by their text/image outputs.
+///
+/// # Arguments
+/// * `path` - Path to the .ipynb file
+/// * `options` - Rendering options (base_path defaults to the notebook path)
+#[command]
+pub fn render_notebook(path: String, options: RenderOptions) -> Result<RenderResult, String> {
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read notebook: {}", e))?;
+    let markdown = notebook_to_markdown(&content)?;
 
-    // Write file
-    fs::write(&file_path, bytes).map_err(|e| format!("Failed to write image: {}", e))?;
+    let options = RenderOptions {
+        base_path: options.base_path.clone().or(Some(path)),
+        ..options
+    };
 
-    // Return relative path
-    Ok(format!("images/{}", file_name))
+    render_markdown_with(markdown, options)
 }
 
-/// Installs the 'mkv' command in PATH (/usr/local/bin)
-/// This allows users to open files/folders from terminal with: mkv <path>
+/// Highlights a code block using syntect.
+///
+/// # Arguments
+/// * `code` - The code to highlight
+/// * `lang` - The language identifier (e.g., "rust", "javascript", "python")
+///
+/// # Returns
+/// * The highlighted HTML string with syntax highlighting classes
 #[command]
-pub fn install_cli_command() -> Result<String, String> {
-    #[cfg(target_os = "macos")]
-    {
-        let script_content = r#"#!/bin/bash
-if [ -z "$1" ]; then
-  open /Applications/MarkViewer.app
-else
-  # Convert to absolute path
-  if [[ "$1" = /* ]]; then
-    ABS_PATH="$1"
-  else
-    ABS_PATH="$(cd "$(dirname "$1")" 2>/dev/null && pwd)/$(basename "$1")"
-  fi
-  open /Applications/MarkViewer.app --args "$ABS_PATH"
-fi
-"#;
+pub fn highlight_code_block(code: String, lang: String) -> String {
+    highlight_code(&code, &lang)
+}
 
-        let script_path = "/usr/local/bin/mkv";
+/// A code block for `highlight_code_blocks`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CodeInput {
+    pub code: String,
+    pub lang: String,
+}
 
-        // Use osascript to run with admin privileges
-        let apple_script = format!(
-            r#"do shell script "echo '{}' > {} && chmod +x {}" with administrator privileges"#,
-            script_content.replace("\"", "\\\"").replace("\n", "\\n"),
-            script_path,
-            script_path
-        );
+/// Highlights many code blocks in one call, e.g. after a theme change.
+///
+/// # Arguments
+/// * `blocks` - The code and language of each block
+///
+/// # Returns
+/// * The highlighted HTML of each block, in input order
+#[command(async)]
+pub fn highlight_code_blocks(blocks: Vec<CodeInput>) -> Vec<String> {
+    blocks
+        .par_iter()
+        .map(|block| highlight_code(&block.code, &block.lang))
+        .collect()
+}
 
-        let output = std::process::Command::new("osascript")
-            .arg("-e")
-            .arg(&apple_script)
-            .output()
-            .map_err(|e| format!("Failed to run installer: {}", e))?;
+/// Tokenizes code using syntect, returning structured tokens instead of HTML.
+///
+/// # Arguments
+/// * `code` - The code to tokenize
+/// * `lang` - The language identifier (e.g., "rust", "javascript", "python")
+///
+/// # Returns
+/// * Tokens with their text, classes, scope and 1-based line number
+#[command]
+pub fn tokenize_code(code: String, lang: String) -> Vec<Token> {
+    highlight_tokens(&code, &lang)
+}
+
+/// Summarizes tasklist completion ("3/7 done") for a document.
+///
+/// # Arguments
+/// * `markdown` - The markdown content
+/// * `top_level_only` - Only count tasks that are not nested (default: false)
+///
+/// # Returns
+/// * Total and completed counts, with a per-section breakdown by heading
+#[command]
+pub fn task_progress(markdown: String, top_level_only: Option<bool>) -> TaskProgress {
+    summarize_tasks(&markdown, top_level_only.unwrap_or(false))
+}
+
+/// Lists a document's tasks with due dates and priorities, for agenda views.
+///
+/// # Arguments
+/// * `markdown` - The markdown content
+///
+/// # Returns
+/// * Every tasklist item in document order; `due` and `priority` are null
+///   when the item has no annotation
+#[command]
+pub fn extract_tasks(markdown: String) -> Vec<Task> {
+    parse_tasks(&markdown)
+}
+
+/// Computes folding regions for the source editor's fold gutter.
+///
+/// # Arguments
+/// * `markdown` - The markdown content
+///
+/// # Returns
+/// * 1-based, inclusive line ranges for front matter, heading sections, fenced
+///   code and multi-line HTML comments, enclosing regions first
+#[command]
+pub fn fold_regions(markdown: String) -> Vec<FoldRegion> {
+    find_fold_regions(&markdown)
+}
+
+/// Shifts and compacts heading levels so a document starts at `start_level`
+/// and never skips a level (H1 → H3 becomes H1 → H2).
+///
+/// Only ATX and setext headings are changed; code blocks and front matter are
+/// left as-is.
+///
+/// # Returns
+/// * The rewritten markdown
+#[command]
+pub fn normalize_headings(markdown: String, start_level: u8) -> String {
+    normalize_heading_levels(&markdown, start_level)
+}
+
+/// Reformats the pipe tables in a document: pads rows to the same number of
+/// columns, adds a missing delimiter row and lines up the pipes.
+///
+/// Column alignments are kept; code blocks are left as-is.
+///
+/// # Returns
+/// * The rewritten markdown, with a warning for each table too malformed to
+///   reformat (those are left untouched)
+#[command]
+pub fn normalize_table(markdown: String) -> NormalizedTables {
+    normalize_tables(&markdown)
+}
+
+/// Detects the language of a document for spellcheck and the webview `lang`.
+///
+/// # Returns
+/// * A BCP 47 tag ("en", "pt-BR") from front matter `lang`, else a confident
+///   guess from the prose, else None
+#[command]
+pub fn detect_document_language(markdown: String) -> Option<String> {
+    detect_language(&markdown)
+}
+
+/// Merges field updates into a document's front matter before saving.
+///
+/// Unknown keys and key order are preserved, a `null` value removes a key and
+/// the body is returned byte-identical. Documents without front matter get a
+/// new YAML block.
+///
+/// # Arguments
+/// * `markdown` - The full document
+/// * `updates` - A JSON object of fields to set
+#[command]
+pub fn update_front_matter(
+    markdown: String,
+    updates: serde_json::Value,
+) -> Result<String, String> {
+    let updates = updates
+        .as_object()
+        .ok_or_else(|| "Front matter updates must be an object".to_string())?;
+    merge_front_matter(&markdown, updates)
+}
+
+/// Reads a document's front matter with defaults inherited from shared
+/// `_meta.yaml` files, so a folder of documents can share e.g. `author` or
+/// `license`.
+///
+/// The document's own keys win, then the closest `_meta.yaml` (its folder,
+/// then each parent folder in turn). Keys are merged at the top level only.
+///
+/// # Arguments
+/// * `markdown` - The markdown content
+/// * `base_path` - Path of the .md file; `_meta.yaml` files are looked up
+///   from its folder upwards
+/// * `root` - The vault folder; the lookup stops there
+///
+/// # Returns
+/// * The merged fields as an object (empty when there are none)
+#[command(async)]
+pub fn resolve_front_matter(
+    markdown: String,
+    base_path: String,
+    root: String,
+) -> serde_json::Value {
+    inherited_front_matter(&markdown, Path::new(&base_path), Path::new(&root))
+}
+
+/// Checks a document's front matter against a schema, e.g. before publishing.
+///
+/// # Arguments
+/// * `markdown` - The markdown content
+/// * `schema` - Required keys and per-field types and allowed values, as a
+///   JSON Schema subset (`required`, `properties`, `type`, `enum`, `items`)
+///
+/// # Returns
+/// * One error per problem, with the field path (`author.name`, `tags[1]`);
+///   empty when the front matter is valid
+#[command]
+pub fn validate_front_matter(markdown: String, schema: serde_json::Value) -> Vec<SchemaError> {
+    validate_front_matter_schema(&markdown, &schema)
+}
+
+/// Flags code blocks with mixed tab/space indentation or trailing whitespace.
+///
+/// Advisory only: the markdown is not modified.
+///
+/// # Returns
+/// * One entry per issue per block, with the block's fence line and affected lines
+#[command]
+pub fn lint_code_blocks(markdown: String) -> Vec<CodeLint> {
+    lint_fenced_code(&markdown)
+}
+
+/// Lists images whose alt text is missing, empty or generic ("image", the file
+/// name), for accessibility audits.
+///
+/// # Returns
+/// * One entry per image, with its line, source and the problem
+#[command]
+pub fn audit_alt_text(markdown: String) -> Vec<AltTextIssue> {
+    audit_image_alt_text(&markdown)
+}
+
+/// Checks a document for a problems panel: broken local images and links,
+/// broken `#anchor` links, missing alt text, duplicate headings and chart
+/// blocks that aren't valid JSON.
+///
+/// # Arguments
+/// * `markdown` - The markdown content
+/// * `base_path` - Path to the markdown file, for resolving relative paths
+///
+/// # Returns
+/// * Every problem ordered by line, with its severity and category, plus
+///   counts per severity
+#[command(async)]
+pub fn lint_document(markdown: String, base_path: String) -> LintReport {
+    lint_markdown(&markdown, &base_path)
+}
+
+/// Lists the local files a document references (images, linked files and
+/// video/audio sources) before exporting or bundling it.
+///
+/// # Arguments
+/// * `markdown` - The markdown content
+/// * `base_path` - Path to the markdown file, for resolving relative paths
+///
+/// # Returns
+/// * One entry per file in document order, with its absolute path, whether
+///   it exists and a MIME type guessed from the extension
+#[command(async)]
+pub fn list_media(markdown: String, base_path: String) -> Vec<MediaRef> {
+    media_references(&markdown, &base_path)
+}
+
+/// Finds in-page `#anchor` links whose target doesn't exist.
+///
+/// # Arguments
+/// * `markdown` - The markdown content to check
+/// * `emoji_policy` - How emoji in headings become ids, as in `RenderOptions` (default strip)
+/// * `github_compatible_ids` - Use GitHub's heading ids, as in `RenderOptions` (default false)
+///
+/// # Returns
+/// * One issue per broken link, with its line and a suggested id when one is close
+#[command]
+pub fn validate_anchors(
+    markdown: String,
+    emoji_policy: Option<EmojiPolicy>,
+    github_compatible_ids: Option<bool>,
+) -> Vec<AnchorIssue> {
+    find_broken_anchors(
+        &markdown,
+        emoji_policy.unwrap_or_default(),
+        github_compatible_ids.unwrap_or(false),
+    )
+}
+
+/// Maps heading text to heading ids, so other tools can link to headings.
+///
+/// # Arguments
+/// * `markdown` - The markdown content
+/// * `emoji_policy` - How emoji in headings become ids, as in `RenderOptions` (default strip)
+/// * `github_compatible_ids` - Use GitHub's heading ids, as in `RenderOptions` (default false)
+///
+/// # Returns
+/// * Heading text to id; repeated headings map to the first heading's id
+#[command]
+pub fn anchor_map(
+    markdown: String,
+    emoji_policy: Option<EmojiPolicy>,
+    github_compatible_ids: Option<bool>,
+) -> HashMap<String, String> {
+    heading_anchor_map(
+        &markdown,
+        emoji_policy.unwrap_or_default(),
+        github_compatible_ids.unwrap_or(false),
+    )
+}
+
+/// Lists heading texts used by more than one heading, so links like
+/// `#heading-notes` can be disambiguated before they are written.
+///
+/// # Arguments
+/// * `markdown` - The markdown content
+/// * `emoji_policy` - How emoji in headings become ids, as in `RenderOptions` (default strip)
+/// * `github_compatible_ids` - Use GitHub's heading ids, as in `RenderOptions` (default false)
+///
+/// # Returns
+/// * Each repeated text with the line and rendered id of every heading using it
+#[command]
+pub fn find_duplicate_headings(
+    markdown: String,
+    emoji_policy: Option<EmojiPolicy>,
+    github_compatible_ids: Option<bool>,
+) -> Vec<DuplicateHeading> {
+    duplicate_headings(
+        &markdown,
+        emoji_policy.unwrap_or_default(),
+        github_compatible_ids.unwrap_or(false),
+    )
+}
+
+/// Lists the most frequent significant words of a document, for tag suggestions.
+///
+/// # Arguments
+/// * `markdown` - The markdown content
+/// * `top_n` - Maximum number of keywords
+/// * `stopwords` - Words to ignore (default: common English words)
+///
+/// # Returns
+/// * Lowercased, lightly stemmed words with their counts, most frequent first;
+///   front matter, code and markup are skipped
+#[command]
+pub fn keyword_summary(
+    markdown: String,
+    top_n: usize,
+    stopwords: Option<Vec<String>>,
+) -> Vec<(String, usize)> {
+    match stopwords {
+        Some(words) => {
+            let words: Vec<String> = words.iter().map(|w| w.to_lowercase()).collect();
+            let words: Vec<&str> = words.iter().map(String::as_str).collect();
+            top_keywords(&markdown, top_n, &words)
+        }
+        None => top_keywords(&markdown, top_n, &default_stopwords()),
+    }
+}
+
+/// Condenses a document to a TL;DR of its headings and opening sentences.
+///
+/// # Arguments
+/// * `markdown` - The markdown content
+/// * `sentences_per_section` - Sentences of prose to keep under each heading
+/// * `keep_code_and_lists` - Keep code blocks, lists and tables (default: false)
+///
+/// # Returns
+/// * Markdown with every heading and the first sentences of each section;
+///   front matter is dropped
+#[command]
+pub fn summarize(
+    markdown: String,
+    sentences_per_section: usize,
+    keep_code_and_lists: Option<bool>,
+) -> String {
+    summarize_sections(
+        &markdown,
+        sentences_per_section,
+        keep_code_and_lists.unwrap_or(false),
+    )
+}
+
+/// Converts the clipboard contents to markdown, for "paste as markdown".
+///
+/// # Returns
+/// * Markdown converted from the clipboard's HTML, or its plain text when it
+///   holds no HTML
+/// * Error if the clipboard is empty or can't be read
+#[command]
+pub fn clipboard_html_to_markdown() -> Result<String, String> {
+    let mut clipboard =
+        arboard::Clipboard::new().map_err(|e| format!("Failed to open clipboard: {}", e))?;
+
+    if let Ok(html) = clipboard.get().html() {
+        let markdown = html_to_markdown(&html);
+        if !markdown.is_empty() {
+            return Ok(markdown);
+        }
+    }
+    match clipboard.get_text() {
+        Ok(text) if !text.trim().is_empty() => Ok(text),
+        Ok(_) | Err(arboard::Error::ContentNotAvailable) => {
+            Err("Clipboard is empty".to_string())
+        }
+        Err(e) => Err(format!("Failed to read clipboard: {}", e)),
+    }
+}
+
+/// Formats a date relative to now ("3 days ago", "in 2 hours").
+///
+/// # Arguments
+/// * `iso` - An ISO 8601 timestamp or date-only value (e.g. "2024-06-01")
+///
+/// # Returns
+/// * The humanized date, or the input unchanged if it isn't a valid date
+#[command]
+pub fn humanize_date(iso: String) -> String {
+    relative_time(&iso, chrono::Utc::now()).unwrap_or(iso)
+}
+
+/// Lists the syntax highlighting themes bundled with syntect
+#[command]
+pub fn list_highlight_themes() -> Vec<String> {
+    list_themes()
+}
+
+/// Renders a small code sample with a theme's colors inlined, for theme previews.
+///
+/// # Arguments
+/// * `theme` - A theme name from `list_highlight_themes`
+/// * `sample_lang` - Sample language ("rust", "python", "javascript"); empty for all
+///
+/// # Returns
+/// * HTML with inline styles, or an error for unknown themes
+#[command]
+pub fn preview_theme(theme: String, sample_lang: String) -> Result<String, String> {
+    preview_theme_html(&theme, &sample_lang)
+}
+
+/// Returns the persisted app settings as a typed struct
+#[command]
+pub fn get_settings(app: AppHandle) -> Result<Settings, String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings store: {}", e))?;
+    Ok(Settings::from_stored(store.get(SETTINGS_KEY)))
+}
+
+/// Updates the provided settings fields and notifies all windows.
+///
+/// # Arguments
+/// * `partial` - An object with only the fields to change (camelCase keys)
+///
+/// # Returns
+/// * The updated settings; emits "settings-changed" with the same payload
+#[command]
+pub fn set_settings(app: AppHandle, partial: serde_json::Value) -> Result<Settings, String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings store: {}", e))?;
+
+    let settings = Settings::from_stored(store.get(SETTINGS_KEY)).merged(&partial)?;
+    let value = serde_json::to_value(&settings).map_err(|e| e.to_string())?;
+    store.set(SETTINGS_KEY, value);
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    app.emit("settings-changed", &settings)
+        .map_err(|e| format!("Failed to emit settings change: {}", e))?;
+
+    Ok(settings)
+}
+
+/// Remembers where a document was being read, so reopening it can scroll
+/// back there. Positions of files that no longer exist are dropped.
+///
+/// # Arguments
+/// * `path` - The document's path
+/// * `block_id` - `data-block-id` of the topmost visible block in the preview
+#[command(async)]
+pub fn save_reading_position(app: AppHandle, path: String, block_id: String) -> Result<(), String> {
+    let store = app
+        .store(READING_STORE)
+        .map_err(|e| format!("Failed to open reading store: {}", e))?;
+
+    let mut positions = ReadingPositions::from_stored(store.get(POSITIONS_KEY));
+    let pruned = positions.prune_missing();
+    if !positions.set(&path, &block_id) && pruned == 0 {
+        return Ok(());
+    }
+    let value = serde_json::to_value(&positions).map_err(|e| e.to_string())?;
+    store.set(POSITIONS_KEY, value);
+    store
+        .save()
+        .map_err(|e| format!("Failed to save reading position: {}", e))
+}
+
+/// Returns the block a document was last read at, saved by
+/// `save_reading_position`
+///
+/// # Returns
+/// * The block id, or None if the document hasn't been read before
+#[command]
+pub fn get_reading_position(app: AppHandle, path: String) -> Result<Option<String>, String> {
+    let store = app
+        .store(READING_STORE)
+        .map_err(|e| format!("Failed to open reading store: {}", e))?;
+    let positions = ReadingPositions::from_stored(store.get(POSITIONS_KEY));
+    Ok(positions.get(&path).map(str::to_string))
+}
+
+/// Exports a folder of markdown files as a static HTML site.
+///
+/// # Arguments
+/// * `root` - The folder to export
+/// * `out_dir` - Where to write the HTML files (directory structure is preserved)
+/// * `options` - Rendering options applied to every file
+///
+/// # Returns
+/// * A report of exported pages and per-file failures, plus the index page path
+#[command(async)]
+pub fn export_folder_html(
+    root: String,
+    out_dir: String,
+    options: RenderOptions,
+) -> Result<ExportReport, String> {
+    export_folder(Path::new(&root), Path::new(&out_dir), &options)
+}
+
+/// Writes a browsable `index.html` for exported pages: pages grouped by folder
+/// (subfolders collapsible), each with its headings as a table of contents.
+///
+/// # Arguments
+/// * `exported_files` - The pages to list, as reported by `export_folder_html`
+/// * `out_path` - Where to write the index; links are relative to its folder
+#[command(async)]
+pub fn generate_index(exported_files: Vec<ExportedDoc>, out_path: String) -> Result<(), String> {
+    export::generate_index(&exported_files, Path::new(&out_path))
+}
+
+/// Exports markdown files as an EPUB 3 book for e-readers.
+///
+/// # Arguments
+/// * `files` - The markdown files, in chapter order
+/// * `metadata` - Book title, author, language and identifier
+/// * `out_path` - Where to write the `.epub` file
+///
+/// # Returns
+/// * The chapter titles and the files that failed to render, which are left
+///   out of the book
+#[command(async)]
+pub fn export_epub(
+    files: Vec<String>,
+    metadata: EpubMeta,
+    out_path: String,
+) -> Result<EpubReport, String> {
+    write_epub(&files, &metadata, Path::new(&out_path))
+}
+
+/// Returns a markdown document demonstrating every supported syntax feature,
+/// to be rendered as a live help page.
+#[command]
+pub fn supported_syntax_sample() -> String {
+    SYNTAX_SAMPLE.to_string()
+}
+
+/// Finds notes under `root` that link to `target` (inline, reference or wikilinks).
+///
+/// # Arguments
+/// * `root` - The vault folder to scan
+/// * `target` - Absolute path of the note being linked to
+///
+/// # Returns
+/// * The linking files with line numbers and the surrounding line
+#[command(async)]
+pub fn find_backlinks(root: String, target: String) -> Vec<Backlink> {
+    collect_backlinks(Path::new(&root), Path::new(&target))
+}
+
+/// Tells the backend a file changed on disk (from the file watcher), dropping
+/// what it cached about the file.
+///
+/// # Arguments
+/// * `path` - The changed file
+#[command]
+pub fn file_changed(path: String) {
+    evict_cached_links(Path::new(&path));
+}
+
+/// Builds the header breadcrumb for a file, e.g. "Vault / Projects / today.md".
+///
+/// # Arguments
+/// * `root` - The vault folder
+/// * `file` - Absolute path of the open file
+///
+/// # Returns
+/// * One segment per folder from `root` down to the file, each with its
+///   absolute path; just the file when it lies outside `root`
+#[command]
+pub fn path_breadcrumb(root: String, file: String) -> Vec<BreadcrumbSegment> {
+    breadcrumb_segments(Path::new(&root), Path::new(&file))
+}
+
+/// Opens today's daily note (or the one `offset_days` away), creating it from
+/// the configured template when it doesn't exist yet.
+///
+/// # Arguments
+/// * `root` - The vault folder
+/// * `offset_days` - Days from today, e.g. -1 for yesterday
+///
+/// # Returns
+/// * The note's absolute path, placed by the daily notes folder and format
+///   settings
+#[command]
+pub fn resolve_daily_note(
+    app: AppHandle,
+    root: String,
+    offset_days: i32,
+) -> Result<String, String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings store: {}", e))?;
+    let settings = Settings::from_stored(store.get(SETTINGS_KEY));
+
+    let date = offset_date(chrono::Local::now().date_naive(), offset_days)
+        .ok_or_else(|| format!("Invalid day offset: {}", offset_days))?;
+    let daily = DailyNotes {
+        folder: &settings.daily_notes_folder,
+        format: &settings.daily_note_format,
+        template: settings.daily_note_template.as_deref(),
+    };
+    let path = daily_notes::resolve_daily_note(Path::new(&root), &daily, date)?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Indexes the markdown files under `root` for the sidebar.
+///
+/// Title, tags, word count and mtime are cached per file, so later calls only
+/// re-read files modified since the last index.
+#[command(async)]
+pub fn index_folder(root: String) -> Vec<FileMeta> {
+    index_files(Path::new(&root))
+}
+
+/// Finds files for quick-open by file name, title or front matter alias.
+///
+/// # Arguments
+/// * `root` - The vault folder
+/// * `query` - Text typed by the user
+/// * `limit` - Maximum number of results (default: 50)
+///
+/// # Returns
+/// * Matching files from the index, best matches first
+#[command(async)]
+pub fn fuzzy_find_files(root: String, query: String, limit: Option<usize>) -> Vec<FileMeta> {
+    let mut files = find_files(Path::new(&root), &query);
+    files.truncate(limit.unwrap_or(50));
+    files
+}
+
+/// Reads the alternate names a note declares in its front matter.
+///
+/// # Arguments
+/// * `markdown` - The markdown content
+///
+/// # Returns
+/// * The `aliases` (or `alias`) field, as a list or a single name
+#[command]
+pub fn extract_aliases(markdown: String) -> Vec<String> {
+    document_aliases(&markdown)
+}
+
+/// Gives a note a stable `id` in its front matter, for bookmarks and links
+/// that survive moving the file.
+///
+/// # Arguments
+/// * `markdown` - The markdown content
+///
+/// # Returns
+/// * The document with a generated UUID `id` added to its front matter
+///   (created if missing), or unchanged if it already has an id
+#[command]
+pub fn ensure_document_id(markdown: String) -> Result<String, String> {
+    file_index::ensure_document_id(&markdown)
+}
+
+/// Finds the note with the given front matter `id`, wherever it is.
+///
+/// # Arguments
+/// * `root` - The vault folder
+/// * `id` - The document id
+///
+/// # Returns
+/// * The file's index entry, or None if no note has that id
+#[command(async)]
+pub fn find_by_id(root: String, id: String) -> Option<FileMeta> {
+    file_index::find_by_id(Path::new(&root), &id)
+}
+
+/// Generates a square thumbnail for the recent-files grid.
+///
+/// # Arguments
+/// * `markdown` - The document content
+/// * `base_path` - Path to the .md file (for resolving image paths)
+/// * `size` - Edge length in pixels (1-1024)
+///
+/// # Returns
+/// * A data URI: a PNG of the first local image, or an SVG with the title's initials
+#[command(async)]
+pub fn document_thumbnail(
+    markdown: String,
+    base_path: String,
+    size: u32,
+) -> Result<String, String> {
+    document_thumbnail_uri(&markdown, &base_path, size)
+}
+
+/// Packages a document and its local images into a zip archive.
+///
+/// Image links are rewritten to point into the bundle's `assets/` folder, so
+/// the note can be opened without its vault. Remote images are left as links.
+///
+/// # Arguments
+/// * `markdown` - The document content
+/// * `base_path` - Path to the .md file (for resolving image paths and naming the bundled file)
+/// * `out_zip` - Where to write the archive
+///
+/// # Returns
+/// * The bundled image paths and the referenced images that were missing
+#[command(async)]
+pub fn export_bundle(
+    markdown: String,
+    base_path: String,
+    out_zip: String,
+) -> Result<BundleReport, String> {
+    write_bundle(&markdown, &base_path, Path::new(&out_zip))
+}
+
+/// Suggests fixes for broken local image links by fuzzy filename match.
+///
+/// # Arguments
+/// * `markdown` - The document content
+/// * `base_path` - Path to the .md file; its folder is searched for images
+///
+/// # Returns
+/// * For each broken image source, the closest existing image (relative to
+///   the document) with a 0–1 confidence; sources without a close match are omitted
+#[command(async)]
+pub fn suggest_image_fixes(markdown: String, base_path: String) -> Vec<ImageFix> {
+    suggest_fixes(&markdown, &base_path)
+}
+
+/// Extracts dominant colors from a document's first local image for accent theming.
+///
+/// Palettes are cached per image and recomputed when the image changes.
+///
+/// # Arguments
+/// * `markdown` - The document content
+/// * `base_path` - Path to the .md file (for resolving image paths)
+///
+/// # Returns
+/// * Up to five `#rrggbb` colors, most dominant first; empty without local images
+#[command(async)]
+pub fn dominant_colors(markdown: String, base_path: String) -> Vec<String> {
+    document_palette(&markdown, &base_path)
+}
+
+/// Draws a chart block to a PNG for exports, without a browser.
+///
+/// # Arguments
+/// * `spec` - The Chart.js config (JSON); bar, line and pie charts are supported
+/// * `width` - Image width in pixels
+/// * `height` - Image height in pixels
+///
+/// # Returns
+/// * The PNG as base64, or an error for unsupported chart types so the caller
+///   can fall back to the data table
+#[command(async)]
+pub fn render_chart_png(spec: String, width: u32, height: u32) -> Result<String, String> {
+    chart::render_chart_png(&spec, width, height)
+}
+
+/// Renders a PlantUML diagram to SVG for embedding in exports.
+///
+/// # Arguments
+/// * `content` - The diagram source
+/// * `server_url` - PlantUML server to fetch from; when unset a local
+///   plantuml.jar (`PLANTUML_JAR`) or `plantuml` command is used
+///
+/// # Returns
+/// * The SVG markup, or an error when no renderer is available
+#[command(async)]
+pub fn render_plantuml(content: String, server_url: Option<String>) -> Result<String, String> {
+    render_plantuml_svg(&content, server_url.as_deref())
+}
+
+/// Opens a path in the system file manager (Finder on macOS)
+///
+/// # Arguments
+/// * `path` - The path to open (file or directory)
+#[command]
+pub fn open_path(path: String) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("open")
+            .arg(&path)
+            .spawn()
+            .map_err(|e| format!("Failed to open path: {}", e))?;
+    }
+    #[cfg(target_os = "windows")]
+    {
+        std::process::Command::new("explorer")
+            .arg(&path)
+            .spawn()
+            .map_err(|e| format!("Failed to open path: {}", e))?;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        std::process::Command::new("xdg-open")
+            .arg(&path)
+            .spawn()
+            .map_err(|e| format!("Failed to open path: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Opens a file in an external editor
+///
+/// # Arguments
+/// * `command` - The command to run (e.g., "code", "cursor", "neovide")
+/// * `args` - Arguments to pass to the command (usually the file path)
+#[command]
+pub fn open_in_editor(command: String, args: Vec<String>) -> Result<(), String> {
+    std::process::Command::new(&command)
+        .args(&args)
+        .spawn()
+        .map_err(|e| format!("Failed to open in editor '{}': {}", command, e))?;
+    Ok(())
+}
+
+/// Opens a file in an external editor, jumping to the given line.
+///
+/// Knows the line-argument convention of common editors and falls back to
+/// just opening the file for unknown ones.
+///
+/// # Arguments
+/// * `command` - The editor command (e.g., "code", "nvim", "subl")
+/// * `path` - The file to open
+/// * `line` - The 1-based line to jump to
+#[command]
+pub fn open_in_editor_at(command: String, path: String, line: u32) -> Result<(), String> {
+    let args = editor_line_args(&command, &path, line);
+    open_in_editor(command, args)
+}
+
+/// Builds the arguments that open `path` at `line` for the given editor command
+fn editor_line_args(command: &str, path: &str, line: u32) -> Vec<String> {
+    let editor = Path::new(command)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(command)
+        .to_lowercase();
+    let line = line.max(1);
+
+    match editor.as_str() {
+        "code" | "code-insiders" | "codium" | "cursor" | "windsurf" => {
+            vec!["-g".to_string(), format!("{}:{}", path, line)]
+        }
+        "subl" | "sublime_text" | "zed" | "hx" | "helix" => vec![format!("{}:{}", path, line)],
+        "nvim" | "vim" | "vi" | "nano" | "emacs" | "emacsclient" | "micro" | "kak" | "mvim"
+        | "gvim" => vec![format!("+{}", line), path.to_string()],
+        "neovide" => vec!["--".to_string(), format!("+{}", line), path.to_string()],
+        "idea" | "webstorm" | "pycharm" | "clion" | "goland" | "rustrover" => {
+            vec!["--line".to_string(), line.to_string(), path.to_string()]
+        }
+        _ => vec![path.to_string()],
+    }
+}
+
+/// Saves image data (base64) to a file and returns the path.
+///
+/// # Arguments
+/// * `base64_data` - The base64 encoded image data
+/// * `base_path` - The path to the .md file (images will be saved in same directory)
+/// * `filename` - Optional filename, will generate one if not provided
+///
+/// # Returns
+/// * The relative path to the saved image
+#[command]
+pub fn save_pasted_image(
+    base64_data: String,
+    base_path: String,
+    filename: Option<String>,
+) -> Result<String, String> {
+    // Decode base64
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    // Remove data URL prefix if present
+    let data = if base64_data.contains(",") {
+        base64_data.split(",").last().unwrap_or(&base64_data)
+    } else {
+        &base64_data
+    };
+
+    let bytes = STANDARD
+        .decode(data)
+        .map_err(|e| format!("Failed to decode base64: {}", e))?;
+
+    // Determine file path
+    let base = Path::new(&base_path);
+    let base_dir = base.parent().unwrap_or(base);
+
+    // Create images directory if it doesn't exist
+    let images_dir = base_dir.join("images");
+    if !images_dir.exists() {
+        fs::create_dir_all(&images_dir).map_err(|e| format!("Failed to create images dir: {}", e))?;
+    }
+
+    // Generate filename
+    let file_name = filename.unwrap_or_else(|| {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        format!("pasted-image-{}.png", timestamp)
+    });
+
+    let file_path = images_dir.join(&file_name);
+
+    // Write file
+    fs::write(&file_path, bytes).map_err(|e| format!("Failed to write image: {}", e))?;
+
+    // Return relative path
+    Ok(format!("images/{}", file_name))
+}
+
+/// Saves a document atomically, normalizing its line endings
+///
+/// # Arguments
+/// * `path` - The path of the file to write
+/// * `content` - The document content
+/// * `line_ending` - "lf", "crlf" or "preserve" (default: the existing file's ending)
+#[command]
+pub fn save_file(
+    path: String,
+    content: String,
+    line_ending: Option<LineEnding>,
+) -> Result<(), String> {
+    save_document(Path::new(&path), &content, line_ending.unwrap_or_default())?;
+    evict_cached_links(Path::new(&path));
+    Ok(())
+}
+
+/// Rewrites relative image links after a document is moved (e.g. "save as").
+///
+/// # Arguments
+/// * `markdown` - The markdown content
+/// * `old_base` - The previous path of the .md file
+/// * `new_base` - The new path of the .md file
+///
+/// # Returns
+/// * The markdown with relative image paths pointing to the same files from the new location
+#[command]
+pub fn rebase_image_paths(markdown: String, old_base: String, new_base: String) -> String {
+    rebase_markdown_image_paths(&markdown, &old_base, &new_base)
+}
+
+/// Installs the 'mkv' command in PATH (/usr/local/bin)
+/// This allows users to open files/folders from terminal with: mkv <path>
+#[command]
+pub fn install_cli_command() -> Result<String, String> {
+    #[cfg(target_os = "macos")]
+    {
+        let script_content = r#"#!/bin/bash
+if [ -z "$1" ]; then
+  open /Applications/MarkViewer.app
+else
+  # Convert to absolute path
+  if [[ "$1" = /* ]]; then
+    ABS_PATH="$1"
+  else
+    ABS_PATH="$(cd "$(dirname "$1")" 2>/dev/null && pwd)/$(basename "$1")"
+  fi
+  open /Applications/MarkViewer.app --args "$ABS_PATH"
+fi
+"#;
+
+        let script_path = "/usr/local/bin/mkv";
+
+        // Use osascript to run with admin privileges
+        let apple_script = format!(
+            r#"do shell script "echo '{}' > {} && chmod +x {}" with administrator privileges"#,
+            script_content.replace("\"", "\\\"").replace("\n", "\\n"),
+            script_path,
+            script_path
+        );
+
+        let output = std::process::Command::new("osascript")
+            .arg("-e")
+            .arg(&apple_script)
+            .output()
+            .map_err(|e| format!("Failed to run installer: {}", e))?;
 
         if output.status.success() {
             Ok("Comando 'mkv' instalado com sucesso! Agora você pode usar:\n\n  mkv .          - Abrir pasta atual\n  mkv arquivo.md - Abrir arquivo\n  mkv            - Abrir MarkViewer".to_string())
@@ -215,74 +1811,939 @@ fi
         }
     }
 
-    #[cfg(target_os = "linux")]
-    {
-        let script_content = r#"#!/bin/bash
-if [ -z "$1" ]; then
-  markviewer
-else
-  if [[ "$1" = /* ]]; then
-    ABS_PATH="$1"
-  else
-    ABS_PATH="$(cd "$(dirname "$1")" 2>/dev/null && pwd)/$(basename "$1")"
-  fi
-  markviewer "$ABS_PATH"
-fi
-"#;
-        let script_path = "/usr/local/bin/mkv";
+    #[cfg(target_os = "linux")]
+    {
+        let script_content = r#"#!/bin/bash
+if [ -z "$1" ]; then
+  markviewer
+else
+  if [[ "$1" = /* ]]; then
+    ABS_PATH="$1"
+  else
+    ABS_PATH="$(cd "$(dirname "$1")" 2>/dev/null && pwd)/$(basename "$1")"
+  fi
+  markviewer "$ABS_PATH"
+fi
+"#;
+        let script_path = "/usr/local/bin/mkv";
+
+        let output = std::process::Command::new("pkexec")
+            .arg("bash")
+            .arg("-c")
+            .arg(format!("echo '{}' > {} && chmod +x {}", script_content, script_path, script_path))
+            .output()
+            .map_err(|e| format!("Failed to run installer: {}", e))?;
+
+        if output.status.success() {
+            Ok("Comando 'mkv' instalado com sucesso!".to_string())
+        } else {
+            Err(format!("Falha ao instalar: {}", String::from_utf8_lossy(&output.stderr)))
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        Err("Instalação automática não suportada no Windows ainda. Adicione o executável ao PATH manualmente.".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_markdown_basic() {
+        let md = "# Hello\n\nThis is **bold**.";
+        let options = RenderOptions {
+            theme: "light".to_string(),
+            ..Default::default()
+        };
+
+        let result = render_markdown_with(md.to_string(), options).unwrap();
+
+        assert!(result.html.contains("<h1"));
+        assert!(result.html.contains("<strong>bold</strong>"));
+        assert!(result.special_blocks.is_empty());
+    }
+
+    #[test]
+    fn test_render_markdown_with_mermaid() {
+        let md = "# Title\n\n```mermaid\ngraph TD\n    A --> B\n```";
+        let options = RenderOptions {
+            theme: "light".to_string(),
+            ..Default::default()
+        };
+
+        let result = render_markdown_with(md.to_string(), options).unwrap();
+
+        assert_eq!(result.special_blocks.len(), 1);
+        assert_eq!(result.special_blocks[0].block_type, "mermaid");
+        assert!(result.html.contains("special-block"));
+    }
+
+    #[test]
+    fn test_render_markdown_without_special_blocks() {
+        let md = "```mermaid\ngraph TD\n    A --> B\n```";
+        let options = RenderOptions {
+            theme: "light".to_string(),
+            extract_special_blocks: Some(false),
+            ..Default::default()
+        };
+
+        let result = render_markdown_with(md.to_string(), options).unwrap();
+
+        assert!(result.special_blocks.is_empty());
+        assert!(!result.html.contains("special-block"));
+        assert!(result.html.contains("<pre"));
+        assert!(result.html.contains("<span"));
+        assert!(result.html.contains("graph TD"));
+    }
+
+    #[test]
+    fn test_render_markdown_csv_table() {
+        let md = "```csv\nname,qty\n\"Apples, red\",3\n```";
+        let options = RenderOptions {
+            theme: "light".to_string(),
+            special_block_types: Some(vec!["csv".to_string()]),
+            ..Default::default()
+        };
+
+        let result = render_markdown_with(md.to_string(), options).unwrap();
+
+        assert!(result.html.contains("<th>name</th>"));
+        assert!(result.html.contains("<td>Apples, red</td>"));
+    }
+
+    #[test]
+    fn test_tokenize_code() {
+        let tokens = tokenize_code("a\nb".to_string(), "text".to_string());
+
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[1].text, "b");
+        assert_eq!(tokens[1].line, 2);
+    }
+
+    #[test]
+    fn test_editor_line_args() {
+        assert_eq!(editor_line_args("code", "/a.md", 12), vec!["-g", "/a.md:12"]);
+        assert_eq!(editor_line_args("/usr/bin/nvim", "/a.md", 3), vec!["+3", "/a.md"]);
+        assert_eq!(editor_line_args("subl", "/a.md", 7), vec!["/a.md:7"]);
+        assert_eq!(editor_line_args("Code.exe", "/a.md", 1), vec!["-g", "/a.md:1"]);
+        assert_eq!(editor_line_args("unknown-editor", "/a.md", 5), vec!["/a.md"]);
+    }
+
+    #[test]
+    fn test_render_markdown_front_matter_formats() {
+        let docs = [
+            "---\ntitle: Notes\n---\n# Body",
+            "+++\ntitle = \"Notes\"\n+++\n# Body",
+            "{\"title\": \"Notes\"}\n# Body",
+        ];
+
+        for md in docs {
+            let result = render_markdown_with(md.to_string(), RenderOptions::default()).unwrap();
+            assert_eq!(result.front_matter.unwrap()["title"], "Notes");
+            assert!(result.html.contains("<h1"));
+            assert!(!result.html.contains("title"));
+        }
+    }
+
+    #[test]
+    fn test_humanize_date_invalid_passthrough() {
+        assert_eq!(humanize_date("someday".to_string()), "someday");
+        assert!(humanize_date("2000-01-01".to_string()).ends_with("years ago"));
+    }
+
+    #[test]
+    fn test_render_markdown_math_modes() {
+        let md = "$$\nx^2\n$$\n\n$$\n\\frac{1}{\n$$";
+
+        let options = RenderOptions {
+            math_mode: Some("client".to_string()),
+            ..Default::default()
+        };
+        let result = render_markdown_with(md.to_string(), options).unwrap();
+        assert_eq!(result.special_blocks.len(), 2);
+        assert!(!result.html.contains("<math"));
+
+        let options = RenderOptions {
+            math_mode: Some("mathml".to_string()),
+            ..Default::default()
+        };
+        let result = render_markdown_with(md.to_string(), options).unwrap();
+        assert!(result.html.contains("<div class=\"math-block\"><math"));
+        assert_eq!(result.special_blocks.len(), 1);
+        assert_eq!(result.special_blocks[0].placeholder_id, "special-block-1");
+        assert_eq!(result.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_supported_syntax_sample_renders() {
+        let options = RenderOptions {
+            theme: "light".to_string(),
+            special_block_types: Some(vec![
+                "mermaid".to_string(),
+                "chart".to_string(),
+                "plantuml".to_string(),
+                "abc".to_string(),
+                "csv".to_string(),
+            ]),
+            math_mode: Some("client".to_string()),
+            ..Default::default()
+        };
+
+        let result = render_markdown_with(supported_syntax_sample(), options).unwrap();
+
+        assert_eq!(result.front_matter.unwrap()["title"], "MarkViewer Syntax Guide");
+        assert!(result.warnings.is_empty());
+        for expected in [
+            "<strong>Bold</strong>",
+            "<del>strikethrough</del>",
+            "<sup>2</sup>",
+            "🚀",
+            "<table data-block-id=",
+            "type=\"checkbox\"",
+            "<dl data-block-id=",
+            "footnote",
+            "<blockquote data-block-id=",
+            "class=\"delimited-table\"",
+            "<details>",
+        ] {
+            assert!(result.html.contains(expected), "missing {}", expected);
+        }
+
+        let types: Vec<&str> = result
+            .special_blocks
+            .iter()
+            .map(|b| b.block_type.as_str())
+            .collect();
+        assert_eq!(types, vec!["mermaid", "chart", "plantuml", "abc", "math"]);
+    }
+
+    #[test]
+    fn test_render_markdown_sanitize() {
+        let md = "# Title\n\n<img src=x onerror=\"alert(1)\">\n\n<script>alert(1)</script>\n\n```mermaid\ngraph TD\n```";
+        let options = RenderOptions {
+            sanitize: Some(true),
+            ..Default::default()
+        };
+
+        let result = render_markdown_with(md.to_string(), options).unwrap();
+
+        assert!(!result.html.contains("<script"));
+        assert!(!result.html.contains("onerror"));
+        assert!(result.html.contains("data-block-type=\"mermaid\""));
+        assert!(result.html.contains("id=\"special-block-0\""));
+        assert!(result.html.contains("id=\"heading-title\""));
+    }
+
+    #[test]
+    fn test_render_matching_sections() {
+        let md = "---\ntitle: Ref\n---\n# Setup\n```mermaid\ngraph TD\n```\n\n# Charts\nA chart:\n\n```chart\n{}\n```\n\n# Other\nNothing here";
+        let options = RenderOptions {
+            theme: "light".to_string(),
+            ..Default::default()
+        };
+
+        let result =
+            render_matching_sections(md.to_string(), "CHART".to_string(), options).unwrap();
+
+        assert!(result.html.contains("Charts"));
+        assert!(!result.html.contains("Setup"));
+        assert!(!result.html.contains("Nothing here"));
+        assert_eq!(result.special_blocks.len(), 1);
+        assert_eq!(result.special_blocks[0].block_type, "chart");
+        assert_eq!(result.special_blocks[0].placeholder_id, "special-block-0");
+        assert!(result.front_matter.is_some());
+    }
+
+    #[test]
+    fn test_render_matching_sections_front_matter_options() {
+        let md = "---\nmarkviewer:\n  theme: dark\n  number_headings: true\n  sanitize: false\n---\n# Setup\n\n# Charts\n\n## Bar chart\n";
+        let options = RenderOptions {
+            theme: "light".to_string(),
+            number_headings: Some(false),
+            ..Default::default()
+        };
+
+        let result =
+            render_matching_sections(md.to_string(), "chart".to_string(), options).unwrap();
+
+        assert_eq!(result.theme, "dark");
+        assert!(result.html.contains("<span class=\"heading-number\">1.1</span> Bar chart"));
+        assert!(!result.html.contains("Setup"));
+        assert_eq!(
+            result.warnings,
+            vec!["Ignoring unknown front matter option 'sanitize'"]
+        );
+    }
+
+    #[test]
+    fn test_render_markdown_emoji_policy() {
+        let options = RenderOptions {
+            theme: "light".to_string(),
+            emoji_policy: Some(EmojiPolicy::Shortcode),
+            ..Default::default()
+        };
+
+        let result = render_markdown_with("## 🚀 Launch".to_string(), options).unwrap();
+
+        assert!(result.html.contains("id=\"heading-rocket-launch\""));
+    }
+
+    #[test]
+    fn test_render_special_block_fallback() {
+        let block = SpecialBlock {
+            block_type: "mermaid".to_string(),
+            content: "graph TD\nA-->B".to_string(),
+            placeholder_id: "special-block-0".to_string(),
+        };
 
-        let output = std::process::Command::new("pkexec")
-            .arg("bash")
-            .arg("-c")
-            .arg(format!("echo '{}' > {} && chmod +x {}", script_content, script_path, script_path))
-            .output()
-            .map_err(|e| format!("Failed to run installer: {}", e))?;
+        let html = render_special_block_fallback(block);
 
-        if output.status.success() {
-            Ok("Comando 'mkv' instalado com sucesso!".to_string())
-        } else {
-            Err(format!("Falha ao instalar: {}", String::from_utf8_lossy(&output.stderr)))
+        assert!(html.starts_with("<pre><code class=\"language-mermaid\">"));
+        assert!(html.contains("A--&gt;B"));
+    }
+
+    #[test]
+    fn test_render_markdown_front_matter_modes() {
+        let md = "---\ntitle: Spec\n---\n# Body\n";
+        let render = |mode| {
+            let options = RenderOptions {
+                theme: "light".to_string(),
+                front_matter_mode: mode,
+                ..Default::default()
+            };
+            render_markdown_with(md.to_string(), options).unwrap().html
+        };
+
+        let stripped = render(None);
+        assert!(!stripped.contains("Spec"));
+
+        let table = render(Some(FrontMatterMode::Render));
+        assert!(table.starts_with("<table class=\"front-matter\">"));
+        assert!(table.contains("<tr><th>title</th><td>Spec</td></tr>"));
+
+        let collapsed = render(Some(FrontMatterMode::Collapse));
+        assert!(collapsed.starts_with("<details class=\"front-matter\">"));
+        assert!(collapsed.contains("<summary>Front matter</summary>"));
+        assert!(collapsed.contains("title: Spec"));
+    }
+
+    #[test]
+    fn test_front_matter_does_not_leak_across_documents() {
+        let first = "---\ntitle: First\n---\nFirst body\n";
+        let second = "---\ntitle: Second\n---\nSecond body\n";
+        let options = RenderOptions {
+            theme: "light".to_string(),
+            front_matter_mode: Some(FrontMatterMode::Collapse),
+            ..Default::default()
+        };
+
+        let result = render_markdown_with(format!("{}\n{}", first, second), options).unwrap();
+
+        let details_end = result.html.find("</details>").unwrap();
+        assert_eq!(result.html.matches("<details").count(), 1);
+        assert!(result.html[..details_end].contains("title: First"));
+        assert!(!result.html[..details_end].contains("Second"));
+        assert_eq!(result.front_matter.unwrap()["title"], "First");
+    }
+
+    #[test]
+    fn test_lint_code_blocks() {
+        let md = "# Code\n\n```go\nfunc main() {\n\tfmt.Println() \n}\n```\n";
+
+        let lints = lint_code_blocks(md.to_string());
+
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].block_line, 3);
+        assert_eq!(lints[0].issue, crate::markdown::code_lint::CodeLintIssue::TrailingWhitespace);
+        assert_eq!(lints[0].lines, vec![5]);
+    }
+
+    #[test]
+    fn test_render_markdown_marks_local_links() {
+        let md = "[Next](next.md) [Spec](spec.pdf) [Site](https://example.com)";
+        let options = RenderOptions {
+            theme: "light".to_string(),
+            base_path: Some("/docs/index.md".to_string()),
+            ..Default::default()
+        };
+
+        let result = render_markdown_with(md.to_string(), options.clone()).unwrap();
+
+        assert!(result.html.contains(r#"href="next.md" data-internal-link="/docs/next.md""#));
+        assert!(result.html.contains(r#"href="spec.pdf" data-open-external="/docs/spec.pdf""#));
+        assert!(result.html.contains(r#"<a href="https://example.com">"#));
+
+        let unmarked = RenderOptions {
+            mark_local_links: Some(false),
+            ..options
+        };
+        let result = render_markdown_with(md.to_string(), unmarked).unwrap();
+        assert!(!result.html.contains("data-internal-link"));
+    }
+
+    #[test]
+    fn test_render_markdown_block_ids_survive_edits() {
+        let options = RenderOptions {
+            theme: "light".to_string(),
+            ..Default::default()
+        };
+        let before = "# Notes\n\nAlpha.\n\n```mermaid\ngraph TD\n```\n\nOmega.";
+        let after = "# Notes\n\nAlpha, revised.\n\n```mermaid\ngraph TD\n```\n\nOmega.";
+
+        let before = render_markdown_with(before.to_string(), options.clone()).unwrap();
+        let after = render_markdown_with(after.to_string(), options).unwrap();
+
+        // Heading and two paragraphs; the special block placeholder is raw HTML
+        assert_eq!(before.block_ids.len(), 3);
+        assert_eq!(before.block_ids[0], after.block_ids[0]);
+        assert_ne!(before.block_ids[1], after.block_ids[1]);
+        assert_eq!(before.block_ids[2], after.block_ids[2]);
+        for id in &after.block_ids {
+            assert!(after.html.contains(&format!("data-block-id=\"{}\"", id)));
         }
     }
 
-    #[cfg(target_os = "windows")]
-    {
-        Err("Instalação automática não suportada no Windows ainda. Adicione o executável ao PATH manualmente.".to_string())
+    #[test]
+    fn test_validate_anchors() {
+        let md = "# Install\n\nSee [install](#heading-install) and [usage](#heading-usage).";
+
+        let issues = validate_anchors(md.to_string(), None, None);
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].anchor, "heading-usage");
+        assert_eq!(issues[0].line, 3);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_render_with_highlight() {
+        let md = "# Deploy\n\nRun `deploy` to deploy.";
+        let options = RenderOptions {
+            theme: "light".to_string(),
+            ..Default::default()
+        };
+        let terms = vec!["DEPLOY".to_string()];
+
+        let result = render_with_highlight(md.to_string(), options, terms, None).unwrap();
+
+        assert_eq!(result.html.matches("<mark class=\"search-hit\">").count(), 2);
+        assert!(result.html.contains("id=\"heading-deploy\""));
+        assert!(result.html.contains("<code>deploy</code>"));
+    }
 
     #[test]
-    fn test_render_markdown_basic() {
-        let md = "# Hello\n\nThis is **bold**.";
+    fn test_normalize_headings() {
+        let md = "# Guide\n\n### Install\n\n```sh\n# comment\n```\n\n### Use\n";
+
+        let normalized = normalize_headings(md.to_string(), 1);
+
+        assert_eq!(
+            normalized,
+            "# Guide\n\n## Install\n\n```sh\n# comment\n```\n\n## Use\n"
+        );
+    }
+
+    #[test]
+    fn test_render_block_anchors() {
+        let md = "- Milk\n- Eggs\n\n| a |\n|---|\n| 1 |";
         let options = RenderOptions {
             theme: "light".to_string(),
-            base_path: None,
+            block_anchors: Some(true),
+            ..Default::default()
         };
 
-        let result = render_markdown(md.to_string(), options).unwrap();
+        let first = render_markdown_with(md.to_string(), options.clone()).unwrap();
+        let second = render_markdown_with(md.to_string(), options).unwrap();
 
-        assert!(result.html.contains("<h1"));
-        assert!(result.html.contains("<strong>bold</strong>"));
-        assert!(result.special_blocks.is_empty());
+        assert_eq!(first.html.matches("<li id=\"block-").count(), 2);
+        assert!(first.html.contains("<table id=\"block-"));
+        assert!(first.html.contains("data-block-id="));
+        assert_eq!(first.html, second.html);
     }
 
     #[test]
-    fn test_render_markdown_with_mermaid() {
-        let md = "# Title\n\n```mermaid\ngraph TD\n    A --> B\n```";
+    fn test_detect_document_language() {
+        let md = "---\nlang: de\n---\n```\ncode\n```";
+        assert_eq!(detect_document_language(md.to_string()), Some("de".to_string()));
+        assert_eq!(detect_document_language("```\nonly code here\n```".to_string()), None);
+    }
+
+    #[test]
+    fn test_render_collapse_code_lines() {
+        let log: String = (1..=30).map(|i| format!("line {}\n", i)).collect();
+        let md = format!("```\n{}```\n\n```\nshort\n```", log);
         let options = RenderOptions {
             theme: "light".to_string(),
-            base_path: None,
+            collapse_code_lines: Some(20),
+            ..Default::default()
         };
 
-        let result = render_markdown(md.to_string(), options).unwrap();
+        let result = render_markdown_with(md, options).unwrap();
+
+        assert_eq!(result.html.matches("collapsible").count(), 1);
+        assert!(result.html.contains("data-lines=\"30\""));
+        assert!(result.html.contains("data-lines=\"1\""));
+    }
+
+    #[test]
+    fn test_update_front_matter() {
+        let md = "---\ntitle: Draft\ncustom: keep me\n---\nBody text\n";
+
+        let updated =
+            update_front_matter(md.to_string(), serde_json::json!({"title": "Final"})).unwrap();
+
+        assert_eq!(updated, "---\ntitle: Final\ncustom: keep me\n---\nBody text\n");
+        assert!(update_front_matter(md.to_string(), serde_json::json!([1])).is_err());
+    }
+
+    #[test]
+    fn test_anchor_map() {
+        let anchors = anchor_map("# Intro\n\n## Intro\n\n## Setup".to_string(), None, None);
+        assert_eq!(anchors.len(), 2);
+        assert_eq!(anchors["Intro"], "heading-intro");
+        assert_eq!(anchors["Setup"], "heading-setup");
+    }
+
+    #[test]
+    fn test_render_with_timing() {
+        let md = "# Title\n\n```rust\nfn main() {}\n```\n\n```mermaid\ngraph TD\n```\n\n```js\nlet x;\n```";
+        let (result, timings) =
+            render_with_timing(md.to_string(), RenderOptions::default()).unwrap();
 
         assert_eq!(result.special_blocks.len(), 1);
-        assert_eq!(result.special_blocks[0].block_type, "mermaid");
-        assert!(result.html.contains("special-block"));
+        assert_eq!(timings.code_blocks, 2);
+        let stages = timings.special_blocks_ms
+            + timings.parse_ms
+            + timings.html_ms
+            + timings.highlight_ms
+            + timings.images_ms
+            + timings.sanitize_ms;
+        assert!(timings.total_ms >= stages * 0.99);
+    }
+
+    #[test]
+    fn test_render_with_remote_base_url() {
+        let md = "![logo](img/logo.png) [guide](../GUIDE.md) ![abs](https://cdn.dev/a.png)";
+        let options = RenderOptions {
+            base_path: Some("/home/user/repo/docs/README.md".to_string()),
+            remote_base_url: Some("https://raw.githubusercontent.com/u/r/main/docs/".to_string()),
+            ..Default::default()
+        };
+
+        let html = render_markdown_with(md.to_string(), options).unwrap().html;
+        assert!(html.contains("src=\"https://raw.githubusercontent.com/u/r/main/docs/img/logo.png\""));
+        assert!(html.contains("href=\"https://raw.githubusercontent.com/u/r/main/GUIDE.md\""));
+        assert!(html.contains("src=\"https://cdn.dev/a.png\""));
+        assert!(!html.contains("__LOCAL_FILE__") && !html.contains("data-internal-link"));
+    }
+
+    #[test]
+    fn test_extract_tasks() {
+        let tasks = extract_tasks("- [ ] Ship 📅 2024-06-01 !high\n- [x] Done".to_string());
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].text, "Ship");
+        assert_eq!(tasks[0].due.as_deref(), Some("2024-06-01"));
+        assert!(tasks[1].done && tasks[1].due.is_none() && tasks[1].priority.is_none());
+    }
+
+    #[test]
+    fn test_render_with_heading_shift() {
+        let md = "# Guide\n\n## Install\n\n```md\n# literal\n```";
+        let options = RenderOptions {
+            heading_shift: Some(1),
+            ..Default::default()
+        };
+
+        let html = render_markdown_with(md.to_string(), options).unwrap().html;
+        assert!(html.contains("<h2") && html.contains("<h3"));
+        assert!(!html.contains("<h1"));
+        assert!(html.contains("id=\"heading-guide\""));
+        // The fenced line keeps its single `#`
+        assert!(html.contains(">#</span> "));
+        assert!(!html.contains("##"));
+    }
+
+    #[test]
+    fn test_fold_regions() {
+        let regions = fold_regions("# A\n\n```\nx\n```\n".to_string());
+        assert_eq!(regions.len(), 2);
+        assert_eq!((regions[0].start, regions[0].end), (1, 5));
+        assert_eq!((regions[1].start, regions[1].end), (3, 5));
+    }
+
+    #[test]
+    fn test_render_slides() {
+        let md = "---\ntitle: Talk\n---\n# Intro\n\n```mermaid\ngraph TD\n```\n\n---\n\n\
+                  # Middle\n\n```\n---\n```\n\n---\n\n# End\n\n```chart\n{}\n```\n";
+        let slides = render_slides(md.to_string(), RenderOptions::default()).unwrap();
+
+        assert_eq!(slides.len(), 3);
+        assert!(slides[0].html.contains("Intro"));
+        assert_eq!(slides[0].front_matter.as_ref().unwrap()["title"], "Talk");
+        assert!(slides[1].html.contains("Middle") && slides[1].html.contains("---"));
+        assert!(slides[2].html.contains("End"));
+        assert_eq!(slides[0].special_blocks[0].placeholder_id, "special-block-0");
+        assert_eq!(slides[2].special_blocks[0].placeholder_id, "special-block-1");
+        assert!(slides[2].html.contains("id=\"special-block-1\""));
+    }
+
+    #[test]
+    fn test_render_color_swatches() {
+        let md = "# Palette\n\nRed is #ff0000, see #heading.\n";
+        let options = RenderOptions {
+            color_swatches: Some(true),
+            ..Default::default()
+        };
+        let result = render_markdown_with(md.to_string(), options).unwrap();
+        assert!(result
+            .html
+            .contains("#ff0000<span class=\"color-swatch\" style=\"background:#ff0000\"></span>"));
+        assert_eq!(result.html.matches("color-swatch").count(), 1);
+
+        let plain = render_markdown_with(md.to_string(), RenderOptions::default()).unwrap();
+        assert!(!plain.html.contains("color-swatch"));
+    }
+
+    #[test]
+    fn test_render_github_refs() {
+        let md = "Fixes #12, see `#13`.";
+        let plain = render_markdown_with(md.to_string(), RenderOptions::default()).unwrap();
+        assert!(!plain.html.contains("github.com"));
+
+        let options = RenderOptions {
+            github_repo: Some("acme/app".to_string()),
+            ..Default::default()
+        };
+        let linked = render_markdown_with(md.to_string(), options).unwrap();
+        assert!(linked
+            .html
+            .contains("<a href=\"https://github.com/acme/app/issues/12\" class=\"github-issue\">#12</a>"));
+        assert!(!linked.html.contains("issues/13"));
+    }
+
+    #[test]
+    fn test_render_shortened_urls() {
+        let url = "https://example.com/docs/guides/getting-started/installation";
+        let md = format!("See {} or [the guide]({}).", url, url);
+        let options = RenderOptions {
+            shorten_urls: Some(true),
+            ..Default::default()
+        };
+        let result = render_markdown_with(md.clone(), options).unwrap();
+        assert!(result.html.contains(&format!(
+            "<a href=\"{}\" title=\"{}\">example.com/docs/guides/getting-started/…</a>",
+            url, url
+        )));
+        assert!(result.html.contains(&format!("<a href=\"{}\">the guide</a>", url)));
+
+        let plain = render_markdown_with(md, RenderOptions::default()).unwrap();
+        assert!(plain.html.contains(&format!("<a href=\"{}\">{}</a>", url, url)));
+    }
+
+    #[test]
+    fn test_front_matter_render_options() {
+        let md = "---\nmarkviewer:\n  theme: dark\n  number_headings: true\n  sanitize: false\n---\n# Intro\n\n## Setup\n";
+        let options = RenderOptions {
+            theme: "light".to_string(),
+            number_headings: Some(false),
+            ..Default::default()
+        };
+        let result = render_markdown_with(md.to_string(), options.clone()).unwrap();
+        assert!(result.html.contains("<span class=\"heading-number\">1.1</span> Setup"));
+        assert_eq!(result.theme, "dark");
+        assert_eq!(
+            result.warnings,
+            vec!["Ignoring unknown front matter option 'sanitize'"]
+        );
+
+        // Without precedence, front matter only fills options left unset
+        let caller_wins = RenderOptions {
+            front_matter_overrides: Some(false),
+            ..options
+        };
+        let result = render_markdown_with(md.to_string(), caller_wins).unwrap();
+        assert!(!result.html.contains("heading-number"));
+        assert_eq!(result.theme, "light");
+    }
+
+    #[test]
+    fn test_source_render_map() {
+        let md = "---\ntitle: Map\n---\nIntro\n\n```mermaid\ngraph TD\n```\n\n## Setup\n\nText\n";
+        let result = render_markdown_with(md.to_string(), RenderOptions::default()).unwrap();
+        let entries = source_render_map(md.to_string(), RenderOptions::default()).unwrap();
+
+        let heading = entries.iter().find(|e| e.start_line == 10).unwrap();
+        assert_eq!(heading.end_line, 10);
+        assert!(result
+            .html
+            .contains(&format!("<h2 data-block-id=\"{}\">", heading.block_id)));
+
+        // The special block placeholder is raw HTML, so it has no block id
+        let lines: Vec<(usize, usize)> =
+            entries.iter().map(|e| (e.start_line, e.end_line)).collect();
+        assert_eq!(lines, vec![(4, 4), (10, 10), (12, 12)]);
+    }
+
+    #[test]
+    fn test_render_patch_one_edited_paragraph() {
+        let previous = "# Title\n\nFirst paragraph.\n\nSecond paragraph.\n\n- a\n- b\n";
+        let current = previous.replace("Second paragraph.", "Second paragraph, edited.");
+        let patch = render_patch(previous.to_string(), current, RenderOptions::default()).unwrap();
+
+        assert_eq!(patch.blocks.changed.len(), 1);
+        assert!(patch.blocks.changed[0].html.starts_with("<p data-block-id="));
+        assert!(patch.blocks.changed[0].html.ends_with("Second paragraph, edited.</p>"));
+        assert!(patch.blocks.added.is_empty());
+        assert!(patch.blocks.removed.is_empty());
+        assert!(patch.blocks.html.is_none());
+        assert_eq!(patch.blocks.block_ids.len(), 4);
+    }
+
+    #[test]
+    fn test_render_document_style() {
+        let md = "---\nstyle:\n  accent: \"#f50\"\n  font: serif\n---\n# Title\n";
+        let options = RenderOptions {
+            allow_document_style: Some(true),
+            sanitize: Some(true),
+            ..Default::default()
+        };
+        let result = render_markdown_with(md.to_string(), options).unwrap();
+        assert!(result.html.starts_with(
+            "<style class=\"document-style\">.prose, .markdown-body { --accent: #f50; \
+             --font: serif; }</style>\n"
+        ));
+
+        let html = render_markdown_with(md.to_string(), RenderOptions::default())
+            .unwrap()
+            .html;
+        assert!(!html.contains("<style"));
+    }
+
+    #[test]
+    fn test_render_heading_task_counts() {
+        let md = "# Plan\n\n## Backend\n\n- [x] API\n- [ ] Tests\n- [ ] Docs\n\n## Notes\n\nText\n";
+        let options = RenderOptions {
+            heading_task_counts: Some(true),
+            ..Default::default()
+        };
+        let html = render_markdown_with(md.to_string(), options).unwrap().html;
+        assert!(html.contains("Backend <span class=\"task-count\">(1/3)</span></h2>"));
+        assert!(html.contains("Notes</h2>"));
+        assert_eq!(html.matches("task-count").count(), 1);
+
+        let html = render_markdown_with(md.to_string(), RenderOptions::default())
+            .unwrap()
+            .html;
+        assert!(!html.contains("task-count"));
+    }
+
+    #[test]
+    fn test_render_anchor_symbol() {
+        let options = RenderOptions {
+            number_headings: Some(true),
+            anchor_placement: Some(AnchorPlacement::After),
+            ..Default::default()
+        };
+        let html = render_markdown_with("## Setup".to_string(), options).unwrap().html;
+        assert!(html.contains(
+            "<span class=\"heading-number\">1</span> Setup <a href=\"#heading-setup\" \
+             class=\"anchor\" id=\"heading-setup\" aria-label=\"Link to section: 1 Setup\">#</a>"
+        ));
+
+        let html = render_markdown_with("## Setup".to_string(), RenderOptions::default())
+            .unwrap()
+            .html;
+        assert!(html.contains("aria-hidden=\"true\" class=\"anchor\""));
+    }
+
+    #[test]
+    fn test_render_special_block_labels() {
+        let md = "```mermaid\ngraph TD\n```\n";
+        let options = RenderOptions {
+            special_block_labels: Some(HashMap::from([(
+                "mermaid".to_string(),
+                "Diagramme Mermaid".to_string(),
+            )])),
+            ..Default::default()
+        };
+        let html = render_markdown_with(md.to_string(), options).unwrap().html;
+        assert!(html.contains("role=\"img\" aria-label=\"Diagramme Mermaid\""));
+
+        let html = render_markdown_with(md.to_string(), RenderOptions::default())
+            .unwrap()
+            .html;
+        assert!(html.contains("aria-label=\"Mermaid diagram\""));
+    }
+
+    #[test]
+    fn test_render_toc() {
+        let md = "---\ntitle: Notes\n---\n# Notes\n\n```md\n# Skipped\n```\n\n## Setup\n\n## Setup\n";
+        let result = render_markdown_with(md.to_string(), RenderOptions::default()).unwrap();
+        let ids: Vec<&str> = result.toc.iter().map(|e| e.anchor_id.as_str()).collect();
+        assert_eq!(ids, vec!["heading-notes", "heading-setup", "heading-setup-1"]);
+        assert_eq!(result.toc[1].level, 2);
+        for id in ids {
+            assert!(result.html.contains(&format!("id=\"{}\"", id)));
+        }
+
+        let options = RenderOptions {
+            github_compatible_ids: Some(true),
+            ..Default::default()
+        };
+        let result = render_markdown_with(md.to_string(), options).unwrap();
+        assert_eq!(result.toc[2].anchor_id, "setup-1");
+    }
+
+    #[test]
+    fn test_render_github_compatible_ids() {
+        let md = "---\nmarkviewer:\n  github_compatible_ids: true\n---\n# Getting Started\n\n## Notes\n\n## Notes\n";
+        let html = render_markdown_with(md.to_string(), RenderOptions::default())
+            .unwrap()
+            .html;
+        assert!(html.contains("id=\"getting-started\""));
+        assert!(html.contains("id=\"notes\""));
+        assert!(html.contains("id=\"notes-1\""));
+        assert!(!html.contains("id=\"heading-"));
+    }
+
+    #[test]
+    fn test_render_escape_unknown_tags() {
+        let md = "Replace <foo> with <b>bold</b> text.";
+        let html = render_markdown_with(md.to_string(), RenderOptions::default())
+            .unwrap()
+            .html;
+        assert!(html.contains("Replace &lt;foo&gt; with <b>bold</b> text."));
+
+        let options = RenderOptions {
+            escape_unknown_tags: Some(false),
+            ..Default::default()
+        };
+        let html = render_markdown_with(md.to_string(), options).unwrap().html;
+        assert!(html.contains("Replace <foo> with"));
+    }
+
+    #[test]
+    fn test_render_kbd_shortcuts_default_on() {
+        let md = "Save with [[kbd:Cmd+S]].";
+        let html = render_markdown_with(md.to_string(), RenderOptions::default()).unwrap().html;
+        assert!(html.contains("<kbd>Cmd</kbd>+<kbd>S</kbd>"));
+
+        let options = RenderOptions {
+            kbd_shortcuts: Some(false),
+            ..Default::default()
+        };
+        let plain = render_markdown_with(md.to_string(), options).unwrap();
+        assert!(!plain.html.contains("<kbd>"));
+    }
+
+    #[test]
+    fn test_render_inline_footnotes_opt_in() {
+        let md = "Text^[An aside.] here.";
+        let plain = render_markdown_with(md.to_string(), RenderOptions::default()).unwrap();
+        assert!(plain.html.contains("^[An aside.]"));
+
+        let options = RenderOptions {
+            inline_footnotes: Some(true),
+            ..Default::default()
+        };
+        let html = render_markdown_with(md.to_string(), options).unwrap().html;
+        assert!(html.contains("href=\"#fn-inline-1\""));
+        assert!(!html.contains("^[An aside.]"));
+    }
+
+    #[test]
+    fn test_render_abbreviations_opt_in() {
+        let md = "The HTML spec.\n\n*[HTML]: HyperText Markup Language";
+        let plain = render_markdown_with(md.to_string(), RenderOptions::default()).unwrap();
+        assert!(!plain.html.contains("<abbr"));
+
+        let options = RenderOptions {
+            abbreviations: Some(true),
+            ..Default::default()
+        };
+        let html = render_markdown_with(md.to_string(), options).unwrap().html;
+        assert!(html.contains("<abbr title=\"HyperText Markup Language\">HTML</abbr>"));
+    }
+
+    #[test]
+    fn test_render_image_embeds_from_attachment_folders() {
+        let dir = std::env::temp_dir().join("markviewer-render-embeds");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("attachments")).unwrap();
+        fs::write(dir.join("attachments/Pasted image.png"), b"a").unwrap();
+        let dir = dir.canonicalize().unwrap();
+
+        let md = "![[Pasted image.png|Diagram]]";
+        let base_path = dir.join("doc.md").display().to_string();
+        let plain = RenderOptions {
+            base_path: Some(base_path.clone()),
+            ..Default::default()
+        };
+        let plain = render_markdown_with(md.to_string(), plain).unwrap();
+        assert!(plain.html.contains("![[Pasted image.png|Diagram]]"));
+
+        let options = RenderOptions {
+            base_path: Some(base_path),
+            attachment_folders: Some(vec!["attachments".to_string()]),
+            ..Default::default()
+        };
+        let html = render_markdown_with(md.to_string(), options).unwrap().html;
+        let image = dir.join("attachments/Pasted image.png");
+        assert!(html.contains(&format!("src=\"__LOCAL_FILE__:{}\"", image.display())));
+        assert!(html.contains("alt=\"Diagram\""));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_render_code_includes_opt_in() {
+        let dir = std::env::temp_dir().join("markviewer-render-includes");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("main.rs"), "fn main() {}\n").unwrap();
+
+        let md = "<!-- include: main.rs -->";
+        let base_path = dir.join("doc.md").display().to_string();
+        let plain = RenderOptions {
+            base_path: Some(base_path.clone()),
+            ..Default::default()
+        };
+        let plain = render_markdown_with(md.to_string(), plain).unwrap();
+        assert!(!plain.html.contains("fn"));
+
+        let options = RenderOptions {
+            base_path: Some(base_path),
+            code_includes: Some(true),
+            ..Default::default()
+        };
+        let html = render_markdown_with(md.to_string(), options).unwrap().html;
+        assert!(html.contains("main"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_render_criticmarkup() {
+        let md = "Use {~~tabs~>spaces~~}, {++always++} {--never--}. \
+                  {==Check==}{>>Really?<<} `{++code++}`";
+        let options = RenderOptions {
+            criticmarkup: Some(true),
+            ..Default::default()
+        };
+        let html = render_markdown_with(md.to_string(), options).unwrap().html;
+        assert!(html.contains(
+            "<del class=\"critic\">tabs</del><ins class=\"critic\">spaces</ins>"
+        ));
+        assert!(html.contains("<ins class=\"critic\">always</ins>"));
+        assert!(html.contains("<del class=\"critic\">never</del>"));
+        assert!(html.contains("<mark class=\"critic\">Check</mark>"));
+        assert!(html.contains("<span class=\"critic critic-comment\">Really?</span>"));
+        assert!(html.contains("<code>{++code++}</code>"));
+
+        let plain = render_markdown_with(md.to_string(), RenderOptions::default()).unwrap();
+        assert!(!plain.html.contains("<ins"));
     }
 
     #[test]
@@ -292,4 +2753,27 @@ mod tests {
 
         assert!(result.contains("span"));
     }
+
+    #[test]
+    fn test_highlight_code_blocks_matches_single_calls() {
+        let blocks = [
+            ("fn main() {}", "rust"),
+            ("print('hi')", "python"),
+            ("plain text", "unknown-lang"),
+            ("let x = 1;", "js"),
+        ];
+        let inputs = blocks
+            .iter()
+            .map(|(code, lang)| CodeInput {
+                code: code.to_string(),
+                lang: lang.to_string(),
+            })
+            .collect();
+
+        let expected: Vec<String> = blocks
+            .iter()
+            .map(|(code, lang)| highlight_code_block(code.to_string(), lang.to_string()))
+            .collect();
+        assert_eq!(highlight_code_blocks(inputs), expected);
+    }
 }
@@ -0,0 +1,226 @@
+use chrono::NaiveDate;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::vault::is_markdown_file;
+
+/// Daily note name when none is configured: `2024-06-01.md`
+pub const DEFAULT_DAILY_NOTE_FORMAT: &str = "YYYY-MM-DD";
+
+/// Date tokens in a daily note format and their strftime equivalents,
+/// longest first so `YYYY` wins over `YY`
+const DATE_TOKENS: &[(&str, &str)] = &[
+    ("YYYY", "%Y"),
+    ("GGGG", "%G"),
+    ("MMMM", "%B"),
+    ("dddd", "%A"),
+    ("MMM", "%b"),
+    ("ddd", "%a"),
+    ("YY", "%y"),
+    ("MM", "%m"),
+    ("DD", "%d"),
+    ("WW", "%V"),
+];
+
+/// Formats `date` with a daily note format.
+///
+/// Tokens are `YYYY`/`YY` (year), `MM`/`MMM`/`MMMM` (month as `06`, `Jun`,
+/// `June`), `DD` (day), `ddd`/`dddd` (weekday as `Sat`, `Saturday`), `WW`
+/// (ISO week) and `GGGG` (the ISO week's year). Everything else is kept,
+/// so `YYYY/MM/YYYY-MM-DD` files notes in month folders.
+pub fn format_note_date(date: NaiveDate, format: &str) -> String {
+    let mut pattern = String::with_capacity(format.len() * 2);
+    let mut rest = format;
+    while let Some(c) = rest.chars().next() {
+        if let Some((token, spec)) = DATE_TOKENS
+            .iter()
+            .find(|(token, _)| rest.starts_with(token))
+        {
+            pattern.push_str(spec);
+            rest = &rest[token.len()..];
+        } else {
+            if c == '%' {
+                pattern.push('%');
+            }
+            pattern.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    date.format(&pattern).to_string()
+}
+
+/// Creates `target` (and its folders) from a template, replacing `{{title}}`
+/// with `title` and `{{date}}` with `date` as `YYYY-MM-DD`.
+///
+/// Without a template the note starts with a `# title` heading. An existing
+/// file is never overwritten.
+pub fn create_from_template(
+    target: &Path,
+    template: Option<&Path>,
+    title: &str,
+    date: NaiveDate,
+) -> Result<(), String> {
+    if target.exists() {
+        return Ok(());
+    }
+
+    let content = match template {
+        Some(template) => fs::read_to_string(template)
+            .map_err(|e| format!("Failed to read template {}: {}", template.display(), e))?
+            .replace("{{title}}", title)
+            .replace("{{date}}", &date.format("%Y-%m-%d").to_string()),
+        None => format!("# {}\n", title),
+    };
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create folder: {}", e))?;
+    }
+    fs::write(target, content).map_err(|e| format!("Failed to create note: {}", e))
+}
+
+/// Daily notes settings
+#[derive(Debug, Clone, Default)]
+pub struct DailyNotes<'a> {
+    /// Folder under the vault root (empty: the root itself)
+    pub folder: &'a str,
+    /// Name format, see [`format_note_date`]; `.md` is added when missing
+    pub format: &'a str,
+    /// Template for new notes, relative to the vault root
+    pub template: Option<&'a str>,
+}
+
+/// True for a relative path without `..` components, which can't point
+/// outside the vault. Both `/` and `\` count as separators.
+pub fn is_vault_relative(path: &str) -> bool {
+    let path = path.trim();
+    let parts: Vec<&str> = path.split(['/', '\\']).collect();
+    !Path::new(path).is_absolute()
+        && !path.starts_with(['/', '\\'])
+        && !parts[0].ends_with(':')
+        && !parts.contains(&"..")
+}
+
+/// Returns the daily note for `date` under `root`, creating it from the
+/// template if it doesn't exist yet. Folder, name and template must stay
+/// inside `root`.
+pub fn resolve_daily_note(
+    root: &Path,
+    settings: &DailyNotes,
+    date: NaiveDate,
+) -> Result<PathBuf, String> {
+    let format = match settings.format.trim() {
+        "" => DEFAULT_DAILY_NOTE_FORMAT,
+        format => format,
+    };
+    let mut name = format_note_date(date, format);
+    let outside = [settings.folder, name.as_str()]
+        .into_iter()
+        .chain(settings.template)
+        .find(|path| !is_vault_relative(path));
+    if let Some(path) = outside {
+        return Err(format!("Daily note path '{}' is outside the vault", path));
+    }
+    if !is_markdown_file(Path::new(&name)) {
+        name.push_str(".md");
+    }
+
+    let path = root.join(settings.folder.trim()).join(&name);
+    let title = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| date.to_string());
+    let template = settings.template.map(|template| root.join(template));
+    create_from_template(&path, template.as_deref(), &title, date)?;
+
+    Ok(path)
+}
+
+/// `date` shifted by `offset_days`, or None if out of range
+pub fn offset_date(date: NaiveDate, offset_days: i32) -> Option<NaiveDate> {
+    date.checked_add_signed(chrono::Duration::days(offset_days.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_format_note_date() {
+        let day = date(2024, 6, 1);
+        assert_eq!(
+            format_note_date(day, DEFAULT_DAILY_NOTE_FORMAT),
+            "2024-06-01"
+        );
+        assert_eq!(
+            format_note_date(day, "YYYY/MM/YYYY-MM-DD ddd"),
+            "2024/06/2024-06-01 Sat"
+        );
+        assert_eq!(
+            format_note_date(day, "GGGG/WW/MMMM D 100%"),
+            "2024/22/June D 100%"
+        );
+        assert_eq!(
+            format_note_date(date(2021, 1, 1), "GGGG-[W]WW"),
+            "2020-[W]53"
+        );
+    }
+
+    #[test]
+    fn test_resolve_daily_note() {
+        let root = std::env::temp_dir().join("markviewer-daily-test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("templates")).unwrap();
+        fs::write(
+            root.join("templates/daily.md"),
+            "# {{title}}\n\nDate: {{date}}\n",
+        )
+        .unwrap();
+
+        let settings = DailyNotes {
+            folder: "Journal",
+            format: "YYYY/MM/YYYY-MM-DD",
+            template: Some("templates/daily.md"),
+        };
+        let day = offset_date(date(2024, 6, 1), -1).unwrap();
+        let path = resolve_daily_note(&root, &settings, day).unwrap();
+        assert_eq!(path, root.join("Journal/2024/05/2024-05-31.md"));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "# 2024-05-31\n\nDate: 2024-05-31\n"
+        );
+
+        // An existing note is returned untouched
+        fs::write(&path, "edited").unwrap();
+        assert_eq!(resolve_daily_note(&root, &settings, day).unwrap(), path);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "edited");
+
+        let plain = resolve_daily_note(&root, &DailyNotes::default(), date(2024, 6, 1)).unwrap();
+        assert_eq!(plain, root.join("2024-06-01.md"));
+        assert_eq!(fs::read_to_string(&plain).unwrap(), "# 2024-06-01\n");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_daily_note_paths_stay_in_vault() {
+        assert!(is_vault_relative("Journal/2024"));
+        assert!(is_vault_relative(""));
+        assert!(!is_vault_relative("/tmp/notes"));
+        assert!(!is_vault_relative("\\server\\notes"));
+        assert!(!is_vault_relative("C:\\notes"));
+        assert!(!is_vault_relative("Journal/../../out"));
+        assert!(!is_vault_relative("..\\out"));
+
+        let root = std::env::temp_dir().join("markviewer-daily-escape");
+        let settings = DailyNotes {
+            template: Some("../secret.md"),
+            ..Default::default()
+        };
+        assert!(resolve_daily_note(&root, &settings, date(2024, 6, 1)).is_err());
+        assert!(!root.exists());
+    }
+}
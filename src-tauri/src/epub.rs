@@ -0,0 +1,504 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::commands::{render_markdown_with, RenderOptions};
+use crate::export::{first_heading, ExportFailure};
+use crate::markdown::links::html_unescape;
+use crate::markdown::{
+    escape_html, get_highlight_css_light, media_references, replace_placeholder,
+    special_block_fallback, MediaKind, MediaRef,
+};
+
+/// Image sources in rendered HTML
+static IMG_SRC: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(<img\s+[^>]*?src=")([^"]+)(")"#).unwrap());
+
+/// Headings listed in the navigation document, with their content
+static HEADING: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<h([1-3])\b[^>]*>(.*?)</h[1-3]>").unwrap());
+
+/// An `id` attribute (not `data-...-id`)
+static HEADING_ID: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\sid="([^"]+)""#).unwrap());
+
+/// Any HTML tag, for extracting plain text
+static TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").unwrap());
+
+/// Image types EPUB readers must support, by extension
+const IMAGE_MEDIA_TYPES: &[(&str, &str)] = &[
+    ("gif", "image/gif"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("png", "image/png"),
+    ("svg", "image/svg+xml"),
+    ("webp", "image/webp"),
+];
+
+/// Book metadata for `export_epub`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EpubMeta {
+    pub title: String,
+    pub author: Option<String>,
+    /// BCP 47 language tag (default: "en")
+    pub language: Option<String>,
+    /// Unique book identifier (default: derived from the title and files)
+    pub identifier: Option<String>,
+}
+
+/// Outcome of an EPUB export
+#[derive(Debug, Default, Serialize)]
+pub struct EpubReport {
+    /// Titles of the chapters written, in reading order
+    pub chapters: Vec<String>,
+    /// Files that couldn't be rendered; the book is written without them
+    pub failures: Vec<ExportFailure>,
+}
+
+/// A rendered chapter
+struct Chapter {
+    file_name: String,
+    title: String,
+    /// (level, id, HTML text) of the chapter's H1–H3 headings
+    headings: Vec<(u8, String, String)>,
+    body: String,
+}
+
+/// An image embedded in the book
+struct BookImage {
+    source: PathBuf,
+    href: String,
+    media_type: &'static str,
+}
+
+/// Writes markdown files to an EPUB 3 book, one chapter per file in the given
+/// order.
+///
+/// Chapters go through the regular render path with special blocks replaced
+/// by their non-JS fallbacks (readers don't run scripts). Local images are
+/// embedded and the navigation document lists every chapter with its
+/// headings. Files that fail to render are reported and left out.
+pub fn write_epub(
+    files: &[String],
+    meta: &EpubMeta,
+    out_path: &Path,
+) -> Result<EpubReport, String> {
+    let mut report = EpubReport::default();
+    let mut chapters = Vec::new();
+    let mut images: Vec<BookImage> = Vec::new();
+
+    for file in files {
+        let file_name = format!("chapter-{}.xhtml", chapters.len() + 1);
+        match render_chapter(Path::new(file), file_name, &mut images) {
+            Ok(chapter) => {
+                report.chapters.push(chapter.title.clone());
+                chapters.push(chapter);
+            }
+            Err(error) => report.failures.push(ExportFailure {
+                source: file.clone(),
+                error,
+            }),
+        }
+    }
+    if chapters.is_empty() {
+        return Err("No chapter could be rendered".to_string());
+    }
+
+    let file = File::create(out_path).map_err(|e| format!("Failed to create EPUB: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    // The mimetype must come first and uncompressed
+    add_file(&mut zip, "mimetype", b"application/epub+zip", stored)?;
+    add_file(
+        &mut zip,
+        "META-INF/container.xml",
+        CONTAINER_XML.as_bytes(),
+        deflated,
+    )?;
+    add_file(
+        &mut zip,
+        "OEBPS/content.opf",
+        package_document(meta, files, &chapters, &images).as_bytes(),
+        deflated,
+    )?;
+    add_file(
+        &mut zip,
+        "OEBPS/nav.xhtml",
+        nav_document(meta, &chapters).as_bytes(),
+        deflated,
+    )?;
+    add_file(
+        &mut zip,
+        "OEBPS/style.css",
+        stylesheet().as_bytes(),
+        deflated,
+    )?;
+    for chapter in &chapters {
+        let page = xhtml_page(&chapter.title, &chapter.body, meta);
+        add_file(
+            &mut zip,
+            &format!("OEBPS/{}", chapter.file_name),
+            page.as_bytes(),
+            deflated,
+        )?;
+    }
+    for image in &images {
+        let bytes = fs::read(&image.source).map_err(|e| format!("Failed to read image: {}", e))?;
+        add_file(&mut zip, &format!("OEBPS/{}", image.href), &bytes, deflated)?;
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to write EPUB: {}", e))?;
+    Ok(report)
+}
+
+fn add_file(
+    zip: &mut ZipWriter<File>,
+    name: &str,
+    bytes: &[u8],
+    options: SimpleFileOptions,
+) -> Result<(), String> {
+    zip.start_file(name, options)
+        .map_err(|e| format!("Failed to write EPUB: {}", e))?;
+    zip.write_all(bytes)
+        .map_err(|e| format!("Failed to write EPUB: {}", e))
+}
+
+/// Renders one file to chapter XHTML, collecting its local images
+fn render_chapter(
+    path: &Path,
+    file_name: String,
+    images: &mut Vec<BookImage>,
+) -> Result<Chapter, String> {
+    let markdown = fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let media = media_references(&markdown, &path.to_string_lossy());
+    let options = RenderOptions {
+        theme: "light".to_string(),
+        ..Default::default()
+    };
+    let result = render_markdown_with(markdown, options)?;
+
+    let mut html = result.html;
+    for block in &result.special_blocks {
+        html = replace_placeholder(&html, &block.placeholder_id, &special_block_fallback(block));
+    }
+    let body = to_xhtml(&embed_images(&html, &media, images));
+
+    let title = result
+        .front_matter
+        .as_ref()
+        .and_then(|fm| fm.get("title"))
+        .and_then(|t| t.as_str())
+        .map(str::to_string)
+        .or_else(|| first_heading(&body))
+        .unwrap_or_else(|| {
+            path.file_stem()
+                .map_or(String::new(), |s| s.to_string_lossy().to_string())
+        });
+
+    let headings = HEADING
+        .captures_iter(&body)
+        .filter_map(|caps| {
+            let id = HEADING_ID.captures(&caps[0])?[1].to_string();
+            let text = TAG.replace_all(&caps[2], "").trim().to_string();
+            Some((caps[1].parse().unwrap_or(1), id, text))
+        })
+        .collect();
+
+    Ok(Chapter {
+        file_name,
+        title,
+        headings,
+        body,
+    })
+}
+
+/// Points local images at copies inside the book, registering each file once.
+/// `media` are the chapter's [`media_references`]; remote images, missing
+/// files and unsupported types are left as they are.
+fn embed_images(html: &str, media: &[MediaRef], images: &mut Vec<BookImage>) -> String {
+    IMG_SRC
+        .replace_all(html, |caps: &Captures| {
+            let src = html_unescape(&caps[2]);
+            let found = media
+                .iter()
+                .find(|media| media.kind == MediaKind::Image && media.src == src);
+            let Some(image) = found.filter(|image| image.exists) else {
+                return caps[0].to_string();
+            };
+            let path = PathBuf::from(&image.path);
+            let media_type = path
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .and_then(|ext| IMAGE_MEDIA_TYPES.iter().find(|(known, _)| *known == ext))
+                .map(|&(_, media_type)| media_type);
+            let Some(media_type) = media_type else {
+                return caps[0].to_string();
+            };
+
+            let href = match images.iter().find(|image| image.source == path) {
+                Some(image) => image.href.clone(),
+                None => {
+                    let extension = path.extension().unwrap_or_default().to_string_lossy();
+                    let href = format!("images/image-{}.{}", images.len() + 1, extension);
+                    images.push(BookImage {
+                        source: path,
+                        href: href.clone(),
+                        media_type,
+                    });
+                    href
+                }
+            };
+            format!("{}{}{}", &caps[1], href, &caps[3])
+        })
+        .into_owned()
+}
+
+/// Adjusts rendered HTML for XHTML: HTML-only named entities become numeric
+/// references and void elements are closed
+fn to_xhtml(html: &str) -> String {
+    static VOID_TAG: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"<(br|hr|img|input|meta|link|source|wbr)\b([^>]*?)\s*/?>").unwrap()
+    });
+    let html = html.replace("&nbsp;", "&#160;");
+    VOID_TAG
+        .replace_all(&html, |caps: &Captures| {
+            format!("<{}{} />", &caps[1], &caps[2])
+        })
+        .into_owned()
+}
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+fn package_document(
+    meta: &EpubMeta,
+    files: &[String],
+    chapters: &[Chapter],
+    images: &[BookImage],
+) -> String {
+    let identifier = meta.identifier.clone().unwrap_or_else(|| {
+        let mut hasher = DefaultHasher::new();
+        (&meta.title, files).hash(&mut hasher);
+        format!("urn:markviewer:{:016x}", hasher.finish())
+    });
+    let modified = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
+
+    let mut manifest = String::from(
+        "    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" \
+         properties=\"nav\"/>\n",
+    );
+    manifest.push_str("    <item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n");
+    let mut spine = String::new();
+    for (index, chapter) in chapters.iter().enumerate() {
+        manifest.push_str(&format!(
+            "    <item id=\"chapter-{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>\n",
+            index + 1,
+            chapter.file_name
+        ));
+        spine.push_str(&format!("    <itemref idref=\"chapter-{}\"/>\n", index + 1));
+    }
+    for (index, image) in images.iter().enumerate() {
+        manifest.push_str(&format!(
+            "    <item id=\"image-{}\" href=\"{}\" media-type=\"{}\"/>\n",
+            index + 1,
+            image.href,
+            image.media_type
+        ));
+    }
+    let creator = meta
+        .author
+        .as_ref()
+        .map(|author| format!("    <dc:creator>{}</dc:creator>\n", escape_html(author)))
+        .unwrap_or_default();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="book-id">{}</dc:identifier>
+    <dc:title>{}</dc:title>
+    <dc:language>{}</dc:language>
+{}    <meta property="dcterms:modified">{}</meta>
+  </metadata>
+  <manifest>
+{}  </manifest>
+  <spine>
+{}  </spine>
+</package>
+"#,
+        escape_html(&identifier),
+        escape_html(&meta.title),
+        escape_html(language(meta)),
+        creator,
+        modified,
+        manifest,
+        spine
+    )
+}
+
+/// The navigation document: each chapter, with its headings nested below
+fn nav_document(meta: &EpubMeta, chapters: &[Chapter]) -> String {
+    let mut toc = String::from("<ol>\n");
+    for chapter in chapters {
+        toc.push_str(&format!(
+            "<li><a href=\"{}\">{}</a>",
+            chapter.file_name,
+            escape_html(&chapter.title)
+        ));
+        let sections: Vec<&(u8, String, String)> = chapter
+            .headings
+            .iter()
+            .filter(|(level, _, text)| *level > 1 || *text != chapter.title)
+            .collect();
+        if !sections.is_empty() {
+            toc.push_str("\n<ol>\n");
+            for (_, id, text) in sections {
+                toc.push_str(&format!(
+                    "<li><a href=\"{}#{}\">{}</a></li>\n",
+                    chapter.file_name, id, text
+                ));
+            }
+            toc.push_str("</ol>\n");
+        }
+        toc.push_str("</li>\n");
+    }
+    toc.push_str("</ol>");
+
+    xhtml_page(
+        &meta.title,
+        &format!(
+            "<nav epub:type=\"toc\" id=\"toc\">\n<h1>{}</h1>\n{}\n</nav>",
+            escape_html(&meta.title),
+            toc
+        ),
+        meta,
+    )
+}
+
+fn xhtml_page(title: &str, body: &str, meta: &EpubMeta) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{lang}" lang="{lang}">
+<head>
+<meta charset="utf-8" />
+<title>{title}</title>
+<link rel="stylesheet" type="text/css" href="style.css" />
+</head>
+<body>
+{body}
+</body>
+</html>
+"#,
+        lang = escape_html(language(meta)),
+        title = escape_html(title),
+        body = body
+    )
+}
+
+fn stylesheet() -> String {
+    format!(
+        "img {{ max-width: 100%; }}\npre {{ white-space: pre-wrap; }}\n\
+         table {{ border-collapse: collapse; }}\nth, td {{ border: 1px solid #999; padding: 0.2em 0.5em; }}\n{}",
+        get_highlight_css_light()
+    )
+}
+
+fn language(meta: &EpubMeta) -> &str {
+    meta.language
+        .as_deref()
+        .filter(|lang| !lang.trim().is_empty())
+        .unwrap_or("en")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use zip::ZipArchive;
+
+    fn read(archive: &mut ZipArchive<File>, name: &str) -> String {
+        let mut text = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        text
+    }
+
+    #[test]
+    fn test_writes_epub_container() {
+        let dir = std::env::temp_dir().join("markviewer-epub-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("img")).unwrap();
+        fs::write(dir.join("img/cover.png"), b"png").unwrap();
+        fs::write(
+            dir.join("one.md"),
+            "# First\n\n![cover](img/cover.png)\n\n## Part A\n\nLine<br>break\n\n```mermaid\ngraph TD\n```\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("two.md"),
+            "---\ntitle: Second\n---\nText ![again](img/cover.png)",
+        )
+        .unwrap();
+
+        let files: Vec<String> = ["one.md", "missing.md", "two.md"]
+            .iter()
+            .map(|name| dir.join(name).display().to_string())
+            .collect();
+        let meta = EpubMeta {
+            title: "Notes & Essays".to_string(),
+            author: Some("Ada".to_string()),
+            ..Default::default()
+        };
+        let out = dir.join("book.epub");
+
+        let report = write_epub(&files, &meta, &out).unwrap();
+        assert_eq!(report.chapters, vec!["First", "Second"]);
+        assert_eq!(report.failures.len(), 1);
+        assert!(report.failures[0].source.ends_with("missing.md"));
+
+        let mut archive = ZipArchive::new(File::open(&out).unwrap()).unwrap();
+        let first = archive.by_index(0).unwrap();
+        assert_eq!(first.name(), "mimetype");
+        assert_eq!(first.compression(), CompressionMethod::Stored);
+        drop(first);
+        assert_eq!(read(&mut archive, "mimetype"), "application/epub+zip");
+
+        let opf = read(&mut archive, "OEBPS/content.opf");
+        assert!(opf.contains("<dc:title>Notes &amp; Essays</dc:title>"));
+        assert!(opf.contains("<dc:creator>Ada</dc:creator>"));
+        assert!(opf.contains("href=\"images/image-1.png\" media-type=\"image/png\""));
+        assert!(!opf.contains("image-2"));
+
+        let nav = read(&mut archive, "OEBPS/nav.xhtml");
+        assert!(nav.contains("<a href=\"chapter-1.xhtml\">First</a>"));
+        assert!(nav.contains("chapter-1.xhtml#heading-part-a\">Part A</a>"));
+        assert!(nav.contains("<a href=\"chapter-2.xhtml\">Second</a>"));
+
+        let chapter = read(&mut archive, "OEBPS/chapter-1.xhtml");
+        assert!(chapter.contains("src=\"images/image-1.png\""));
+        assert!(chapter.contains("<br />"));
+        assert!(chapter.contains("<pre><code class=\"language-mermaid\">graph TD</code></pre>"));
+        assert!(!chapter.contains("special-block"));
+        assert_eq!(read(&mut archive, "OEBPS/images/image-1.png"), "png");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
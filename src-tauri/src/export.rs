@@ -0,0 +1,450 @@
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::{render_markdown_with, RenderOptions};
+use crate::markdown::images::relative_path;
+use crate::markdown::{escape_html, get_highlight_css_light, media_references};
+use crate::vault::markdown_files;
+
+/// Relative links to markdown files (optionally with an #anchor)
+static MD_LINK_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r##"href="([^"#:?]+)\.(?:md|markdown)(#[^"]*)?""##).unwrap());
+
+/// First-level heading in rendered HTML
+static H1_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<h1[^>]*>(.*?)</h1>").unwrap());
+
+/// Second and third level headings with their anchor, for the index TOC
+static TOC_HEADING_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r##"(?s)<h([23])[^>]*><a href="#([^"]*)" aria-hidden="true" class="anchor"[^>]*></a>(.*?)</h[23]>"##,
+    )
+    .unwrap()
+});
+
+/// Any HTML tag, for extracting plain text
+static TAG_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").unwrap());
+
+/// A successfully exported document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedDoc {
+    /// Source markdown path
+    pub source: String,
+    /// Written HTML path
+    pub output: String,
+    /// Output path relative to the export folder (forward slashes)
+    pub relative: String,
+    /// Title from front matter, the first H1, or the file name
+    pub title: String,
+}
+
+/// A document that failed to export
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportFailure {
+    pub source: String,
+    pub error: String,
+}
+
+/// Outcome of a folder export
+#[derive(Debug, Default, Serialize)]
+pub struct ExportReport {
+    pub exported: Vec<ExportedDoc>,
+    pub failures: Vec<ExportFailure>,
+    /// Path of the generated index page
+    pub index: Option<String>,
+}
+
+/// Renders every markdown file under `root` to HTML in `out_dir`.
+///
+/// - The directory structure is preserved (`notes/a.md` -> `notes/a.html`)
+/// - Relative images inside `root` are copied next to the exported pages
+/// - Links to other markdown files are rewritten to `.html`
+/// - Special blocks are left as code, since exported pages have no JS
+/// - An `index.html` listing all exported pages is generated, or a
+///   `contents.html` when the folder has its own root `index.md`
+pub fn export_folder(
+    root: &Path,
+    out_dir: &Path,
+    options: &RenderOptions,
+) -> Result<ExportReport, String> {
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", root.display()));
+    }
+    fs::create_dir_all(out_dir).map_err(|e| format!("Failed to create output dir: {}", e))?;
+
+    let results: Vec<Result<ExportedDoc, ExportFailure>> = markdown_files(root)
+        .par_iter()
+        .map(|source| {
+            export_file(root, out_dir, source, options).map_err(|error| ExportFailure {
+                source: source.display().to_string(),
+                error,
+            })
+        })
+        .collect();
+
+    let mut report = ExportReport::default();
+    for result in results {
+        match result {
+            Ok(doc) => report.exported.push(doc),
+            Err(failure) => report.failures.push(failure),
+        }
+    }
+
+    let index_path = out_dir.join(index_file_name(&report.exported));
+    generate_index(&report.exported, &index_path)?;
+    report.index = Some(index_path.display().to_string());
+
+    Ok(report)
+}
+
+/// `index.html`, unless an exported page already has that name, then the
+/// first free `contents.html`, `contents-1.html`…
+fn index_file_name(docs: &[ExportedDoc]) -> String {
+    let taken = |name: &str| docs.iter().any(|doc| doc.relative == name);
+    if !taken("index.html") {
+        return "index.html".to_string();
+    }
+    let mut name = "contents.html".to_string();
+    let mut counter = 0;
+    while taken(&name) {
+        counter += 1;
+        name = format!("contents-{}.html", counter);
+    }
+    name
+}
+
+fn export_file(
+    root: &Path,
+    out_dir: &Path,
+    source: &Path,
+    options: &RenderOptions,
+) -> Result<ExportedDoc, String> {
+    let relative = source
+        .strip_prefix(root)
+        .map_err(|_| "File is outside the export root".to_string())?;
+    let output = out_dir.join(relative).with_extension("html");
+
+    let markdown =
+        fs::read_to_string(source).map_err(|e| format!("Failed to read file: {}", e))?;
+    copy_media(&markdown, root, source, out_dir);
+    // Pages are styled light, code blocks included
+    let render_options = RenderOptions {
+        theme: "light".to_string(),
+        base_path: None,
+        extract_special_blocks: Some(false),
+        ..options.clone()
+    };
+    let result = render_markdown_with(markdown, render_options)?;
+
+    let html = rewrite_markdown_links(&result.html);
+
+    let title = result
+        .front_matter
+        .as_ref()
+        .and_then(|fm| fm.get("title"))
+        .and_then(|t| t.as_str())
+        .map(str::to_string)
+        .or_else(|| first_heading(&html))
+        .unwrap_or_else(|| file_stem(relative));
+
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create dir: {}", e))?;
+    }
+    fs::write(&output, page_html(&title, &html))
+        .map_err(|e| format!("Failed to write HTML: {}", e))?;
+
+    Ok(ExportedDoc {
+        source: source.display().to_string(),
+        output: output.display().to_string(),
+        relative: relative.with_extension("html").to_string_lossy().replace('\\', "/"),
+        title,
+    })
+}
+
+/// Rewrites relative links to markdown files so they point to the exported `.html`
+pub fn rewrite_markdown_links(html: &str) -> String {
+    MD_LINK_REGEX
+        .replace_all(html, |caps: &regex::Captures| {
+            let anchor = caps.get(2).map_or("", |m| m.as_str());
+            format!("href=\"{}.html{}\"", &caps[1], anchor)
+        })
+        .to_string()
+}
+
+/// Copies the local files a page references with relative links (images,
+/// linked files, video and audio, see [`media_references`]) into the export
+/// folder, keeping their position relative to the page. Files outside `root`
+/// are skipped.
+fn copy_media(markdown: &str, root: &Path, source: &Path, out_dir: &Path) {
+    for media in media_references(markdown, &source.to_string_lossy()) {
+        if !media.exists || Path::new(&media.src).is_absolute() {
+            continue;
+        }
+        let path = Path::new(&media.path);
+        let Ok(relative) = path.strip_prefix(root) else {
+            continue;
+        };
+        let target = out_dir.join(relative);
+        if !target.exists() {
+            if let Some(parent) = target.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            let _ = fs::copy(path, &target);
+        }
+    }
+}
+
+/// Plain text of the first H1, if any
+pub fn first_heading(html: &str) -> Option<String> {
+    H1_REGEX
+        .captures(html)
+        .map(|caps| TAG_REGEX.replace_all(&caps[1], "").trim().to_string())
+        .filter(|title| !title.is_empty())
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Wraps rendered content in a standalone HTML page
+pub fn page_html(title: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{}</title>
+<style>
+body {{ max-width: 48rem; margin: 2rem auto; padding: 0 1rem; font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; line-height: 1.6; color: #24292e; }}
+pre {{ padding: 1rem; overflow-x: auto; background: #f6f8fa; border-radius: 6px; }}
+img {{ max-width: 100%; }}
+table {{ border-collapse: collapse; }}
+th, td {{ border: 1px solid #d0d7de; padding: 0.4rem 0.8rem; }}
+{}
+</style>
+</head>
+<body>
+<main class="markdown-body">
+{}
+</main>
+</body>
+</html>
+"#,
+        escape_html(title),
+        get_highlight_css_light(),
+        body
+    )
+}
+
+/// Exported documents of one folder and its subfolders
+#[derive(Default)]
+struct IndexFolder<'a> {
+    docs: Vec<&'a ExportedDoc>,
+    folders: BTreeMap<String, IndexFolder<'a>>,
+}
+
+/// Writes an `index.html` linking to every exported page, with each page's
+/// headings as a table of contents.
+///
+/// Pages are grouped by folder, subfolders in collapsible sections. The index
+/// is written as markdown and rendered through the normal pipeline; headings
+/// are read from the exported pages, so pages that can't be read are listed
+/// without them.
+pub fn generate_index(docs: &[ExportedDoc], out_path: &Path) -> Result<(), String> {
+    let index_dir = out_path.parent().unwrap_or(Path::new(""));
+    let mut root = IndexFolder::default();
+    for doc in docs {
+        let mut folder = &mut root;
+        let mut parts: Vec<&str> = doc.relative.split('/').collect();
+        parts.pop();
+        for part in parts {
+            folder = folder.folders.entry(part.to_string()).or_default();
+        }
+        folder.docs.push(doc);
+    }
+
+    let mut markdown = String::from("# Index\n\n");
+    push_index_folder(&mut markdown, &root, index_dir);
+
+    let options = RenderOptions {
+        extract_special_blocks: Some(false),
+        block_ids: Some(false),
+        ..Default::default()
+    };
+    let result = render_markdown_with(markdown, options)?;
+
+    if let Some(parent) = out_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create dir: {}", e))?;
+    }
+    fs::write(out_path, page_html("Index", &result.html))
+        .map_err(|e| format!("Failed to write index: {}", e))
+}
+
+fn push_index_folder(markdown: &mut String, folder: &IndexFolder, index_dir: &Path) {
+    let mut docs = folder.docs.clone();
+    docs.sort_by(|a, b| a.relative.cmp(&b.relative));
+    for doc in &docs {
+        let href = relative_path(index_dir, Path::new(&doc.output))
+            .to_string_lossy()
+            .replace('\\', "/");
+        markdown.push_str(&format!(
+            "- [{}](<{}>)\n",
+            escape_markdown(&doc.title),
+            href
+        ));
+        for (level, id, text) in page_headings(Path::new(&doc.output)) {
+            markdown.push_str(&format!(
+                "{}- [{}](<{}#{}>)\n",
+                "  ".repeat(level),
+                escape_markdown(&text),
+                href,
+                id
+            ));
+        }
+    }
+    if !docs.is_empty() {
+        markdown.push('\n');
+    }
+
+    for (name, subfolder) in &folder.folders {
+        markdown.push_str(&format!(
+            "<details open>\n<summary>{}</summary>\n\n",
+            escape_html(name)
+        ));
+        push_index_folder(markdown, subfolder, index_dir);
+        markdown.push_str("</details>\n\n");
+    }
+}
+
+/// Depth (1 for h2), anchor and text of the h2/h3 headings of an exported page
+fn page_headings(page: &Path) -> Vec<(usize, String, String)> {
+    let Ok(html) = fs::read_to_string(page) else {
+        return Vec::new();
+    };
+    TOC_HEADING_REGEX
+        .captures_iter(&html)
+        .map(|caps| {
+            let level = if &caps[1] == "2" { 1 } else { 2 };
+            let text = TAG_REGEX.replace_all(&caps[3], "").trim().to_string();
+            (level, caps[2].to_string(), text)
+        })
+        .filter(|(_, _, text)| !text.is_empty())
+        .collect()
+}
+
+/// Escapes characters that would be read as markdown in link text
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '&') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_markdown_links() {
+        let html = r#"<a href="other.md">x</a> <a href="sub/page.md#intro">y</a> <a href="https://x.com/a.md">z</a>"#;
+        let result = rewrite_markdown_links(html);
+        assert!(result.contains(r#"href="other.html""#));
+        assert!(result.contains(r#"href="sub/page.html#intro""#));
+        assert!(result.contains(r#"href="https://x.com/a.md""#));
+    }
+
+    #[test]
+    fn test_first_heading() {
+        assert_eq!(
+            first_heading(r##"<h1 id="x"><a href="#x"></a>Hello <em>World</em></h1>"##).as_deref(),
+            Some("Hello World")
+        );
+        assert_eq!(first_heading("<h2>Nope</h2>"), None);
+    }
+
+    #[test]
+    fn test_export_folder() {
+        let root = std::env::temp_dir().join("markviewer-export-src");
+        let out = std::env::temp_dir().join("markviewer-export-out");
+        let _ = fs::remove_dir_all(&root);
+        let _ = fs::remove_dir_all(&out);
+        fs::create_dir_all(root.join("notes/images")).unwrap();
+        fs::write(root.join("index.md"), "# Home\n\nSee [notes](notes/a.md).").unwrap();
+        fs::write(root.join("notes/a.md"), "# Note A\n\n![pic](images/p.png)").unwrap();
+        fs::write(root.join("notes/images/p.png"), [0u8, 1, 2]).unwrap();
+
+        let report = export_folder(&root, &out, &RenderOptions::default()).unwrap();
+
+        assert_eq!(report.exported.len(), 2);
+        assert!(report.failures.is_empty());
+        let home = fs::read_to_string(out.join("index.html")).unwrap();
+        assert!(home.contains("<title>Home</title>"));
+        assert!(home.contains(r#"href="notes/a.html""#));
+        assert!(out.join("notes/a.html").exists());
+        assert!(out.join("notes/images/p.png").exists());
+
+        let index_path = out.join("contents.html");
+        assert_eq!(report.index, Some(index_path.display().to_string()));
+        let index = fs::read_to_string(&index_path).unwrap();
+        assert!(index.contains("Note A"));
+        assert!(index.contains(r#"href="index.html""#));
+        assert!(index.contains(r#"href="notes/a.html""#));
+        assert!(index.contains("<summary>notes</summary>"));
+
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_dir_all(&out).unwrap();
+    }
+
+    #[test]
+    fn test_generate_index() {
+        let out = std::env::temp_dir().join("markviewer-index-out");
+        let _ = fs::remove_dir_all(&out);
+        fs::create_dir_all(out.join("guides/advanced")).unwrap();
+        let page = |relative: &str, title: &str, body: &str| {
+            let output = out.join(relative);
+            fs::write(&output, page_html(title, body)).unwrap();
+            ExportedDoc {
+                source: String::new(),
+                output: output.display().to_string(),
+                relative: relative.to_string(),
+                title: title.to_string(),
+            }
+        };
+        let docs = vec![
+            page(
+                "guides/advanced/tuning.html",
+                "Tuning",
+                "<h1>Tuning</h1><h2><a href=\"#heading-caches\" aria-hidden=\"true\" \
+                 class=\"anchor\" id=\"heading-caches\"></a>Caches</h2>",
+            ),
+            page("home.html", "Home *page*", "<p>Hi</p>"),
+            page("guides/start.html", "Getting started", ""),
+        ];
+
+        generate_index(&docs, &out.join("index.html")).unwrap();
+        let index = fs::read_to_string(out.join("index.html")).unwrap();
+
+        assert!(index.contains(r#"<a href="home.html">Home *page*</a>"#));
+        assert!(
+            index.contains(r#"<a href="guides/advanced/tuning.html#heading-caches">Caches</a>"#)
+        );
+        let guides = index.find("<summary>guides</summary>").unwrap();
+        let advanced = index.find("<summary>advanced</summary>").unwrap();
+        let start = index.find("guides/start.html").unwrap();
+        assert!(index.find("home.html").unwrap() < guides);
+        assert!(guides < start && start < advanced);
+
+        fs::remove_dir_all(&out).unwrap();
+    }
+}
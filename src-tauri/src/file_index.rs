@@ -0,0 +1,416 @@
+use once_cell::sync::Lazy;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::markdown::lines::{atx_heading, source_lines, SourceLine};
+use crate::markdown::{extract_front_matter, merge_front_matter};
+use crate::vault::markdown_files;
+
+/// Inline `#tags` (must contain a letter, so `#1` and `#` headings don't count)
+static HASHTAG_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:^|\s)#([\w/-]*[\p{L}][\w/-]*)").unwrap());
+
+/// Inline code spans, which never contain tags
+static CODE_SPAN_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"`[^`]*`").unwrap());
+
+/// Indexed metadata per file, keyed by path and invalidated by modification time
+static META_CACHE: Lazy<Mutex<HashMap<PathBuf, (SystemTime, FileMeta)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Sidebar metadata for a markdown file
+#[derive(Debug, Clone, Serialize)]
+pub struct FileMeta {
+    /// Absolute path of the file
+    pub path: String,
+    /// Path relative to the indexed folder, with `/` separators
+    pub relative: String,
+    /// Front matter title, else the first `#` heading, else the file name
+    pub title: String,
+    /// Front matter tags followed by inline `#tags`, without duplicates
+    pub tags: Vec<String>,
+    /// Alternate names from the front matter `aliases` (or `alias`) field
+    pub aliases: Vec<String>,
+    /// Stable id from the front matter `id` field
+    pub id: Option<String>,
+    /// Words outside fenced code and front matter
+    pub word_count: usize,
+    /// Modification time in seconds since the Unix epoch
+    pub modified: u64,
+}
+
+/// Indexes every markdown file under `root` in parallel.
+///
+/// Results are cached per file and only recomputed when its mtime changes, so
+/// repeated calls only re-read edited files. Unreadable files are skipped.
+pub fn index_files(root: &Path) -> Vec<FileMeta> {
+    markdown_files(root)
+        .par_iter()
+        .filter_map(|path| cached_meta(root, path))
+        .collect()
+}
+
+/// Finds indexed files whose file name, title or an alias matches `query`,
+/// best matches first.
+///
+/// Matching is case-insensitive: an exact name ranks above a prefix, then a
+/// substring, then the query's characters appearing in order ("mtg" finds
+/// "meeting"). Ties are ordered by relative path.
+pub fn find_files(root: &Path, query: &str) -> Vec<FileMeta> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut ranked: Vec<(u8, FileMeta)> = index_files(root)
+        .into_iter()
+        .filter_map(|meta| {
+            let stem = Path::new(&meta.path)
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let rank = std::iter::once(&stem)
+                .chain(std::iter::once(&meta.title))
+                .chain(&meta.aliases)
+                .filter_map(|name| match_rank(&name.to_lowercase(), &query))
+                .min()?;
+            Some((rank, meta))
+        })
+        .collect();
+
+    ranked.sort_by(|(a_rank, a), (b_rank, b)| {
+        a_rank.cmp(b_rank).then_with(|| a.relative.cmp(&b.relative))
+    });
+    ranked.into_iter().map(|(_, meta)| meta).collect()
+}
+
+/// Finds the file whose front matter `id` is `id`, wherever it was moved.
+///
+/// Uses the cached index, so repeated lookups only re-read edited files.
+pub fn find_by_id(root: &Path, id: &str) -> Option<FileMeta> {
+    let id = id.trim();
+    if id.is_empty() {
+        return None;
+    }
+    index_files(root)
+        .into_iter()
+        .find(|meta| meta.id.as_deref() == Some(id))
+}
+
+/// 0 for an exact match, 1 for a prefix, 2 for a substring, 3 for a
+/// subsequence; None when `name` doesn't match (both already lowercased)
+fn match_rank(name: &str, query: &str) -> Option<u8> {
+    if name == query {
+        Some(0)
+    } else if name.starts_with(query) {
+        Some(1)
+    } else if name.contains(query) {
+        Some(2)
+    } else {
+        let mut chars = name.chars();
+        query.chars().all(|q| chars.any(|c| c == q)).then_some(3)
+    }
+}
+
+fn cached_meta(root: &Path, path: &Path) -> Option<FileMeta> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+
+    if let Ok(cache) = META_CACHE.lock() {
+        if let Some((cached_at, meta)) = cache.get(path) {
+            if *cached_at == modified {
+                return Some(meta.clone());
+            }
+        }
+    }
+
+    let content = fs::read_to_string(path).ok()?;
+    let meta = file_meta(root, path, &content, modified);
+
+    if let Ok(mut cache) = META_CACHE.lock() {
+        cache.insert(path.to_path_buf(), (modified, meta.clone()));
+    }
+
+    Some(meta)
+}
+
+fn file_meta(root: &Path, path: &Path, content: &str, modified: SystemTime) -> FileMeta {
+    let (front_matter, body) = extract_front_matter(content);
+    let fields = front_matter.and_then(|fm| fm.value);
+    let lines: Vec<_> = source_lines(body).into_iter().filter(|l| !l.in_code).collect();
+
+    let title = title_from(fields.as_ref(), &lines).unwrap_or_else(|| {
+        path.file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    });
+
+    let mut tags = fields.as_ref().map(front_matter_tags).unwrap_or_default();
+    for line in &lines {
+        if atx_heading(line.text).is_some() {
+            continue;
+        }
+        let text = CODE_SPAN_REGEX.replace_all(line.text, "");
+        for caps in HASHTAG_REGEX.captures_iter(&text) {
+            let tag = caps[1].to_string();
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+    }
+
+    FileMeta {
+        path: path.display().to_string(),
+        relative: path
+            .strip_prefix(root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/"),
+        title,
+        tags,
+        aliases: fields.as_ref().map(front_matter_aliases).unwrap_or_default(),
+        id: fields.as_ref().and_then(front_matter_id),
+        word_count: lines.iter().map(|l| count_words(l.text)).sum(),
+        modified: modified
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    }
+}
+
+/// Front matter title, else the text of the first `#` heading
+pub fn document_title(markdown: &str) -> Option<String> {
+    let (front_matter, body) = extract_front_matter(markdown);
+    let fields = front_matter.and_then(|fm| fm.value);
+    let lines: Vec<_> = source_lines(body).into_iter().filter(|l| !l.in_code).collect();
+    title_from(fields.as_ref(), &lines)
+}
+
+fn title_from(fields: Option<&Value>, lines: &[SourceLine]) -> Option<String> {
+    fields
+        .and_then(|f| f.get("title"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or_else(|| {
+            lines.iter().find_map(|l| match atx_heading(l.text) {
+                Some((1, text)) if !text.is_empty() => Some(text.to_string()),
+                _ => None,
+            })
+        })
+}
+
+/// Front matter aliases of a document
+pub fn document_aliases(markdown: &str) -> Vec<String> {
+    extract_front_matter(markdown)
+        .0
+        .and_then(|fm| fm.value)
+        .map(|fields| front_matter_aliases(&fields))
+        .unwrap_or_default()
+}
+
+/// Front matter `id` of a document
+pub fn document_id(markdown: &str) -> Option<String> {
+    extract_front_matter(markdown)
+        .0
+        .and_then(|fm| fm.value)
+        .and_then(|fields| front_matter_id(&fields))
+}
+
+/// Gives a document a random UUID `id` in its front matter unless it has one,
+/// creating the front matter if needed.
+///
+/// Other keys keep their order (see [`merge_front_matter`]). Returns the
+/// document unchanged when it already has an id.
+pub fn ensure_document_id(markdown: &str) -> Result<String, String> {
+    if document_id(markdown).is_some() {
+        return Ok(markdown.to_string());
+    }
+    let mut updates = Map::new();
+    updates.insert("id".to_string(), Value::String(Uuid::new_v4().to_string()));
+    merge_front_matter(markdown, &updates)
+}
+
+/// `id` as a non-empty string or number
+fn front_matter_id(fields: &Value) -> Option<String> {
+    let id = match fields.get("id")? {
+        Value::String(s) => s.trim().to_string(),
+        Value::Number(n) => n.to_string(),
+        _ => return None,
+    };
+    (!id.is_empty()).then_some(id)
+}
+
+/// `aliases` (or `alias`) as a list or a single string, trimmed and without
+/// duplicates
+fn front_matter_aliases(fields: &Value) -> Vec<String> {
+    let raw: Vec<&str> = match fields.get("aliases").or_else(|| fields.get("alias")) {
+        Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).collect(),
+        Some(Value::String(s)) => vec![s.as_str()],
+        _ => Vec::new(),
+    };
+
+    let mut aliases: Vec<String> = Vec::new();
+    for alias in raw {
+        let alias = alias.trim().to_string();
+        if !alias.is_empty() && !aliases.contains(&alias) {
+            aliases.push(alias);
+        }
+    }
+    aliases
+}
+
+/// Counts tokens with a letter or digit, so list and heading markers are skipped
+fn count_words(text: &str) -> usize {
+    text.split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+/// `tags` as a list or a comma/space separated string; a leading `#` is dropped
+fn front_matter_tags(fields: &Value) -> Vec<String> {
+    let raw: Vec<String> = match fields.get("tags") {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        Some(Value::String(s)) => s.split([',', ' ']).map(str::to_string).collect(),
+        _ => Vec::new(),
+    };
+
+    let mut tags: Vec<String> = Vec::new();
+    for tag in raw {
+        let tag = tag.trim().trim_start_matches('#').to_string();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_meta() {
+        let content = "---\ntitle: Plan\ntags: [work, \"#q3\"]\n---\n# Heading\n\nShip it #work #urgent `#code`\n\n```\n#not a tag\nlots of code words\n```\n";
+        let meta = file_meta(
+            Path::new("/vault"),
+            Path::new("/vault/notes/plan.md"),
+            content,
+            UNIX_EPOCH,
+        );
+
+        assert_eq!(meta.title, "Plan");
+        assert_eq!(meta.relative, "notes/plan.md");
+        assert_eq!(meta.tags, vec!["work", "q3", "urgent"]);
+        assert_eq!(meta.word_count, 6);
+        assert_eq!(meta.modified, 0);
+    }
+
+    #[test]
+    fn test_title_fallbacks() {
+        let root = Path::new("/vault");
+        let heading =
+            file_meta(root, Path::new("/vault/a.md"), "Intro\n\n# Real Title", UNIX_EPOCH);
+        assert_eq!(heading.title, "Real Title");
+
+        let stem = file_meta(root, Path::new("/vault/b note.md"), "## Sub only", UNIX_EPOCH);
+        assert_eq!(stem.title, "b note");
+    }
+
+    #[test]
+    fn test_front_matter_aliases() {
+        assert_eq!(
+            document_aliases("---\naliases: [Q3 Plan, \" roadmap \", Q3 Plan]\n---\n"),
+            vec!["Q3 Plan", "roadmap"]
+        );
+        assert_eq!(document_aliases("---\nalias: Standup\n---\n"), vec!["Standup"]);
+        assert!(document_aliases("# No front matter").is_empty());
+    }
+
+    #[test]
+    fn test_ensure_document_id() {
+        let md = "---\ntitle: Plan\ntags: [a]\n---\n# Plan\n";
+        let with_id = ensure_document_id(md).unwrap();
+        let id = document_id(&with_id).unwrap();
+
+        assert_eq!(Uuid::parse_str(&id).unwrap().get_version_num(), 4);
+        assert!(with_id.starts_with("---\ntitle: Plan\ntags:"));
+        assert!(with_id.ends_with(&format!("id: {}\n---\n# Plan\n", id)));
+        assert_eq!(ensure_document_id(&with_id).unwrap(), with_id);
+
+        let created = ensure_document_id("# Bare\n").unwrap();
+        assert!(created.starts_with("---\nid: "));
+        assert!(created.ends_with("---\n# Bare\n"));
+    }
+
+    #[test]
+    fn test_find_by_id() {
+        let root = std::env::temp_dir().join("markviewer-find-id-test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("moved")).unwrap();
+        fs::write(root.join("moved/note.md"), "---\nid: abc-123\n---\nNote").unwrap();
+        fs::write(root.join("other.md"), "---\nid: 42\n---\nOther").unwrap();
+
+        let found = find_by_id(&root, "abc-123").unwrap();
+        assert_eq!(found.relative, "moved/note.md");
+        assert_eq!(find_by_id(&root, "42").unwrap().relative, "other.md");
+        assert!(find_by_id(&root, "missing").is_none());
+        assert!(find_by_id(&root, " ").is_none());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_find_files_by_alias() {
+        let root = std::env::temp_dir().join("markviewer-find-test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("2024-06-03.md"), "---\naliases: [Weekly Sync]\n---\nNotes").unwrap();
+        fs::write(root.join("sync-engine.md"), "# Sync engine").unwrap();
+        fs::write(root.join("other.md"), "# Other").unwrap();
+
+        let names = |query: &str| -> Vec<String> {
+            find_files(&root, query).into_iter().map(|m| m.relative).collect()
+        };
+        assert_eq!(names("weekly sync"), vec!["2024-06-03.md"]);
+        assert_eq!(names("sync"), vec!["sync-engine.md", "2024-06-03.md"]);
+        assert_eq!(names("wksync"), vec!["2024-06-03.md"]);
+        assert!(names("  ").is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_index_files_uses_cache() {
+        let root = std::env::temp_dir().join("markviewer-index-test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("one.md"), "# One\n\nfirst #tag").unwrap();
+        fs::write(root.join("two.md"), "two words").unwrap();
+
+        let first = index_files(&root);
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].title, "One");
+        assert_eq!(first[0].tags, vec!["tag"]);
+        assert_eq!(first[1].word_count, 2);
+
+        // Cached entries are reused while the mtime is unchanged
+        let path = root.join("two.md");
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        let mut cached = first[1].clone();
+        cached.title = "From cache".to_string();
+        META_CACHE.lock().unwrap().insert(path, (modified, cached));
+        assert_eq!(index_files(&root)[1].title, "From cache");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,136 @@
+use serde::Serialize;
+use std::path::Path;
+
+use crate::markdown::images::{local_image_sources, relative_path};
+use crate::vault::image_files;
+
+/// Suggestions below this similarity are not reported
+const MIN_CONFIDENCE: f64 = 0.6;
+
+/// A broken image link and the existing file it most likely meant
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImageFix {
+    /// The image source as written in the document
+    pub src: String,
+    /// Replacement source, relative to the document's folder
+    pub suggestion: String,
+    /// Filename similarity from 0 to 1 (1: same name ignoring case)
+    pub confidence: f64,
+}
+
+/// Suggests replacements for local image links whose file doesn't exist.
+///
+/// Images anywhere under the document's folder are compared by file name
+/// (case-insensitive edit distance), so a file that was renamed in case or
+/// moved into a subfolder like `images/` is found. Each broken source gets
+/// at most one suggestion; ties go to the shorter path.
+pub fn suggest_fixes(markdown: &str, base_path: &str) -> Vec<ImageFix> {
+    let base = Path::new(base_path);
+    let base_dir = base.parent().unwrap_or(base);
+    let mut candidates: Option<Vec<_>> = None;
+    let mut fixes: Vec<ImageFix> = Vec::new();
+
+    for (src, path) in local_image_sources(markdown, base_path) {
+        if path.is_file() || fixes.iter().any(|fix| fix.src == src) {
+            continue;
+        }
+        let Some(wanted) = path.file_name().map(|n| n.to_string_lossy().to_lowercase()) else {
+            continue;
+        };
+
+        let candidates = candidates.get_or_insert_with(|| image_files(base_dir));
+        let best = candidates
+            .iter()
+            .filter_map(|candidate| {
+                let name = candidate.file_name()?.to_string_lossy().to_lowercase();
+                let relative = relative_path(base_dir, candidate)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                Some((similarity(&wanted, &name), relative))
+            })
+            .filter(|(confidence, _)| *confidence >= MIN_CONFIDENCE)
+            .max_by(|a, b| a.0.total_cmp(&b.0).then_with(|| b.1.len().cmp(&a.1.len())));
+
+        if let Some((confidence, suggestion)) = best {
+            fixes.push(ImageFix {
+                src,
+                suggestion,
+                confidence,
+            });
+        }
+    }
+
+    fixes
+}
+
+/// 1 minus the edit distance relative to the longer name
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(&a, &b) as f64 / longest as f64
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("logo.png", "logo.png"), 1.0);
+        assert_eq!(similarity("kitten", "sitting"), 1.0 - 3.0 / 7.0);
+        assert!(similarity("diagram.png", "diagram-v2.png") > MIN_CONFIDENCE);
+        assert!(similarity("logo.png", "screenshot.jpg") < MIN_CONFIDENCE);
+    }
+
+    #[test]
+    fn test_suggests_moved_and_renamed_images() {
+        let dir = std::env::temp_dir().join("markviewer-image-fixes-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("notes/images")).unwrap();
+        fs::write(dir.join("notes/Logo.PNG"), b"x").unwrap();
+        fs::write(dir.join("notes/images/chart.png"), b"x").unwrap();
+        fs::write(dir.join("notes/images/diagram-v2.png"), b"x").unwrap();
+        fs::write(dir.join("notes/ok.png"), b"x").unwrap();
+
+        let doc = dir.join("notes/doc.md").display().to_string();
+        let md = "![a](logo.png) ![b](chart.png) ![c](img/diagram.png)\n\
+                  ![d](ok.png) ![e](unrelated-screenshot.gif) ![f](https://x.dev/a.png)\n\
+                  <img src=\"chart.png\">";
+        let fixes = suggest_fixes(md, &doc);
+
+        assert_eq!(fixes.len(), 3);
+        assert_eq!(
+            fixes[0],
+            ImageFix {
+                src: "logo.png".to_string(),
+                suggestion: "Logo.PNG".to_string(),
+                confidence: 1.0,
+            }
+        );
+        assert_eq!(fixes[1].src, "chart.png");
+        assert_eq!(fixes[1].suggestion, "images/chart.png");
+        assert_eq!(fixes[2].src, "img/diagram.png");
+        assert_eq!(fixes[2].suggestion, "images/diagram-v2.png");
+        assert!(fixes[2].confidence < 1.0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,7 +1,37 @@
+mod backlinks;
+mod bundle;
+mod chart;
 mod commands;
+mod daily_notes;
+mod epub;
+mod export;
+mod file_index;
+mod image_fixes;
 mod markdown;
+mod palette;
+mod plantuml;
+mod reading;
+mod save;
+mod settings;
+mod thumbnail;
+mod vault;
 
-use commands::{highlight_code_block, install_cli_command, open_in_editor, open_path, render_markdown, save_pasted_image};
+use commands::{
+    anchor_map, audit_alt_text, clipboard_html_to_markdown, detect_document_language,
+    document_thumbnail, dominant_colors, ensure_document_id, export_bundle, export_epub,
+    export_folder_html, extract_aliases, extract_tasks, file_changed, find_backlinks, find_by_id,
+    find_duplicate_headings, fold_regions, fuzzy_find_files, generate_index, get_reading_position,
+    get_settings, highlight_code_block, highlight_code_blocks, humanize_date, index_folder,
+    install_cli_command, keyword_summary, lint_code_blocks, lint_document, list_highlight_themes,
+    list_media, normalize_headings, normalize_table, open_in_editor, open_in_editor_at, open_path,
+    path_breadcrumb, preview_theme, rebase_image_paths, render_chart_png, render_markdown,
+    render_matching_sections, render_notebook, render_patch, render_plantuml, render_rtf,
+    render_slides, render_special_block_fallback, render_with_highlight, render_with_timing,
+    resolve_daily_note, resolve_front_matter, save_file, save_pasted_image, save_reading_position,
+    search_snippet, set_settings, source_render_map, suggest_image_fixes, summarize,
+    supported_syntax_sample, task_progress, tokenize_code, update_front_matter, validate_anchors,
+    validate_front_matter,
+};
 use tauri::menu::{MenuBuilder, MenuItemBuilder, PredefinedMenuItem, SubmenuBuilder};
 use tauri::{Emitter, Manager};
 use tauri_plugin_cli::CliExt;
@@ -200,11 +230,71 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             render_markdown,
+            render_notebook,
             highlight_code_block,
+            tokenize_code,
+            task_progress,
             save_pasted_image,
+            rebase_image_paths,
             open_path,
             open_in_editor,
-            install_cli_command
+            open_in_editor_at,
+            install_cli_command,
+            humanize_date,
+            list_highlight_themes,
+            preview_theme,
+            get_settings,
+            set_settings,
+            export_folder_html,
+            supported_syntax_sample,
+            find_backlinks,
+            render_matching_sections,
+            render_special_block_fallback,
+            lint_code_blocks,
+            index_folder,
+            document_thumbnail,
+            render_plantuml,
+            validate_anchors,
+            render_with_highlight,
+            normalize_headings,
+            detect_document_language,
+            dominant_colors,
+            update_front_matter,
+            anchor_map,
+            render_with_timing,
+            export_bundle,
+            extract_tasks,
+            suggest_image_fixes,
+            fold_regions,
+            save_file,
+            render_slides,
+            keyword_summary,
+            validate_front_matter,
+            export_epub,
+            summarize,
+            path_breadcrumb,
+            fuzzy_find_files,
+            extract_aliases,
+            clipboard_html_to_markdown,
+            source_render_map,
+            highlight_code_blocks,
+            resolve_daily_note,
+            normalize_table,
+            render_chart_png,
+            find_duplicate_headings,
+            render_patch,
+            audit_alt_text,
+            generate_index,
+            list_media,
+            ensure_document_id,
+            find_by_id,
+            lint_document,
+            render_rtf,
+            resolve_front_matter,
+            save_reading_position,
+            get_reading_position,
+            search_snippet,
+            file_changed
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,151 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::lines::{next_code_span, source_lines};
+use super::parser::escape_html;
+
+/// `*[HTML]: HyperText Markup Language`
+static DEFINITION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s{0,3}\*\[([^\]]+)\]:\s*(.*?)\s*$").unwrap());
+
+/// Text where abbreviations are never expanded: links and images (text and
+/// target), autolinks, HTML tags, bare URLs and reference definitions
+static PROTECTED: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"!?\[[^\]]*\]\([^)]*\)|!?\[[^\]]*\]\[[^\]]*\]|<[^>]*>|https?://\S+|^\s{0,3}\[[^\]]+\]:.*$",
+    )
+    .unwrap()
+});
+
+/// Expands PHP Markdown Extra abbreviations.
+///
+/// Definition lines (`*[HTML]: HyperText Markup Language`) are blanked, keeping
+/// line numbers, and every whole-word, case-sensitive occurrence of a defined
+/// term becomes `<abbr title="...">HTML</abbr>`. Code, links and raw HTML are
+/// left alone. A term defined twice uses the last definition.
+pub fn expand_abbreviations(markdown: &str) -> String {
+    if !markdown.contains("*[") {
+        return markdown.to_string();
+    }
+
+    let lines = source_lines(markdown);
+    let mut definitions: Vec<(String, String)> = Vec::new();
+    let mut is_definition = vec![false; lines.len()];
+    for (index, line) in lines.iter().enumerate() {
+        let Some(caps) = DEFINITION.captures(line.text).filter(|_| !line.in_code) else {
+            continue;
+        };
+        let term = caps[1].trim().to_string();
+        definitions.retain(|(existing, _)| *existing != term);
+        definitions.push((term, caps[2].to_string()));
+        is_definition[index] = true;
+    }
+    if definitions.is_empty() {
+        return markdown.to_string();
+    }
+
+    // Longest terms first, so "HTML5" wins over "HTML"
+    definitions.sort_by_key(|definition| std::cmp::Reverse(definition.0.len()));
+    let pattern = definitions
+        .iter()
+        .map(|(term, _)| regex::escape(term))
+        .collect::<Vec<_>>()
+        .join("|");
+    let terms = Regex::new(&pattern).unwrap();
+
+    let output: Vec<String> = lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            if is_definition[index] {
+                String::new()
+            } else if line.in_code {
+                line.text.to_string()
+            } else {
+                expand_line(line.text, &terms, &definitions)
+            }
+        })
+        .collect();
+
+    let mut result = output.join("\n");
+    if markdown.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+/// Wraps terms in a line, skipping code spans and protected text
+fn expand_line(line: &str, terms: &Regex, definitions: &[(String, String)]) -> String {
+    let mut protected: Vec<(usize, usize)> = Vec::new();
+    let mut pos = 0;
+    while let Some(span) = next_code_span(line, pos) {
+        protected.push(span);
+        pos = span.1;
+    }
+    for found in PROTECTED.find_iter(line) {
+        protected.push((found.start(), found.end()));
+    }
+
+    let mut result = String::with_capacity(line.len());
+    let mut copied = 0;
+    for found in terms.find_iter(line) {
+        let (start, end) = (found.start(), found.end());
+        let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+        let whole_word = !line[..start].chars().next_back().is_some_and(is_word_char)
+            && !line[end..].chars().next().is_some_and(is_word_char);
+        let is_protected = protected.iter().any(|&(from, to)| start < to && end > from);
+        if !whole_word || is_protected {
+            continue;
+        }
+
+        let title = &definitions
+            .iter()
+            .find(|(term, _)| term == found.as_str())
+            .unwrap()
+            .1;
+        result.push_str(&line[copied..start]);
+        result.push_str(&format!(
+            "<abbr title=\"{}\">{}</abbr>",
+            escape_html(title),
+            found.as_str()
+        ));
+        copied = end;
+    }
+    result.push_str(&line[copied..]);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expands_defined_terms() {
+        let md = "HTML and CSS, not HTMLX or html.\n\n*[HTML]: HyperText Markup Language\n*[CSS]: Cascading \"Style\" Sheets\n";
+        assert_eq!(
+            expand_abbreviations(md),
+            "<abbr title=\"HyperText Markup Language\">HTML</abbr> and \
+             <abbr title=\"Cascading &quot;Style&quot; Sheets\">CSS</abbr>, not HTMLX or html.\n\n\n\n"
+        );
+    }
+
+    #[test]
+    fn test_code_and_links_untouched() {
+        let md = "*[API]: Application Programming Interface\nAPI `API` [API](https://x.dev/API) <span title=\"API\">\n```\nAPI\n```";
+        assert_eq!(
+            expand_abbreviations(md),
+            "\n<abbr title=\"Application Programming Interface\">API</abbr> `API` \
+             [API](https://x.dev/API) <span title=\"API\">\n```\nAPI\n```"
+        );
+    }
+
+    #[test]
+    fn test_longest_term_and_last_definition_win() {
+        let md = "HTML5 HTML\n*[HTML]: old\n*[HTML5]: Fifth\n*[HTML]: Markup";
+        assert_eq!(
+            expand_abbreviations(md),
+            "<abbr title=\"Fifth\">HTML5</abbr> <abbr title=\"Markup\">HTML</abbr>\n\n\n"
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+
+use super::images::MD_IMAGE_PATTERN;
+use super::lines::{next_code_span, source_lines};
+
+static MD_IMAGE: Lazy<Regex> = Lazy::new(|| Regex::new(MD_IMAGE_PATTERN).unwrap());
+
+static HTML_IMAGE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<img\b[^>]*>").unwrap());
+
+/// `name="value"` or `name='value'` inside a tag
+static ATTRIBUTE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)\s([a-z-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap());
+
+/// Alt texts that say nothing about the image
+const GENERIC_ALT: &[&str] = &[
+    "image",
+    "img",
+    "picture",
+    "pic",
+    "photo",
+    "screenshot",
+    "graphic",
+    "figure",
+    "alt",
+    "untitled",
+];
+
+/// Kind of alt text problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AltTextProblem {
+    /// An `<img>` without an `alt` attribute
+    Missing,
+    /// Blank alt text
+    Empty,
+    /// Alt text like "image" or the file name
+    Generic,
+}
+
+/// An image whose alt text doesn't describe it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AltTextIssue {
+    /// 1-based source line
+    pub line: usize,
+    pub src: String,
+    /// The alt text as written, if any
+    pub alt: Option<String>,
+    pub problem: AltTextProblem,
+    /// Human-readable summary
+    pub message: String,
+}
+
+/// Lists images (`![alt](src)` and `<img>`) with missing, empty or generic
+/// alt text, in document order.
+///
+/// Images in fenced code and code spans are ignored. An alt text is generic
+/// when it is a word like "image" or "screenshot" or the image's file name.
+pub fn audit_image_alt_text(markdown: &str) -> Vec<AltTextIssue> {
+    let mut issues = Vec::new();
+
+    for line in source_lines(markdown).into_iter().filter(|l| !l.in_code) {
+        let mut spans = Vec::new();
+        let mut pos = 0;
+        while let Some((start, end)) = next_code_span(line.text, pos) {
+            spans.push(start..end);
+            pos = end;
+        }
+        let in_code = |at: usize| spans.iter().any(|span| span.contains(&at));
+
+        let markdown_images = MD_IMAGE.captures_iter(line.text).map(|caps| {
+            let src = &caps[2];
+            let src = src
+                .strip_prefix('<')
+                .and_then(|s| s.strip_suffix('>'))
+                .unwrap_or(src);
+            (
+                caps.get(0).unwrap().start(),
+                src.to_string(),
+                Some(caps[1].to_string()),
+            )
+        });
+        let html_images = HTML_IMAGE.find_iter(line.text).map(|tag| {
+            let mut src = String::new();
+            let mut alt = None;
+            for caps in ATTRIBUTE.captures_iter(tag.as_str()) {
+                let value = caps.get(2).or_else(|| caps.get(3)).unwrap().as_str();
+                match caps[1].to_ascii_lowercase().as_str() {
+                    "src" => src = value.to_string(),
+                    "alt" => alt = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+            (tag.start(), src, alt)
+        });
+
+        let mut images: Vec<(usize, String, Option<String>)> = markdown_images
+            .chain(html_images)
+            .filter(|(at, _, _)| !in_code(*at))
+            .collect();
+        images.sort_by_key(|(at, _, _)| *at);
+
+        for (_, src, alt) in images {
+            let problem = match alt.as_deref().map(str::trim) {
+                None => AltTextProblem::Missing,
+                Some("") => AltTextProblem::Empty,
+                Some(text) if is_generic(text, &src) => AltTextProblem::Generic,
+                Some(_) => continue,
+            };
+            let message = match problem {
+                AltTextProblem::Missing => format!("Image {} has no alt text", src),
+                AltTextProblem::Empty => format!("Image {} has empty alt text", src),
+                AltTextProblem::Generic => format!(
+                    "Alt text '{}' doesn't describe image {}",
+                    alt.as_deref().unwrap_or_default().trim(),
+                    src
+                ),
+            };
+            issues.push(AltTextIssue {
+                line: line.number,
+                src,
+                alt,
+                problem,
+                message,
+            });
+        }
+    }
+
+    issues
+}
+
+/// True for placeholder words and for the image's own file name
+fn is_generic(alt: &str, src: &str) -> bool {
+    let alt = alt.to_lowercase();
+    if GENERIC_ALT.contains(&alt.as_str()) {
+        return true;
+    }
+
+    let file = src.split(['?', '#']).next().unwrap_or(src);
+    let file = file
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(file)
+        .to_lowercase();
+    let stem = file
+        .rsplit_once('.')
+        .map_or(file.as_str(), |(stem, _)| stem);
+    !file.is_empty() && (alt == file || alt == stem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(markdown: &str) -> Vec<(usize, AltTextProblem)> {
+        audit_image_alt_text(markdown)
+            .into_iter()
+            .map(|issue| (issue.line, issue.problem))
+            .collect()
+    }
+
+    #[test]
+    fn test_markdown_images() {
+        assert_eq!(problems("![](a.png)"), vec![(1, AltTextProblem::Empty)]);
+        assert!(problems("![A cat](a.png)").is_empty());
+
+        let issue = &audit_image_alt_text("Intro\n\n![ Image ](<img/a b.png>)")[0];
+        assert_eq!(issue.line, 3);
+        assert_eq!(issue.src, "img/a b.png");
+        assert_eq!(issue.problem, AltTextProblem::Generic);
+        assert_eq!(
+            issue.message,
+            "Alt text 'Image' doesn't describe image img/a b.png"
+        );
+    }
+
+    #[test]
+    fn test_file_name_alt_is_generic() {
+        assert_eq!(
+            problems("![diagram-2](assets/diagram-2.svg) ![cat.jpg](cat.jpg?v=1)"),
+            vec![(1, AltTextProblem::Generic), (1, AltTextProblem::Generic)]
+        );
+        assert!(problems("![Deployment diagram](assets/diagram-2.svg)").is_empty());
+    }
+
+    #[test]
+    fn test_html_images() {
+        let md = "<img src=\"a.png\"> <IMG ALT='' SRC='b.png'>\n<img alt=\"A dog\" src=\"c.png\">";
+        assert_eq!(
+            problems(md),
+            vec![(1, AltTextProblem::Missing), (1, AltTextProblem::Empty)]
+        );
+        assert_eq!(audit_image_alt_text(md)[1].src, "b.png");
+    }
+
+    #[test]
+    fn test_code_is_ignored() {
+        let md = "`![](a.png)`\n\n```\n<img src=\"b.png\">\n```\n";
+        assert!(problems(md).is_empty());
+    }
+}
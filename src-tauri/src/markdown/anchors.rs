@@ -0,0 +1,332 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use super::front_matter::extract_front_matter;
+use super::lines::source_lines;
+use super::links::percent_decode;
+use super::parser::{render_markdown_html_with, HtmlOptions, RenderedHtml};
+use super::slug::{heading_text, EmojiPolicy, HEADING_ID_PREFIX};
+
+/// `id` attributes in rendered HTML
+static ID_ATTR: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\sid="([^"]*)""#).unwrap());
+
+/// In-page anchor links: inline `](#x)`, reference definitions `]: #x` and `href="#x"`
+static ANCHOR_LINK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r##"\]\(\s*<?#([^)\s>]*)>?|^\s{0,3}\[[^\]]+\]:\s*<?#(\S*?)>?(?:\s|$)|href="#([^"]*)""##,
+    )
+    .unwrap()
+});
+
+/// A rendered heading: its anchor id and inner HTML
+static HEADING: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?s)<h[1-6][^>]*><a href="[^"]*" aria-hidden="true" class="anchor" id="([^"]*)"></a>(.*?)</h[1-6]>"#,
+    )
+    .unwrap()
+});
+
+/// A rendered heading with its top-level block id (when it is a top-level
+/// block), anchor id and inner HTML
+static BLOCK_HEADING: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?s)<h[1-6](?: data-block-id="([^"]*)")?[^>]*><a href="[^"]*" aria-hidden="true" class="anchor" id="([^"]*)"></a>(.*?)</h[1-6]>"#,
+    )
+    .unwrap()
+});
+
+/// Start tags of top-level blocks
+static BLOCK_ID_ATTR: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"\sdata-block-id="([^"]*)""#).unwrap());
+
+/// Inline code spans, which never contain links
+static CODE_SPAN: Lazy<Regex> = Lazy::new(|| Regex::new(r"`[^`]*`").unwrap());
+
+/// An in-page link whose target id doesn't exist
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnchorIssue {
+    /// 1-based line of the link
+    pub line: usize,
+    /// The fragment, without `#`
+    pub anchor: String,
+    /// An existing id the link probably meant (e.g. with the `heading-` prefix)
+    pub suggestion: Option<String>,
+}
+
+/// Reports `#anchor` links that don't match any id in the rendered document.
+///
+/// Ids are read from the HTML the renderer produces, so heading ids follow the
+/// same slugifier (emoji policy, GitHub ids) and raw HTML ids, footnotes
+/// and `#block-...` deep links count too.
+pub fn find_broken_anchors(
+    markdown: &str,
+    emoji_policy: EmojiPolicy,
+    github_ids: bool,
+) -> Vec<AnchorIssue> {
+    let (_, body) = extract_front_matter(markdown);
+    let options = HtmlOptions {
+        emoji_policy,
+        github_ids,
+        block_anchors: true,
+        ..Default::default()
+    };
+    let html = render_markdown_html_with(body, &options).html;
+    broken_anchors_in(markdown, &html)
+}
+
+/// [`find_broken_anchors`] against an already rendered body; `html` needs
+/// block anchors for `#block-...` links to count
+pub(super) fn broken_anchors_in(markdown: &str, html: &str) -> Vec<AnchorIssue> {
+    let ids: HashSet<&str> = ID_ATTR
+        .captures_iter(html)
+        .map(|c| c.get(1).unwrap().as_str())
+        .collect();
+
+    let mut issues = Vec::new();
+    for line in source_lines(markdown).into_iter().filter(|l| !l.in_code) {
+        let text = CODE_SPAN.replace_all(line.text, "");
+        for caps in ANCHOR_LINK.captures_iter(&text) {
+            let anchor = (1..=3).find_map(|i| caps.get(i)).unwrap().as_str();
+            if anchor.is_empty()
+                || ids.contains(anchor)
+                || ids.contains(percent_decode(anchor).as_str())
+            {
+                continue;
+            }
+            // The same heading with or without the `heading-` prefix
+            let lower = anchor.to_lowercase();
+            let suggestion = match lower.strip_prefix(HEADING_ID_PREFIX) {
+                Some(unprefixed) => unprefixed.to_string(),
+                None => format!("{}{}", HEADING_ID_PREFIX, lower),
+            };
+            issues.push(AnchorIssue {
+                line: line.number,
+                anchor: anchor.to_string(),
+                suggestion: ids.contains(suggestion.as_str()).then_some(suggestion),
+            });
+        }
+    }
+
+    issues
+}
+
+/// Maps each heading's plain text to the id the renderer gives it.
+///
+/// Ids come from the rendered document, so they always match the slugifier
+/// and emoji policy. A text used by several headings maps to the first one's
+/// id; later duplicates get suffixed ids (`-1`, `-2`) in the document.
+pub fn heading_anchor_map(
+    markdown: &str,
+    emoji_policy: EmojiPolicy,
+    github_ids: bool,
+) -> HashMap<String, String> {
+    let (_, body) = extract_front_matter(markdown);
+    let options = HtmlOptions {
+        emoji_policy,
+        github_ids,
+        ..Default::default()
+    };
+    let html = render_markdown_html_with(body, &options).html;
+
+    let mut anchors = HashMap::new();
+    for caps in HEADING.captures_iter(&html) {
+        let text = heading_text(&caps[2]).trim().to_string();
+        anchors.entry(text).or_insert_with(|| caps[1].to_string());
+    }
+    anchors
+}
+
+/// Headings sharing the same text, with the ids the renderer gives them
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DuplicateHeading {
+    /// Plain text of the headings
+    pub text: String,
+    /// 1-based line of each heading, in document order
+    pub lines: Vec<usize>,
+    /// Id of each heading, in the same order (`heading-notes`, `heading-notes-1`…)
+    pub ids: Vec<String>,
+}
+
+/// Reports heading texts used more than once, so links to them can be made
+/// unambiguous.
+///
+/// Ids come from the rendered document, so the suffixes match what the
+/// renderer assigns. A heading nested in a quote or list reports the first
+/// line of its enclosing block.
+pub fn duplicate_headings(
+    markdown: &str,
+    emoji_policy: EmojiPolicy,
+    github_ids: bool,
+) -> Vec<DuplicateHeading> {
+    let (_, body) = extract_front_matter(markdown);
+    let options = HtmlOptions {
+        emoji_policy,
+        github_ids,
+        block_ids: true,
+        ..Default::default()
+    };
+    let rendered = render_markdown_html_with(body, &options);
+    duplicate_headings_in(markdown, body, &rendered)
+}
+
+/// [`duplicate_headings`] against an already rendered `body` (the markdown
+/// after its front matter); `rendered` needs block ids for the lines
+pub(super) fn duplicate_headings_in(
+    markdown: &str,
+    body: &str,
+    rendered: &RenderedHtml,
+) -> Vec<DuplicateHeading> {
+    let front_matter_lines = markdown[..markdown.len() - body.len()]
+        .matches('\n')
+        .count();
+    let block_lines: HashMap<&str, usize> = rendered
+        .block_ids
+        .iter()
+        .zip(&rendered.block_lines)
+        .map(|(id, &(start, _))| (id.as_str(), start))
+        .collect();
+    // Where each top-level block starts in the HTML, for nested headings
+    let block_starts: Vec<(usize, &str)> = BLOCK_ID_ATTR
+        .captures_iter(&rendered.html)
+        .map(|caps| (caps.get(0).unwrap().start(), caps.get(1).unwrap().as_str()))
+        .collect();
+
+    let mut duplicates: Vec<DuplicateHeading> = Vec::new();
+    for caps in BLOCK_HEADING.captures_iter(&rendered.html) {
+        let block_id = caps.get(1).map(|id| id.as_str()).or_else(|| {
+            let position = caps.get(0).unwrap().start();
+            let enclosing = block_starts.partition_point(|&(start, _)| start < position);
+            enclosing.checked_sub(1).map(|i| block_starts[i].1)
+        });
+        let line = block_id
+            .and_then(|id| block_lines.get(id))
+            .map_or(0, |line| line + front_matter_lines);
+
+        let text = heading_text(&caps[3]).trim().to_string();
+        let index = match duplicates.iter().position(|d| d.text == text) {
+            Some(index) => index,
+            None => {
+                duplicates.push(DuplicateHeading {
+                    text,
+                    lines: Vec::new(),
+                    ids: Vec::new(),
+                });
+                duplicates.len() - 1
+            }
+        };
+        duplicates[index].lines.push(line);
+        duplicates[index].ids.push(caps[2].to_string());
+    }
+
+    duplicates.retain(|heading| heading.ids.len() > 1);
+    duplicates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_and_broken_anchors() {
+        let md = "# Setup\n\n## 🚀 Launch\n\n[ok](#heading-setup) [emoji](#heading-launch)\n[broken](#heading-missing)\n[short](#Setup)";
+        let issues = find_broken_anchors(md, EmojiPolicy::Strip, false);
+
+        assert_eq!(
+            issues,
+            vec![
+                AnchorIssue {
+                    line: 6,
+                    anchor: "heading-missing".to_string(),
+                    suggestion: None,
+                },
+                AnchorIssue {
+                    line: 7,
+                    anchor: "Setup".to_string(),
+                    suggestion: Some("heading-setup".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_follows_emoji_policy() {
+        let md = "## 🚀 Launch\n\n[go](#heading-rocket-launch)";
+        assert!(find_broken_anchors(md, EmojiPolicy::Shortcode, false).is_empty());
+        assert_eq!(find_broken_anchors(md, EmojiPolicy::Strip, false).len(), 1);
+    }
+
+    #[test]
+    fn test_other_targets_and_code_are_ignored() {
+        let md = "<a id=\"custom\"></a>\n\n[a](#custom) `[b](#nope)`\n\n```\n[c](#nope)\n```\n\n[ref]: #custom\n<a href=\"#gone\">x</a>";
+        let issues = find_broken_anchors(md, EmojiPolicy::Strip, false);
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].anchor, "gone");
+        assert_eq!(issues[0].line, 10);
+    }
+
+    #[test]
+    fn test_heading_anchor_map() {
+        let md = "---\ntitle: x\n---\n# Getting *Started*\n\n## 🚀 Launch\n\n## Notes\n\n### Notes\n\n```\n# not a heading\n```";
+        let anchors = heading_anchor_map(md, EmojiPolicy::Strip, false);
+
+        assert_eq!(anchors.len(), 3);
+        assert_eq!(anchors["Getting Started"], "heading-getting-started");
+        assert_eq!(anchors["🚀 Launch"], "heading-launch");
+        assert_eq!(anchors["Notes"], "heading-notes");
+
+        let anchors = heading_anchor_map(md, EmojiPolicy::Shortcode, false);
+        assert_eq!(anchors["🚀 Launch"], "heading-rocket-launch");
+    }
+
+    #[test]
+    fn test_github_ids() {
+        let md = "# Getting Started\n\n## Notes\n\n## Notes\n\n\
+                  [ok](#getting-started) [dup](#notes-1) [old](#heading-notes)";
+        let anchors = heading_anchor_map(md, EmojiPolicy::Strip, true);
+        assert_eq!(anchors["Getting Started"], "getting-started");
+
+        let issues = find_broken_anchors(md, EmojiPolicy::Strip, true);
+        assert_eq!(
+            issues,
+            vec![AnchorIssue {
+                line: 7,
+                anchor: "heading-notes".to_string(),
+                suggestion: Some("notes".to_string()),
+            }]
+        );
+
+        let duplicates = duplicate_headings(md, EmojiPolicy::Strip, true);
+        assert_eq!(duplicates[0].ids, vec!["notes", "notes-1"]);
+    }
+
+    #[test]
+    fn test_duplicate_headings() {
+        let md = "# Notes\n\n# Notes\n\n[second](#heading-notes-1) [third](#heading-notes-2)";
+        let issues = find_broken_anchors(md, EmojiPolicy::Strip, false);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].anchor, "heading-notes-2");
+    }
+
+    #[test]
+    fn test_find_duplicate_headings() {
+        let md = "---\ntitle: x\n---\n# Notes\n\ntext\n\n## *Notes*\n\n# Intro\n\n> # Notes\n";
+        let duplicates = duplicate_headings(md, EmojiPolicy::Strip, false);
+
+        assert_eq!(
+            duplicates,
+            vec![DuplicateHeading {
+                text: "Notes".to_string(),
+                lines: vec![4, 8, 12],
+                ids: vec![
+                    "heading-notes".to_string(),
+                    "heading-notes-1".to_string(),
+                    "heading-notes-2".to_string(),
+                ],
+            }]
+        );
+        assert!(duplicate_headings("# A\n\n# B\n", EmojiPolicy::Strip, false).is_empty());
+    }
+}
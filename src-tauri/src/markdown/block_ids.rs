@@ -0,0 +1,328 @@
+use comrak::nodes::{AstNode, NodeValue};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+
+/// `data-sourcepos` attributes emitted by comrak's sourcepos option, with the
+/// start of the tag they belong to
+static SOURCEPOS_ATTR: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(<(\w+)[^<>]*?) data-sourcepos="([^"]*)""#).unwrap());
+
+/// A deep-link id for a list item or table
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockAnchor {
+    /// Element the id goes on ("li" or "table")
+    pub tag: &'static str,
+    /// comrak sourcepos of the node ("3:1-4:9")
+    pub sourcepos: String,
+    /// The `block-...` id
+    pub id: String,
+}
+
+/// Computes a content-derived id for each top-level block of a parsed document.
+///
+/// Returns `(sourcepos, id)` pairs in document order. The id hashes the
+/// block's source lines, so editing one block leaves the others' ids intact.
+/// Identical blocks get a numeric suffix ("b-1a2b3c4d5e6f-1").
+pub fn top_level_block_ids<'a>(root: &'a AstNode<'a>, markdown: &str) -> Vec<(String, String)> {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut blocks = Vec::new();
+
+    for node in root.children() {
+        let data = node.data.borrow();
+        if matches!(data.value, NodeValue::FrontMatter(_)) {
+            continue;
+        }
+
+        let source = source_text(&lines, data.sourcepos.start.line, data.sourcepos.end.line);
+        let id = unique_id(&mut seen, "b", &source);
+        blocks.push((data.sourcepos.to_string(), id));
+    }
+
+    blocks
+}
+
+/// Computes `block-...` ids for every list item and table, at any depth.
+///
+/// Like top-level block ids they hash the source, so they stay stable while
+/// the content is unchanged. A list item hashes only its own text (not nested
+/// lists, its marker or its task checkbox), so renumbering, ticking or editing
+/// a sub-item keeps its link working.
+pub fn block_anchor_ids<'a>(root: &'a AstNode<'a>, markdown: &str) -> Vec<BlockAnchor> {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut anchors = Vec::new();
+
+    for node in root.descendants() {
+        let data = node.data.borrow();
+        let (tag, source) = match data.value {
+            NodeValue::Item(_) | NodeValue::TaskItem(_) => {
+                // Stop before the first nested list
+                let end = node
+                    .children()
+                    .find(|child| matches!(child.data.borrow().value, NodeValue::List(_)))
+                    .map_or(data.sourcepos.end.line, |list| {
+                        list.data.borrow().sourcepos.start.line.saturating_sub(1)
+                    });
+                let source = source_text(&lines, data.sourcepos.start.line, end);
+                ("li", strip_list_marker(&source).to_string())
+            }
+            NodeValue::Table(_) => {
+                let (start, end) = (data.sourcepos.start.line, data.sourcepos.end.line);
+                ("table", source_text(&lines, start, end))
+            }
+            _ => continue,
+        };
+
+        anchors.push(BlockAnchor {
+            tag,
+            sourcepos: data.sourcepos.to_string(),
+            id: unique_id(&mut seen, "block", &source),
+        });
+    }
+
+    anchors
+}
+
+/// Source lines `start..=end` (1-based), joined and trimmed
+fn source_text(lines: &[&str], start: usize, end: usize) -> String {
+    let start = start.saturating_sub(1);
+    let end = end.min(lines.len());
+    lines
+        .get(start..end)
+        .map(|l| l.join("\n").trim().to_string())
+        .unwrap_or_default()
+}
+
+/// `{prefix}-{hash}`, with a numeric suffix for repeated content
+fn unique_id(seen: &mut HashMap<String, usize>, prefix: &str, source: &str) -> String {
+    let base = format!("{}-{:012x}", prefix, fnv1a(source) >> 16);
+    let count = seen.entry(base.clone()).or_insert(0);
+    let id = if *count == 0 {
+        base
+    } else {
+        format!("{}-{}", base, count)
+    };
+    *count += 1;
+    id
+}
+
+/// Drops a leading bullet/number marker and task checkbox from item source
+fn strip_list_marker(source: &str) -> &str {
+    let text = source.trim_start();
+    let text = match text.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None => {
+            let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            text[digits..].strip_prefix(['.', ')']).filter(|_| digits > 0).unwrap_or(text)
+        }
+    };
+    let text = text.trim_start();
+    ["[ ]", "[x]", "[X]"]
+        .iter()
+        .find_map(|checkbox| text.strip_prefix(checkbox))
+        .unwrap_or(text)
+        .trim_start()
+}
+
+/// Replaces the `data-sourcepos` attribute of each top-level block element
+/// with its `data-block-id`, adds an `id` to anchored list items and tables
+/// and drops all other sourcepos attributes.
+///
+/// Returns the HTML and the block ids that were placed, in document order.
+/// Blocks rendered without a wrapping element (raw HTML) get no id.
+pub fn tag_blocks(
+    html: &str,
+    blocks: &[(String, String)],
+    anchors: &[BlockAnchor],
+) -> (String, Vec<String>) {
+    let mut pending: HashMap<&str, &str> =
+        blocks.iter().map(|(pos, id)| (pos.as_str(), id.as_str())).collect();
+    // Sourcepos, then tag: a one-item list and its item share a sourcepos
+    let mut pending_anchors: HashMap<&str, HashMap<&str, &str>> = HashMap::new();
+    for anchor in anchors {
+        pending_anchors
+            .entry(anchor.sourcepos.as_str())
+            .or_default()
+            .insert(anchor.tag, anchor.id.as_str());
+    }
+    let mut placed = Vec::new();
+
+    let html = SOURCEPOS_ATTR
+        .replace_all(html, |caps: &Captures| {
+            let mut tag = caps[1].to_string();
+            let anchor = pending_anchors
+                .get_mut(&caps[3])
+                .and_then(|tags| tags.remove(&caps[2]));
+            if let Some(id) = anchor {
+                tag.push_str(&format!(" id=\"{}\"", id));
+            }
+            if let Some(id) = pending.remove(&caps[3]) {
+                placed.push(id.to_string());
+                tag.push_str(&format!(" data-block-id=\"{}\"", id));
+            }
+            tag
+        })
+        .into_owned();
+
+    (html, placed)
+}
+
+/// Line ranges of the placed blocks, in the same order as `placed`
+///
+/// `blocks` are the `(sourcepos, id)` pairs from [`top_level_block_ids`];
+/// ranges are 1-based and inclusive.
+pub fn placed_block_lines(blocks: &[(String, String)], placed: &[String]) -> Vec<(usize, usize)> {
+    let positions: HashMap<&str, &str> =
+        blocks.iter().map(|(pos, id)| (id.as_str(), pos.as_str())).collect();
+    placed
+        .iter()
+        .map(|id| positions.get(id.as_str()).map_or((0, 0), |pos| sourcepos_lines(pos)))
+        .collect()
+}
+
+/// Start and end lines of a `3:1-4:9` sourcepos
+fn sourcepos_lines(sourcepos: &str) -> (usize, usize) {
+    let line = |part: Option<&str>| {
+        part.and_then(|p| p.split(':').next())
+            .and_then(|l| l.parse().ok())
+            .unwrap_or(0)
+    };
+    let mut parts = sourcepos.split('-');
+    (line(parts.next()), line(parts.next()))
+}
+
+/// 64-bit FNV-1a: a small hash that is stable across builds and platforms
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::parser::{render_markdown_html_with, HtmlOptions};
+
+    fn render(markdown: &str) -> (String, Vec<String>) {
+        let options = HtmlOptions {
+            block_ids: true,
+            ..Default::default()
+        };
+        let rendered = render_markdown_html_with(markdown, &options);
+        (rendered.html, rendered.block_ids)
+    }
+
+    fn render_anchors(markdown: &str) -> String {
+        let options = HtmlOptions {
+            block_anchors: true,
+            ..Default::default()
+        };
+        render_markdown_html_with(markdown, &options).html
+    }
+
+    fn ids(html: &str) -> Vec<String> {
+        Regex::new(r#" id="(block-[^"]*)""#)
+            .unwrap()
+            .captures_iter(html)
+            .map(|c| c[1].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_every_top_level_block_gets_an_id() {
+        let (html, ids) = render("# Title\n\nFirst paragraph.\n\n- a\n- b\n\n> quote");
+
+        assert_eq!(ids.len(), 4);
+        for id in &ids {
+            assert!(html.contains(&format!("data-block-id=\"{}\"", id)));
+        }
+        assert!(!html.contains("data-sourcepos"));
+        assert!(html.contains("<li>a</li>"));
+    }
+
+    #[test]
+    fn test_editing_one_block_keeps_other_ids() {
+        let (_, before) = render("# Title\n\nFirst paragraph.\n\nSecond paragraph.");
+        let (_, after) = render("# Title\n\nFirst paragraph, edited.\n\nSecond paragraph.");
+
+        assert_eq!(before[0], after[0]);
+        assert_ne!(before[1], after[1]);
+        assert_eq!(before[2], after[2]);
+    }
+
+    #[test]
+    fn test_block_lines() {
+        let rendered = render_markdown_html_with(
+            "# Title\n\nFirst\nparagraph.\n\n- a\n- b",
+            &HtmlOptions {
+                block_ids: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(rendered.block_lines, vec![(1, 1), (3, 4), (6, 7)]);
+        assert_eq!(sourcepos_lines("12:1-15:3"), (12, 15));
+    }
+
+    #[test]
+    fn test_duplicate_blocks_get_unique_ids() {
+        let (_, ids) = render("Same\n\nSame");
+        assert_eq!(ids[1], format!("{}-1", ids[0]));
+    }
+
+    #[test]
+    fn test_tag_blocks() {
+        let html = r#"<ul data-sourcepos="1:1-2:3"><li data-sourcepos="1:1-1:3">a</li></ul>"#;
+        let blocks = vec![("1:1-2:3".to_string(), "b-1".to_string())];
+
+        let (tagged, placed) = tag_blocks(html, &blocks, &[]);
+
+        assert_eq!(tagged, r#"<ul data-block-id="b-1"><li>a</li></ul>"#);
+        assert_eq!(placed, vec!["b-1"]);
+    }
+
+    #[test]
+    fn test_tag_anchors_by_element() {
+        let html = r#"<ul data-sourcepos="1:1-1:3"><li data-sourcepos="1:1-1:3">a</li></ul>"#;
+        let blocks = vec![("1:1-1:3".to_string(), "b-1".to_string())];
+        let anchors = vec![BlockAnchor {
+            tag: "li",
+            sourcepos: "1:1-1:3".to_string(),
+            id: "block-1".to_string(),
+        }];
+
+        let (tagged, _) = tag_blocks(html, &blocks, &anchors);
+
+        assert_eq!(tagged, r#"<ul data-block-id="b-1"><li id="block-1">a</li></ul>"#);
+    }
+
+    #[test]
+    fn test_list_items_and_tables_get_anchor_ids() {
+        let md = "- one\n- two\n  - nested\n\n| a | b |\n|---|---|\n| 1 | 2 |";
+        let html = render_anchors(md);
+
+        let ids = ids(&html);
+        assert_eq!(ids.len(), 4);
+        assert!(html.contains(&format!("<table id=\"{}\">", ids[3])));
+        assert!(!html.contains("data-sourcepos"));
+        assert!(!html.contains("data-block-id"));
+    }
+
+    #[test]
+    fn test_anchor_ids_are_stable() {
+        let before = ids(&render_anchors("- [ ] one\n- two\n  - nested"));
+        let after = ids(&render_anchors("Intro\n\n- [x] one\n- two\n  - nested, edited"));
+
+        assert_eq!(before, ids(&render_anchors("- [ ] one\n- two\n  - nested")));
+        assert_eq!(before[0], after[0]);
+        assert_eq!(before[1], after[1]);
+        assert_ne!(before[2], after[2]);
+    }
+
+    #[test]
+    fn test_strip_list_marker() {
+        assert_eq!(strip_list_marker("- [x] done"), "done");
+        assert_eq!(strip_list_marker("12) step"), "step");
+    }
+}
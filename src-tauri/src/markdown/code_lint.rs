@@ -0,0 +1,135 @@
+use serde::Serialize;
+
+use super::lines::{fenced_blocks, SourceLine};
+
+/// Kind of problem found in a code block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CodeLintIssue {
+    /// Indentation mixes tabs and spaces (within a line or across the block)
+    MixedIndentation,
+    /// Lines end with spaces or tabs
+    TrailingWhitespace,
+}
+
+/// An advisory lint for one fenced code block
+#[derive(Debug, Clone, Serialize)]
+pub struct CodeLint {
+    /// 1-based line of the block's opening fence
+    pub block_line: usize,
+    /// Language of the block, if given
+    pub lang: Option<String>,
+    pub issue: CodeLintIssue,
+    /// 1-based source lines with the issue
+    pub lines: Vec<usize>,
+    /// Human-readable summary
+    pub message: String,
+}
+
+/// Flags fenced code blocks with mixed tab/space indentation or trailing
+/// whitespace. Reports at most one lint per issue per block.
+pub fn lint_fenced_code(markdown: &str) -> Vec<CodeLint> {
+    let mut lints = Vec::new();
+
+    for block in fenced_blocks(markdown) {
+        let lang = block.lang.map(str::to_string);
+        let mut push = |issue, lines: Vec<usize>, what: &str| {
+            if !lines.is_empty() {
+                let message = format!("{} on {} line(s)", what, lines.len());
+                lints.push(CodeLint {
+                    block_line: block.start_line,
+                    lang: lang.clone(),
+                    issue,
+                    lines,
+                    message,
+                });
+            }
+        };
+
+        push(
+            CodeLintIssue::MixedIndentation,
+            mixed_indentation_lines(&block.lines),
+            "Mixed tabs and spaces in indentation",
+        );
+
+        let trailing = block
+            .lines
+            .iter()
+            .filter(|l| !l.text.trim().is_empty() && l.text.ends_with([' ', '\t']))
+            .map(|l| l.number)
+            .collect();
+        push(CodeLintIssue::TrailingWhitespace, trailing, "Trailing whitespace");
+    }
+
+    lints
+}
+
+/// Lines whose indent mixes tabs and spaces, plus the lines using the less
+/// common style when the block indents with both
+fn mixed_indentation_lines(lines: &[SourceLine]) -> Vec<usize> {
+    let mut mixed = Vec::new();
+    let mut tabs = Vec::new();
+    let mut spaces = Vec::new();
+
+    for line in lines {
+        let indent_len = line.text.len() - line.text.trim_start_matches([' ', '\t']).len();
+        let indent = &line.text[..indent_len];
+        if indent_len == line.text.len() {
+            continue; // blank line
+        }
+        match (indent.contains('\t'), indent.contains(' ')) {
+            (true, true) => mixed.push(line.number),
+            (true, false) => tabs.push(line.number),
+            (false, true) => spaces.push(line.number),
+            (false, false) => {}
+        }
+    }
+
+    if !tabs.is_empty() && !spaces.is_empty() {
+        mixed.extend(if tabs.len() < spaces.len() { tabs } else { spaces });
+    }
+    mixed.sort_unstable();
+    mixed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_block_has_no_lints() {
+        let md = "```rust\nfn main() {\n    run();\n}\n```\n";
+        assert!(lint_fenced_code(md).is_empty());
+    }
+
+    #[test]
+    fn test_mixed_indentation() {
+        let md = "Intro\n\n```python\ndef f():\n    a = 1\n\tb = 2\n    return a\n```\n";
+        let lints = lint_fenced_code(md);
+
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].issue, CodeLintIssue::MixedIndentation);
+        assert_eq!(lints[0].block_line, 3);
+        assert_eq!(lints[0].lang.as_deref(), Some("python"));
+        assert_eq!(lints[0].lines, vec![6]);
+    }
+
+    #[test]
+    fn test_mixed_within_line() {
+        let md = "```\nif x:\n \tpass\n```";
+        let lints = lint_fenced_code(md);
+        assert_eq!(lints[0].issue, CodeLintIssue::MixedIndentation);
+        assert_eq!(lints[0].lines, vec![3]);
+    }
+
+    #[test]
+    fn test_trailing_whitespace_per_block() {
+        let md = "```\na \nb\t\n\n```\n\n```js\nok\n```\n\n    indented  \n";
+        let lints = lint_fenced_code(md);
+
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].issue, CodeLintIssue::TrailingWhitespace);
+        assert_eq!(lints[0].lines, vec![2, 3]);
+        assert_eq!(lints[0].message, "Trailing whitespace on 2 line(s)");
+    }
+}
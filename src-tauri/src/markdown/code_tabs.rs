@@ -0,0 +1,140 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::parser::escape_html;
+
+/// The comment that starts a tab group
+static TABS_MARKER: Lazy<Regex> = Lazy::new(|| Regex::new(r"<!--\s*tabs\s*-->").unwrap());
+
+/// The `lang` attribute of a code block's `<pre>`
+static PRE_LANG: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"^<pre\b[^>]*?\slang="([^"]*)""#).unwrap());
+
+/// Groups the code blocks following a `<!-- tabs -->` comment into a tabbed
+/// `<div class="code-tabs">`, for showing one example in several languages.
+///
+/// The group runs over consecutive code blocks (only whitespace between
+/// them) and each tab is labelled with its block's language. The first tab
+/// is selected; the preview switches panels by `data-tab` when a tab is
+/// clicked. A marker not followed by a code block is left alone.
+pub fn group_code_tabs(html: &str) -> String {
+    let mut result = String::with_capacity(html.len());
+    let mut pos = 0;
+
+    while let Some(marker) = TABS_MARKER.find_at(html, pos) {
+        let blocks = code_blocks_at(html, marker.end());
+        let Some(&(_, end)) = blocks.last() else {
+            result.push_str(&html[pos..marker.end()]);
+            pos = marker.end();
+            continue;
+        };
+
+        let blocks: Vec<&str> = blocks
+            .iter()
+            .map(|&(start, end)| &html[start..end])
+            .collect();
+        result.push_str(&html[pos..marker.start()]);
+        result.push_str(&tab_group(&blocks));
+        pos = end;
+    }
+    result.push_str(&html[pos..]);
+    result
+}
+
+/// `(start, end)` of each `<pre>` element in the run starting at `from`
+fn code_blocks_at(html: &str, from: usize) -> Vec<(usize, usize)> {
+    let mut blocks = Vec::new();
+    let mut pos = from;
+    loop {
+        let start = pos + (html[pos..].len() - html[pos..].trim_start().len());
+        if !html[start..].starts_with("<pre") {
+            break;
+        }
+        let Some(close) = html[start..].find("</pre>") else {
+            break;
+        };
+        pos = start + close + "</pre>".len();
+        blocks.push((start, pos));
+    }
+    blocks
+}
+
+/// The tab buttons and one panel per code block
+fn tab_group(blocks: &[&str]) -> String {
+    let mut buttons = String::new();
+    let mut panels = String::new();
+    for (index, block) in blocks.iter().enumerate() {
+        let label = PRE_LANG
+            .captures(block)
+            .map_or_else(|| "Text".to_string(), |caps| caps[1].to_string());
+        let selected = index == 0;
+        buttons.push_str(&format!(
+            "<button type=\"button\" class=\"code-tab{}\" role=\"tab\" aria-selected=\"{}\" \
+             data-tab=\"{}\">{}</button>",
+            if selected { " active" } else { "" },
+            selected,
+            index,
+            escape_html(&label)
+        ));
+        panels.push_str(&format!(
+            "<div class=\"code-tab-panel{}\" role=\"tabpanel\" data-tab=\"{}\"{}>{}</div>\n",
+            if selected { " active" } else { "" },
+            index,
+            if selected { "" } else { " hidden" },
+            block
+        ));
+    }
+
+    format!(
+        "<div class=\"code-tabs\">\n<div class=\"code-tab-list\" role=\"tablist\">{}</div>\n{}</div>",
+        buttons, panels
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::parser::render_markdown_html;
+
+    #[test]
+    fn test_two_language_group() {
+        let md =
+            "<!-- tabs -->\n\n```rust\nfn main() {}\n```\n\n```python\nprint(1)\n```\n\nAfter.";
+        let html = render_markdown_html(md);
+
+        assert!(html.starts_with("<div class=\"code-tabs\">"));
+        assert!(html.contains(
+            "<button type=\"button\" class=\"code-tab active\" role=\"tab\" \
+             aria-selected=\"true\" data-tab=\"0\">rust</button>"
+        ));
+        assert!(html.contains(
+            "<button type=\"button\" class=\"code-tab\" role=\"tab\" \
+             aria-selected=\"false\" data-tab=\"1\">python</button>"
+        ));
+        assert!(html.contains(
+            "<div class=\"code-tab-panel active\" role=\"tabpanel\" data-tab=\"0\"><pre class="
+        ));
+        assert!(html.contains(
+            "<div class=\"code-tab-panel\" role=\"tabpanel\" data-tab=\"1\" hidden><pre class="
+        ));
+        assert!(!html.contains("<!-- tabs -->"));
+        assert!(html.ends_with("</div>\n<p>After.</p>\n"));
+    }
+
+    #[test]
+    fn test_group_stops_at_other_content() {
+        let html = "<!-- tabs -->\n<pre lang=\"sh\"><code>ls</code></pre>\n<p>Text</p>\n\
+                    <pre><code>x</code></pre>";
+        let grouped = group_code_tabs(html);
+
+        assert_eq!(grouped.matches("code-tab-panel").count(), 1);
+        assert!(grouped.contains("data-tab=\"0\">sh</button>"));
+        assert!(grouped.ends_with("</div>\n<p>Text</p>\n<pre><code>x</code></pre>"));
+    }
+
+    #[test]
+    fn test_marker_without_code_is_kept() {
+        let html = "<!-- tabs -->\n<p>No code</p>\n";
+        assert_eq!(group_code_tabs(html), html);
+    }
+}
@@ -0,0 +1,85 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+use super::search_hits::{tag_name, TAG};
+
+/// `#RGB`, `#RRGGBB` or `#RRGGBBAA` not glued to a word, entity (`&#123;`)
+/// or URL; the first group is whatever precedes the `#`
+static HEX_COLOR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(^|[^\w&#/])(#(?:[0-9a-fA-F]{8}|[0-9a-fA-F]{6}|[0-9a-fA-F]{3}))\b").unwrap()
+});
+
+/// Elements whose text never gets swatches
+const SKIPPED_ELEMENTS: &[&str] = &["pre", "a", "script", "style", "textarea", "svg", "math"];
+
+/// Inserts a `<span class="color-swatch">` showing the color after each hex
+/// color code in rendered prose and inline code.
+///
+/// Only whole `#RGB`, `#RRGGBB` and `#RRGGBBAA` codes match, so `#heading`
+/// or `#12345` don't. Code blocks, links, attributes and entities are left
+/// alone.
+pub fn insert_color_swatches(html: &str) -> String {
+    if !html.contains('#') {
+        return html.to_string();
+    }
+
+    let mut result = String::with_capacity(html.len());
+    let mut skip_depth = 0usize;
+    let mut last = 0;
+
+    for tag in TAG.find_iter(html) {
+        push_text(&mut result, &html[last..tag.start()], skip_depth == 0);
+        result.push_str(tag.as_str());
+        last = tag.end();
+
+        let (name, closing) = tag_name(tag.as_str());
+        if SKIPPED_ELEMENTS.contains(&name.as_str()) && !tag.as_str().ends_with("/>") {
+            if closing {
+                skip_depth = skip_depth.saturating_sub(1);
+            } else {
+                skip_depth += 1;
+            }
+        }
+    }
+    push_text(&mut result, &html[last..], skip_depth == 0);
+
+    result
+}
+
+fn push_text(result: &mut String, text: &str, swatches: bool) {
+    if !swatches || !text.contains('#') {
+        result.push_str(text);
+        return;
+    }
+    let with_swatches = HEX_COLOR.replace_all(text, |caps: &Captures| {
+        format!(
+            "{}{}<span class=\"color-swatch\" style=\"background:{}\"></span>",
+            &caps[1], &caps[2], &caps[2]
+        )
+    });
+    result.push_str(&with_swatches);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_colors_get_swatches() {
+        let html = "<p>Primary is #ff0000, text <code>#333</code> and glass #00000080.</p>";
+        assert_eq!(
+            insert_color_swatches(html),
+            "<p>Primary is #ff0000<span class=\"color-swatch\" style=\"background:#ff0000\"></span>, \
+             text <code>#333<span class=\"color-swatch\" style=\"background:#333\"></span></code> \
+             and glass #00000080<span class=\"color-swatch\" style=\"background:#00000080\"></span>.</p>"
+        );
+    }
+
+    #[test]
+    fn test_non_colors_untouched() {
+        let html = "<h2 id=\"heading\"><a href=\"#heading\">#heading</a></h2>\
+                    <p>See #heading, issue #12345, #ff00001, a&#123;b, x.com/#fff</p>\
+                    <pre><code>color: #ff0000;</code></pre>";
+        assert_eq!(insert_color_swatches(html), html);
+    }
+}
@@ -0,0 +1,104 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+use super::lines::{next_code_span, source_lines};
+
+/// `{++ins++}`, `{--del--}`, `{~~old~>new~~}`, `{==highlight==}` or
+/// `{>>comment<<}` on one line
+static CRITIC: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\{\+\+(.*?)\+\+\}|\{--(.*?)--\}|\{~~(.*?)~>(.*?)~~\}|\{==(.*?)==\}|\{>>(.*?)<<\}")
+        .unwrap()
+});
+
+/// Expands CriticMarkup edits into HTML: insertions become `<ins>`, deletions
+/// `<del>`, substitutions a `<del>` followed by an `<ins>`, highlights
+/// `<mark>` and comments `<span class="critic-comment">`.
+///
+/// Every element gets the `critic` class. The text inside stays markdown.
+/// Code spans and fenced code are left alone, and line numbers are preserved.
+pub fn expand_criticmarkup(markdown: &str) -> String {
+    let lines: Vec<String> = source_lines(markdown)
+        .into_iter()
+        .map(|line| {
+            if line.in_code || !line.text.contains('{') {
+                line.text.to_string()
+            } else {
+                expand_outside_code_spans(line.text)
+            }
+        })
+        .collect();
+
+    let mut result = lines.join("\n");
+    if markdown.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+/// Applies the expansion to the parts of a line outside backtick code spans
+fn expand_outside_code_spans(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut pos = 0;
+
+    while let Some((start, end)) = next_code_span(line, pos) {
+        result.push_str(&expand(&line[pos..start]));
+        result.push_str(&line[start..end]);
+        pos = end;
+    }
+    result.push_str(&expand(&line[pos..]));
+
+    result
+}
+
+fn expand(text: &str) -> String {
+    CRITIC
+        .replace_all(text, |caps: &Captures| {
+            if let Some(inserted) = caps.get(1) {
+                format!("<ins class=\"critic\">{}</ins>", inserted.as_str())
+            } else if let Some(deleted) = caps.get(2) {
+                format!("<del class=\"critic\">{}</del>", deleted.as_str())
+            } else if let (Some(old), Some(new)) = (caps.get(3), caps.get(4)) {
+                format!(
+                    "<del class=\"critic\">{}</del><ins class=\"critic\">{}</ins>",
+                    old.as_str(),
+                    new.as_str()
+                )
+            } else if let Some(highlighted) = caps.get(5) {
+                format!("<mark class=\"critic\">{}</mark>", highlighted.as_str())
+            } else {
+                format!("<span class=\"critic critic-comment\">{}</span>", &caps[6])
+            }
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_each_form() {
+        assert_eq!(
+            expand_criticmarkup("Add {++new **text**++} and {--old--}."),
+            "Add <ins class=\"critic\">new **text**</ins> and <del class=\"critic\">old</del>."
+        );
+        assert_eq!(
+            expand_criticmarkup("Use {~~tabs~>spaces~~}."),
+            "Use <del class=\"critic\">tabs</del><ins class=\"critic\">spaces</ins>."
+        );
+        assert_eq!(
+            expand_criticmarkup("{==Check this==}{>>Is it right?<<}"),
+            "<mark class=\"critic\">Check this</mark>\
+             <span class=\"critic critic-comment\">Is it right?</span>"
+        );
+    }
+
+    #[test]
+    fn test_code_untouched() {
+        let md = "`{++a++}` {--b--}\n```\n{++c++}\n```\n";
+        assert_eq!(
+            expand_criticmarkup(md),
+            "`{++a++}` <del class=\"critic\">b</del>\n```\n{++c++}\n```\n"
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+/// Formats a date relative to `now` ("3 days ago", "in 2 hours").
+///
+/// Accepts RFC 3339 / ISO 8601 timestamps (with or without offset) and
+/// date-only values. Date-only values are compared by calendar day.
+/// Returns None if the input can't be parsed.
+pub fn relative_time(input: &str, now: DateTime<Utc>) -> Option<String> {
+    let input = input.trim();
+
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        let days = (date - now.date_naive()).num_days();
+        return Some(match days {
+            0 => "today".to_string(),
+            -1 => "yesterday".to_string(),
+            1 => "tomorrow".to_string(),
+            _ => describe(days * 86_400),
+        });
+    }
+
+    let timestamp = DateTime::parse_from_rfc3339(input)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+                .iter()
+                .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+                .map(|naive| naive.and_utc())
+        })?;
+
+    Some(describe((timestamp - now).num_seconds()))
+}
+
+/// Describes a signed offset in seconds (negative = past)
+fn describe(seconds: i64) -> String {
+    let abs = seconds.unsigned_abs();
+    if abs < 60 {
+        return "just now".to_string();
+    }
+
+    let (amount, unit) = match abs {
+        s if s < 3_600 => (s / 60, "minute"),
+        s if s < 86_400 => (s / 3_600, "hour"),
+        s if s < 30 * 86_400 => (s / 86_400, "day"),
+        s if s < 365 * 86_400 => (s / (30 * 86_400), "month"),
+        s => (s / (365 * 86_400), "year"),
+    };
+    let plural = if amount == 1 { "" } else { "s" };
+
+    if seconds < 0 {
+        format!("{} {}{} ago", amount, unit, plural)
+    } else {
+        format!("in {} {}{}", amount, unit, plural)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-06-15T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_past_dates() {
+        assert_eq!(relative_time("2024-06-12", now()).unwrap(), "3 days ago");
+        assert_eq!(relative_time("2024-06-14", now()).unwrap(), "yesterday");
+        assert_eq!(relative_time("2024-06-15T09:00:00Z", now()).unwrap(), "3 hours ago");
+        assert_eq!(relative_time("2023-06-01", now()).unwrap(), "1 year ago");
+    }
+
+    #[test]
+    fn test_future_dates() {
+        assert_eq!(relative_time("2024-06-15T14:00:00+00:00", now()).unwrap(), "in 2 hours");
+        assert_eq!(relative_time("2024-06-16", now()).unwrap(), "tomorrow");
+        assert_eq!(relative_time("2024-08-20", now()).unwrap(), "in 2 months");
+    }
+
+    #[test]
+    fn test_naive_and_offset_timestamps() {
+        assert_eq!(relative_time("2024-06-15 11:30", now()).unwrap(), "30 minutes ago");
+        assert_eq!(relative_time("2024-06-15T12:00:00+02:00", now()).unwrap(), "2 hours ago");
+        assert_eq!(relative_time("2024-06-15T12:00:30Z", now()).unwrap(), "just now");
+    }
+
+    #[test]
+    fn test_invalid_dates() {
+        assert!(relative_time("not a date", now()).is_none());
+        assert!(relative_time("2024-13-45", now()).is_none());
+        assert!(relative_time("", now()).is_none());
+    }
+}
@@ -0,0 +1,88 @@
+use super::parser::escape_html;
+
+/// Renders CSV/TSV content as an HTML table, using the first row as the header.
+///
+/// Quoted fields may contain delimiters and newlines; newlines inside a cell are
+/// rendered as `<br>` so the table stays a single raw HTML block for comrak.
+pub fn render_delimited_table(content: &str, delimiter: u8) -> Result<String, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(content.as_bytes());
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Failed to parse table data: {}", e))?;
+        rows.push(record.iter().map(format_cell).collect::<Vec<_>>());
+    }
+
+    let Some((header, body)) = rows.split_first() else {
+        return Err("Table data is empty".to_string());
+    };
+
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut html = String::from("<table class=\"delimited-table\"><thead><tr>");
+    for i in 0..columns {
+        html.push_str(&format!("<th>{}</th>", header.get(i).map_or("", String::as_str)));
+    }
+    html.push_str("</tr></thead><tbody>");
+    for row in body {
+        html.push_str("<tr>");
+        for i in 0..columns {
+            html.push_str(&format!("<td>{}</td>", row.get(i).map_or("", String::as_str)));
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</tbody></table>");
+
+    Ok(html)
+}
+
+/// Delimiter byte for a fence language ("csv" or "tsv")
+pub fn delimiter_for(lang: &str) -> Option<u8> {
+    match lang {
+        "csv" => Some(b','),
+        "tsv" => Some(b'\t'),
+        _ => None,
+    }
+}
+
+fn format_cell(cell: &str) -> String {
+    escape_html(cell.trim()).replace("\r\n", "<br>").replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_table() {
+        let html = render_delimited_table("name,age\nAna,30\nBob,25", b',').unwrap();
+        assert!(html.contains("<thead><tr><th>name</th><th>age</th></tr></thead>"));
+        assert!(html.contains("<tr><td>Ana</td><td>30</td></tr>"));
+        assert!(html.contains("<tr><td>Bob</td><td>25</td></tr>"));
+    }
+
+    #[test]
+    fn test_quoted_fields() {
+        let html =
+            render_delimited_table("item,note\n\"a, b\",\"line 1\nline 2\"", b',').unwrap();
+        assert!(html.contains("<td>a, b</td>"));
+        assert!(html.contains("<td>line 1<br>line 2</td>"));
+        assert!(!html.contains('\n'));
+    }
+
+    #[test]
+    fn test_tsv_and_ragged_rows() {
+        let html = render_delimited_table("a\tb\tc\n1\t2", b'\t').unwrap();
+        assert!(html.contains("<th>c</th>"));
+        assert!(html.contains("<tr><td>1</td><td>2</td><td></td></tr>"));
+    }
+
+    #[test]
+    fn test_escapes_html() {
+        let html = render_delimited_table("h\n<script>", b',').unwrap();
+        assert!(html.contains("&lt;script&gt;"));
+    }
+}
@@ -0,0 +1,133 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+
+/// Elements rendered markdown is shown in: the preview's article and
+/// exported pages
+pub const DOCUMENT_STYLE_SCOPE: &str = ".prose, .markdown-body";
+
+/// Custom property names: `accent`, `code_bg`, `heading-font`
+static PROPERTY_NAME: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z][A-Za-z0-9_-]*$").unwrap());
+
+/// Hex colors, `rgb()`/`hsl()` functions and named colors
+static COLOR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:#[0-9A-Fa-f]{3,8}|(?:rgba?|hsla?)\([0-9.,%/ ]+\)|[A-Za-z]+)$").unwrap()
+});
+
+/// Numbers with an optional unit
+static LENGTH: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^-?(?:\d+\.?\d*|\.\d+)(?:px|em|rem|%|pt|ch|ex|vw|vh)?$").unwrap());
+
+/// Comma-separated font names, bare or quoted
+static FONT_FAMILY: Lazy<Regex> = Lazy::new(|| {
+    let name = r#"(?:[A-Za-z0-9][A-Za-z0-9 -]*|"[A-Za-z0-9 -]+"|'[A-Za-z0-9 -]+')"#;
+    Regex::new(&format!(r"^{name}(?:\s*,\s*{name})*$", name = name)).unwrap()
+});
+
+/// Builds a `<style>` block setting CSS custom properties from a front
+/// matter `style:` mapping (`accent: "#f50"` sets `--accent`), scoped to
+/// the rendered content.
+///
+/// Only colors, lengths and font families are accepted so a document can't
+/// inject other CSS; anything else is skipped with a warning. Returns None
+/// when no property is valid.
+pub fn document_style_block(style: &Value, warnings: &mut Vec<String>) -> Option<String> {
+    let Some(style) = style.as_object() else {
+        warnings.push("Front matter 'style' must be a mapping of CSS variables".to_string());
+        return None;
+    };
+
+    let mut declarations = Vec::new();
+    for (name, value) in style {
+        if !PROPERTY_NAME.is_match(name) {
+            warnings.push(format!(
+                "Front matter style '{}' is not a valid name; skipped",
+                name
+            ));
+            continue;
+        }
+        match safe_value(value) {
+            Some(value) => declarations.push(format!("--{}: {};", name, value)),
+            None => warnings.push(format!(
+                "Front matter style '{}' is not a color, length or font; skipped",
+                name
+            )),
+        }
+    }
+
+    if declarations.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "<style class=\"document-style\">{} {{ {} }}</style>\n",
+        DOCUMENT_STYLE_SCOPE,
+        declarations.join(" ")
+    ))
+}
+
+/// The value as CSS if it is a color, length or font family
+fn safe_value(value: &Value) -> Option<String> {
+    let text = match value {
+        Value::String(s) => s.trim().to_string(),
+        Value::Number(n) => n.to_string(),
+        _ => return None,
+    };
+    let safe = COLOR.is_match(&text) || LENGTH.is_match(&text) || FONT_FAMILY.is_match(&text);
+    safe.then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_style_block() {
+        let mut warnings = Vec::new();
+        let style = json!({
+            "accent": "#f50",
+            "font": "\"Iowan Old Style\", serif",
+            "gap": "1.5rem",
+            "width": 40,
+            "shade": "rgba(0, 0, 0, 0.5)"
+        });
+
+        let block = document_style_block(&style, &mut warnings).unwrap();
+
+        assert_eq!(
+            block,
+            "<style class=\"document-style\">.prose, .markdown-body { --accent: #f50; \
+             --font: \"Iowan Old Style\", serif; --gap: 1.5rem; --width: 40; \
+             --shade: rgba(0, 0, 0, 0.5); }</style>\n"
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_rejects_css_injection() {
+        let mut warnings = Vec::new();
+        let style = json!({
+            "accent": "red; } body { display: none",
+            "bg": "url(https://example.com/x.png)",
+            "font": "serif</style><script>",
+            "bad name": "red",
+            "nested": {"a": 1}
+        });
+
+        assert_eq!(document_style_block(&style, &mut warnings), None);
+        assert_eq!(warnings.len(), 5);
+        assert!(warnings
+            .iter()
+            .any(|w| w.contains("'bad name' is not a valid name")));
+    }
+
+    #[test]
+    fn test_style_must_be_a_mapping() {
+        let mut warnings = Vec::new();
+        assert_eq!(document_style_block(&json!("red"), &mut warnings), None);
+        assert_eq!(
+            warnings,
+            vec!["Front matter 'style' must be a mapping of CSS variables"]
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use serde_json::Value;
+
+use super::parser::escape_html;
+use super::special_blocks::SpecialBlock;
+
+/// Renders a non-JS HTML representation of a special block.
+///
+/// Diagrams (mermaid, plantuml) and ABC tunes become a code block of their
+/// source, charts a data table built from the Chart.js datasets and math the raw
+/// TeX. Anything else (or a chart that can't be read) falls back to the raw
+/// content in a code block.
+pub fn special_block_fallback(block: &SpecialBlock) -> String {
+    match block.block_type.as_str() {
+        "mermaid" | "plantuml" | "abc" => code_block(&block.content, Some(&block.block_type)),
+        "chart" => chart_table(&block.content)
+            .unwrap_or_else(|| code_block(&block.content, Some("json"))),
+        "math" => format!(
+            "<code class=\"math-fallback\">{}</code>",
+            escape_html(block.content.trim())
+        ),
+        _ => code_block(&block.content, None),
+    }
+}
+
+fn code_block(content: &str, lang: Option<&str>) -> String {
+    let class = lang.map(|l| format!(" class=\"language-{}\"", l)).unwrap_or_default();
+    format!("<pre><code{}>{}</code></pre>", class, escape_html(content))
+}
+
+/// Builds a table with one row per label and one column per dataset
+fn chart_table(content: &str) -> Option<String> {
+    let config: Value = serde_json::from_str(content).ok()?;
+    let data = config.get("data")?;
+    let datasets = data.get("datasets")?.as_array().filter(|d| !d.is_empty())?;
+    let labels: Vec<String> = data
+        .get("labels")
+        .and_then(Value::as_array)
+        .map(|labels| labels.iter().map(cell_text).collect())
+        .unwrap_or_default();
+
+    let columns: Vec<&[Value]> = datasets
+        .iter()
+        .map(|d| d.get("data").and_then(Value::as_array).map_or(&[][..], Vec::as_slice))
+        .collect();
+    let rows = columns.iter().map(|c| c.len()).max().unwrap_or(0).max(labels.len());
+
+    let mut html = String::from("<table class=\"chart-fallback\">\n");
+    if let Some(title) = config.pointer("/options/plugins/title/text").and_then(Value::as_str) {
+        html.push_str(&format!("<caption>{}</caption>\n", escape_html(title)));
+    }
+
+    html.push_str("<thead><tr><th></th>");
+    for (i, dataset) in datasets.iter().enumerate() {
+        let name = dataset
+            .get("label")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Series {}", i + 1));
+        html.push_str(&format!("<th>{}</th>", escape_html(&name)));
+    }
+    html.push_str("</tr></thead>\n<tbody>\n");
+
+    for row in 0..rows {
+        let label = labels.get(row).cloned().unwrap_or_else(|| {
+            // Scatter-style points carry their own x value
+            columns
+                .iter()
+                .find_map(|c| c.get(row).and_then(|p| p.get("x")).map(cell_text))
+                .unwrap_or_else(|| (row + 1).to_string())
+        });
+        html.push_str(&format!("<tr><th>{}</th>", escape_html(&label)));
+        for column in &columns {
+            let value = column.get(row).map(point_value).unwrap_or_default();
+            html.push_str(&format!("<td>{}</td>", escape_html(&value)));
+        }
+        html.push_str("</tr>\n");
+    }
+
+    html.push_str("</tbody>\n</table>");
+    Some(html)
+}
+
+/// The y value of a data point ({x, y} objects or plain values)
+fn point_value(point: &Value) -> String {
+    point.get("y").map(cell_text).unwrap_or_else(|| cell_text(point))
+}
+
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(block_type: &str, content: &str) -> SpecialBlock {
+        SpecialBlock {
+            block_type: block_type.to_string(),
+            content: content.to_string(),
+            placeholder_id: "special-block-0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_mermaid_fallback() {
+        let html = special_block_fallback(&block("mermaid", "graph TD\nA-->B\n"));
+        assert_eq!(
+            html,
+            "<pre><code class=\"language-mermaid\">graph TD\nA--&gt;B\n</code></pre>"
+        );
+    }
+
+    #[test]
+    fn test_chart_fallback() {
+        let chart = r#"{
+            "type": "bar",
+            "data": {
+                "labels": ["Jan", "Feb"],
+                "datasets": [
+                    {"label": "Sales", "data": [10, 20]},
+                    {"label": "Costs", "data": [5]}
+                ]
+            },
+            "options": {"plugins": {"title": {"text": "Q1"}}}
+        }"#;
+
+        let html = special_block_fallback(&block("chart", chart));
+
+        assert!(html.contains("<caption>Q1</caption>"));
+        assert!(html.contains("<th>Sales</th><th>Costs</th>"));
+        assert!(html.contains("<tr><th>Jan</th><td>10</td><td>5</td></tr>"));
+        assert!(html.contains("<tr><th>Feb</th><td>20</td><td></td></tr>"));
+    }
+
+    #[test]
+    fn test_scatter_chart_fallback() {
+        let chart = r#"{"data": {"datasets": [{"data": [{"x": 1, "y": 2}]}]}}"#;
+        let html = special_block_fallback(&block("chart", chart));
+        assert!(html.contains("<th>Series 1</th>"));
+        assert!(html.contains("<tr><th>1</th><td>2</td></tr>"));
+    }
+
+    #[test]
+    fn test_invalid_chart_falls_back_to_source() {
+        let html = special_block_fallback(&block("chart", "not json"));
+        assert_eq!(html, "<pre><code class=\"language-json\">not json</code></pre>");
+    }
+
+    #[test]
+    fn test_math_fallback() {
+        let html = special_block_fallback(&block("math", "x < y\n"));
+        assert_eq!(html, "<code class=\"math-fallback\">x &lt; y</code>");
+    }
+}
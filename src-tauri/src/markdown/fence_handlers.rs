@@ -0,0 +1,217 @@
+use once_cell::sync::Lazy;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, RwLock};
+
+use super::math::tex_to_mathml;
+use super::special_blocks::DEFAULT_SPECIAL_BLOCK_TYPES;
+
+/// Turns a fenced block's content into HTML; None falls back to highlighting
+pub type RenderFence = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+/// How a fence language is rendered instead of being highlighted
+#[derive(Clone)]
+pub enum FenceHandler {
+    /// Rendered by the frontend: the block becomes a placeholder and is
+    /// returned as a `SpecialBlock` (mermaid, charts, ...)
+    Client,
+    /// Rendered to HTML in the backend while the document is rendered
+    Server(RenderFence),
+}
+
+/// Handlers by fence language, in registration order
+#[derive(Clone, Default)]
+pub struct FenceRegistry {
+    handlers: Vec<(String, FenceHandler)>,
+}
+
+impl FenceRegistry {
+    /// A registry with the frontend-rendered special blocks registered
+    pub fn with_defaults() -> Self {
+        Self {
+            handlers: DEFAULT_SPECIAL_BLOCK_TYPES
+                .iter()
+                .map(|lang| (lang.to_string(), FenceHandler::Client))
+                .collect(),
+        }
+    }
+
+    /// Registers `handler` for a fence language (case-insensitive), replacing
+    /// any handler already registered for it
+    pub fn register(&mut self, lang: &str, handler: FenceHandler) {
+        let lang = lang.to_lowercase();
+        match self
+            .handlers
+            .iter_mut()
+            .find(|(registered, _)| *registered == lang)
+        {
+            Some(entry) => entry.1 = handler,
+            None => self.handlers.push((lang, handler)),
+        }
+    }
+
+    /// Removes the handler for a fence language; returns whether one was
+    /// registered
+    pub fn unregister(&mut self, lang: &str) -> bool {
+        let lang = lang.to_lowercase();
+        let before = self.handlers.len();
+        self.handlers.retain(|(registered, _)| *registered != lang);
+        self.handlers.len() != before
+    }
+
+    /// Fence languages rendered by the frontend, in registration order
+    pub fn client_block_types(&self) -> Vec<String> {
+        self.handlers
+            .iter()
+            .filter(|(_, handler)| matches!(handler, FenceHandler::Client))
+            .map(|(lang, _)| lang.clone())
+            .collect()
+    }
+
+    /// Whether any fence language is rendered in the backend
+    pub fn has_server_handlers(&self) -> bool {
+        self.handlers
+            .iter()
+            .any(|(_, handler)| matches!(handler, FenceHandler::Server(_)))
+    }
+
+    /// Renders a fenced block with the backend handler for its language, if any
+    pub fn render(&self, lang: &str, content: &str) -> Option<String> {
+        let lang = lang.to_lowercase();
+        self.handlers
+            .iter()
+            .find_map(|(registered, handler)| match handler {
+                FenceHandler::Server(render) if *registered == lang => Some(render),
+                _ => None,
+            })
+            .and_then(|render| render(content))
+    }
+}
+
+/// The app's handlers. The frontend-rendered special blocks are registered by
+/// default.
+static REGISTRY: Lazy<RwLock<FenceRegistry>> =
+    Lazy::new(|| RwLock::new(FenceRegistry::with_defaults()));
+
+/// A copy of the app's handlers, for rendering one document. Handlers are
+/// shared, so this is cheap, and they can render nested documents (or change
+/// the registry) without deadlocking.
+pub fn fence_registry() -> FenceRegistry {
+    REGISTRY.read().unwrap().clone()
+}
+
+/// Registers `handler` for a fence language (case-insensitive), replacing any
+/// handler already registered for it.
+pub fn register_fence_handler(lang: &str, handler: FenceHandler) {
+    REGISTRY.write().unwrap().register(lang, handler);
+}
+
+/// Removes the handler for a fence language, so its blocks are highlighted as
+/// code again. Returns whether one was registered.
+pub fn unregister_fence_handler(lang: &str) -> bool {
+    REGISTRY.write().unwrap().unregister(lang)
+}
+
+/// Fence languages rendered by the frontend, in registration order
+pub fn client_block_types() -> Vec<String> {
+    REGISTRY.read().unwrap().client_block_types()
+}
+
+/// Renders ```math fences (TeX) to MathML in a `<div class="math-block">`,
+/// e.g. `register_fence_handler("math", mathml_fence_handler())`. TeX that
+/// doesn't convert stays a code block.
+pub fn mathml_fence_handler() -> FenceHandler {
+    FenceHandler::Server(Arc::new(|content| {
+        let tex = content.trim();
+        if tex.is_empty() {
+            return None;
+        }
+        let mathml = tex_to_mathml(tex).ok()?;
+        Some(format!("<div class=\"math-block\">{}</div>", mathml))
+    }))
+}
+
+/// Renders Graphviz fences (```dot / ```graphviz) to inline SVG with the
+/// `dot` command, in a `<div class="graphviz">`. Without `dot` on PATH, or
+/// for graphs it rejects, the block stays code.
+pub fn graphviz_fence_handler() -> FenceHandler {
+    FenceHandler::Server(Arc::new(|content| {
+        if content.trim().is_empty() {
+            return None;
+        }
+        let mut child = Command::new("dot")
+            .arg("-Tsvg")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        child.stdin.take()?.write_all(content.as_bytes()).ok()?;
+        let output = child.wait_with_output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let svg = String::from_utf8_lossy(&output.stdout);
+        // Drop the XML declaration and doctype dot writes before the <svg>
+        let start = svg.find("<svg")?;
+        let svg = svg[start..].trim_end();
+        Some(format!("<div class=\"graphviz\">{}</div>", svg))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shout() -> FenceHandler {
+        FenceHandler::Server(Arc::new(|content| {
+            (!content.is_empty()).then(|| format!("<p>{}</p>", content.to_uppercase()))
+        }))
+    }
+
+    #[test]
+    fn test_special_blocks_are_default_client_handlers() {
+        let registry = FenceRegistry::with_defaults();
+        assert_eq!(registry.client_block_types(), DEFAULT_SPECIAL_BLOCK_TYPES);
+        assert!(!registry.has_server_handlers());
+        assert_eq!(registry.render("mermaid", "graph TD"), None);
+    }
+
+    #[test]
+    fn test_register_and_unregister_server_handler() {
+        let mut registry = FenceRegistry::default();
+        registry.register("Shout", shout());
+
+        assert!(registry.has_server_handlers());
+        assert_eq!(registry.render("shout", "hi").as_deref(), Some("<p>HI</p>"));
+        assert_eq!(registry.render("shout", ""), None);
+        assert!(registry.client_block_types().is_empty());
+
+        registry.register("shout", FenceHandler::Client);
+        assert_eq!(registry.client_block_types(), vec!["shout"]);
+        assert_eq!(registry.render("shout", "hi"), None);
+
+        assert!(registry.unregister("SHOUT"));
+        assert!(!registry.unregister("shout"));
+    }
+
+    #[test]
+    fn test_mathml_handler() {
+        let mut registry = FenceRegistry::default();
+        registry.register("math", mathml_fence_handler());
+
+        let html = registry.render("math", "x^2\n").unwrap();
+        assert!(html.starts_with("<div class=\"math-block\"><math"));
+        assert_eq!(registry.render("math", "  \n"), None);
+    }
+
+    #[test]
+    fn test_graphviz_handler_declines_invalid_graphs() {
+        let mut registry = FenceRegistry::default();
+        registry.register("dot", graphviz_fence_handler());
+
+        // Rejected by dot, or dot isn't installed: either way it stays code
+        assert_eq!(registry.render("dot", "not a graph {"), None);
+        assert_eq!(registry.render("dot", ""), None);
+    }
+}
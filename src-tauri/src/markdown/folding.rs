@@ -0,0 +1,149 @@
+use serde::Serialize;
+
+use super::front_matter::extract_front_matter;
+use super::headings::find_headings;
+use super::lines::{fenced_blocks, source_lines};
+
+/// What a folding region covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FoldKind {
+    FrontMatter,
+    /// A heading and everything up to the next heading of the same or a
+    /// higher level
+    Section,
+    Code,
+    Comment,
+}
+
+/// A foldable range of source lines
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FoldRegion {
+    /// 1-based first line (the heading, opening fence or `<!--`)
+    pub start: usize,
+    /// 1-based last line, inclusive
+    pub end: usize,
+    pub kind: FoldKind,
+}
+
+/// Computes folding regions for a source editor.
+///
+/// Regions are sorted by start line with enclosing regions first, so a code
+/// block inside a section follows the section. Sections end before trailing
+/// blank lines; regions of a single line are omitted.
+pub fn find_fold_regions(markdown: &str) -> Vec<FoldRegion> {
+    let (_, body) = extract_front_matter(markdown);
+    let front = &markdown[..markdown.len() - body.len()];
+    let offset = front.lines().count();
+    let mut regions = Vec::new();
+
+    if offset > 1 {
+        regions.push(FoldRegion {
+            start: 1,
+            end: offset,
+            kind: FoldKind::FrontMatter,
+        });
+    }
+
+    let lines = source_lines(body);
+    let total = lines.len();
+
+    let headings = find_headings(&lines);
+    for (index, heading) in headings.iter().enumerate() {
+        let next = headings[index + 1..]
+            .iter()
+            .find(|next| next.level <= heading.level)
+            .map_or(total, |next| next.line);
+        let mut end = next;
+        while end > heading.line + 1 && lines[end - 1].text.trim().is_empty() {
+            end -= 1;
+        }
+        regions.push(FoldRegion {
+            start: offset + heading.line + 1,
+            end: offset + end,
+            kind: FoldKind::Section,
+        });
+    }
+
+    for block in fenced_blocks(body) {
+        // The closing fence follows the content unless the block runs to the end
+        let end = (block.start_line + block.lines.len() + 1).min(total);
+        regions.push(FoldRegion {
+            start: offset + block.start_line,
+            end: offset + end,
+            kind: FoldKind::Code,
+        });
+    }
+
+    let mut comment_start = None;
+    for line in lines.iter().filter(|line| !line.in_code) {
+        let mut rest = line.text;
+        loop {
+            match comment_start {
+                None => match rest.find("<!--") {
+                    Some(open) => {
+                        comment_start = Some(line.number);
+                        rest = &rest[open + 4..];
+                    }
+                    None => break,
+                },
+                Some(start) => match rest.find("-->") {
+                    Some(close) => {
+                        regions.push(FoldRegion {
+                            start: offset + start,
+                            end: offset + line.number,
+                            kind: FoldKind::Comment,
+                        });
+                        comment_start = None;
+                        rest = &rest[close + 3..];
+                    }
+                    None => break,
+                },
+            }
+        }
+    }
+
+    regions.retain(|region| region.end > region.start);
+    regions.sort_by_key(|region| (region.start, std::cmp::Reverse(region.end)));
+    regions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(start: usize, end: usize, kind: FoldKind) -> FoldRegion {
+        FoldRegion { start, end, kind }
+    }
+
+    #[test]
+    fn test_code_block_nested_in_section() {
+        let md = "---\ntitle: x\n---\n# Guide\n\nIntro\n\n## Install\n\n```sh\n# not a heading\nmake\n```\n\n## Use\nRun it.\n\n# Appendix\n<!--\nnotes\n--> <!-- inline -->\n";
+        assert_eq!(
+            find_fold_regions(md),
+            vec![
+                region(1, 3, FoldKind::FrontMatter),
+                region(4, 16, FoldKind::Section),
+                region(8, 13, FoldKind::Section),
+                region(10, 13, FoldKind::Code),
+                region(15, 16, FoldKind::Section),
+                region(18, 21, FoldKind::Section),
+                region(19, 21, FoldKind::Comment),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unterminated_fence_and_single_lines() {
+        let md = "# Title\n## Empty\n```\ncode";
+        assert_eq!(
+            find_fold_regions(md),
+            vec![
+                region(1, 4, FoldKind::Section),
+                region(2, 4, FoldKind::Section),
+                region(3, 4, FoldKind::Code)
+            ]
+        );
+        assert!(find_fold_regions("# Only a heading").is_empty());
+    }
+}
@@ -0,0 +1,153 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashSet;
+
+use super::lines::{next_code_span, source_lines};
+
+/// Labels of explicit footnotes, from references `[^x]` and definitions `[^x]:`
+static FOOTNOTE_LABEL: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[\^([^\]\s]+)\]").unwrap());
+
+/// Label prefix for footnotes generated from inline notes
+const INLINE_LABEL_PREFIX: &str = "inline-";
+
+/// Rewrites Pandoc-style inline footnotes `^[note text]` into regular
+/// footnotes so comrak renders them.
+///
+/// Each note becomes a `[^inline-N]` reference and its text a definition
+/// appended to the end of the document, so line numbers are preserved.
+/// Labels already used by explicit footnotes are skipped. Notes may contain
+/// brackets (`^[see [docs](x)]`) but not span lines; code spans and fenced
+/// code are left alone.
+pub fn expand_inline_footnotes(markdown: &str) -> String {
+    if !markdown.contains("^[") {
+        return markdown.to_string();
+    }
+
+    let taken: HashSet<&str> = FOOTNOTE_LABEL
+        .captures_iter(markdown)
+        .map(|c| c.get(1).unwrap().as_str())
+        .collect();
+    let mut next_number = 0;
+    let mut definitions = Vec::new();
+
+    let lines: Vec<String> = source_lines(markdown)
+        .into_iter()
+        .map(|line| {
+            if line.in_code || !line.text.contains("^[") {
+                return line.text.to_string();
+            }
+            replace_notes(line.text, |note| {
+                let label = loop {
+                    next_number += 1;
+                    let label = format!("{}{}", INLINE_LABEL_PREFIX, next_number);
+                    if !taken.contains(label.as_str()) {
+                        break label;
+                    }
+                };
+                definitions.push(format!("[^{}]: {}", label, note.trim()));
+                format!("[^{}]", label)
+            })
+        })
+        .collect();
+
+    let mut result = lines.join("\n");
+    if !definitions.is_empty() {
+        result.push_str("\n\n");
+        result.push_str(&definitions.join("\n"));
+    }
+    if markdown.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+/// Replaces each `^[...]` outside code spans with the result of `reference`
+fn replace_notes(line: &str, mut reference: impl FnMut(&str) -> String) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut copied = 0;
+    let mut pos = 0;
+
+    while let Some(found) = line[pos..].find("^[") {
+        let open = pos + found;
+        if let Some((_, end)) = next_code_span(line, pos).filter(|&(start, _)| start < open) {
+            pos = end;
+            continue;
+        }
+        if line[..open].ends_with('\\') {
+            pos = open + 2;
+            continue;
+        }
+        match note_end(line, open + 2) {
+            Some(close) if !line[open + 2..close].trim().is_empty() => {
+                result.push_str(&line[copied..open]);
+                result.push_str(&reference(&line[open + 2..close]));
+                copied = close + 1;
+                pos = close + 1;
+            }
+            _ => pos = open + 2,
+        }
+    }
+    result.push_str(&line[copied..]);
+
+    result
+}
+
+/// Index of the `]` closing a note whose text starts at `from`, skipping
+/// nested brackets, escapes and code spans
+fn note_end(line: &str, from: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut pos = from;
+
+    while pos < line.len() {
+        let c = line[pos..].chars().next()?;
+        match c {
+            '\\' => {
+                pos += 1 + line[pos + 1..].chars().next().map_or(0, char::len_utf8);
+                continue;
+            }
+            '`' => {
+                if let Some((start, end)) = next_code_span(line, pos) {
+                    if start == pos {
+                        pos = end;
+                        continue;
+                    }
+                }
+            }
+            '[' => depth += 1,
+            ']' if depth == 0 => return Some(pos),
+            ']' => depth -= 1,
+            _ => {}
+        }
+        pos += c.len_utf8();
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mixed_inline_and_referenced_footnotes() {
+        let md = "Text[^1] and an aside^[Quick [note](https://x.dev).] here.\n\
+                  Another^[Second `]` note].\n\n[^1]: Explicit.\n[^inline-1]: Taken.\n";
+        assert_eq!(
+            expand_inline_footnotes(md),
+            "Text[^1] and an aside[^inline-2] here.\n\
+             Another[^inline-3].\n\n[^1]: Explicit.\n[^inline-1]: Taken.\n\n\
+             [^inline-2]: Quick [note](https://x.dev).\n[^inline-3]: Second `]` note\n"
+        );
+    }
+
+    #[test]
+    fn test_code_and_escapes_untouched() {
+        let md = "`^[code]` \\^[escaped] ^[unclosed\n```\n^[fenced]\n```";
+        assert_eq!(expand_inline_footnotes(md), md);
+    }
+
+    #[test]
+    fn test_without_inline_notes() {
+        assert_eq!(expand_inline_footnotes("x^2^ and [^a]"), "x^2^ and [^a]");
+    }
+}
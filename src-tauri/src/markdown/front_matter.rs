@@ -0,0 +1,456 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+use super::parser::escape_html;
+
+/// Shared metadata file a folder's documents inherit front matter from
+pub const SHARED_META_FILE: &str = "_meta.yaml";
+
+/// Front-matter syntax, detected from the opening delimiter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrontMatterFormat {
+    /// `---` delimited YAML
+    Yaml,
+    /// `+++` delimited TOML (Zola/Hugo)
+    Toml,
+    /// A leading JSON object
+    Json,
+}
+
+/// How front matter appears in rendered output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrontMatterMode {
+    /// Omit it from the HTML
+    #[default]
+    Strip,
+    /// Show the parsed fields as a key/value table
+    Render,
+    /// Show the raw text in a collapsed `<details>` block
+    Collapse,
+}
+
+/// Front matter split off the top of a document
+#[derive(Debug, Clone)]
+pub struct FrontMatter {
+    pub format: FrontMatterFormat,
+    /// The raw front-matter text (without delimiters)
+    pub raw: String,
+    /// The parsed value, or None if it failed to parse
+    pub value: Option<Value>,
+}
+
+/// Splits front matter (YAML `---`, TOML `+++` or a leading JSON object) from
+/// the document body.
+///
+/// Returns the front matter, if any, and the remaining body.
+pub fn extract_front_matter(markdown: &str) -> (Option<FrontMatter>, &str) {
+    if markdown.starts_with('{') {
+        return split_json(markdown);
+    }
+
+    let first_line = markdown.lines().next().unwrap_or("").trim_end();
+    let (format, closing): (FrontMatterFormat, &[&str]) = match first_line {
+        "---" => (FrontMatterFormat::Yaml, &["---", "..."][..]),
+        "+++" => (FrontMatterFormat::Toml, &["+++"][..]),
+        _ => return (None, markdown),
+    };
+
+    let content_start = markdown.find('\n').map_or(markdown.len(), |i| i + 1);
+    let mut offset = content_start;
+    for line in markdown[content_start..].split_inclusive('\n') {
+        if closing.contains(&line.trim_end()) {
+            let raw = markdown[content_start..offset].to_string();
+            let body = &markdown[offset + line.len()..];
+            let value = parse_raw(format, &raw);
+            return (Some(FrontMatter { format, raw, value }), body);
+        }
+        offset += line.len();
+    }
+
+    // Unterminated block: treat as regular content
+    (None, markdown)
+}
+
+/// Renders front matter for display according to `mode`.
+///
+/// Returns None in strip mode. Render mode falls back to the collapsed raw
+/// text when the front matter isn't a parsed mapping.
+pub fn front_matter_html(front_matter: &FrontMatter, mode: FrontMatterMode) -> Option<String> {
+    match mode {
+        FrontMatterMode::Strip => None,
+        FrontMatterMode::Render => match front_matter.value {
+            Some(Value::Object(ref fields)) => {
+                let mut html = String::from("<table class=\"front-matter\">\n<tbody>\n");
+                for (key, value) in fields {
+                    html.push_str(&format!(
+                        "<tr><th>{}</th><td>{}</td></tr>\n",
+                        escape_html(key),
+                        escape_html(&display_value(value))
+                    ));
+                }
+                html.push_str("</tbody>\n</table>\n");
+                Some(html)
+            }
+            _ => front_matter_html(front_matter, FrontMatterMode::Collapse),
+        },
+        FrontMatterMode::Collapse => {
+            let lang = match front_matter.format {
+                FrontMatterFormat::Yaml => "yaml",
+                FrontMatterFormat::Toml => "toml",
+                FrontMatterFormat::Json => "json",
+            };
+            Some(format!(
+                "<details class=\"front-matter\">\n<summary>Front matter</summary>\n\
+                 <pre><code class=\"language-{}\">{}</code></pre>\n</details>\n",
+                lang,
+                escape_html(&front_matter.raw)
+            ))
+        }
+    }
+}
+
+/// Table cell text: strings as-is, lists of scalars comma-separated, JSON otherwise
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) if items.iter().all(|v| !v.is_array() && !v.is_object()) => {
+            items.iter().map(display_value).collect::<Vec<_>>().join(", ")
+        }
+        other => other.to_string(),
+    }
+}
+
+/// Merges `updates` into a document's front matter and returns the document.
+///
+/// Existing keys keep their position, new keys are appended and a `null`
+/// update removes the key; keys not mentioned are kept as they are. Only the
+/// front matter is re-serialized (in its own format): the body is left
+/// byte-identical. A document without front matter gets a new YAML block.
+pub fn merge_front_matter(markdown: &str, updates: &Map<String, Value>) -> Result<String, String> {
+    let (front_matter, body) = extract_front_matter(markdown);
+    let Some(front_matter) = front_matter else {
+        let yaml = merge_yaml("", updates)?;
+        return Ok(format!("---\n{}---\n{}", yaml, markdown));
+    };
+
+    let front = &markdown[..markdown.len() - body.len()];
+    let merged = match front_matter.format {
+        FrontMatterFormat::Json => {
+            let mut fields = match front_matter.value {
+                Some(Value::Object(fields)) => fields,
+                _ => return Err("Front matter is not a JSON object".to_string()),
+            };
+            for (key, value) in updates {
+                match value {
+                    Value::Null => fields = fields.into_iter().filter(|(k, _)| k != key).collect(),
+                    value => {
+                        fields.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            let json = serde_json::to_string_pretty(&fields)
+                .map_err(|e| format!("Failed to write front matter: {}", e))?;
+            return Ok(format!("{}\n{}", json, body));
+        }
+        FrontMatterFormat::Yaml => merge_yaml(&front_matter.raw, updates)?,
+        FrontMatterFormat::Toml => merge_toml(&front_matter.raw, updates)?,
+    };
+
+    // Keep the original delimiter lines (`---`/`...`, `+++`)
+    let opening = &front[..front.find('\n').map_or(front.len(), |i| i + 1)];
+    let closing = &front[opening.len() + front_matter.raw.len()..];
+    Ok(format!("{}{}{}{}", opening, merged, closing, body))
+}
+
+/// A document's front matter with defaults inherited from `_meta.yaml`
+/// files in its folder and every folder above it, up to and including
+/// `root` (the vault). Folders outside `root` are never read.
+///
+/// Top-level keys are merged, so later sources replace a key's whole value:
+/// the farthest `_meta.yaml` comes first, each closer one overrides it, and
+/// the document's own front matter overrides them all. Missing, unreadable
+/// or non-mapping files are skipped. Returns an empty object when nothing
+/// applies.
+pub fn inherited_front_matter(markdown: &str, base_path: &Path, root: &Path) -> Value {
+    let dir = base_path.parent().unwrap_or(base_path);
+    let mut sources: Vec<Map<String, Value>> = dir
+        .ancestors()
+        .take_while(|folder| folder.starts_with(root))
+        .filter_map(|folder| fs::read_to_string(folder.join(SHARED_META_FILE)).ok())
+        .filter_map(|yaml| match serde_yaml::from_str::<Value>(&yaml) {
+            Ok(Value::Object(fields)) => Some(fields),
+            _ => None,
+        })
+        .collect();
+    sources.reverse();
+    if let (Some(front_matter), _) = extract_front_matter(markdown) {
+        if let Some(Value::Object(fields)) = front_matter.value {
+            sources.push(fields);
+        }
+    }
+
+    let mut resolved = Map::new();
+    for (key, value) in sources.into_iter().flatten() {
+        resolved.insert(key, value);
+    }
+    Value::Object(resolved)
+}
+
+fn merge_yaml(raw: &str, updates: &Map<String, Value>) -> Result<String, String> {
+    let mut mapping: serde_yaml::Mapping = if raw.trim().is_empty() {
+        serde_yaml::Mapping::new()
+    } else {
+        serde_yaml::from_str(raw).map_err(|e| format!("Invalid YAML front matter: {}", e))?
+    };
+    for (key, value) in updates {
+        if value.is_null() {
+            mapping = mapping
+                .into_iter()
+                .filter(|(k, _)| k.as_str() != Some(key.as_str()))
+                .collect();
+        } else {
+            let value = serde_yaml::to_value(value)
+                .map_err(|e| format!("Invalid front matter value for {}: {}", key, e))?;
+            mapping.insert(serde_yaml::Value::String(key.clone()), value);
+        }
+    }
+    serde_yaml::to_string(&mapping).map_err(|e| format!("Failed to write front matter: {}", e))
+}
+
+fn merge_toml(raw: &str, updates: &Map<String, Value>) -> Result<String, String> {
+    let mut table: toml::Table =
+        toml::from_str(raw).map_err(|e| format!("Invalid TOML front matter: {}", e))?;
+    for (key, value) in updates {
+        if value.is_null() {
+            table = table.into_iter().filter(|(k, _)| k != key).collect();
+        } else {
+            let value = toml::Value::try_from(value)
+                .map_err(|e| format!("Invalid front matter value for {}: {}", key, e))?;
+            table.insert(key.clone(), value);
+        }
+    }
+    toml::to_string(&table).map_err(|e| format!("Failed to write front matter: {}", e))
+}
+
+fn split_json(markdown: &str) -> (Option<FrontMatter>, &str) {
+    let mut stream = serde_json::Deserializer::from_str(markdown).into_iter::<Value>();
+    match stream.next() {
+        Some(Ok(value @ Value::Object(_))) => {
+            let end = stream.byte_offset();
+            let rest = &markdown[end..];
+            // The object must end its line to count as front matter
+            let line_end = rest.find('\n').map_or(rest.len(), |i| i + 1);
+            if !rest[..line_end].trim().is_empty() {
+                return (None, markdown);
+            }
+            let front_matter = FrontMatter {
+                format: FrontMatterFormat::Json,
+                raw: markdown[..end].to_string(),
+                value: Some(value),
+            };
+            (Some(front_matter), &rest[line_end..])
+        }
+        _ => (None, markdown),
+    }
+}
+
+fn parse_raw(format: FrontMatterFormat, raw: &str) -> Option<Value> {
+    match format {
+        FrontMatterFormat::Yaml => serde_yaml::from_str::<Value>(raw).ok(),
+        FrontMatterFormat::Toml => toml::from_str::<toml::Value>(raw).ok().map(toml_to_json),
+        FrontMatterFormat::Json => serde_json::from_str(raw).ok(),
+    }
+}
+
+/// Converts TOML to JSON, rendering datetimes as strings
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_inherited_front_matter() {
+        let root = std::env::temp_dir().join("markviewer-shared-meta");
+        let _ = fs::remove_dir_all(&root);
+        let guides = root.join("docs").join("guides");
+        fs::create_dir_all(&guides).unwrap();
+        fs::write(
+            root.join(SHARED_META_FILE),
+            "author: Team\nlicense: MIT\ntags: [docs]\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("docs").join(SHARED_META_FILE),
+            "author: Docs Team\n",
+        )
+        .unwrap();
+        fs::write(guides.join(SHARED_META_FILE), "- not a mapping\n").unwrap();
+
+        let doc = guides.join("setup.md");
+        let resolved = inherited_front_matter(
+            "---\ntitle: Setup\ntags: [guide]\n---\n# Setup",
+            &doc,
+            &root,
+        );
+        assert_eq!(
+            resolved,
+            serde_json::json!({
+                "author": "Docs Team",
+                "license": "MIT",
+                "tags": ["guide"],
+                "title": "Setup",
+            })
+        );
+        // Inherited keys keep the order of the farthest file that sets them
+        let keys: Vec<&String> = resolved.as_object().unwrap().keys().collect();
+        assert_eq!(keys, vec!["author", "license", "tags", "title"]);
+
+        let resolved = inherited_front_matter("---\nauthor: Ana\n---\n", &doc, &root);
+        assert_eq!(resolved["author"], "Ana");
+        assert_eq!(resolved["license"], "MIT");
+
+        // Nothing above the vault root is read
+        let resolved = inherited_front_matter("", &doc, &root.join("docs"));
+        assert_eq!(resolved, serde_json::json!({ "author": "Docs Team" }));
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_yaml_front_matter() {
+        let md = "---\ntitle: Hello\ntags: [a, b]\n---\n# Body\n";
+        let (front_matter, body) = extract_front_matter(md);
+        let front_matter = front_matter.unwrap();
+
+        assert_eq!(front_matter.format, FrontMatterFormat::Yaml);
+        let value = front_matter.value.unwrap();
+        assert_eq!(value["title"], "Hello");
+        assert_eq!(value["tags"][1], "b");
+        assert_eq!(body, "# Body\n");
+    }
+
+    #[test]
+    fn test_merge_keeps_unknown_keys_and_body() {
+        let md = "---\ntitle: Old\ncustom:\n  tool: x\ntags: [a]\n---\n# Body  \n\ttext\n";
+        let updates = serde_json::json!({"title": "New", "tags": null, "draft": true});
+
+        let merged = merge_front_matter(md, updates.as_object().unwrap()).unwrap();
+
+        assert_eq!(
+            merged,
+            "---\ntitle: New\ncustom:\n  tool: x\ndraft: true\n---\n# Body  \n\ttext\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_toml_and_json() {
+        let updates = serde_json::json!({"title": "New"});
+        let updates = updates.as_object().unwrap();
+
+        let toml = merge_front_matter("+++\ntitle = \"Old\"\nzone = 1\n+++\nBody", updates).unwrap();
+        assert_eq!(toml, "+++\ntitle = \"New\"\nzone = 1\n+++\nBody");
+
+        let json = merge_front_matter("{\"zone\": 1, \"title\": \"Old\"}\nBody", updates).unwrap();
+        assert_eq!(json, "{\n  \"zone\": 1,\n  \"title\": \"New\"\n}\nBody");
+    }
+
+    #[test]
+    fn test_merge_adds_front_matter() {
+        let updates = serde_json::json!({"title": "Notes"});
+        let merged = merge_front_matter("# Notes\n", updates.as_object().unwrap()).unwrap();
+        assert_eq!(merged, "---\ntitle: Notes\n---\n# Notes\n");
+    }
+
+    #[test]
+    fn test_toml_front_matter() {
+        let md = "+++\ntitle = \"Hello\"\ndate = 2024-01-15\n[extra]\ndraft = true\n+++\nBody";
+        let (front_matter, body) = extract_front_matter(md);
+        let front_matter = front_matter.unwrap();
+
+        assert_eq!(front_matter.format, FrontMatterFormat::Toml);
+        let value = front_matter.value.unwrap();
+        assert_eq!(value["title"], "Hello");
+        assert_eq!(value["date"], "2024-01-15");
+        assert_eq!(value["extra"]["draft"], true);
+        assert_eq!(body, "Body");
+    }
+
+    #[test]
+    fn test_json_front_matter() {
+        let md = "{\n  \"title\": \"Hello\",\n  \"weight\": 3\n}\n\n# Body";
+        let (front_matter, body) = extract_front_matter(md);
+        let front_matter = front_matter.unwrap();
+
+        assert_eq!(front_matter.format, FrontMatterFormat::Json);
+        assert_eq!(front_matter.value.unwrap()["weight"], 3);
+        assert_eq!(body, "\n# Body");
+    }
+
+    #[test]
+    fn test_no_front_matter() {
+        let md = "# Title\n\n---\n\ntext";
+        let (front_matter, body) = extract_front_matter(md);
+        assert!(front_matter.is_none());
+        assert_eq!(body, md);
+
+        let (front_matter, _) = extract_front_matter("---\nunterminated");
+        assert!(front_matter.is_none());
+
+        let (front_matter, _) = extract_front_matter("{not json}");
+        assert!(front_matter.is_none());
+    }
+
+    #[test]
+    fn test_front_matter_html_modes() {
+        let md = "---\ntitle: A & B\ntags: [x, y]\nextra: {n: 1}\n---\nBody";
+        let front_matter = extract_front_matter(md).0.unwrap();
+
+        assert_eq!(front_matter_html(&front_matter, FrontMatterMode::Strip), None);
+
+        let table = front_matter_html(&front_matter, FrontMatterMode::Render).unwrap();
+        assert!(table.starts_with("<table class=\"front-matter\">"));
+        assert!(table.contains("<tr><th>title</th><td>A &amp; B</td></tr>"));
+        assert!(table.contains("<tr><th>tags</th><td>x, y</td></tr>"));
+        assert!(table.contains("<tr><th>extra</th><td>{&quot;n&quot;:1}</td></tr>"));
+
+        let details = front_matter_html(&front_matter, FrontMatterMode::Collapse).unwrap();
+        assert!(details.contains("<summary>Front matter</summary>"));
+        assert!(details.contains("<code class=\"language-yaml\">title: A &amp; B\n"));
+    }
+
+    #[test]
+    fn test_render_mode_falls_back_for_unparsed() {
+        let front_matter = extract_front_matter("---\n: : bad\n---\ntext").0.unwrap();
+        let html = front_matter_html(&front_matter, FrontMatterMode::Render).unwrap();
+        assert!(html.starts_with("<details class=\"front-matter\">"));
+    }
+
+    #[test]
+    fn test_invalid_yaml_is_still_stripped() {
+        let (front_matter, body) = extract_front_matter("---\n: : bad\n  - [\n---\ntext");
+        let front_matter = front_matter.unwrap();
+        assert!(front_matter.value.is_none());
+        assert_eq!(body, "text");
+    }
+}
@@ -0,0 +1,220 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use super::front_matter::extract_front_matter;
+
+/// A front-matter field that doesn't match the schema
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SchemaError {
+    /// Path of the field, e.g. `title`, `author.name` or `tags[1]`; empty for
+    /// the front matter as a whole
+    pub path: String,
+    pub message: String,
+}
+
+/// Checks a document's front matter against a schema.
+///
+/// The schema is a small subset of JSON Schema:
+///
+/// ```json
+/// {
+///   "required": ["title", "status"],
+///   "properties": {
+///     "title": { "type": "string" },
+///     "status": { "type": "string", "enum": ["draft", "published"] },
+///     "tags": { "type": "array", "items": { "type": "string" } },
+///     "author": { "type": "object", "required": ["name"] }
+///   }
+/// }
+/// ```
+///
+/// Types are `string`, `number`, `integer`, `boolean`, `array`, `object` and
+/// `date` (a `YYYY-MM-DD` string, optionally followed by a time). Keys
+/// without a schema are allowed. A document without front matter is a single
+/// error when the schema requires fields.
+pub fn validate_front_matter_schema(markdown: &str, schema: &Value) -> Vec<SchemaError> {
+    let mut errors = Vec::new();
+    match extract_front_matter(markdown).0 {
+        Some(front_matter) => match front_matter.value {
+            Some(value) => check_value(&value, schema, "", &mut errors),
+            None => errors.push(error("", "front matter could not be parsed")),
+        },
+        None if required_keys(schema).next().is_some() => {
+            errors.push(error("", "no front matter"));
+        }
+        None => {}
+    }
+    errors
+}
+
+/// Checks `value` against `schema`, recording errors under `path`
+fn check_value(value: &Value, schema: &Value, path: &str, errors: &mut Vec<SchemaError>) {
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        match has_type(value, expected) {
+            Some(true) => {}
+            Some(false) => {
+                errors.push(error(
+                    path,
+                    &format!("expected {}, found {}", expected, type_name(value)),
+                ));
+                return;
+            }
+            None => {
+                errors.push(error(
+                    path,
+                    &format!("unknown type '{}' in schema", expected),
+                ));
+                return;
+            }
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            errors.push(error(
+                path,
+                &format!("{} is not one of {}", value, allowed.join(", ")),
+            ));
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (index, item) in items.iter().enumerate() {
+            check_value(item, item_schema, &format!("{}[{}]", path, index), errors);
+        }
+    }
+
+    if let Value::Object(fields) = value {
+        for key in required_keys(schema) {
+            if matches!(fields.get(key), None | Some(Value::Null)) {
+                errors.push(error(&child_path(path, key), "required field is missing"));
+            }
+        }
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (key, field_schema) in properties {
+                if let Some(field) = fields.get(key).filter(|field| !field.is_null()) {
+                    check_value(field, field_schema, &child_path(path, key), errors);
+                }
+            }
+        }
+    }
+}
+
+fn required_keys(schema: &Value) -> impl Iterator<Item = &str> {
+    schema
+        .get("required")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+}
+
+/// Whether `value` has the schema type `expected`; None for unknown types
+fn has_type(value: &Value, expected: &str) -> Option<bool> {
+    let matches = match expected {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "date" => value.as_str().is_some_and(is_date),
+        _ => return None,
+    };
+    Some(matches)
+}
+
+/// `YYYY-MM-DD`, optionally followed by a time
+fn is_date(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    let digits = |range: std::ops::Range<usize>| bytes[range].iter().all(u8::is_ascii_digit);
+    bytes.len() >= 10
+        && digits(0..4)
+        && bytes[4] == b'-'
+        && digits(5..7)
+        && bytes[7] == b'-'
+        && digits(8..10)
+        && (bytes.len() == 10 || matches!(bytes[10], b'T' | b' '))
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn error(path: &str, message: &str) -> SchemaError {
+    SchemaError {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "required": ["title", "date", "status"],
+            "properties": {
+                "title": { "type": "string" },
+                "date": { "type": "date" },
+                "status": { "type": "string", "enum": ["draft", "published"] },
+                "tags": { "type": "array", "items": { "type": "string" } },
+                "author": { "type": "object", "required": ["name"] }
+            }
+        })
+    }
+
+    #[test]
+    fn test_valid_front_matter() {
+        let md =
+            "---\ntitle: Hello\ndate: 2024-05-01\nstatus: draft\ntags: [a, b]\nextra: 1\n---\nBody";
+        assert!(validate_front_matter_schema(md, &schema()).is_empty());
+    }
+
+    #[test]
+    fn test_reports_qualified_paths() {
+        let md = "---\ntitle: 42\ndate: May 1st\nstatus: archived\ntags: [a, 2]\nauthor:\n  email: x@y.z\n---\n";
+        let errors = validate_front_matter_schema(md, &schema());
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["title", "date", "status", "tags[1]", "author.name"]
+        );
+        assert_eq!(
+            errors[2].message,
+            "\"archived\" is not one of \"draft\", \"published\""
+        );
+        assert_eq!(errors[3].message, "expected string, found number");
+    }
+
+    #[test]
+    fn test_missing_front_matter() {
+        assert_eq!(
+            validate_front_matter_schema("# Post", &schema()),
+            vec![error("", "no front matter")]
+        );
+        assert!(validate_front_matter_schema("# Post", &json!({ "properties": {} })).is_empty());
+
+        let errors = validate_front_matter_schema("---\ntitle: Hi\n---\n", &schema());
+        let paths: Vec<&str> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["date", "status"]);
+    }
+}
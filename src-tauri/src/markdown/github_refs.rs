@@ -0,0 +1,120 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+use super::search_hits::{tag_name, TAG};
+
+/// `owner/repo#123`, `#123`, `GH-123` or `@user`, not glued to a word, path,
+/// entity or email address; the first group is whatever precedes the reference
+static REFERENCE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(^|[^\w/&#@.-])(?:([A-Za-z0-9][\w.-]*/[\w.-]+)#(\d+)|#(\d+)|GH-(\d+)|@([A-Za-z0-9][A-Za-z0-9-]*))\b",
+    )
+    .unwrap()
+});
+
+/// `owner/name`
+static REPO: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z0-9][\w.-]*/[\w.-]+$").unwrap());
+
+/// Elements whose text is never linked
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "a", "pre", "code", "script", "style", "textarea", "svg", "math",
+];
+
+const GITHUB_URL: &str = "https://github.com";
+
+/// Links GitHub references in rendered HTML against the `owner/name` repo:
+/// `#123` and `GH-123` to its issues, `owner/repo#123` to another repo's
+/// issues and `@user` to profiles.
+///
+/// Code, existing links, attributes and entities are left alone, as is
+/// everything when `repo` isn't an `owner/name` pair.
+pub fn link_github_refs(html: &str, repo: &str) -> String {
+    let repo = repo.trim().trim_matches('/');
+    if !REPO.is_match(repo) {
+        return html.to_string();
+    }
+
+    let mut result = String::with_capacity(html.len());
+    let mut skip_depth = 0usize;
+    let mut last = 0;
+
+    for tag in TAG.find_iter(html) {
+        push_text(&mut result, &html[last..tag.start()], repo, skip_depth == 0);
+        result.push_str(tag.as_str());
+        last = tag.end();
+
+        let (name, closing) = tag_name(tag.as_str());
+        if SKIPPED_ELEMENTS.contains(&name.as_str()) && !tag.as_str().ends_with("/>") {
+            if closing {
+                skip_depth = skip_depth.saturating_sub(1);
+            } else {
+                skip_depth += 1;
+            }
+        }
+    }
+    push_text(&mut result, &html[last..], repo, skip_depth == 0);
+
+    result
+}
+
+fn push_text(result: &mut String, text: &str, repo: &str, link: bool) {
+    if !link || !text.contains(['#', '@', 'G']) {
+        result.push_str(text);
+        return;
+    }
+    let linked = REFERENCE.replace_all(text, |caps: &Captures| {
+        let (url, class) = if let (Some(other), Some(number)) = (caps.get(2), caps.get(3)) {
+            let url = format!(
+                "{}/{}/issues/{}",
+                GITHUB_URL,
+                other.as_str(),
+                number.as_str()
+            );
+            (url, "github-issue")
+        } else if let Some(number) = caps.get(4).or_else(|| caps.get(5)) {
+            let url = format!("{}/{}/issues/{}", GITHUB_URL, repo, number.as_str());
+            (url, "github-issue")
+        } else {
+            (format!("{}/{}", GITHUB_URL, &caps[6]), "github-mention")
+        };
+        let reference = &caps[0][caps[1].len()..];
+        format!(
+            "{}<a href=\"{}\" class=\"{}\">{}</a>",
+            &caps[1], url, class, reference
+        )
+    });
+    result.push_str(&linked);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_links_references() {
+        let html = "<p>Fixes #12 and GH-7, see rust-lang/rust#99 (thanks @octo-cat).</p>";
+        assert_eq!(
+            link_github_refs(html, "acme/app"),
+            "<p>Fixes <a href=\"https://github.com/acme/app/issues/12\" class=\"github-issue\">#12</a> \
+             and <a href=\"https://github.com/acme/app/issues/7\" class=\"github-issue\">GH-7</a>, \
+             see <a href=\"https://github.com/rust-lang/rust/issues/99\" class=\"github-issue\">rust-lang/rust#99</a> \
+             (thanks <a href=\"https://github.com/octo-cat\" class=\"github-mention\">@octo-cat</a>).</p>"
+        );
+    }
+
+    #[test]
+    fn test_requires_repo() {
+        let html = "<p>Fixes #12</p>";
+        assert_eq!(link_github_refs(html, ""), html);
+        assert_eq!(link_github_refs(html, "not a repo"), html);
+        assert!(link_github_refs(html, "acme/app").contains("/acme/app/issues/12\""));
+    }
+
+    #[test]
+    fn test_code_links_and_non_references_untouched() {
+        let html = "<p><code>#12</code> <a href=\"#12\">#12</a> mail a@b.dev, \
+                    x.com/o/r#3, #12abc, &#123; <h2 id=\"x\">GHOST-1</h2></p>\
+                    <pre><code>@user #4</code></pre>";
+        assert_eq!(link_github_refs(html, "acme/app"), html);
+    }
+}
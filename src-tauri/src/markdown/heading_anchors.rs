@@ -0,0 +1,90 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+use super::parser::escape_html;
+use super::slug::heading_text;
+
+/// Symbol shown in heading anchors when none is configured
+pub const DEFAULT_ANCHOR_SYMBOL: &str = "#";
+
+/// An empty heading anchor and the heading content after it
+static EMPTY_ANCHOR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r##"(?s)<a href="#([^"]*)" aria-hidden="true" class="anchor" id="([^"]*)"></a>(.*?)(</h[1-6]>)"##,
+    )
+    .unwrap()
+});
+
+/// Where a visible heading anchor goes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnchorPlacement {
+    /// Before the heading text
+    #[default]
+    Before,
+    /// After the heading text
+    After,
+}
+
+/// Fills the empty heading anchors with a visible symbol placed before or
+/// after the heading text.
+///
+/// The anchors are no longer hidden from screen readers, so each gets an
+/// `aria-label` naming its heading whatever the symbol is.
+pub fn style_heading_anchors(html: &str, symbol: &str, placement: AnchorPlacement) -> String {
+    EMPTY_ANCHOR
+        .replace_all(html, |caps: &Captures| {
+            let label = heading_text(&caps[3]);
+            let anchor = format!(
+                "<a href=\"#{}\" class=\"anchor\" id=\"{}\" aria-label=\"Link to section: {}\">{}</a>",
+                &caps[1],
+                &caps[2],
+                escape_html(label.trim()),
+                escape_html(symbol)
+            );
+            match placement {
+                AnchorPlacement::Before => format!("{}{}{}", anchor, &caps[3], &caps[4]),
+                AnchorPlacement::After => format!("{} {}{}", &caps[3], anchor, &caps[4]),
+            }
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADING: &str = "<h2><a href=\"#heading-a-b\" aria-hidden=\"true\" class=\"anchor\" \
+                           id=\"heading-a-b\"></a>A &amp; <em>B</em></h2>";
+
+    #[test]
+    fn test_anchor_before_heading() {
+        let html = style_heading_anchors(HEADING, DEFAULT_ANCHOR_SYMBOL, AnchorPlacement::Before);
+        assert_eq!(
+            html,
+            "<h2><a href=\"#heading-a-b\" class=\"anchor\" id=\"heading-a-b\" \
+             aria-label=\"Link to section: A &amp; B\">#</a>A &amp; <em>B</em></h2>"
+        );
+    }
+
+    #[test]
+    fn test_anchor_after_heading() {
+        let html = style_heading_anchors(HEADING, DEFAULT_ANCHOR_SYMBOL, AnchorPlacement::After);
+        assert_eq!(
+            html,
+            "<h2>A &amp; <em>B</em> <a href=\"#heading-a-b\" class=\"anchor\" id=\"heading-a-b\" \
+             aria-label=\"Link to section: A &amp; B\">#</a></h2>"
+        );
+    }
+
+    #[test]
+    fn test_custom_symbol() {
+        let html = style_heading_anchors(HEADING, "¶", AnchorPlacement::After);
+        assert!(html.contains("aria-label=\"Link to section: A &amp; B\">¶</a></h2>"));
+        assert!(!html.contains("aria-hidden"));
+
+        let html = style_heading_anchors(HEADING, "<link>", AnchorPlacement::Before);
+        assert!(html.contains(">&lt;link&gt;</a>A"));
+    }
+}
@@ -0,0 +1,66 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+/// A heading's start tag and its permalink anchor, if any
+static HEADING_START: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"<h([1-6])(?:\s[^>]*)?>(?:<a [^>]*class="anchor"[^>]*></a>)?"#).unwrap()
+});
+
+/// Prefixes rendered headings with outline numbers (`1`, `1.1`, `1.2`, `2`…)
+/// in a `<span class="heading-number">`.
+///
+/// Numbers follow the outline rather than raw levels, so a document whose
+/// top headings are `##` starts at `1` and a skipped level (`##` then `####`)
+/// doesn't produce a `0`.
+pub fn number_headings(html: &str) -> String {
+    // Open headings as (level, count)
+    let mut outline: Vec<(u8, usize)> = Vec::new();
+
+    HEADING_START
+        .replace_all(html, |caps: &Captures| {
+            let level: u8 = caps[1].parse().unwrap_or(1);
+            while outline.last().is_some_and(|&(open, _)| open > level) {
+                outline.pop();
+            }
+            match outline.last_mut() {
+                Some((open, count)) if *open == level => *count += 1,
+                _ => outline.push((level, 1)),
+            }
+
+            let number: Vec<String> = outline.iter().map(|(_, count)| count.to_string()).collect();
+            format!(
+                "{}<span class=\"heading-number\">{}</span> ",
+                &caps[0],
+                number.join(".")
+            )
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numbers_follow_outline() {
+        let html = "<h2><a href=\"#a\" aria-hidden=\"true\" class=\"anchor\" id=\"a\"></a>A</h2>\
+                    <h3>B</h3><h3>C</h3><h5>D</h5><h2>E</h2><h4>F</h4><h1>G</h1>";
+        let numbered = number_headings(html);
+        let numbers: Vec<&str> = numbered
+            .split("<span class=\"heading-number\">")
+            .skip(1)
+            .map(|rest| rest.split('<').next().unwrap())
+            .collect();
+        assert_eq!(numbers, vec!["1", "1.1", "1.2", "1.2.1", "2", "2.1", "1"]);
+        assert!(numbered.starts_with(
+            "<h2><a href=\"#a\" aria-hidden=\"true\" class=\"anchor\" id=\"a\"></a>\
+             <span class=\"heading-number\">1</span> A</h2>"
+        ));
+    }
+
+    #[test]
+    fn test_leaves_other_elements() {
+        let html = "<p>no headings</p><header>x</header><hr>";
+        assert_eq!(number_headings(html), html);
+    }
+}
@@ -0,0 +1,233 @@
+use super::front_matter::extract_front_matter;
+use super::lines::{atx_heading, source_lines, SourceLine};
+
+/// A heading located in the source
+#[derive(Debug, Clone)]
+pub(super) struct SourceHeading {
+    /// Index of the (first) heading line
+    pub line: usize,
+    /// Index of the setext underline, if the heading is setext
+    pub underline: Option<usize>,
+    pub level: u8,
+    /// Heading text; setext paragraphs are joined with spaces
+    pub text: String,
+}
+
+/// Shifts and compacts heading levels so the document starts at `start_level`
+/// and never skips a level, keeping the relative structure.
+///
+/// A heading becomes one level below the nearest preceding heading with a lower
+/// original level (or `start_level` when there is none), so H1 → H3 → H3 → H2
+/// becomes H1 → H2 → H2 → H2. Levels are capped at 6. ATX and setext headings
+/// are handled; fenced code and front matter are untouched. Setext headings
+/// that end up deeper than H2 are rewritten as ATX headings.
+pub fn normalize_heading_levels(markdown: &str, start_level: u8) -> String {
+    let start_level = start_level.clamp(1, 6);
+    // (original level, new level) of the open ancestors
+    let mut stack: Vec<(u8, u8)> = Vec::new();
+
+    relevel_headings(markdown, |original| {
+        while stack.last().is_some_and(|&(open, _)| open >= original) {
+            stack.pop();
+        }
+        let level = stack.last().map_or(start_level, |&(_, new)| (new + 1).min(6));
+        stack.push((original, level));
+        level
+    })
+}
+
+/// Moves every heading `shift` levels deeper (or shallower when negative),
+/// clamped to H1–H6, e.g. so an embedded document nests under the heading
+/// that includes it.
+///
+/// Unlike [`normalize_heading_levels`] the structure isn't compacted, only
+/// offset. Code and front matter are untouched.
+pub fn shift_heading_levels(markdown: &str, shift: i8) -> String {
+    if shift == 0 {
+        return markdown.to_string();
+    }
+    relevel_headings(markdown, |level| (level as i8 + shift).clamp(1, 6) as u8)
+}
+
+/// Rewrites each heading to the level `new_level` returns for its original
+/// level, visiting headings in document order
+fn relevel_headings(markdown: &str, mut new_level: impl FnMut(u8) -> u8) -> String {
+    let (_, body) = extract_front_matter(markdown);
+    let front = &markdown[..markdown.len() - body.len()];
+
+    let lines = source_lines(body);
+    let mut output: Vec<Option<String>> =
+        lines.iter().map(|line| Some(line.text.to_string())).collect();
+    for heading in find_headings(&lines) {
+        let level = new_level(heading.level);
+        if level == heading.level {
+            continue;
+        }
+
+        match heading.underline {
+            None => {
+                let text = lines[heading.line].text;
+                let trimmed = text.trim_start();
+                let indent = &text[..text.len() - trimmed.len()];
+                let rest = &trimmed[heading.level as usize..];
+                let hashes = "#".repeat(level as usize);
+                output[heading.line] = Some(format!("{}{}{}", indent, hashes, rest));
+            }
+            Some(underline) if level <= 2 => {
+                let marker = if level == 1 { "=" } else { "-" };
+                let width = lines[underline].text.trim().len();
+                output[underline] = Some(marker.repeat(width));
+            }
+            Some(underline) => {
+                let hashes = "#".repeat(level as usize);
+                output[heading.line] = Some(format!("{} {}", hashes, heading.text));
+                for line in &mut output[heading.line + 1..=underline] {
+                    *line = None;
+                }
+            }
+        }
+    }
+
+    let mut result = front.to_string();
+    result.push_str(&output.into_iter().flatten().collect::<Vec<_>>().join("\n"));
+    if body.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+/// Finds ATX headings and setext headings (a paragraph followed by a `===` or
+/// `---` underline) outside fenced code
+pub(super) fn find_headings(lines: &[SourceLine]) -> Vec<SourceHeading> {
+    let mut headings = Vec::new();
+    let mut paragraph_start: Option<usize> = None;
+
+    for (index, line) in lines.iter().enumerate() {
+        let text = line.text;
+        if line.in_code || text.trim().is_empty() {
+            paragraph_start = None;
+            continue;
+        }
+        if let Some((level, title)) = atx_heading(text) {
+            headings.push(SourceHeading {
+                line: index,
+                underline: None,
+                level,
+                text: title.to_string(),
+            });
+            paragraph_start = None;
+            continue;
+        }
+        if let (Some(start), Some(level)) = (paragraph_start, setext_level(text)) {
+            let title: Vec<&str> = lines[start..index].iter().map(|l| l.text.trim()).collect();
+            headings.push(SourceHeading {
+                line: start,
+                underline: Some(index),
+                level,
+                text: title.join(" "),
+            });
+            paragraph_start = None;
+            continue;
+        }
+        if paragraph_start.is_none() && !starts_other_block(text) {
+            paragraph_start = Some(index);
+        }
+    }
+
+    headings
+}
+
+/// The level of a setext underline (`===` → 1, `---` → 2)
+fn setext_level(line: &str) -> Option<u8> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let underline = trimmed.trim_end();
+    if !underline.is_empty() && underline.chars().all(|c| c == '=') {
+        Some(1)
+    } else if !underline.is_empty() && underline.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// Lines that start a block other than a paragraph (list items, quotes, HTML,
+/// thematic breaks, indented code), which can't begin a setext heading
+fn starts_other_block(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    let after_marker = |rest: &str| rest.is_empty() || rest.starts_with([' ', '\t']);
+
+    let bullet = trimmed.starts_with(['-', '*', '+']) && after_marker(&trimmed[1..]);
+    let digits = trimmed.len() - trimmed.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let ordered = digits > 0
+        && trimmed[digits..].starts_with(['.', ')'])
+        && after_marker(&trimmed[digits + 1..]);
+    let compact: String = trimmed.chars().filter(|c| !c.is_whitespace()).collect();
+    let thematic_break = compact.len() >= 3
+        && ['*', '_', '-'].iter().any(|&m| compact.chars().all(|c| c == m));
+
+    bullet
+        || ordered
+        || thematic_break
+        || trimmed.starts_with(['>', '<'])
+        || line.len() - trimmed.len() > 3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compacts_skipped_levels() {
+        let md = "# Title\n\n### Part\n\nText\n\n#### Detail\n\n### Next\n\n## Back\n";
+        assert_eq!(
+            normalize_heading_levels(md, 1),
+            "# Title\n\n## Part\n\nText\n\n### Detail\n\n## Next\n\n## Back\n"
+        );
+    }
+
+    #[test]
+    fn test_shifts_to_start_level() {
+        let md = "### A ###\n#### B\n### C";
+        assert_eq!(normalize_heading_levels(md, 1), "# A ###\n## B\n# C");
+        assert_eq!(normalize_heading_levels("# A\n## B", 2), "## A\n### B");
+    }
+
+    #[test]
+    fn test_code_and_front_matter_untouched() {
+        let md = "---\ntitle: x\n---\n### Top\n\n```md\n### not a heading\n```\n";
+        assert_eq!(
+            normalize_heading_levels(md, 1),
+            "---\ntitle: x\n---\n# Top\n\n```md\n### not a heading\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_emphasis_paragraph_can_be_setext() {
+        let md = "*Intro* text\n===\n\n#### Deep";
+        assert_eq!(normalize_heading_levels(md, 1), "*Intro* text\n===\n\n## Deep");
+    }
+
+    #[test]
+    fn test_shift_heading_levels() {
+        let md = "---\ntitle: x\n---\n# Title\n\nIntro\n\n## Part\n\n```\n# code\n```\n\nSub\n---\n";
+        assert_eq!(
+            shift_heading_levels(md, 1),
+            "---\ntitle: x\n---\n## Title\n\nIntro\n\n### Part\n\n```\n# code\n```\n\n### Sub\n"
+        );
+        assert_eq!(shift_heading_levels("## A\n###### B", -2), "# A\n#### B");
+        assert_eq!(shift_heading_levels("##### A\n###### B", 3), "###### A\n###### B");
+    }
+
+    #[test]
+    fn test_setext_headings() {
+        let md = "Title\n=====\n\nPart\n----\n\nList:\n\n- item\n---";
+        assert_eq!(
+            normalize_heading_levels(md, 2),
+            "Title\n-----\n\n### Part\n\nList:\n\n- item\n---"
+        );
+        assert_eq!(normalize_heading_levels("Two\nlines\n===\n", 3), "### Two lines\n");
+    }
+}
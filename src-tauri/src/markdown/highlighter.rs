@@ -1,24 +1,126 @@
 use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
 use syntect::highlighting::ThemeSet;
-use syntect::html::{ClassStyle, ClassedHTMLGenerator};
-use syntect::parsing::SyntaxSet;
+use syntect::html::{highlighted_html_for_string, ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
 use syntect::util::LinesWithEndings;
 
+use super::parser::escape_html;
+
 /// Lazy-loaded syntax set (expensive to create)
 static SYNTAX_SET: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
 
 /// Lazy-loaded theme set
 static THEME_SET: Lazy<ThemeSet> = Lazy::new(ThemeSet::load_defaults);
 
-/// Highlights code using syntect with CSS classes
+/// A highlighted token, for frontends that build their own DOM
+#[derive(Debug, Clone, Serialize)]
+pub struct Token {
+    /// The token text (without the line ending)
+    pub text: String,
+    /// Space-separated classes, matching the nested spans `highlight_code` emits
+    pub class: String,
+    /// The innermost scope name (e.g. "keyword.control.rust")
+    pub scope: String,
+    /// 1-based source line
+    pub line: usize,
+}
+
+/// Highlighted code with its line count
+#[derive(Debug, Clone)]
+pub struct HighlightedCode {
+    pub html: String,
+    /// Number of source lines
+    pub lines: usize,
+}
+
+/// Alternative language names mapped to the name the highlighter handles
 ///
-/// Uses class-based highlighting (prefix: "hl-") so themes can be switched
-/// via CSS without re-rendering the HTML.
-pub fn highlight_code(code: &str, lang: &str) -> String {
-    let syntax = SYNTAX_SET
+/// JSON with comments goes to syntect's JSON syntax, which accepts `//` and
+/// `/* */` comments; JSON5 (unquoted keys, trailing commas) reads best as
+/// JavaScript. syntect ships no TOML or INI syntax, so those use the small
+/// line-based highlighter in [`highlight_config`].
+const LANGUAGE_ALIASES: &[(&str, &str)] = &[
+    ("console", "shell-session"),
+    ("shellsession", "shell-session"),
+    ("jsonc", "json"),
+    ("json5", "javascript"),
+    ("cfg", "ini"),
+    ("dosini", "ini"),
+];
+
+/// Values in TOML/INI files: strings, comments, booleans, numbers and dates
+static CONFIG_VALUE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#""(?:[^"\\]|\\.)*"|'[^']*'|(?P<comment>[#;].*$)|(?P<bool>\b(?:true|false)\b)|(?P<number>[+-]?\b\d[\d_]*(?:[.:eE+-]\d[\d_]*)*(?:[T ]\d[\d:.]*)?Z?\b)"#,
+    )
+    .unwrap()
+});
+
+/// Prompt at the start of a shell session line: `$ `, `# `, `% `, optionally
+/// preceded by a `(venv) ` marker and `user@host:path`
+static SHELL_PROMPT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:\([\w.-]+\) )?(?:[\w.-]+@[\w.-]+(?::[^\s$#%]*)?)?[$#%](?: |$)").unwrap()
+});
+
+/// Lowercases a language name and resolves aliases
+fn canonical_lang(lang: &str) -> String {
+    let lang = lang.to_lowercase();
+    LANGUAGE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == lang)
+        .map_or(lang, |(_, name)| name.to_string())
+}
+
+/// Finds the syntax for a language token or extension, falling back to plain text
+fn find_syntax(lang: &str) -> &'static SyntaxReference {
+    SYNTAX_SET
         .find_syntax_by_token(lang)
         .or_else(|| SYNTAX_SET.find_syntax_by_extension(lang))
-        .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text());
+        .unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text())
+}
+
+/// Class on highlighted `<pre>`s of light documents; the light highlight CSS
+/// gives it a background that matches the token colors
+pub const LIGHT_CODE_THEME_CLASS: &str = "code-theme-github-light";
+
+/// Class on highlighted `<pre>`s of dark documents, styled by the dark CSS
+pub const DARK_CODE_THEME_CLASS: &str = "code-theme-github-dark";
+
+/// The `<pre>` class for code in a light or dark document
+pub fn code_theme_class(dark: bool) -> &'static str {
+    if dark {
+        DARK_CODE_THEME_CLASS
+    } else {
+        LIGHT_CODE_THEME_CLASS
+    }
+}
+
+/// Highlights code using syntect with CSS classes
+///
+/// Uses class-based highlighting (spans carry syntect's scope names, e.g.
+/// `storage type function rust`) so themes can be switched via CSS without
+/// re-rendering the HTML.
+pub fn highlight_code(code: &str, lang: &str) -> String {
+    match canonical_lang(lang).as_str() {
+        "diff" | "patch" | "udiff" => highlight_diff(code),
+        "shell-session" => highlight_shell_session(code),
+        lang @ ("toml" | "ini") => highlight_config(code, lang),
+        lang => highlight_with_syntect(code, lang),
+    }
+}
+
+/// Like [`highlight_code`], also counting the lines of the block
+pub fn highlight_code_block(code: &str, lang: &str) -> HighlightedCode {
+    HighlightedCode {
+        html: highlight_code(code, lang),
+        lines: LinesWithEndings::from(code).count(),
+    }
+}
+
+fn highlight_with_syntect(code: &str, lang: &str) -> String {
+    let syntax = find_syntax(lang);
 
     let mut html_generator =
         ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAX_SET, ClassStyle::Spaced);
@@ -30,53 +132,294 @@ pub fn highlight_code(code: &str, lang: &str) -> String {
     html_generator.finalize()
 }
 
+/// Highlights a unified diff.
+///
+/// Hunk headers (`@@ -1,4 +1,6 @@`) get a distinct `hl-diff-hunk` block so hunks
+/// are visually separated, and `\ No newline at end of file` markers are styled
+/// as meta. Everything else goes through syntect's Diff syntax.
+fn highlight_diff(code: &str) -> String {
+    let mut html = String::new();
+    let mut pending = String::new();
+
+    for line in LinesWithEndings::from(code) {
+        let class = if line.starts_with("@@") {
+            Some("hl-diff-hunk")
+        } else if line.starts_with("\\ ") {
+            Some("hl-diff-meta meta")
+        } else {
+            None
+        };
+
+        match class {
+            Some(class) => {
+                if !pending.is_empty() {
+                    html.push_str(&highlight_with_syntect(&pending, "diff"));
+                    pending.clear();
+                }
+                let content = line.trim_end_matches(['\r', '\n']);
+                let ending = &line[content.len()..];
+                html.push_str(&format!(
+                    "<span class=\"{}\">{}</span>{}",
+                    class,
+                    escape_html(content),
+                    ending
+                ));
+            }
+            None => pending.push_str(line),
+        }
+    }
+
+    if !pending.is_empty() {
+        html.push_str(&highlight_with_syntect(&pending, "diff"));
+    }
+
+    html
+}
+
+/// Highlights a pasted shell session (`console`, `shell-session`).
+///
+/// Lines starting with a prompt get a `hl-prompt` span for the prompt and the
+/// command highlighted as bash; lines continued with a trailing `\` stay
+/// commands. All other lines are output, wrapped in a dimmed `hl-output` span.
+fn highlight_shell_session(code: &str) -> String {
+    let mut html = String::new();
+    let mut continued = false;
+
+    for line in LinesWithEndings::from(code) {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let ending = &line[content.len()..];
+        let prompt_len = if continued {
+            Some(0)
+        } else {
+            SHELL_PROMPT.find(content).map(|m| m.end())
+        };
+
+        match prompt_len {
+            Some(len) => {
+                let (prompt, command) = content.split_at(len);
+                if !prompt.is_empty() {
+                    html.push_str(&format!(
+                        "<span class=\"hl-prompt\">{}</span>",
+                        escape_html(prompt)
+                    ));
+                }
+                html.push_str(&highlight_with_syntect(command, "bash"));
+                html.push_str(ending);
+                continued = command.ends_with('\\');
+            }
+            None => {
+                html.push_str(&format!(
+                    "<span class=\"hl-output\">{}</span>{}",
+                    escape_html(content),
+                    ending
+                ));
+            }
+        }
+    }
+
+    html
+}
+
+/// Highlights TOML and INI files line by line.
+///
+/// Section headers (`[server]`, `[[items]]`), keys, comments, strings,
+/// booleans, numbers and dates get the same scope classes syntect uses
+/// (e.g. `entity name section toml`), so the theme CSS applies unchanged.
+fn highlight_config(code: &str, lang: &str) -> String {
+    let mut html = String::new();
+
+    for line in LinesWithEndings::from(code) {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let ending = &line[content.len()..];
+        let trimmed = content.trim_start();
+        let indent = &content[..content.len() - trimmed.len()];
+        html.push_str(indent);
+
+        if trimmed.starts_with(['#', ';']) {
+            html.push_str(&config_span("comment line", trimmed, lang));
+        } else if trimmed.starts_with('[') {
+            // `[section]` with an optional trailing comment
+            let end = trimmed.rfind(']').map_or(trimmed.len(), |i| i + 1);
+            html.push_str(&config_span("entity name section", &trimmed[..end], lang));
+            html.push_str(&config_values(&trimmed[end..], lang));
+        } else if let Some(eq) = trimmed.find('=').filter(|&i| !trimmed[..i].contains('"')) {
+            let key = trimmed[..eq].trim_end();
+            html.push_str(&config_span("entity name tag", key, lang));
+            html.push_str(&escape_html(&trimmed[key.len()..=eq]));
+            html.push_str(&config_values(&trimmed[eq + 1..], lang));
+        } else {
+            // Continuation of a multi-line array or inline table
+            html.push_str(&config_values(trimmed, lang));
+        }
+        html.push_str(ending);
+    }
+
+    html
+}
+
+fn config_values(text: &str, lang: &str) -> String {
+    let mut html = String::new();
+    let mut last = 0;
+    for caps in CONFIG_VALUE.captures_iter(text) {
+        let token = caps.get(0).unwrap();
+        let scope = if caps.name("comment").is_some() {
+            "comment line"
+        } else if caps.name("bool").is_some() {
+            "constant language"
+        } else if caps.name("number").is_some() {
+            "constant numeric"
+        } else {
+            "string quoted"
+        };
+        html.push_str(&escape_html(&text[last..token.start()]));
+        html.push_str(&config_span(scope, token.as_str(), lang));
+        last = token.end();
+    }
+    html.push_str(&escape_html(&text[last..]));
+    html
+}
+
+fn config_span(scope: &str, text: &str, lang: &str) -> String {
+    format!("<span class=\"{} {}\">{}</span>", scope, lang, escape_html(text))
+}
+
+/// Tokenizes code into structured tokens instead of HTML.
+///
+/// Each token carries its line number so line boundaries can be reconstructed.
+pub fn highlight_tokens(code: &str, lang: &str) -> Vec<Token> {
+    let mut parse_state = ParseState::new(find_syntax(&canonical_lang(lang)));
+    let mut stack = ScopeStack::new();
+    let mut tokens = Vec::new();
+
+    for (index, line) in LinesWithEndings::from(code).enumerate() {
+        let content_len = line.trim_end_matches(['\r', '\n']).len();
+        let ops = parse_state.parse_line(line, &SYNTAX_SET).unwrap_or_default();
+        let mut pos = 0;
+
+        for (offset, op) in ops {
+            let offset = offset.min(content_len);
+            if offset > pos {
+                tokens.push(make_token(&line[pos..offset], &stack, index + 1));
+                pos = offset;
+            }
+            let _ = stack.apply(&op);
+        }
+        if pos < content_len {
+            tokens.push(make_token(&line[pos..content_len], &stack, index + 1));
+        }
+    }
+
+    tokens
+}
+
+fn make_token(text: &str, stack: &ScopeStack, line: usize) -> Token {
+    let scopes = stack.as_slice();
+    Token {
+        text: text.to_string(),
+        class: scopes
+            .iter()
+            .map(|scope| scope.build_string().replace('.', " "))
+            .collect::<Vec<_>>()
+            .join(" "),
+        scope: scopes.last().map(|s| s.build_string()).unwrap_or_default(),
+        line,
+    }
+}
+
+/// Short code samples used for theme previews
+const THEME_SAMPLES: &[(&str, &str)] = &[
+    (
+        "rust",
+        "// Greets the world\nfn main() {\n    let name = \"world\";\n    println!(\"Hello, {}!\", name);\n}\n",
+    ),
+    (
+        "python",
+        "def fib(n: int) -> int:\n    \"\"\"Returns the nth number.\"\"\"\n    return n if n < 2 else fib(n - 1) + fib(n - 2)\n",
+    ),
+    (
+        "javascript",
+        "const items = [1, 2, 3];\nexport function total() {\n  return items.reduce((a, b) => a + b, 0);\n}\n",
+    ),
+];
+
+/// Lists the names of the bundled syntect themes
+pub fn list_themes() -> Vec<String> {
+    THEME_SET.themes.keys().cloned().collect()
+}
+
+/// Renders a code sample with a bundled theme's colors inlined, for preview swatches.
+///
+/// Uses the sample for `sample_lang` when one exists, otherwise all samples.
+pub fn preview_theme_html(theme: &str, sample_lang: &str) -> Result<String, String> {
+    let theme = THEME_SET
+        .themes
+        .get(theme)
+        .ok_or_else(|| format!("Unknown theme: {}", theme))?;
+
+    let lang = sample_lang.trim().to_lowercase();
+    let selected: Vec<&(&str, &str)> = match THEME_SAMPLES.iter().find(|(l, _)| *l == lang) {
+        Some(sample) => vec![sample],
+        None => THEME_SAMPLES.iter().collect(),
+    };
+
+    let mut html = String::new();
+    for (lang, code) in selected {
+        let rendered = highlighted_html_for_string(code, &SYNTAX_SET, find_syntax(lang), theme)
+            .map_err(|e| format!("Failed to render preview: {}", e))?;
+        html.push_str(&rendered);
+    }
+
+    Ok(html)
+}
+
 /// Returns CSS for syntax highlighting (light theme)
+///
+/// Rules target the scope classes syntect emits (`keyword control rust`,
+/// `entity name function rust`), inside the `code-theme-github-light` block.
 pub fn get_highlight_css_light() -> &'static str {
     r#"
-/* Syntect highlight classes - Light theme */
-.hljs { color: #24292e; }
-.hljs-keyword, .hljs-selector-tag, .hljs-type { color: #d73a49; }
-.hljs-string, .hljs-attribute { color: #032f62; }
-.hljs-comment, .hljs-quote { color: #6a737d; font-style: italic; }
-.hljs-function, .hljs-title { color: #6f42c1; }
-.hljs-number, .hljs-literal { color: #005cc5; }
-.hljs-operator { color: #d73a49; }
-.hljs-variable, .hljs-template-variable { color: #e36209; }
-.hljs-built_in { color: #005cc5; }
-.hljs-symbol { color: #6f42c1; }
-.hljs-meta { color: #6a737d; }
-.hljs-params { color: #24292e; }
-.hljs-class .hljs-title { color: #6f42c1; }
-.hljs-doctag { color: #d73a49; }
-.hljs-regexp { color: #032f62; }
-.hljs-section { color: #005cc5; font-weight: bold; }
-.hljs-addition { color: #22863a; background: #f0fff4; }
-.hljs-deletion { color: #b31d28; background: #ffeef0; }
+/* Syntect scope classes - Light theme */
+.code-theme-github-light { color: #24292e; background: #f6f8fa; }
+.code-theme-github-light .comment { color: #6a737d; font-style: italic; }
+.code-theme-github-light .keyword, .code-theme-github-light .storage, .code-theme-github-light .entity.name.tag { color: #d73a49; }
+.code-theme-github-light .string, .code-theme-github-light .entity.other.attribute-name { color: #032f62; }
+.code-theme-github-light .constant, .code-theme-github-light .support.constant, .code-theme-github-light .support.function, .code-theme-github-light .support.type { color: #005cc5; }
+.code-theme-github-light .entity.name.function, .code-theme-github-light .entity.name.type, .code-theme-github-light .entity.name.class, .code-theme-github-light .support.macro, .code-theme-github-light .constant.other.symbol { color: #6f42c1; }
+.code-theme-github-light .variable { color: #e36209; }
+.code-theme-github-light .variable.parameter { color: #24292e; }
+.code-theme-github-light .meta.preprocessor, .code-theme-github-light .meta.annotation, .code-theme-github-light .comment.block.documentation { color: #6a737d; }
+.code-theme-github-light .entity.name.section, .code-theme-github-light .markup.heading { color: #005cc5; font-weight: bold; }
+.code-theme-github-light .markup.inserted { color: #22863a; background: #f0fff4; }
+.code-theme-github-light .markup.deleted { color: #b31d28; background: #ffeef0; }
+.hl-diff-hunk { display: inline-block; width: 100%; color: #6f42c1; background: #f1f8ff; }
+.hl-diff-hunk:not(:first-child) { margin-top: 0.5em; border-top: 1px dashed #d1d5da; }
+.hl-diff-meta { color: #6a737d; font-style: italic; }
+.hl-prompt { color: #6f42c1; font-weight: bold; user-select: none; }
+.hl-output { color: #6a737d; }
 "#
 }
 
 /// Returns CSS for syntax highlighting (dark theme)
 pub fn get_highlight_css_dark() -> &'static str {
     r#"
-/* Syntect highlight classes - Dark theme */
-.dark .hljs { color: #e1e4e8; }
-.dark .hljs-keyword, .dark .hljs-selector-tag, .dark .hljs-type { color: #ff7b72; }
-.dark .hljs-string, .dark .hljs-attribute { color: #a5d6ff; }
-.dark .hljs-comment, .dark .hljs-quote { color: #8b949e; font-style: italic; }
-.dark .hljs-function, .dark .hljs-title { color: #d2a8ff; }
-.dark .hljs-number, .dark .hljs-literal { color: #79c0ff; }
-.dark .hljs-operator { color: #ff7b72; }
-.dark .hljs-variable, .dark .hljs-template-variable { color: #ffa657; }
-.dark .hljs-built_in { color: #79c0ff; }
-.dark .hljs-symbol { color: #d2a8ff; }
-.dark .hljs-meta { color: #8b949e; }
-.dark .hljs-params { color: #e1e4e8; }
-.dark .hljs-class .hljs-title { color: #d2a8ff; }
-.dark .hljs-doctag { color: #ff7b72; }
-.dark .hljs-regexp { color: #a5d6ff; }
-.dark .hljs-section { color: #79c0ff; font-weight: bold; }
-.dark .hljs-addition { color: #7ee787; background: rgba(46, 160, 67, 0.15); }
-.dark .hljs-deletion { color: #ffa198; background: rgba(248, 81, 73, 0.15); }
+/* Syntect scope classes - Dark theme */
+.code-theme-github-dark { color: #e1e4e8; background: #161b22; }
+.code-theme-github-dark .comment { color: #8b949e; font-style: italic; }
+.code-theme-github-dark .keyword, .code-theme-github-dark .storage, .code-theme-github-dark .entity.name.tag { color: #ff7b72; }
+.code-theme-github-dark .string, .code-theme-github-dark .entity.other.attribute-name { color: #a5d6ff; }
+.code-theme-github-dark .constant, .code-theme-github-dark .support.constant, .code-theme-github-dark .support.function, .code-theme-github-dark .support.type { color: #79c0ff; }
+.code-theme-github-dark .entity.name.function, .code-theme-github-dark .entity.name.type, .code-theme-github-dark .entity.name.class, .code-theme-github-dark .support.macro, .code-theme-github-dark .constant.other.symbol { color: #d2a8ff; }
+.code-theme-github-dark .variable { color: #ffa657; }
+.code-theme-github-dark .variable.parameter { color: #e1e4e8; }
+.code-theme-github-dark .meta.preprocessor, .code-theme-github-dark .meta.annotation, .code-theme-github-dark .comment.block.documentation { color: #8b949e; }
+.code-theme-github-dark .entity.name.section, .code-theme-github-dark .markup.heading { color: #79c0ff; font-weight: bold; }
+.code-theme-github-dark .markup.inserted { color: #7ee787; background: rgba(46, 160, 67, 0.15); }
+.code-theme-github-dark .markup.deleted { color: #ffa198; background: rgba(248, 81, 73, 0.15); }
+.dark .hl-diff-hunk { display: inline-block; width: 100%; color: #d2a8ff; background: rgba(56, 139, 253, 0.15); }
+.dark .hl-diff-hunk:not(:first-child) { margin-top: 0.5em; border-top: 1px dashed #30363d; }
+.dark .hl-diff-meta { color: #8b949e; font-style: italic; }
+.dark .hl-prompt { color: #d2a8ff; font-weight: bold; user-select: none; }
+.dark .hl-output { color: #8b949e; }
 "#
 }
 
@@ -92,6 +435,47 @@ mod tests {
         assert!(html.contains("fn"));
     }
 
+    #[test]
+    fn test_highlight_code_block_counts_lines() {
+        let block = highlight_code_block("a = 1\nb = 2\n", "python");
+        assert_eq!(block.lines, 2);
+        assert_eq!(block.html, highlight_code("a = 1\nb = 2\n", "python"));
+        assert_eq!(highlight_code_block("$ ls\nfile", "console").lines, 2);
+    }
+
+    #[test]
+    fn test_highlight_jsonc_comments() {
+        let code = "{\n  // port to listen on\n  \"port\": 8080\n}";
+        for lang in ["jsonc", "JSON5"] {
+            let html = highlight_code(code, lang);
+            assert!(html.contains("comment"), "{} comments not highlighted", lang);
+            assert!(html.contains("8080"));
+        }
+    }
+
+    #[test]
+    fn test_highlight_toml() {
+        let code = "# Server\n[server]\nhost = \"localhost\" # default\nport = 8080\ntls = true\n";
+        let html = highlight_code(code, "toml");
+
+        assert!(html.contains("<span class=\"entity name section toml\">[server]</span>"));
+        assert!(html.contains("<span class=\"comment line toml\"># Server</span>"));
+        assert!(html.contains("<span class=\"entity name tag toml\">host</span> ="));
+        assert!(html.contains("<span class=\"string quoted toml\">&quot;localhost&quot;</span>"));
+        assert!(html.contains("<span class=\"comment line toml\"># default</span>"));
+        assert!(html.contains("<span class=\"constant numeric toml\">8080</span>"));
+        assert!(html.contains("<span class=\"constant language toml\">true</span>"));
+    }
+
+    #[test]
+    fn test_highlight_ini() {
+        let html = highlight_code("; settings\n[core]\neditor = vim\n", "cfg");
+
+        assert!(html.contains("<span class=\"comment line ini\">; settings</span>"));
+        assert!(html.contains("<span class=\"entity name section ini\">[core]</span>"));
+        assert!(html.contains("<span class=\"entity name tag ini\">editor</span> = vim"));
+    }
+
     #[test]
     fn test_highlight_unknown_lang() {
         let code = "some text";
@@ -106,4 +490,128 @@ mod tests {
         let html = highlight_code(code, "js");
         assert!(html.contains("span"));
     }
+
+    #[test]
+    fn test_highlight_tokens_rust() {
+        let tokens = highlight_tokens("fn main() {\n    let x = 1;\n}", "rust");
+
+        let text: String = tokens.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(text, "fn main() {    let x = 1;}");
+
+        let keyword = tokens.iter().find(|t| t.text == "fn").unwrap();
+        assert!(keyword.class.starts_with("source rust"));
+        assert!(!keyword.scope.is_empty());
+        assert_eq!(keyword.line, 1);
+
+        assert!(tokens.iter().any(|t| t.text == "let" && t.line == 2));
+        assert_eq!(tokens.last().unwrap().line, 3);
+    }
+
+    #[test]
+    fn test_highlight_tokens_plain_text() {
+        let tokens = highlight_tokens("hello\nworld", "unknown_lang_xyz");
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].text, "hello");
+        assert_eq!(tokens[1].line, 2);
+    }
+
+    #[test]
+    fn test_css_styles_code_theme_class() {
+        let light = format!(".{} {{", code_theme_class(false));
+        let dark = format!(".{} {{", code_theme_class(true));
+        assert!(get_highlight_css_light().contains(&light));
+        assert!(get_highlight_css_dark().contains(&dark));
+        assert!(get_highlight_css_dark().contains("background: #161b22"));
+    }
+
+    /// Whether any rule in `css` styles a span with these classes
+    fn css_covers(css: &str, classes: &[&str]) -> bool {
+        css.lines()
+            .filter_map(|line| line.split_once(" {"))
+            .flat_map(|(selectors, _)| selectors.split(','))
+            .any(|selector| {
+                let compound = selector.trim().rsplit(' ').next().unwrap_or("");
+                compound.starts_with('.')
+                    && compound
+                        .split('.')
+                        .filter(|class| !class.is_empty())
+                        .all(|class| classes.contains(&class))
+            })
+    }
+
+    #[test]
+    fn test_css_covers_emitted_classes() {
+        let leaf = Regex::new(r#"<span class="([^"]*)">[^<]*</span>"#).unwrap();
+        let html = highlight_code("fn main(){}", "rust");
+        let token_roots = [
+            "comment", "keyword", "storage", "string", "constant", "entity", "variable", "support",
+            "markup",
+        ];
+
+        let mut checked = 0;
+        for found in leaf.captures_iter(&html) {
+            let classes: Vec<&str> = found[1].split_whitespace().collect();
+            if !token_roots.contains(&classes[0]) {
+                continue;
+            }
+            checked += 1;
+            for css in [get_highlight_css_light(), get_highlight_css_dark()] {
+                assert!(css_covers(css, &classes), "unstyled {:?}", classes);
+            }
+        }
+        assert!(checked >= 2, "{}", html);
+
+        assert!(!get_highlight_css_light().contains("hljs"));
+        assert!(!get_highlight_css_dark().contains("hljs"));
+    }
+
+    #[test]
+    fn test_list_themes() {
+        let themes = list_themes();
+        assert!(themes.contains(&"InspiredGitHub".to_string()));
+        assert!(themes.contains(&"base16-ocean.dark".to_string()));
+    }
+
+    #[test]
+    fn test_preview_theme() {
+        let html = preview_theme_html("InspiredGitHub", "rust").unwrap();
+        assert!(html.contains("style=\""));
+        assert!(html.contains("fn"));
+        assert!(!html.contains("def"));
+
+        let all = preview_theme_html("base16-ocean.dark", "").unwrap();
+        assert!(all.contains("fn") && all.contains("def") && all.contains("const"));
+
+        assert!(preview_theme_html("no-such-theme", "rust").is_err());
+    }
+
+    #[test]
+    fn test_highlight_diff_hunks() {
+        let diff = "--- a/f.rs\n+++ b/f.rs\n@@ -1,2 +1,2 @@\n-old\n+new\n@@ -10 +10 @@\n ctx\n\\ No newline at end of file\n";
+        let html = highlight_code(diff, "diff");
+
+        assert_eq!(html.matches("<span class=\"hl-diff-hunk\">").count(), 2);
+        assert!(html.contains("<span class=\"hl-diff-hunk\">@@ -1,2 +1,2 @@</span>"));
+        assert!(html.contains("<span class=\"hl-diff-meta meta\">\\ No newline at end of file</span>"));
+        assert!(html.contains("new"));
+        assert!(html.contains("old"));
+    }
+
+    #[test]
+    fn test_highlight_shell_session() {
+        let session = "$ ls -la\ntotal 8\n-rw-r--r-- 1 me me 0 notes.md\nuser@host:~/src# echo \"a\" \\\n  > out.txt\n$\n";
+
+        for lang in ["console", "shell-session", "ShellSession"] {
+            let html = highlight_code(session, lang);
+
+            assert_eq!(html.matches("<span class=\"hl-prompt\">").count(), 3);
+            assert!(html.contains("<span class=\"hl-prompt\">$ </span>"));
+            assert!(html.contains("<span class=\"hl-prompt\">user@host:~/src# </span>"));
+            assert!(html.contains("<span class=\"hl-output\">total 8</span>"));
+            assert!(html.contains("<span class=\"hl-output\">-rw-r--r-- 1 me me 0 notes.md</span>"));
+            // The continuation line is part of the command, not output
+            assert!(!html.contains("<span class=\"hl-output\">  &gt; out.txt</span>"));
+            assert!(html.contains("echo"));
+        }
+    }
 }
@@ -0,0 +1,612 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::search_hits::{tag_name, TAG};
+
+/// `name="value"`, `name='value'` or `name=value` inside a start tag
+static ATTRIBUTE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"([a-zA-Z_:][\w:.-]*)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap()
+});
+
+/// `&name;`, `&#123;` or `&#x1F;`
+static ENTITY: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"&(?:#(\d+)|#[xX]([0-9a-fA-F]+)|([a-zA-Z]+));").unwrap());
+
+/// Elements that never have children
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements dropped with everything inside them
+const DROPPED_ELEMENTS: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "title", "svg", "button", "select",
+];
+
+/// Elements rendered as blocks separated by blank lines
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "html",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "ul",
+];
+
+/// A parsed HTML node
+enum Node {
+    Text(String),
+    Element {
+        name: String,
+        attributes: Vec<(String, String)>,
+        children: Vec<Node>,
+    },
+}
+
+impl Node {
+    fn attribute(&self, key: &str) -> Option<&str> {
+        match self {
+            Node::Element { attributes, .. } => attributes
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.as_str()),
+            Node::Text(_) => None,
+        }
+    }
+
+    fn is_block(&self) -> bool {
+        matches!(self, Node::Element { name, .. } if BLOCK_ELEMENTS.contains(&name.as_str()))
+    }
+}
+
+/// Converts an HTML fragment (e.g. copied from a web page) to markdown.
+///
+/// Headings, paragraphs, emphasis, links, images, inline code, code blocks,
+/// nested lists, blockquotes, rules and tables are converted; other elements
+/// keep only their text. Scripts, styles and the document head are dropped,
+/// and a clipboard `<!--StartFragment-->` marker limits the input to the
+/// copied fragment.
+pub fn html_to_markdown(html: &str) -> String {
+    let fragment = match (
+        html.find("<!--StartFragment-->"),
+        html.find("<!--EndFragment-->"),
+    ) {
+        (Some(start), Some(end)) if start < end => &html[start + 20..end],
+        _ => html,
+    };
+
+    let markdown = render_blocks(&parse(fragment), "\n\n");
+    let mut markdown = markdown.trim().to_string();
+    if !markdown.is_empty() {
+        markdown.push('\n');
+    }
+    markdown
+}
+
+/// Builds a node tree, closing unclosed elements when an ancestor closes
+fn parse(html: &str) -> Vec<Node> {
+    // Open elements, innermost last; the first is a nameless root
+    let mut stack = vec![element(String::new(), Vec::new())];
+    let mut last = 0;
+
+    for tag in TAG.find_iter(html) {
+        push_child(&mut stack, &html[last..tag.start()]);
+        last = tag.end();
+
+        let raw = tag.as_str();
+        if raw.starts_with("<!") || raw.starts_with("<?") {
+            continue;
+        }
+        let (name, closing) = tag_name(raw);
+        if name.is_empty() {
+            continue;
+        }
+
+        if closing {
+            if let Some(open) = stack.iter().rposition(|node| is_element(node, &name)) {
+                while stack.len() > open.max(1) {
+                    close_top(&mut stack);
+                }
+            }
+            continue;
+        }
+
+        // A new list item, paragraph, row or cell implicitly closes the previous one
+        if stack.len() > 1 {
+            let implied = match name.as_str() {
+                "td" | "th" => stack
+                    .last()
+                    .is_some_and(|open| is_element(open, "td") || is_element(open, "th")),
+                "li" | "p" | "tr" => stack.last().is_some_and(|open| is_element(open, &name)),
+                _ => false,
+            };
+            if implied {
+                close_top(&mut stack);
+            }
+        }
+
+        let attributes = ATTRIBUTE
+            .captures_iter(raw)
+            .map(|caps| {
+                let value = caps
+                    .get(2)
+                    .or(caps.get(3))
+                    .or(caps.get(4))
+                    .map_or("", |m| m.as_str());
+                (caps[1].to_ascii_lowercase(), decode_entities(value))
+            })
+            .collect();
+        let void = VOID_ELEMENTS.contains(&name.as_str()) || raw.ends_with("/>");
+        stack.push(element(name, attributes));
+        if void {
+            close_top(&mut stack);
+        }
+    }
+
+    push_child(&mut stack, &html[last..]);
+    while stack.len() > 1 {
+        close_top(&mut stack);
+    }
+    match stack.pop() {
+        Some(Node::Element { children, .. }) => children,
+        _ => Vec::new(),
+    }
+}
+
+fn element(name: String, attributes: Vec<(String, String)>) -> Node {
+    Node::Element {
+        name,
+        attributes,
+        children: Vec::new(),
+    }
+}
+
+/// Adds decoded text to the innermost open element
+fn push_child(stack: &mut [Node], text: &str) {
+    if let (Some(Node::Element { children, .. }), false) = (stack.last_mut(), text.is_empty()) {
+        children.push(Node::Text(decode_entities(text)));
+    }
+}
+
+/// Closes the innermost open element, adding it to its parent
+fn close_top(stack: &mut Vec<Node>) {
+    let Some(node) = stack.pop() else {
+        return;
+    };
+    if let Some(Node::Element { children, .. }) = stack.last_mut() {
+        children.push(node);
+    }
+}
+
+/// Renders nodes as markdown blocks joined by `separator`; runs of inline
+/// nodes between blocks become paragraphs
+fn render_blocks(nodes: &[Node], separator: &str) -> String {
+    let mut blocks: Vec<String> = Vec::new();
+    let mut inline: Vec<&Node> = Vec::new();
+
+    let flush = |inline: &mut Vec<&Node>, blocks: &mut Vec<String>| {
+        let paragraph = render_inline(inline);
+        if !paragraph.is_empty() {
+            blocks.push(paragraph);
+        }
+        inline.clear();
+    };
+
+    for node in nodes {
+        if node.is_block() {
+            flush(&mut inline, &mut blocks);
+            let block = render_block(node);
+            if !block.trim().is_empty() {
+                blocks.push(block);
+            }
+        } else {
+            inline.push(node);
+        }
+    }
+    flush(&mut inline, &mut blocks);
+
+    blocks.join(separator)
+}
+
+fn render_block(node: &Node) -> String {
+    let Node::Element { name, children, .. } = node else {
+        return String::new();
+    };
+
+    match name.as_str() {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level = name[1..].parse().unwrap_or(1);
+            let text = render_inline(&children.iter().collect::<Vec<_>>());
+            format!("{} {}", "#".repeat(level), text.replace('\n', " "))
+        }
+        "hr" => "---".to_string(),
+        "pre" => render_code_block(node),
+        "blockquote" => render_blocks(children, "\n\n")
+            .lines()
+            .map(|line| {
+                if line.is_empty() {
+                    ">".to_string()
+                } else {
+                    format!("> {}", line)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        "ul" | "ol" => render_list(node),
+        "table" => render_table(node),
+        _ => render_blocks(children, "\n\n"),
+    }
+}
+
+fn render_code_block(node: &Node) -> String {
+    let Node::Element { children, .. } = node else {
+        return String::new();
+    };
+    let language = children
+        .iter()
+        .find_map(|child| child.attribute("class"))
+        .or_else(|| node.attribute("class"))
+        .and_then(|class| {
+            class.split_whitespace().find_map(|c| {
+                c.strip_prefix("language-")
+                    .or_else(|| c.strip_prefix("lang-"))
+            })
+        })
+        .unwrap_or("");
+
+    let code = text_content(children);
+    let code = code.strip_prefix('\n').unwrap_or(&code).trim_end();
+    let mut fence = "```".to_string();
+    while code.contains(fence.as_str()) {
+        fence.push('`');
+    }
+    format!("{}{}\n{}\n{}", fence, language, code, fence)
+}
+
+fn render_list(node: &Node) -> String {
+    let Node::Element { name, children, .. } = node else {
+        return String::new();
+    };
+    let start: usize = node
+        .attribute("start")
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(1);
+
+    let mut items = Vec::new();
+    for (index, item) in children
+        .iter()
+        .filter(|child| is_element(child, "li"))
+        .enumerate()
+    {
+        let marker = if name == "ol" {
+            format!("{}. ", start + index)
+        } else {
+            "- ".to_string()
+        };
+        let Node::Element { children, .. } = item else {
+            continue;
+        };
+        let content = render_blocks(children, "\n");
+        let indent = " ".repeat(marker.len());
+        let mut lines = content.lines();
+        let mut text = format!("{}{}", marker, lines.next().unwrap_or_default());
+        for line in lines {
+            text.push('\n');
+            if !line.is_empty() {
+                text.push_str(&indent);
+                text.push_str(line);
+            }
+        }
+        items.push(text.trim_end().to_string());
+    }
+    items.join("\n")
+}
+
+fn render_table(node: &Node) -> String {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    collect_rows(node, &mut rows);
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        return String::new();
+    }
+
+    let line = |cells: &[String]| {
+        let mut cells = cells.to_vec();
+        cells.resize(columns, String::new());
+        format!("| {} |", cells.join(" | "))
+    };
+    let mut table = vec![line(&rows[0]), line(&vec!["---".to_string(); columns])];
+    table.extend(rows[1..].iter().map(|row| line(row)));
+    table.join("\n")
+}
+
+fn collect_rows(node: &Node, rows: &mut Vec<Vec<String>>) {
+    let Node::Element { name, children, .. } = node else {
+        return;
+    };
+    if name == "tr" {
+        let cells = children
+            .iter()
+            .filter(|cell| is_element(cell, "td") || is_element(cell, "th"))
+            .map(|cell| match cell {
+                Node::Element { children, .. } => {
+                    let text = render_inline(&children.iter().collect::<Vec<_>>());
+                    text.replace('\n', " ").replace('|', "\\|")
+                }
+                Node::Text(_) => String::new(),
+            })
+            .collect();
+        rows.push(cells);
+    } else {
+        for child in children {
+            collect_rows(child, rows);
+        }
+    }
+}
+
+/// Renders inline nodes to one paragraph with collapsed whitespace
+fn render_inline(nodes: &[&Node]) -> String {
+    let mut text = String::new();
+    for node in nodes {
+        push_inline(node, &mut text);
+    }
+    // Hard breaks are `\` + newline; trim the spaces around them
+    text.split('\n')
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+fn push_inline(node: &Node, out: &mut String) {
+    let (name, children) = match node {
+        Node::Text(text) => {
+            push_text(out, &escape_markdown(text));
+            return;
+        }
+        Node::Element { name, children, .. } => (name.as_str(), children),
+    };
+    if DROPPED_ELEMENTS.contains(&name) {
+        return;
+    }
+
+    let inner = || {
+        let mut inner = String::new();
+        for child in children {
+            push_inline(child, &mut inner);
+        }
+        inner
+    };
+    let wrap = |out: &mut String, marker: &str| {
+        let inner = inner();
+        let trimmed = inner.trim();
+        if trimmed.is_empty() {
+            push_text(out, &inner);
+            return;
+        }
+        if inner.starts_with(char::is_whitespace) {
+            push_text(out, " ");
+        }
+        out.push_str(marker);
+        out.push_str(trimmed);
+        out.push_str(marker);
+        if inner.ends_with(char::is_whitespace) {
+            push_text(out, " ");
+        }
+    };
+
+    match name {
+        "br" => out.push_str("\\\n"),
+        "strong" | "b" => wrap(out, "**"),
+        "em" | "i" => wrap(out, "*"),
+        "del" | "s" | "strike" => wrap(out, "~~"),
+        "code" | "kbd" | "samp" => {
+            let code = text_content(children);
+            let code = code.split_whitespace().collect::<Vec<_>>().join(" ");
+            if code.is_empty() {
+                return;
+            }
+            let mut fence = "`".to_string();
+            while code.contains(fence.as_str()) {
+                fence.push('`');
+            }
+            let pad = if code.starts_with('`') || code.ends_with('`') {
+                " "
+            } else {
+                ""
+            };
+            out.push_str(&format!("{}{}{}{}{}", fence, pad, code, pad, fence));
+        }
+        "a" => {
+            let text = inner();
+            match node.attribute("href").filter(|href| !href.is_empty()) {
+                Some(href) if !href.starts_with("javascript:") => {
+                    let text = text.trim();
+                    let text = if text.is_empty() { href } else { text };
+                    out.push_str(&format!("[{}]({})", text, link_destination(href)));
+                }
+                _ => push_text(out, &text),
+            }
+        }
+        "img" => {
+            if let Some(src) = node.attribute("src").filter(|src| !src.is_empty()) {
+                let alt = escape_markdown(node.attribute("alt").unwrap_or_default());
+                out.push_str(&format!("![{}]({})", alt.trim(), link_destination(src)));
+            }
+        }
+        _ if BLOCK_ELEMENTS.contains(&name) => {
+            // Blocks nested in inline elements keep only their text flow
+            push_text(out, " ");
+            out.push_str(&inner());
+            push_text(out, " ");
+        }
+        _ => out.push_str(&inner()),
+    }
+}
+
+/// Appends text, collapsing whitespace runs (including across calls) to one space
+fn push_text(out: &mut String, text: &str) {
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !out.is_empty() && !out.ends_with([' ', '\n']) {
+                out.push(' ');
+            }
+        } else {
+            out.push(c);
+        }
+    }
+}
+
+/// Text of nodes as written (for code), with `<br>` as a newline
+fn text_content(nodes: &[Node]) -> String {
+    let mut text = String::new();
+    for node in nodes {
+        match node {
+            Node::Text(t) => text.push_str(t),
+            Node::Element { name, .. } if name == "br" => text.push('\n'),
+            Node::Element { children, .. } => text.push_str(&text_content(children)),
+        }
+    }
+    text
+}
+
+/// Escapes characters that would otherwise start markdown syntax
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Wraps a URL in `<>` when it contains spaces or parentheses
+fn link_destination(url: &str) -> String {
+    if url.contains([' ', '(', ')']) {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        url.to_string()
+    }
+}
+
+fn is_element(node: &Node, tag: &str) -> bool {
+    matches!(node, Node::Element { name, .. } if name == tag)
+}
+
+/// Decodes numeric entities and the common named ones; `&nbsp;` becomes a space
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    ENTITY
+        .replace_all(text, |caps: &regex::Captures| {
+            let decoded = if let Some(decimal) = caps.get(1) {
+                decimal.as_str().parse().ok().and_then(char::from_u32)
+            } else if let Some(hex) = caps.get(2) {
+                u32::from_str_radix(hex.as_str(), 16)
+                    .ok()
+                    .and_then(char::from_u32)
+            } else {
+                match &caps[3] {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some(' '),
+                    "ndash" => Some('–'),
+                    "mdash" => Some('—'),
+                    "hellip" => Some('…'),
+                    "lsquo" => Some('‘'),
+                    "rsquo" => Some('’'),
+                    "ldquo" => Some('“'),
+                    "rdquo" => Some('”'),
+                    "copy" => Some('©'),
+                    _ => None,
+                }
+            };
+            decoded.map_or_else(|| caps[0].to_string(), String::from)
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_converts_common_markup() {
+        let html = "<html><head><title>x</title><style>p{}</style></head><body>\
+                    <h2>Install &amp; run</h2>\
+                    <p>Use <strong>cargo</strong> or <em>npm</em>, see \
+                    <a href=\"https://example.com/docs\">the docs</a>.<br>Next line</p>\
+                    <pre><code class=\"language-sh\">cargo build\n</code></pre>\
+                    <ul><li>one<li>two<ul><li>nested</li></ul></li></ul>\
+                    <ol start=\"3\"><li>three</li></ol>\
+                    <blockquote><p>Quoted <code>x</code></p></blockquote>\
+                    <p><img src=\"a.png\" alt=\"Alt\"> 2 * 3_000</p>\
+                    </body></html>";
+        assert_eq!(
+            html_to_markdown(html),
+            "## Install & run\n\n\
+             Use **cargo** or *npm*, see [the docs](https://example.com/docs).\\\nNext line\n\n\
+             ```sh\ncargo build\n```\n\n\
+             - one\n- two\n  - nested\n\n\
+             3. three\n\n\
+             > Quoted `x`\n\n\
+             ![Alt](a.png) 2 \\* 3\\_000\n"
+        );
+    }
+
+    #[test]
+    fn test_tables_and_clipboard_fragments() {
+        let html = "Version:0.9\nStartHTML:0\n<html><body><!--StartFragment-->\
+                    <table><thead><tr><th>Name</th><th>Value</th></tr></thead>\
+                    <tbody><tr><td>a|b</td><td>1</td></tr><tr><td>c</td></tr></tbody></table>\
+                    <!--EndFragment--></body></html>";
+        assert_eq!(
+            html_to_markdown(html),
+            "| Name | Value |\n| --- | --- |\n| a\\|b | 1 |\n| c |  |\n"
+        );
+    }
+
+    #[test]
+    fn test_plain_text_and_entities() {
+        assert_eq!(
+            html_to_markdown("  just   text&nbsp;&#169; &#x2014;  "),
+            "just text © —\n"
+        );
+        assert_eq!(html_to_markdown(""), "");
+    }
+}
@@ -1,5 +1,37 @@
-use regex::Regex;
-use std::path::Path;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::path::{Component, Path, PathBuf};
+
+use super::lines::{next_code_span, source_lines};
+use super::links::percent_decode;
+
+/// Obsidian-style image embeds: `![[chart.png]]`, `![[chart.png|alt or width]]`
+static IMAGE_EMBED: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"!\[\[([^\]|<>\n]+)(?:\|([^\]\n]*))?\]\]").unwrap());
+
+/// An `<img>` tag: its attributes and the `/` of a self-closing tag
+static IMG_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<img\b([^>]*?)\s*(/?)>"#).unwrap());
+
+/// A `width` attribute, or a width set in the `style` attribute
+static WIDTH_ATTR: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)\swidth=|\sstyle="[^"]*width"#).unwrap());
+
+/// The `class` attribute
+static CLASS_ATTR: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\sclass="([^"]*)""#).unwrap());
+
+/// `<img>` tags split around their `src`, for resolving
+static IMG_WITH_SRC: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"<img\s+([^>]*?)src="([^"]+)"([^>]*)>"#).unwrap());
+
+/// The `src` of an `<img>` tag, with the text before and after it
+static IMG_SRC: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(<img\s+[^>]*?src=")([^"]+)(")"#).unwrap());
+
+static MD_IMAGE: Lazy<Regex> = Lazy::new(|| Regex::new(MD_IMAGE_PATTERN).unwrap());
+
+/// File extensions an embed must have to become an image
+const EMBED_IMAGE_EXTENSIONS: &[&str] =
+    &["png", "jpg", "jpeg", "gif", "svg", "webp", "bmp", "avif"];
 
 /// Resolves image paths in HTML to absolute file:// URIs.
 ///
@@ -8,15 +40,41 @@ use std::path::Path;
 /// - Absolute paths are converted to file:// URIs
 /// - Relative paths are resolved against the base_path (directory of the .md file)
 pub fn resolve_image_paths(html: &str, base_path: &str) -> String {
-    let img_regex = Regex::new(r#"<img\s+([^>]*?)src="([^"]+)"([^>]*)>"#).unwrap();
+    resolve_image_paths_with(html, base_path, &[])
+}
 
-    img_regex
-        .replace_all(html, |caps: &regex::Captures| {
+/// Like [`resolve_image_paths`], but an image linked by bare file name
+/// (`chart.png`) that isn't next to the document is looked up in each of
+/// `attachment_dirs` in order, like Obsidian's attachment folder. Relative
+/// folders are resolved against the document's directory.
+///
+/// When attachment folders are given and the image isn't found anywhere, its
+/// `<img>` gets `data-unresolved="true"`. URLs and paths with folders are
+/// resolved as usual.
+pub fn resolve_image_paths_with(
+    html: &str,
+    base_path: &str,
+    attachment_dirs: &[String],
+) -> String {
+    IMG_WITH_SRC
+        .replace_all(html, |caps: &Captures| {
             let before = &caps[1];
             let src = &caps[2];
             let after = &caps[3];
 
-            let resolved_src = resolve_single_path(src, base_path);
+            let searched = is_attachment_name(src, attachment_dirs);
+            let attachment = searched
+                .then(|| find_attachment(src, base_path, attachment_dirs))
+                .flatten();
+            let (resolved_src, unresolved) = match attachment {
+                Some(path) => (format!("__LOCAL_FILE__:{}", path.display()), false),
+                None => (resolve_single_path(src, base_path), searched),
+            };
+            let before = if unresolved {
+                format!("data-unresolved=\"true\" {}", before)
+            } else {
+                before.to_string()
+            };
 
             // Ensure self-closing tag
             let after_clean = after.trim_end_matches('/').trim();
@@ -29,6 +87,40 @@ pub fn resolve_image_paths(html: &str, base_path: &str) -> String {
         .to_string()
 }
 
+/// Inline style that fits an image to the reading column
+pub const CONSTRAINED_IMAGE_STYLE: &str = "max-width:100%;height:auto";
+
+/// Adds `class="constrained"` and a `max-width:100%` style to images so large
+/// ones don't overflow the reading column.
+///
+/// Images that set a `width` attribute or a width in their style are left
+/// as they are. An image with its own style only gets the class. Other
+/// attributes and the tag's self-closing form are kept.
+pub fn constrain_images(html: &str) -> String {
+    IMG_TAG
+        .replace_all(html, |caps: &Captures| {
+            let attributes = &caps[1];
+            if WIDTH_ATTR.is_match(attributes) {
+                return caps[0].to_string();
+            }
+
+            let mut attributes = match CLASS_ATTR.captures(attributes) {
+                Some(class) => attributes.replacen(
+                    &class[0],
+                    &format!(" class=\"{} constrained\"", &class[1]),
+                    1,
+                ),
+                None => format!("{} class=\"constrained\"", attributes),
+            };
+            if !attributes.contains(" style=") {
+                attributes.push_str(&format!(" style=\"{}\"", CONSTRAINED_IMAGE_STYLE));
+            }
+            let close = if caps[2].is_empty() { ">" } else { " />" };
+            format!("<img{}{}", attributes, close)
+        })
+        .to_string()
+}
+
 /// Resolves a single image path to absolute path (not file:// URI)
 /// The frontend will convert to asset:// using Tauri's convertFileSrc
 fn resolve_single_path(src: &str, base_path: &str) -> String {
@@ -48,8 +140,8 @@ fn resolve_single_path(src: &str, base_path: &str) -> String {
     }
 
     // Already a file:// URI - extract path
-    if src.starts_with("file://") {
-        return src[7..].to_string();
+    if let Some(path) = src.strip_prefix("file://") {
+        return path.to_string();
     }
 
     // Absolute path (Unix style) - return as-is with marker
@@ -77,19 +169,294 @@ fn resolve_single_path(src: &str, base_path: &str) -> String {
     format!("__LOCAL_FILE__:{}", final_path)
 }
 
-/// Resolves markdown image syntax ![alt](src) paths before rendering
-/// This is called before comrak to ensure relative paths work
-pub fn resolve_markdown_image_paths(markdown: &str, base_path: &str) -> String {
-    let img_regex = Regex::new(r"!\[([^\]]*)\]\(([^)]+)\)").unwrap();
+/// True when `src` is a bare file name and there are attachment folders to
+/// search for it
+fn is_attachment_name(src: &str, attachment_dirs: &[String]) -> bool {
+    !attachment_dirs.is_empty() && is_relative_path(src) && !src.contains(['/', '\\'])
+}
+
+/// Looks up an image by file name next to the document, then in each
+/// attachment folder
+fn find_attachment(src: &str, base_path: &str, attachment_dirs: &[String]) -> Option<PathBuf> {
+    let base_dir = parent_dir(base_path);
+    let src = percent_decode(src);
+    std::iter::once(base_dir.join(&src))
+        .chain(attachment_dirs.iter().map(|dir| base_dir.join(dir).join(&src)))
+        .find(|candidate| candidate.is_file())
+        .map(|path| path.canonicalize().unwrap_or_else(|_| normalize_path(&path)))
+}
 
-    img_regex
-        .replace_all(markdown, |caps: &regex::Captures| {
+/// Turns Obsidian-style embeds of images (`![[chart.png]]`) into markdown
+/// images, so they resolve like `![](chart.png)`, attachment folders
+/// included.
+///
+/// Text after a `|` becomes the alt text, unless it is a size like `300` or
+/// `300x200`, which is dropped. Embeds of notes and other files, code spans
+/// and fenced code are left alone, and line numbers are preserved.
+pub fn expand_image_embeds(markdown: &str) -> String {
+    if !markdown.contains("![[") {
+        return markdown.to_string();
+    }
+    rewrite_outside_code(markdown, expand_embeds)
+}
+
+/// Applies `rewrite` to each run of text outside fenced code, raw `<pre>`
+/// blocks and code spans, line by line, so code samples stay as written
+fn rewrite_outside_code(markdown: &str, rewrite: impl Fn(&str) -> String) -> String {
+    let lines: Vec<String> = source_lines(markdown)
+        .into_iter()
+        .map(|line| {
+            if line.in_code {
+                return line.text.to_string();
+            }
+            let mut result = String::with_capacity(line.text.len());
+            let mut pos = 0;
+            while let Some((start, end)) = next_code_span(line.text, pos) {
+                result.push_str(&rewrite(&line.text[pos..start]));
+                result.push_str(&line.text[start..end]);
+                pos = end;
+            }
+            result.push_str(&rewrite(&line.text[pos..]));
+            result
+        })
+        .collect();
+
+    let mut result = lines.join("\n");
+    if markdown.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+/// The runs of text [`rewrite_outside_code`] would rewrite, in order
+fn text_outside_code(markdown: &str) -> Vec<&str> {
+    let mut runs = Vec::new();
+    for line in source_lines(markdown).into_iter().filter(|line| !line.in_code) {
+        let mut pos = 0;
+        while let Some((start, end)) = next_code_span(line.text, pos) {
+            runs.push(&line.text[pos..start]);
+            pos = end;
+        }
+        runs.push(&line.text[pos..]);
+    }
+    runs
+}
+
+fn expand_embeds(text: &str) -> String {
+    IMAGE_EMBED
+        .replace_all(text, |caps: &Captures| {
+            let name = caps[1].trim();
+            let is_image = Path::new(name)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| EMBED_IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+            if !is_image {
+                return caps[0].to_string();
+            }
+            let alt = caps.get(2).map_or("", |alt| alt.as_str().trim());
+            let is_size = |alt: &str| {
+                alt.split('x')
+                    .all(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+            };
+            let alt = if is_size(alt) { "" } else { alt };
+            format!("![{}](<{}>)", alt, name)
+        })
+        .into_owned()
+}
+
+/// Markdown image syntax: `![alt](src)`, `![alt](<src with spaces>)` and
+/// `![alt](src "Title")` (titles may also use single quotes or parentheses)
+pub(super) const MD_IMAGE_PATTERN: &str =
+    r#"!\[([^\]]*)\]\(\s*(<[^>]*>|[^\s)]+)((?:\s+(?:"[^"]*"|'[^']*'|\([^)]*\)))?)\s*\)"#;
+
+/// Rewrites the source of every markdown and `<img>` image outside code,
+/// preserving alt text, title and other attributes.
+///
+/// `rewrite` receives the bare URL (without angle brackets).
+fn rewrite_image_sources(markdown: &str, rewrite: impl Fn(&str) -> String) -> String {
+    rewrite_outside_code(markdown, |text| {
+        let text = rewrite_markdown_images(text, &rewrite);
+        IMG_SRC
+            .replace_all(&text, |caps: &Captures| {
+                format!("{}{}{}", &caps[1], rewrite(&caps[2]), &caps[3])
+            })
+            .into_owned()
+    })
+}
+
+/// Rewrites the source of every markdown image in `text`, preserving alt
+/// text and title
+fn rewrite_markdown_images(text: &str, rewrite: impl Fn(&str) -> String) -> String {
+    MD_IMAGE
+        .replace_all(text, |caps: &Captures| {
             let alt = &caps[1];
             let src = &caps[2];
-            let resolved_src = resolve_single_path(src, base_path);
-            format!("![{}]({})", alt, resolved_src)
+            let title = &caps[3];
+            let rewritten = match src.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+                Some(inner) => format!("<{}>", rewrite(inner)),
+                None => rewrite(src),
+            };
+            format!("![{}]({}{})", alt, rewritten, title)
         })
-        .to_string()
+        .into_owned()
+}
+
+/// Resolves markdown image syntax ![alt](src) paths before rendering
+/// This is called before comrak to ensure relative paths work
+pub fn resolve_markdown_image_paths(markdown: &str, base_path: &str) -> String {
+    rewrite_outside_code(markdown, |text| {
+        rewrite_markdown_images(text, |src| resolve_single_path(src, base_path))
+    })
+}
+
+/// Lists the local images referenced by a document (markdown and `<img>`
+/// syntax), resolved against the directory of `base_path`, in document order.
+///
+/// Remote URLs and data URIs are skipped; files are not checked for existence.
+pub fn local_image_paths(markdown: &str, base_path: &str) -> Vec<PathBuf> {
+    local_image_sources(markdown, base_path)
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect()
+}
+
+/// Like [`local_image_paths`], pairing each resolved path with the source as
+/// written in the document.
+pub fn local_image_sources(markdown: &str, base_path: &str) -> Vec<(String, PathBuf)> {
+    let base_dir = parent_dir(base_path);
+
+    let mut sources = Vec::new();
+    for text in text_outside_code(markdown) {
+        let mut found: Vec<(usize, &str)> = MD_IMAGE
+            .captures_iter(text)
+            .map(|caps| {
+                let src = caps.get(2).unwrap();
+                let url = src.as_str();
+                let url = url.strip_prefix('<').and_then(|u| u.strip_suffix('>')).unwrap_or(url);
+                (src.start(), url)
+            })
+            .chain(IMG_SRC.captures_iter(text).map(|caps| {
+                let src = caps.get(2).unwrap();
+                (src.start(), src.as_str())
+            }))
+            .collect();
+        found.sort_by_key(|(position, _)| *position);
+        sources.extend(
+            found
+                .into_iter()
+                .filter_map(|(_, src)| Some((src.to_string(), local_image_path(src, &base_dir)?))),
+        );
+    }
+    sources
+}
+
+/// Rewrites the sources of local images (markdown and `<img>` syntax).
+///
+/// `rewrite` receives each image's resolved path, as in [`local_image_paths`];
+/// returning None keeps the original source. Remote URLs and data URIs are
+/// left unchanged.
+pub fn rewrite_local_image_paths(
+    markdown: &str,
+    base_path: &str,
+    rewrite: impl Fn(&Path) -> Option<String>,
+) -> String {
+    let base_dir = parent_dir(base_path);
+    let rewrite_src = |src: &str| {
+        local_image_path(src, &base_dir)
+            .and_then(|path| rewrite(&path))
+            .unwrap_or_else(|| src.to_string())
+    };
+
+    rewrite_image_sources(markdown, rewrite_src)
+}
+
+/// Resolves an image source to a local path; None for URLs and data URIs
+fn local_image_path(src: &str, base_dir: &Path) -> Option<PathBuf> {
+    if is_relative_path(src) {
+        Some(normalize_path(&base_dir.join(src)))
+    } else if src.starts_with('/') {
+        Some(PathBuf::from(src))
+    } else {
+        src.strip_prefix("file://").map(PathBuf::from)
+    }
+}
+
+/// Rewrites relative image links so they keep pointing at the same files after
+/// the document moves from `old_base` to `new_base` (both paths to the .md file).
+///
+/// Remote URLs, data URIs and absolute paths are left unchanged.
+pub fn rebase_markdown_image_paths(markdown: &str, old_base: &str, new_base: &str) -> String {
+    let old_dir = parent_dir(old_base);
+    let new_dir = parent_dir(new_base);
+
+    rewrite_image_sources(markdown, |src| rebase_single_path(src, &old_dir, &new_dir))
+}
+
+/// Rebases a single relative path from one directory to another
+fn rebase_single_path(src: &str, old_dir: &Path, new_dir: &Path) -> String {
+    if !is_relative_path(src) {
+        return src.to_string();
+    }
+
+    let target = normalize_path(&old_dir.join(src));
+    relative_path(new_dir, &target)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// True for local relative paths (not URLs, data URIs, anchors or absolute paths)
+fn is_relative_path(src: &str) -> bool {
+    !(src.is_empty()
+        || src.starts_with('/')
+        || src.starts_with('#')
+        || src.contains("://")
+        || src.starts_with("data:")
+        || src.starts_with("mailto:")
+        || (src.len() >= 2 && src.chars().nth(1) == Some(':')))
+}
+
+/// Directory containing the given file path
+fn parent_dir(file_path: &str) -> PathBuf {
+    let path = Path::new(file_path);
+    normalize_path(path.parent().unwrap_or(path))
+}
+
+/// Lexically normalizes a path, resolving `.` and `..` without touching the filesystem
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+/// Computes the path of `target` relative to the directory `from`
+pub fn relative_path(from: &Path, target: &Path) -> PathBuf {
+    let from: Vec<Component> = from.components().collect();
+    let target: Vec<Component> = target.components().collect();
+
+    let common = from
+        .iter()
+        .zip(target.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..from.len() {
+        relative.push("..");
+    }
+    for component in &target[common..] {
+        relative.push(component.as_os_str());
+    }
+    relative
 }
 
 #[cfg(test)]
@@ -114,7 +481,7 @@ mod tests {
     fn test_absolute_path() {
         let html = r#"<img src="/Users/test/photo.png">"#;
         let result = resolve_image_paths(html, "/some/path/file.md");
-        assert!(result.contains("file:///Users/test/photo.png"));
+        assert!(result.contains("__LOCAL_FILE__:/Users/test/photo.png"));
     }
 
     #[test]
@@ -122,17 +489,40 @@ mod tests {
         let html = r#"<img src="./images/photo.png">"#;
         let result = resolve_image_paths(html, "/some/path/file.md");
         // Should resolve to /some/path/images/photo.png
-        assert!(result.contains("file://"));
+        assert!(result.contains("__LOCAL_FILE__:"));
         assert!(result.contains("images/photo.png") || result.contains("images%2Fphoto.png"));
     }
 
+    #[test]
+    fn test_constrain_images() {
+        let html = r#"<p><img src="a.png" alt="A" /><img src="b.png" class="wide"></p>"#;
+        assert_eq!(
+            constrain_images(html),
+            concat!(
+                r#"<p><img src="a.png" alt="A" class="constrained" "#,
+                r#"style="max-width:100%;height:auto" />"#,
+                r#"<img src="b.png" class="wide constrained" "#,
+                r#"style="max-width:100%;height:auto"></p>"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_constrain_images_respects_width() {
+        let html = r#"<img src="a.png" width="200" /><img src="b.png" style="width: 50%">"#;
+        assert_eq!(constrain_images(html), html);
+
+        let styled = constrain_images(r#"<img src="c.png" style="border:0">"#);
+        assert_eq!(styled, r#"<img src="c.png" style="border:0" class="constrained">"#);
+    }
+
     #[test]
     fn test_multiple_images() {
         let html = r#"<img src="a.png"><img src="b.png">"#;
         let result = resolve_image_paths(html, "/path/file.md");
-        assert!(result.contains("file://"));
+        assert!(result.contains("__LOCAL_FILE__:"));
         // Both should be resolved
-        let count = result.matches("file://").count();
+        let count = result.matches("__LOCAL_FILE__:").count();
         assert_eq!(count, 2);
     }
 
@@ -140,7 +530,7 @@ mod tests {
     fn test_markdown_image_resolution() {
         let md = "![Alt text](./img/photo.png)";
         let result = resolve_markdown_image_paths(md, "/path/to/file.md");
-        assert!(result.contains("file://"));
+        assert!(result.contains("__LOCAL_FILE__:"));
     }
 
     #[test]
@@ -149,4 +539,159 @@ mod tests {
         let result = resolve_markdown_image_paths(md, "/path/file.md");
         assert!(result.contains("![My Photo]"));
     }
+
+    #[test]
+    fn test_rebase_moving_down() {
+        let md = "![A](images/a.png) and ![B](./b.png)";
+        let result = rebase_markdown_image_paths(md, "/notes/doc.md", "/notes/sub/deep/doc.md");
+        assert_eq!(result, "![A](../../images/a.png) and ![B](../../b.png)");
+    }
+
+    #[test]
+    fn test_rebase_moving_up() {
+        let md = "![A](../images/a.png)\n<img src=\"pics/b.png\" alt=\"b\">";
+        let result = rebase_markdown_image_paths(md, "/notes/sub/doc.md", "/doc.md");
+        assert_eq!(result, "![A](notes/images/a.png)\n<img src=\"notes/sub/pics/b.png\" alt=\"b\">");
+    }
+
+    #[test]
+    fn test_rebase_passthrough() {
+        let md = "![](https://x.com/a.png) ![](/abs/a.png) ![](data:image/png;base64,AA==)";
+        let result = rebase_markdown_image_paths(md, "/notes/doc.md", "/other/doc.md");
+        assert_eq!(result, md);
+    }
+
+    #[test]
+    fn test_markdown_image_title_preserved() {
+        let md = r#"![Photo](img/photo.png "My holiday (2024)")"#;
+        let result = resolve_markdown_image_paths(md, "/path/file.md");
+        assert!(result.starts_with("![Photo](__LOCAL_FILE__:/path/img/photo.png "));
+        assert!(result.ends_with(r#" "My holiday (2024)")"#));
+    }
+
+    #[test]
+    fn test_markdown_image_angle_brackets() {
+        let md = "![A](<my images/a.png> 'Single quoted title')";
+        let result = rebase_markdown_image_paths(md, "/notes/doc.md", "/notes/sub/doc.md");
+        assert_eq!(result, "![A](<../my images/a.png> 'Single quoted title')");
+    }
+
+    #[test]
+    fn test_rebase_with_title() {
+        let md = r#"![A](a.png "Title with spaces") and [a link](doc.md "Link title")"#;
+        let result = rebase_markdown_image_paths(md, "/notes/doc.md", "/notes/sub/doc.md");
+        assert_eq!(
+            result,
+            r#"![A](../a.png "Title with spaces") and [a link](doc.md "Link title")"#
+        );
+    }
+
+    #[test]
+    fn test_image_syntax_in_code_unchanged() {
+        let md = "```md\n![x](img.png)\n<img src=\"img.png\">\n```\n\nUse `![x](img.png)` for ![y](img.png)\n";
+        let rebased = rebase_markdown_image_paths(md, "/notes/doc.md", "/notes/sub/doc.md");
+        assert_eq!(
+            rebased,
+            "```md\n![x](img.png)\n<img src=\"img.png\">\n```\n\nUse `![x](img.png)` for ![y](../img.png)\n"
+        );
+
+        let rewritten =
+            rewrite_local_image_paths(md, "/notes/doc.md", |_| Some("assets/img.png".to_string()));
+        assert_eq!(
+            rewritten,
+            "```md\n![x](img.png)\n<img src=\"img.png\">\n```\n\nUse `![x](img.png)` for ![y](assets/img.png)\n"
+        );
+        assert_eq!(local_image_paths(md, "/notes/doc.md"), vec![PathBuf::from("/notes/img.png")]);
+    }
+
+    #[test]
+    fn test_local_image_paths() {
+        let md = "![a](https://x.com/a.png)\n<img src=\"img/b.png\">\n![c](<../c d.png> \"C\")\n![e](/abs/e.png)";
+        let paths = local_image_paths(md, "/notes/doc.md");
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("/notes/img/b.png"),
+                PathBuf::from("/c d.png"),
+                PathBuf::from("/abs/e.png"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rewrite_local_image_paths() {
+        let md = "![a](img/a.png \"A\") ![b](https://x.com/b.png) <img src=\"../c.png\"> ![d](d.png)";
+        let rewritten = rewrite_local_image_paths(md, "/notes/doc.md", |path| {
+            (path != Path::new("/notes/d.png")).then(|| format!("assets/{}", path.display()))
+        });
+        assert_eq!(
+            rewritten,
+            "![a](assets//notes/img/a.png \"A\") ![b](https://x.com/b.png) \
+             <img src=\"assets//c.png\"> ![d](d.png)"
+        );
+    }
+
+    #[test]
+    fn test_attachment_folders() {
+        let dir = std::env::temp_dir().join("markviewer-attachments-test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("notes")).unwrap();
+        std::fs::create_dir_all(dir.join("attachments")).unwrap();
+        std::fs::write(dir.join("notes/local.png"), b"a").unwrap();
+        std::fs::write(dir.join("notes/chart.png"), b"b").unwrap();
+        std::fs::write(dir.join("attachments/chart.png"), b"c").unwrap();
+        std::fs::write(dir.join("attachments/pie.png"), b"d").unwrap();
+        let dir = dir.canonicalize().unwrap();
+
+        let base = dir.join("notes/doc.md").display().to_string();
+        let folders = vec!["../attachments".to_string()];
+        let html = r#"<img src="chart.png" /><img src="pie.png" alt="p" /><img src="gone.png" /><img src="sub/x.png" /><img src="https://x.dev/a.png" />"#;
+        let result = resolve_image_paths_with(html, &base, &folders);
+
+        let local = |path: &str| format!("__LOCAL_FILE__:{}", dir.join(path).display());
+        assert!(result.contains(&format!("<img src=\"{}\" />", local("notes/chart.png"))));
+        assert!(result.contains(&format!("<img src=\"{}\" alt=\"p\" />", local("attachments/pie.png"))));
+        assert!(result.contains(&format!(
+            "<img data-unresolved=\"true\" src=\"{}\" />",
+            local("notes/gone.png")
+        )));
+        assert!(result.contains(&format!("<img src=\"{}\" />", local("notes/sub/x.png"))));
+        assert!(result.contains("<img src=\"https://x.dev/a.png\" />"));
+        assert!(!resolve_image_paths(html, &base).contains("data-unresolved"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_expand_image_embeds() {
+        let md = "![[chart.png]] ![[Pasted image 1.PNG|A chart]] ![[pie.png|300x200]]\n\
+                  ![[Other note]] ![[doc.pdf]] `![[code.png]]`\n```\n![[fenced.png]]\n```\n";
+        assert_eq!(
+            expand_image_embeds(md),
+            "![](<chart.png>) ![A chart](<Pasted image 1.PNG>) ![](<pie.png>)\n\
+             ![[Other note]] ![[doc.pdf]] `![[code.png]]`\n```\n![[fenced.png]]\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_attachment_with_encoded_name() {
+        let dir = std::env::temp_dir().join("markviewer-attachments-encoded");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("attachments")).unwrap();
+        std::fs::write(dir.join("attachments/my chart.png"), b"a").unwrap();
+        let dir = dir.canonicalize().unwrap();
+
+        let base = dir.join("doc.md").display().to_string();
+        let html = r#"<img src="my%20chart.png" alt="" />"#;
+        let result = resolve_image_paths_with(html, &base, &["attachments".to_string()]);
+        assert_eq!(
+            result,
+            format!(
+                "<img src=\"__LOCAL_FILE__:{}\" alt=\"\" />",
+                dir.join("attachments/my chart.png").display()
+            )
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,221 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fs;
+use std::path::{Component, Path};
+
+use super::lines::source_lines;
+
+/// `<!-- include: src/main.rs#L10-L20 -->` on a line of its own; the range
+/// is optional and may be a single line (`#L10`)
+static INCLUDE_DIRECTIVE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*<!--\s*include:\s*([^#\s]+)(?:#L(\d+)(?:-L?(\d+))?)?\s*-->\s*$").unwrap()
+});
+
+/// Replaces `<!-- include: path#L10-L20 -->` lines with a fenced code block
+/// holding those lines of the file, so docs quote source that stays current.
+///
+/// Paths are relative to the directory of `base_path` (the .md file) and may
+/// not leave it. The fence language is the file's extension, which the
+/// highlighter resolves. A missing file, an escaping path or a range past
+/// the end of the file becomes a `text` fence explaining the problem, and a
+/// warning. Directives inside code blocks are left alone.
+pub fn expand_code_includes(markdown: &str, base_path: &str, warnings: &mut Vec<String>) -> String {
+    let base = Path::new(base_path);
+    let base_dir = base.parent().unwrap_or(base);
+    let mut result = String::with_capacity(markdown.len());
+
+    for line in source_lines(markdown) {
+        let caps = if line.in_code {
+            None
+        } else {
+            INCLUDE_DIRECTIVE.captures(line.text)
+        };
+        let Some(caps) = caps else {
+            result.push_str(line.text);
+            result.push('\n');
+            continue;
+        };
+
+        let target = &caps[1];
+        let start = caps.get(2).and_then(|m| m.as_str().parse::<usize>().ok());
+        let end = caps.get(3).and_then(|m| m.as_str().parse::<usize>().ok());
+        let fence = match read_lines(base_dir, target, start, end.or(start)) {
+            Ok(code) => {
+                let lang = Path::new(target)
+                    .extension()
+                    .map(|ext| ext.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                code_fence(&lang, &code)
+            }
+            Err(e) => {
+                let message = format!("Line {}: include {} failed: {}", line.number, target, e);
+                let fence = code_fence("text", &message);
+                warnings.push(message);
+                fence
+            }
+        };
+        result.push_str(&fence);
+    }
+
+    if !markdown.ends_with('\n') {
+        result.pop();
+    }
+    result
+}
+
+/// Lines `start..=end` (1-based) of the file, or all of it without a range
+fn read_lines(
+    base_dir: &Path,
+    target: &str,
+    start: Option<usize>,
+    end: Option<usize>,
+) -> Result<String, String> {
+    let relative = Path::new(target);
+    let escapes = relative.components().any(|c| {
+        matches!(
+            c,
+            Component::ParentDir | Component::RootDir | Component::Prefix(_)
+        )
+    });
+    if escapes {
+        return Err("path must stay inside the document's folder".to_string());
+    }
+
+    // Symlinks may still point elsewhere
+    let path = base_dir.join(relative);
+    let resolved = path
+        .canonicalize()
+        .map_err(|_| "file not found".to_string())?;
+    let root = base_dir
+        .canonicalize()
+        .map_err(|_| "file not found".to_string())?;
+    if !resolved.starts_with(&root) {
+        return Err("path must stay inside the document's folder".to_string());
+    }
+    let content = fs::read_to_string(&resolved).map_err(|e| e.to_string())?;
+
+    let lines: Vec<&str> = content.lines().collect();
+    let (Some(start), Some(end)) = (start, end) else {
+        return Ok(content);
+    };
+    if start == 0 || start > end || start > lines.len() {
+        return Err(format!(
+            "lines {}-{} are outside the file's {} lines",
+            start,
+            end,
+            lines.len()
+        ));
+    }
+    Ok(lines[start - 1..end.min(lines.len())].join("\n"))
+}
+
+/// A fenced block longer than any backtick run in `code`
+fn code_fence(lang: &str, code: &str) -> String {
+    let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!(
+        "{}{}\n{}\n{}\n",
+        fence,
+        lang,
+        code.trim_end_matches('\n'),
+        fence
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::parser::render_markdown_html;
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        let source: String = (1..=30)
+            .map(|n| format!("let line{} = {};\n", n, n))
+            .collect();
+        fs::write(dir.join("src/main.rs"), source).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_includes_line_range() {
+        let dir = fixture("markviewer-include-range");
+        let doc = dir.join("README.md");
+        let md = "Intro\n\n<!-- include: src/main.rs#L10-L12 -->\n\nAfter\n";
+        let mut warnings = Vec::new();
+
+        let expanded = expand_code_includes(md, doc.to_str().unwrap(), &mut warnings);
+
+        assert_eq!(
+            expanded,
+            "Intro\n\n```rs\nlet line10 = 10;\nlet line11 = 11;\nlet line12 = 12;\n```\n\nAfter\n"
+        );
+        assert!(warnings.is_empty());
+        let html = render_markdown_html(&expanded);
+        assert!(
+            html.contains("<pre class=\"code-theme-github-light\" data-lines=\"3\" lang=\"rs\">")
+        );
+        assert!(html.contains("<span"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_single_line_and_whole_file() {
+        let dir = fixture("markviewer-include-single");
+        let doc = dir.join("doc.md");
+        let mut warnings = Vec::new();
+
+        let single = expand_code_includes(
+            "<!-- include: src/main.rs#L3 -->",
+            doc.to_str().unwrap(),
+            &mut warnings,
+        );
+        assert_eq!(single, "```rs\nlet line3 = 3;\n```");
+
+        let whole = expand_code_includes(
+            "<!-- include: src/main.rs -->\n",
+            doc.to_str().unwrap(),
+            &mut warnings,
+        );
+        assert!(whole.contains("let line1 = 1;\n") && whole.contains("let line30 = 30;\n```\n"));
+        assert!(warnings.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_bad_includes_become_warning_fences() {
+        let dir = fixture("markviewer-include-bad");
+        let doc = dir.join("docs.md");
+        let md = "<!-- include: ../secret.txt -->\n\
+                  <!-- include: src/missing.rs -->\n\
+                  <!-- include: src/main.rs#L40-L50 -->\n\
+                  ```\n<!-- include: src/main.rs -->\n```\n";
+        let mut warnings = Vec::new();
+
+        let expanded = expand_code_includes(md, doc.to_str().unwrap(), &mut warnings);
+
+        assert_eq!(
+            warnings,
+            vec![
+                "Line 1: include ../secret.txt failed: path must stay inside the document's folder",
+                "Line 2: include src/missing.rs failed: file not found",
+                "Line 3: include src/main.rs failed: lines 40-50 are outside the file's 30 lines",
+            ]
+        );
+        assert!(expanded.starts_with("```text\nLine 1: include ../secret.txt failed"));
+        assert!(expanded.ends_with("```\n<!-- include: src/main.rs -->\n```\n"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fence_outlasts_backticks_in_code() {
+        assert_eq!(
+            code_fence("md", "```\nx\n```"),
+            "````md\n```\nx\n```\n````\n"
+        );
+    }
+}
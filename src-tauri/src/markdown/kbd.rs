@@ -0,0 +1,111 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+use super::lines::{next_code_span, source_lines};
+use super::parser::escape_html;
+
+/// Prefix that turns a `[[...]]` into a key combination instead of a wikilink
+pub const KBD_PREFIX: &str = "kbd:";
+
+/// `[[kbd:Cmd+Shift+P]]`
+static KBD_SHORTCUT: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[\[kbd:([^\]\n]+)\]\]").unwrap());
+
+/// Expands `[[kbd:Cmd+S]]` into `<kbd>Cmd</kbd>+<kbd>S</kbd>`.
+///
+/// Keys are separated by `+`; a `+` right after a separator is the plus key
+/// itself (`[[kbd:Ctrl++]]`). Code spans and fenced code are left alone, and
+/// line numbers are preserved.
+pub fn expand_kbd_shortcuts(markdown: &str) -> String {
+    let lines: Vec<String> = source_lines(markdown)
+        .into_iter()
+        .map(|line| {
+            if line.in_code || !line.text.contains("[[kbd:") {
+                line.text.to_string()
+            } else {
+                expand_outside_code_spans(line.text)
+            }
+        })
+        .collect();
+
+    let mut result = lines.join("\n");
+    if markdown.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+/// Applies the expansion to the parts of a line outside backtick code spans
+fn expand_outside_code_spans(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut pos = 0;
+
+    while let Some((start, end)) = next_code_span(line, pos) {
+        result.push_str(&expand(&line[pos..start]));
+        result.push_str(&line[start..end]);
+        pos = end;
+    }
+    result.push_str(&expand(&line[pos..]));
+
+    result
+}
+
+fn expand(text: &str) -> String {
+    KBD_SHORTCUT
+        .replace_all(text, |caps: &Captures| {
+            split_keys(&caps[1])
+                .iter()
+                .map(|key| format!("<kbd>{}</kbd>", escape_html(key)))
+                .collect::<Vec<_>>()
+                .join("+")
+        })
+        .into_owned()
+}
+
+/// Splits a combination on `+`, keeping a literal `+` key
+fn split_keys(combo: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut key = String::new();
+    for c in combo.chars() {
+        if c == '+' && !key.trim().is_empty() {
+            keys.push(key.trim().to_string());
+            key.clear();
+        } else {
+            key.push(c);
+        }
+    }
+    if !key.trim().is_empty() {
+        keys.push(key.trim().to_string());
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_multi_key_combo() {
+        assert_eq!(
+            expand_kbd_shortcuts("Save with [[kbd:Cmd+S]], palette [[kbd: Cmd + Shift + P ]]."),
+            "Save with <kbd>Cmd</kbd>+<kbd>S</kbd>, palette \
+             <kbd>Cmd</kbd>+<kbd>Shift</kbd>+<kbd>P</kbd>."
+        );
+    }
+
+    #[test]
+    fn test_plus_key_and_escaping() {
+        assert_eq!(
+            expand_kbd_shortcuts("[[kbd:Ctrl++]] [[kbd:<]]"),
+            "<kbd>Ctrl</kbd>+<kbd>+</kbd> <kbd>&lt;</kbd>"
+        );
+    }
+
+    #[test]
+    fn test_code_and_wikilinks_untouched() {
+        let md = "`[[kbd:A]]` and ``x ` [[kbd:B]]`` [[Note]] [[kbd:C]]\n```\n[[kbd:D]]\n```\n";
+        assert_eq!(
+            expand_kbd_shortcuts(md),
+            "`[[kbd:A]]` and ``x ` [[kbd:B]]`` [[Note]] <kbd>C</kbd>\n```\n[[kbd:D]]\n```\n"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+
+use super::front_matter::extract_front_matter;
+use super::language::prose_text;
+
+/// English words too common to say anything about a document, separated by
+/// whitespace
+pub const DEFAULT_STOPWORDS: &str =
+    "a about above after again against all also am an and any are as at be \
+     because been before being below between both but by can could did do does \
+     doing done down during each even every few for from further get gets got \
+     had has have having he her here hers herself him himself his how however i \
+     if in into is it its itself just let like made make many may me might more \
+     most much must my myself need no nor not now of off on once one only or \
+     other our ours ourselves out over own same see she should so some still \
+     such than that the their theirs them themselves then there these they this \
+     those through to too under until up us use used using very was way we well \
+     were what when where which while who whom why will with within without \
+     would yet you your yours yourself yourselves";
+
+/// [`DEFAULT_STOPWORDS`] as a list
+pub fn default_stopwords() -> Vec<&'static str> {
+    DEFAULT_STOPWORDS.split_whitespace().collect()
+}
+
+/// Words shorter than this are never keywords
+const MIN_WORD_LEN: usize = 3;
+
+/// A word: letters, then letters, digits, apostrophes or inner hyphens
+static WORD: Lazy<Regex> = Lazy::new(|| Regex::new(r"\p{L}[\p{L}\p{N}'’]*(?:-\p{L}+)*").unwrap());
+
+/// The most frequent significant words of a document's prose, most frequent
+/// first (ties alphabetically), at most `top_n`.
+///
+/// Front matter, code, URLs and markup are skipped. Words are lowercased and
+/// lightly stemmed (plurals and possessives), and words in `stopwords` or
+/// shorter than three letters are ignored.
+pub fn top_keywords(markdown: &str, top_n: usize, stopwords: &[&str]) -> Vec<(String, usize)> {
+    let (_, body) = extract_front_matter(markdown);
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for word in WORD.find_iter(&prose_text(body)) {
+        let word = word.as_str().to_lowercase();
+        if stopwords.contains(&word.as_str()) {
+            continue;
+        }
+        let stem = light_stem(&word);
+        if stem.chars().count() < MIN_WORD_LEN || stopwords.contains(&stem.as_str()) {
+            continue;
+        }
+        *counts.entry(stem).or_insert(0) += 1;
+    }
+
+    let mut keywords: Vec<(String, usize)> = counts.into_iter().collect();
+    keywords.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    keywords.truncate(top_n);
+    keywords
+}
+
+/// Strips possessives and regular plural endings: `parsers` → `parser`,
+/// `libraries` → `library`, `classes` → `class`
+fn light_stem(word: &str) -> String {
+    let word = word
+        .trim_end_matches("'s")
+        .trim_end_matches("’s")
+        .trim_end_matches(['\'', '’']);
+
+    if let Some(stem) = word.strip_suffix("ies").filter(|s| s.len() > 2) {
+        format!("{}y", stem)
+    } else if let Some(stem) = ["sses", "shes", "ches", "xes"]
+        .iter()
+        .find_map(|suffix| word.strip_suffix(suffix).map(|s| (s, suffix)))
+        .map(|(s, suffix)| format!("{}{}", s, &suffix[..suffix.len() - 2]))
+    {
+        stem
+    } else if word.len() > 3
+        && word.ends_with('s')
+        && !["ss", "us", "is"]
+            .iter()
+            .any(|suffix| word.ends_with(suffix))
+    {
+        word[..word.len() - 1].to_string()
+    } else {
+        word.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_words_surface() {
+        let md = "---\ntags: [ignored, ignored]\n---\n\
+                  # Parsers\n\nThe parser reads the markdown and the parser's output is HTML.\n\
+                  Good parsers are fast. We wrote two libraries and one library test.\n\n\
+                  ```rust\nlet parser = parser::new();\n```\n\
+                  See [the docs](https://example.com/parser) and `parser`.";
+
+        assert_eq!(
+            top_keywords(md, 3, &default_stopwords()),
+            vec![
+                ("parser".to_string(), 4),
+                ("library".to_string(), 2),
+                ("doc".to_string(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stopwords_filtered() {
+        let md = "The and of the to is it was the and a an the.";
+        assert!(top_keywords(md, 10, &default_stopwords()).is_empty());
+
+        let keywords = top_keywords("Rust, rust and Tauri", 10, &["rust", "and"]);
+        assert_eq!(keywords, vec![("tauri".to_string(), 1)]);
+    }
+
+    #[test]
+    fn test_light_stem() {
+        assert_eq!(light_stem("classes"), "class");
+        assert_eq!(light_stem("branches"), "branch");
+        assert_eq!(light_stem("status"), "status");
+        assert_eq!(light_stem("analysis"), "analysis");
+        assert_eq!(light_stem("users'"), "user");
+    }
+}
@@ -0,0 +1,173 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+
+use super::front_matter::extract_front_matter;
+use super::lines::source_lines;
+
+/// Markup that isn't prose: code spans, HTML tags, URLs and link/image targets
+static NON_PROSE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"`[^`]*`|<[^>]*>|\]\([^)]*\)|https?://\S+|\[\^?[^\]]*\]:.*$").unwrap()
+});
+
+/// Below this many letters a statistical guess isn't attempted
+const MIN_PROSE_LETTERS: usize = 20;
+
+/// ISO 639-3 codes reported by whatlang with a two-letter ISO 639-1 equivalent
+const ISO_639_1: &[(&str, &str)] = &[
+    ("afr", "af"),
+    ("aka", "ak"),
+    ("amh", "am"),
+    ("ara", "ar"),
+    ("aze", "az"),
+    ("bel", "be"),
+    ("ben", "bn"),
+    ("bul", "bg"),
+    ("cat", "ca"),
+    ("ces", "cs"),
+    ("cmn", "zh"),
+    ("dan", "da"),
+    ("deu", "de"),
+    ("ell", "el"),
+    ("eng", "en"),
+    ("epo", "eo"),
+    ("est", "et"),
+    ("fin", "fi"),
+    ("fra", "fr"),
+    ("guj", "gu"),
+    ("heb", "he"),
+    ("hin", "hi"),
+    ("hrv", "hr"),
+    ("hun", "hu"),
+    ("hye", "hy"),
+    ("ind", "id"),
+    ("ita", "it"),
+    ("jav", "jv"),
+    ("jpn", "ja"),
+    ("kan", "kn"),
+    ("kat", "ka"),
+    ("khm", "km"),
+    ("kor", "ko"),
+    ("lat", "la"),
+    ("lav", "lv"),
+    ("lit", "lt"),
+    ("mal", "ml"),
+    ("mar", "mr"),
+    ("mkd", "mk"),
+    ("mya", "my"),
+    ("nep", "ne"),
+    ("nld", "nl"),
+    ("nob", "nb"),
+    ("ori", "or"),
+    ("pan", "pa"),
+    ("pes", "fa"),
+    ("pol", "pl"),
+    ("por", "pt"),
+    ("ron", "ro"),
+    ("rus", "ru"),
+    ("sin", "si"),
+    ("slk", "sk"),
+    ("slv", "sl"),
+    ("sna", "sn"),
+    ("spa", "es"),
+    ("srp", "sr"),
+    ("swe", "sv"),
+    ("tam", "ta"),
+    ("tel", "te"),
+    ("tgl", "tl"),
+    ("tha", "th"),
+    ("tuk", "tk"),
+    ("tur", "tr"),
+    ("ukr", "uk"),
+    ("urd", "ur"),
+    ("uzb", "uz"),
+    ("vie", "vi"),
+    ("yid", "yi"),
+    ("zul", "zu"),
+];
+
+/// Detects the language a document is written in, as a BCP 47 tag.
+///
+/// A front matter `lang` (or `language`) value wins, with `_` normalized to
+/// `-` ("pt_BR" → "pt-BR"). Otherwise the prose outside code, HTML and link
+/// targets is classified with whatlang; only a reliable guess is returned,
+/// as a two-letter code where one exists ("en", "pt") and the ISO 639-3
+/// code otherwise.
+pub fn detect_language(markdown: &str) -> Option<String> {
+    let (front_matter, body) = extract_front_matter(markdown);
+    let declared = front_matter
+        .and_then(|fm| fm.value)
+        .and_then(|value| declared_language(&value));
+    if declared.is_some() {
+        return declared;
+    }
+
+    let prose = prose_text(body);
+    if prose.chars().filter(|c| c.is_alphabetic()).count() < MIN_PROSE_LETTERS {
+        return None;
+    }
+
+    let info = whatlang::detect(&prose).filter(whatlang::Info::is_reliable)?;
+    let code = info.lang().code();
+    let tag = ISO_639_1
+        .iter()
+        .find(|(iso3, _)| *iso3 == code)
+        .map_or(code, |&(_, iso1)| iso1);
+    Some(tag.to_string())
+}
+
+fn declared_language(fields: &Value) -> Option<String> {
+    ["lang", "language"]
+        .iter()
+        .find_map(|key| fields.get(key).and_then(Value::as_str))
+        .map(|lang| lang.trim().replace('_', "-"))
+        .filter(|lang| !lang.is_empty())
+}
+
+/// Text outside fenced code with inline markup removed
+pub(super) fn prose_text(body: &str) -> String {
+    let mut prose = String::new();
+    for line in source_lines(body).into_iter().filter(|l| !l.in_code) {
+        let text = NON_PROSE.replace_all(line.text, " ");
+        let text = text.trim_start_matches(|c: char| c.is_whitespace() || "#>-*+|".contains(c));
+        if !text.trim().is_empty() {
+            prose.push_str(text.trim_end());
+            prose.push('\n');
+        }
+    }
+    prose
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_front_matter_language_wins() {
+        let md = "---\nlang: pt_BR\n---\nThis paragraph is clearly written in English.";
+        assert_eq!(detect_language(md), Some("pt-BR".to_string()));
+    }
+
+    #[test]
+    fn test_detects_prose_language() {
+        let english = "The weather was lovely this morning, so we walked along the river \
+                       and talked about the books we have been reading lately.";
+        assert_eq!(detect_language(english), Some("en".to_string()));
+
+        let portuguese = "O tempo estava lindo esta manhã, então caminhamos ao longo do rio e \
+                          conversamos sobre os livros que temos lido ultimamente.";
+        assert_eq!(detect_language(portuguese), Some("pt".to_string()));
+    }
+
+    #[test]
+    fn test_code_is_not_prose() {
+        let md = "```rust\nfn main() { println!(\"Bonjour tout le monde, comment allez-vous\"); }\n```\n\n`let x = 1;`";
+        assert_eq!(prose_text(md), "");
+        assert_eq!(detect_language(md), None);
+    }
+
+    #[test]
+    fn test_short_text_is_not_guessed() {
+        assert_eq!(detect_language("# Hi\n\nOk"), None);
+    }
+}
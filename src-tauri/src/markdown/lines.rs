@@ -0,0 +1,256 @@
+/// A markdown source line annotated with its fenced-code context
+#[derive(Debug, Clone, Copy)]
+pub struct SourceLine<'a> {
+    /// 1-based line number
+    pub number: usize,
+    /// The line text (without line ending)
+    pub text: &'a str,
+    /// True for fence delimiters and the lines inside a fenced code block,
+    /// and for raw `<pre>` blocks, which are rendered verbatim too
+    pub in_code: bool,
+    /// True for the lines of a raw `<pre>` block
+    pub in_raw_pre: bool,
+}
+
+/// Splits markdown into lines, marking which ones belong to fenced code blocks.
+///
+/// Uses the same fence rules as the special-block extractor: a block opens with
+/// ``` or ~~~ and closes with a line starting with the same fence. Raw HTML
+/// `<pre>` blocks run to the line containing `</pre>`, as in CommonMark, and
+/// count as code.
+pub fn source_lines(markdown: &str) -> Vec<SourceLine<'_>> {
+    let mut lines = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut in_raw_pre = false;
+
+    for (index, text) in markdown.lines().enumerate() {
+        let trimmed = text.trim_start();
+        if in_raw_pre || (fence.is_none() && opens_raw_pre(text)) {
+            in_raw_pre = !text.to_ascii_lowercase().contains("</pre>");
+            lines.push(SourceLine {
+                number: index + 1,
+                text,
+                in_code: true,
+                in_raw_pre: true,
+            });
+            continue;
+        }
+        let opens = if trimmed.starts_with("```") {
+            Some("```")
+        } else if trimmed.starts_with("~~~") {
+            Some("~~~")
+        } else {
+            None
+        };
+
+        let in_code = match (fence, opens) {
+            (Some(current), Some(marker)) if current == marker => {
+                fence = None;
+                true
+            }
+            (Some(_), _) => true,
+            (None, Some(marker)) => {
+                fence = Some(marker);
+                true
+            }
+            (None, None) => false,
+        };
+
+        lines.push(SourceLine {
+            number: index + 1,
+            text,
+            in_code,
+            in_raw_pre: false,
+        });
+    }
+
+    lines
+}
+
+/// Whether a line starts a raw HTML `<pre>` block: the tag at most three
+/// spaces in, followed by whitespace, `>` or the end of the line
+fn opens_raw_pre(line: &str) -> bool {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 || trimmed.len() < 4 {
+        return false;
+    }
+    if !trimmed.as_bytes()[..4].eq_ignore_ascii_case(b"<pre") {
+        return false;
+    }
+    let after = trimmed[4..].chars().next();
+    !matches!(after, Some(c) if c != '>' && !c.is_whitespace())
+}
+
+/// A fenced code block located in the source
+#[derive(Debug, Clone)]
+pub struct FencedBlock<'a> {
+    /// 1-based line number of the opening fence
+    pub start_line: usize,
+    /// Info string language (first word after the fence), if any
+    pub lang: Option<&'a str>,
+    /// Lines between the fences
+    pub lines: Vec<SourceLine<'a>>,
+}
+
+/// Groups the fenced code blocks of a document, following [`source_lines`].
+///
+/// An unterminated block runs to the end of the document.
+pub fn fenced_blocks(markdown: &str) -> Vec<FencedBlock<'_>> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(&str, FencedBlock)> = None;
+
+    for line in source_lines(markdown).into_iter().filter(|l| !l.in_raw_pre) {
+        let trimmed = line.text.trim_start();
+        match current.take() {
+            Some((marker, block)) if line.in_code && trimmed.starts_with(marker) => {
+                blocks.push(block);
+            }
+            Some((marker, mut block)) => {
+                block.lines.push(line);
+                current = Some((marker, block));
+            }
+            None if line.in_code => {
+                let marker = &trimmed[..3];
+                let lang = trimmed.trim_start_matches(marker).split_whitespace().next();
+                let block = FencedBlock {
+                    start_line: line.number,
+                    lang,
+                    lines: Vec::new(),
+                };
+                current = Some((marker, block));
+            }
+            None => {}
+        }
+    }
+
+    blocks.extend(current.map(|(_, block)| block));
+    blocks
+}
+
+/// Parses an ATX heading line, returning its level and text
+pub fn atx_heading(line: &str) -> Option<(u8, &str)> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+
+    // Strip an optional closing sequence of #s
+    let text = rest.trim();
+    let without_closing = text.trim_end_matches('#');
+    let text = if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+        without_closing.trim_end()
+    } else {
+        text
+    };
+
+    Some((level as u8, text))
+}
+
+/// Byte range of the next backtick code span starting at or after `from`.
+///
+/// A span closes on the next run of the same number of backticks; an
+/// unmatched run is literal text and skipped.
+pub fn next_code_span(line: &str, from: usize) -> Option<(usize, usize)> {
+    let mut pos = from;
+    while let Some((open, ticks)) = tick_run(line, pos) {
+        let mut search = open + ticks;
+        while let Some((at, len)) = tick_run(line, search) {
+            if len == ticks {
+                return Some((open, at + len));
+            }
+            search = at + len;
+        }
+        pos = open + ticks;
+    }
+    None
+}
+
+/// Start and length of the next run of backticks at or after `from`
+fn tick_run(line: &str, from: usize) -> Option<(usize, usize)> {
+    let start = from + line[from..].find('`')?;
+    let len = line[start..].len() - line[start..].trim_start_matches('`').len();
+    Some((start, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_lines_marks_fences() {
+        let md = "text\n```rust\n# not a heading\n```\nafter\n~~~\n```\n~~~";
+        let flags: Vec<bool> = source_lines(md).iter().map(|l| l.in_code).collect();
+        assert_eq!(flags, vec![false, true, true, true, false, true, true, true]);
+    }
+
+    #[test]
+    fn test_source_lines_marks_raw_pre() {
+        let md = "text\n<pre class=\"art\">\n```\n\n^[x]</PRE>\n<preview>\n```\n<pre>\n```";
+        let flags: Vec<(bool, bool)> = source_lines(md)
+            .iter()
+            .map(|l| (l.in_code, l.in_raw_pre))
+            .collect();
+        assert_eq!(
+            flags,
+            vec![
+                (false, false),
+                (true, true),
+                (true, true),
+                (true, true),
+                (true, true),
+                (false, false),
+                (true, false),
+                (true, false),
+                (true, false),
+            ]
+        );
+        assert_eq!(fenced_blocks(md).len(), 1);
+
+        // Multi-byte characters where the tag name would be
+        assert!(!source_lines("## 🚀 Launch
+🚀🚀")[0].in_code);
+    }
+
+    #[test]
+    fn test_fenced_blocks() {
+        let md = "text\n```rust title\nfn a() {}\n~~~\n```\n~~~\nopen";
+        let blocks = fenced_blocks(md);
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].start_line, 2);
+        assert_eq!(blocks[0].lang, Some("rust"));
+        let texts: Vec<&str> = blocks[0].lines.iter().map(|l| l.text).collect();
+        assert_eq!(texts, vec!["fn a() {}", "~~~"]);
+        assert_eq!(blocks[1].start_line, 6);
+        assert_eq!(blocks[1].lang, None);
+        assert_eq!(blocks[1].lines[0].number, 7);
+    }
+
+    #[test]
+    fn test_next_code_span() {
+        let line = "a `b` c ``d ` e`` ` f";
+        assert_eq!(next_code_span(line, 0), Some((2, 5)));
+        assert_eq!(next_code_span(line, 5), Some((8, 17)));
+        assert_eq!(next_code_span(line, 17), None);
+    }
+
+    #[test]
+    fn test_atx_heading() {
+        assert_eq!(atx_heading("# Title"), Some((1, "Title")));
+        assert_eq!(atx_heading("### Deep ###"), Some((3, "Deep")));
+        assert_eq!(atx_heading("## C#"), Some((2, "C#")));
+        assert_eq!(atx_heading("#hashtag"), None);
+        assert_eq!(atx_heading("####### seven"), None);
+        assert_eq!(atx_heading("    # indented code"), None);
+    }
+}
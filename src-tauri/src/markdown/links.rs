@@ -0,0 +1,161 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::path::{Path, PathBuf};
+
+use super::images::normalize_path;
+use super::parser::escape_html;
+use crate::vault::is_markdown_file;
+
+static ANCHOR_TAG: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"<a\s([^>]*?)href="([^"]*)"([^>]*)>"#).unwrap());
+
+/// A URL scheme such as `https:`, `data:` or `tel:`; one-letter schemes are
+/// Windows drives
+static URL_SCHEME: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z][A-Za-z0-9+.-]+:").unwrap());
+
+/// Marks links to local files so the frontend can handle clicks itself.
+///
+/// Links to markdown files get `data-internal-link="<absolute path>"` (opened
+/// in-app); other local files get `data-open-external="<absolute path>"`
+/// (passed to `open_path`). The href is kept. Relative links resolve against
+/// the directory of `base_path` (the .md file). URLs and anchors are untouched.
+pub fn mark_local_links(html: &str, base_path: &str) -> String {
+    let base = Path::new(base_path);
+    let base_dir = base.parent().unwrap_or(base);
+
+    ANCHOR_TAG
+        .replace_all(html, |caps: &Captures| {
+            let tag = &caps[0];
+            if tag.contains("data-internal-link=") || tag.contains("data-open-external=") {
+                return tag.to_string();
+            }
+            let Some(path) = resolve_local_link(&html_unescape(&caps[2]), base_dir) else {
+                return tag.to_string();
+            };
+
+            let attribute = if is_markdown_file(&path) {
+                "data-internal-link"
+            } else {
+                "data-open-external"
+            };
+            let path = path.to_string_lossy().replace('\\', "/");
+            format!(
+                "<a {}href=\"{}\" {}=\"{}\"{}>",
+                &caps[1],
+                &caps[2],
+                attribute,
+                escape_html(&path),
+                &caps[3]
+            )
+        })
+        .into_owned()
+}
+
+/// Resolves a relative/absolute link target to a normalized local path.
+///
+/// Returns None for URLs, any other `scheme:` link (`mailto:`, `tel:`,
+/// `javascript:`, `data:`…) and same-document anchors. Query strings and
+/// fragments are dropped and %XX escapes decoded.
+pub fn resolve_local_link(target: &str, source_dir: &Path) -> Option<PathBuf> {
+    if target.is_empty() || target.starts_with('#') || URL_SCHEME.is_match(target) {
+        return None;
+    }
+
+    let path = target.split(['#', '?']).next().unwrap_or(target);
+    let path = percent_decode(path);
+    let path = Path::new(&path);
+
+    Some(normalize_path(&if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        source_dir.join(path)
+    }))
+}
+
+/// Decodes %XX escapes (e.g. %20) in link targets
+pub fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = (bytes[i + 1] as char)
+                .to_digit(16)
+                .zip((bytes[i + 2] as char).to_digit(16));
+            if let Some((high, low)) = hex {
+                decoded.push((high * 16 + low) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Reverses the attribute escaping comrak applies to hrefs
+pub fn html_unescape(value: &str) -> String {
+    value.replace("&amp;", "&").replace("&quot;", "\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("my%20note.md"), "my note.md");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+
+    #[test]
+    fn test_mark_local_links() {
+        let html = concat!(
+            r#"<a href="other.md#intro">a</a> "#,
+            r#"<a href="../docs/My%20Notes.markdown">b</a> "#,
+            r#"<a href="files/report.pdf" title="Report">c</a> "#,
+            r#"<a href="https://example.com/x.md">d</a> "#,
+            r##"<a href="#local">e</a>"##
+        );
+
+        let marked = mark_local_links(html, "/home/user/notes/index.md");
+
+        assert!(marked.contains(
+            r#"<a href="other.md#intro" data-internal-link="/home/user/notes/other.md">"#
+        ));
+        assert!(marked.contains(r#"data-internal-link="/home/user/docs/My Notes.markdown""#));
+        assert!(marked.contains(
+            r#"<a href="files/report.pdf" data-open-external="/home/user/notes/files/report.pdf" title="Report">"#
+        ));
+        assert!(marked.contains(r#"<a href="https://example.com/x.md">"#));
+        assert!(marked.contains(r##"<a href="#local">"##));
+    }
+
+    #[test]
+    fn test_resolve_local_link_skips_schemes() {
+        let dir = Path::new("/notes");
+        for target in [
+            "https://example.com/a.md",
+            "mailto:me@example.com",
+            "tel:+15550100",
+            "javascript:alert(1)",
+            "data:text/plain,hi",
+            "obsidian://open?file=a",
+            "#top",
+        ] {
+            assert_eq!(resolve_local_link(target, dir), None, "{}", target);
+        }
+        assert_eq!(
+            resolve_local_link("sub/a.md#x", dir),
+            Some(PathBuf::from("/notes/sub/a.md"))
+        );
+    }
+
+    #[test]
+    fn test_mark_local_links_is_idempotent() {
+        let once = mark_local_links(r#"<a href="a.md">a</a>"#, "/notes/index.md");
+        assert_eq!(mark_local_links(&once, "/notes/index.md"), once);
+    }
+}
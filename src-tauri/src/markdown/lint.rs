@@ -0,0 +1,327 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::path::Path;
+
+use super::alt_text::{audit_image_alt_text, AltTextProblem};
+use super::anchors::{broken_anchors_in, duplicate_headings_in};
+use super::front_matter::extract_front_matter;
+use super::images::MD_IMAGE_PATTERN;
+use super::lines::{fenced_blocks, next_code_span, source_lines};
+use super::links::{html_unescape, resolve_local_link};
+use super::parser::{render_markdown_html_with, HtmlOptions};
+
+static MD_IMAGE: Lazy<Regex> = Lazy::new(|| Regex::new(MD_IMAGE_PATTERN).unwrap());
+
+/// `src` of an `<img>` tag
+static HTML_IMAGE_SRC: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)<img\b[^>]*?\ssrc\s*=\s*"([^"]*)""#).unwrap());
+
+/// Link targets: inline `[text](target)`, reference definitions
+/// `[label]: target` and `<a href="target">`
+static LINK_TARGET: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"(?:^|[^!\\])\[[^\]]*\]\(\s*<?([^)\s>]+)>?|^\s{0,3}\[[^\]]+\]:\s*<?([^\s>]+)>?|(?i)<a\b[^>]*?\shref\s*=\s*"([^"]*)""#,
+    )
+    .unwrap()
+});
+
+/// How serious a problem is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Something is broken for readers
+    Error,
+    /// Likely a mistake
+    Warning,
+    /// A suggestion
+    Info,
+}
+
+/// Which check found a problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintCategory {
+    BrokenImage,
+    BrokenAnchor,
+    BrokenLink,
+    AltText,
+    DuplicateHeading,
+    ChartJson,
+}
+
+/// One entry of a [`LintReport`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LintProblem {
+    /// 1-based source line
+    pub line: usize,
+    pub severity: Severity,
+    pub category: LintCategory,
+    /// Human-readable summary
+    pub message: String,
+}
+
+/// Every problem found in a document, ordered by line
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LintReport {
+    pub problems: Vec<LintProblem>,
+    pub errors: usize,
+    pub warnings: usize,
+    pub infos: usize,
+}
+
+/// Runs the document checks together for a problems panel: broken local
+/// images and links, broken `#anchor` links, missing or generic alt text,
+/// duplicate headings and chart blocks that aren't valid JSON.
+///
+/// The document is rendered once for the anchor and heading checks; links
+/// and images are read in a single pass over the source. Relative paths
+/// resolve against the directory of `base_path` (the .md file).
+pub fn lint_markdown(markdown: &str, base_path: &str) -> LintReport {
+    let mut problems = local_target_problems(markdown, base_path);
+
+    let (_, body) = extract_front_matter(markdown);
+    let options = HtmlOptions {
+        block_ids: true,
+        block_anchors: true,
+        ..Default::default()
+    };
+    let rendered = render_markdown_html_with(body, &options);
+
+    for issue in broken_anchors_in(markdown, &rendered.html) {
+        let message = match issue.suggestion {
+            Some(ref suggestion) => format!(
+                "Anchor '#{}' doesn't exist; did you mean '#{}'?",
+                issue.anchor, suggestion
+            ),
+            None => format!("Anchor '#{}' doesn't exist", issue.anchor),
+        };
+        problems.push(LintProblem {
+            line: issue.line,
+            severity: Severity::Error,
+            category: LintCategory::BrokenAnchor,
+            message,
+        });
+    }
+
+    for heading in duplicate_headings_in(markdown, body, &rendered) {
+        let first = heading.lines[0];
+        for (line, id) in heading.lines.iter().zip(&heading.ids).skip(1) {
+            problems.push(LintProblem {
+                line: *line,
+                severity: Severity::Warning,
+                category: LintCategory::DuplicateHeading,
+                message: format!(
+                    "Heading '{}' repeats line {}; its id is '{}'",
+                    heading.text, first, id
+                ),
+            });
+        }
+    }
+
+    for issue in audit_image_alt_text(markdown) {
+        let severity = match issue.problem {
+            AltTextProblem::Generic => Severity::Info,
+            AltTextProblem::Missing | AltTextProblem::Empty => Severity::Warning,
+        };
+        problems.push(LintProblem {
+            line: issue.line,
+            severity,
+            category: LintCategory::AltText,
+            message: issue.message,
+        });
+    }
+
+    problems.extend(chart_json_problems(markdown));
+
+    // Stable, so problems on one line keep the order of the checks
+    problems.sort_by_key(|problem| problem.line);
+    let count = |severity| problems.iter().filter(|p| p.severity == severity).count();
+    LintReport {
+        errors: count(Severity::Error),
+        warnings: count(Severity::Warning),
+        infos: count(Severity::Info),
+        problems,
+    }
+}
+
+/// Images and links to local files that don't exist, outside code.
+///
+/// URLs, data URIs and same-document anchors are skipped; a link's
+/// `#fragment` and query are ignored.
+pub fn local_target_problems(markdown: &str, base_path: &str) -> Vec<LintProblem> {
+    let base = Path::new(base_path);
+    let base_dir = base.parent().unwrap_or(base);
+    let mut problems = Vec::new();
+
+    for line in source_lines(markdown).into_iter().filter(|l| !l.in_code) {
+        let mut spans = Vec::new();
+        let mut pos = 0;
+        while let Some((start, end)) = next_code_span(line.text, pos) {
+            spans.push(start..end);
+            pos = end;
+        }
+        let in_code = |at: usize| spans.iter().any(|span| span.contains(&at));
+
+        let images = MD_IMAGE
+            .captures_iter(line.text)
+            .map(|caps| caps.get(2).unwrap())
+            .chain(
+                HTML_IMAGE_SRC
+                    .captures_iter(line.text)
+                    .map(|caps| caps.get(1).unwrap()),
+            )
+            .map(|target| (target.start(), target.as_str(), LintCategory::BrokenImage));
+        let links = LINK_TARGET.captures_iter(line.text).map(|caps| {
+            let target = (1..=3).find_map(|i| caps.get(i)).unwrap();
+            (target.start(), target.as_str(), LintCategory::BrokenLink)
+        });
+        let mut targets: Vec<_> = images
+            .chain(links)
+            .filter(|(at, ..)| !in_code(*at))
+            .collect();
+        targets.sort_by_key(|(at, ..)| *at);
+
+        for (_, target, category) in targets {
+            let target = target
+                .strip_prefix('<')
+                .and_then(|t| t.strip_suffix('>'))
+                .unwrap_or(target);
+            let target = html_unescape(target);
+            let Some(path) = resolve_local_link(&target, base_dir) else {
+                continue;
+            };
+            if path.exists() {
+                continue;
+            }
+            let what = match category {
+                LintCategory::BrokenImage => "Image",
+                _ => "Link target",
+            };
+            problems.push(LintProblem {
+                line: line.number,
+                severity: Severity::Error,
+                category,
+                message: format!("{} '{}' not found", what, target),
+            });
+        }
+    }
+
+    problems
+}
+
+/// `chart` code blocks whose Chart.js config isn't valid JSON, reported on
+/// the line of the syntax error
+pub fn chart_json_problems(markdown: &str) -> Vec<LintProblem> {
+    fenced_blocks(markdown)
+        .into_iter()
+        .filter(|block| {
+            block
+                .lang
+                .is_some_and(|lang| lang.eq_ignore_ascii_case("chart"))
+        })
+        .filter_map(|block| {
+            let source: Vec<&str> = block.lines.iter().map(|line| line.text).collect();
+            let error = serde_json::from_str::<serde_json::Value>(&source.join("\n")).err()?;
+            Some(LintProblem {
+                line: block.start_line + error.line().max(1),
+                severity: Severity::Error,
+                category: LintCategory::ChartJson,
+                message: format!("Chart config is not valid JSON: {}", error),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_local_targets() {
+        let dir = std::env::temp_dir().join("markviewer-lint-targets");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("logo.png"), b"png").unwrap();
+        fs::write(dir.join("other.md"), b"# Other").unwrap();
+        let doc = dir.join("doc.md");
+
+        let md = "![Logo](logo.png) ![Gone](gone.png)\n\
+                  [ok](other.md#intro) [missing](missing.md) [web](https://example.com)\n\
+                  `[code](nothere.md)` <img src=\"lost.jpg\"> [mail](mailto:a@b.c)\n\
+                  [ref]: ./notes/old.md\n\
+                  ```\n![Fenced](fenced.png)\n```\n";
+        let problems = local_target_problems(md, doc.to_str().unwrap());
+
+        let found: Vec<(usize, LintCategory, &str)> = problems
+            .iter()
+            .map(|p| (p.line, p.category, p.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (1, LintCategory::BrokenImage, "Image 'gone.png' not found"),
+                (
+                    2,
+                    LintCategory::BrokenLink,
+                    "Link target 'missing.md' not found"
+                ),
+                (3, LintCategory::BrokenImage, "Image 'lost.jpg' not found"),
+                (
+                    4,
+                    LintCategory::BrokenLink,
+                    "Link target './notes/old.md' not found"
+                ),
+            ]
+        );
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_chart_json() {
+        let md = "# Charts\n\n```chart\n{\"type\": \"bar\"}\n```\n\n```chart\n{\n  \"type\": \"bar\",\n}\n```\n";
+        let problems = chart_json_problems(md);
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].line, 10);
+        assert_eq!(problems[0].severity, Severity::Error);
+        assert!(problems[0]
+            .message
+            .starts_with("Chart config is not valid JSON"));
+    }
+
+    #[test]
+    fn test_lint_report() {
+        let md = "---\ntitle: Notes\n---\n# Notes\n\n\
+                  ![](missing.png)\n\n\
+                  See [setup](#setup) and [the guide](guide.md).\n\n\
+                  ## Notes\n\n\
+                  ```chart\n{bad}\n```\n";
+        let report = lint_markdown(md, "/nonexistent/docs/notes.md");
+
+        let found: Vec<(usize, Severity, LintCategory)> = report
+            .problems
+            .iter()
+            .map(|p| (p.line, p.severity, p.category))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (6, Severity::Error, LintCategory::BrokenImage),
+                (6, Severity::Warning, LintCategory::AltText),
+                (8, Severity::Error, LintCategory::BrokenLink),
+                (8, Severity::Error, LintCategory::BrokenAnchor),
+                (10, Severity::Warning, LintCategory::DuplicateHeading),
+                (13, Severity::Error, LintCategory::ChartJson),
+            ]
+        );
+        assert_eq!(report.problems[3].message, "Anchor '#setup' doesn't exist");
+        assert_eq!(
+            report.problems[4].message,
+            "Heading 'Notes' repeats line 4; its id is 'heading-notes-1'"
+        );
+        assert_eq!((report.errors, report.warnings, report.infos), (4, 2, 0));
+    }
+}
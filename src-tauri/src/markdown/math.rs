@@ -0,0 +1,25 @@
+use latex2mathml::{latex_to_mathml, DisplayStyle};
+
+/// Converts a TeX display-math expression to MathML.
+pub fn tex_to_mathml(tex: &str) -> Result<String, String> {
+    latex_to_mathml(tex.trim(), DisplayStyle::Block)
+        .map_err(|e| format!("Failed to convert math to MathML: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tex_to_mathml() {
+        let mathml = tex_to_mathml("x^2 + 1").unwrap();
+        assert!(mathml.starts_with("<math"));
+        assert!(mathml.contains("display=\"block\""));
+        assert!(mathml.contains("<msup>"));
+    }
+
+    #[test]
+    fn test_invalid_tex() {
+        assert!(tex_to_mathml("\\frac{1}{").is_err());
+    }
+}
@@ -0,0 +1,199 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::links::{html_unescape, resolve_local_link};
+use super::parser::render_markdown_html;
+use crate::vault::is_markdown_file;
+
+/// Tags that reference media, with their attributes
+static MEDIA_TAG: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)<(/?)(img|a|video|audio|source)\b([^>]*)>").unwrap());
+
+/// A `src`, `href` or `poster` attribute
+static SOURCE_ATTR: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)\s(src|href|poster)="([^"]*)""#).unwrap());
+
+/// MIME types by file extension
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("avif", "image/avif"),
+    ("bmp", "image/bmp"),
+    ("csv", "text/csv"),
+    ("flac", "audio/flac"),
+    ("gif", "image/gif"),
+    ("ico", "image/x-icon"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("json", "application/json"),
+    ("m4a", "audio/mp4"),
+    ("mov", "video/quicktime"),
+    ("mp3", "audio/mpeg"),
+    ("mp4", "video/mp4"),
+    ("oga", "audio/ogg"),
+    ("ogg", "audio/ogg"),
+    ("ogv", "video/ogg"),
+    ("pdf", "application/pdf"),
+    ("png", "image/png"),
+    ("svg", "image/svg+xml"),
+    ("txt", "text/plain"),
+    ("wav", "audio/wav"),
+    ("webm", "video/webm"),
+    ("webp", "image/webp"),
+    ("zip", "application/zip"),
+];
+
+/// How a document uses a media file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaKind {
+    Image,
+    Video,
+    Audio,
+    /// A linked file (`[report](report.pdf)`)
+    File,
+}
+
+/// A local file a document references
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MediaRef {
+    pub kind: MediaKind,
+    /// The reference as written in the rendered document
+    pub src: String,
+    /// Absolute path it resolves to
+    pub path: String,
+    pub exists: bool,
+    /// Guessed from the extension; None if unknown
+    pub mime_type: Option<String>,
+}
+
+/// Lists every local file a document touches: images, linked files and
+/// `<video>`/`<audio>` sources (including `<source>` children and posters),
+/// in document order.
+///
+/// A preflight for exporting or bundling a document. References are read
+/// from the rendered HTML, so code blocks are skipped and reference links
+/// resolved. Relative paths resolve against the directory of
+/// `base_path` (the .md file); URLs, data URIs and links to other markdown
+/// documents are left out, and each file is listed once.
+pub fn media_references(markdown: &str, base_path: &str) -> Vec<MediaRef> {
+    let html = render_markdown_html(markdown);
+    let base = Path::new(base_path);
+    let base_dir = base.parent().unwrap_or(base);
+
+    let mut seen = HashSet::new();
+    let mut media = Vec::new();
+    // The open <video>/<audio> element its <source> children belong to
+    let mut player = None;
+
+    for caps in MEDIA_TAG.captures_iter(&html) {
+        let tag = caps[2].to_ascii_lowercase();
+        let kind = match tag.as_str() {
+            "video" | "audio" if !caps[1].is_empty() => {
+                player = None;
+                continue;
+            }
+            "video" => MediaKind::Video,
+            "audio" => MediaKind::Audio,
+            _ if !caps[1].is_empty() => continue,
+            "img" => MediaKind::Image,
+            "a" => MediaKind::File,
+            _ => player.unwrap_or(MediaKind::Video),
+        };
+        if matches!(kind, MediaKind::Video | MediaKind::Audio) && tag != "source" {
+            player = Some(kind);
+        }
+
+        for attr in SOURCE_ATTR.captures_iter(&caps[3]) {
+            let src = html_unescape(&attr[2]);
+            let kind = if attr[1].eq_ignore_ascii_case("poster") {
+                MediaKind::Image
+            } else {
+                kind
+            };
+            let Some(path) = resolve_local_link(&src, base_dir) else {
+                continue;
+            };
+            if kind == MediaKind::File && is_markdown_file(&path) {
+                continue;
+            }
+            if !seen.insert(path.clone()) {
+                continue;
+            }
+
+            let mime_type = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(str::to_ascii_lowercase)
+                .and_then(|ext| MIME_TYPES.iter().find(|(known, _)| *known == ext))
+                .map(|(_, mime)| mime.to_string());
+            media.push(MediaRef {
+                kind,
+                src,
+                exists: path.is_file(),
+                path: path.to_string_lossy().replace('\\', "/"),
+                mime_type,
+            });
+        }
+    }
+
+    media
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_lists_images_files_and_video() {
+        let dir = std::env::temp_dir().join("markviewer_list_media");
+        fs::create_dir_all(dir.join("img")).unwrap();
+        fs::write(dir.join("img/chart.png"), b"png").unwrap();
+        fs::write(dir.join("report.pdf"), b"pdf").unwrap();
+        let doc = dir.join("doc.md");
+
+        let md = "![Chart](img/chart.png)\n\n\
+                  See [the report](report.pdf) and [notes](other.md).\n\n\
+                  <video controls poster=\"img/chart.png\">\n\
+                  <source src=\"clips/demo%20one.mp4\" type=\"video/mp4\">\n\
+                  </video>\n\n\
+                  ![Remote](https://example.com/a.png) ![Inline](data:image/png;base64,AA==)\n\n\
+                  ```\n![Code](img/missing.png)\n```\n";
+        let media = media_references(md, doc.to_str().unwrap());
+
+        let found: Vec<(MediaKind, &str, bool, Option<&str>)> = media
+            .iter()
+            .map(|m| (m.kind, m.src.as_str(), m.exists, m.mime_type.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (MediaKind::Image, "img/chart.png", true, Some("image/png")),
+                (MediaKind::File, "report.pdf", true, Some("application/pdf")),
+                (
+                    MediaKind::Video,
+                    "clips/demo%20one.mp4",
+                    false,
+                    Some("video/mp4")
+                ),
+            ]
+        );
+        let expected = dir.join("clips/demo one.mp4");
+        assert_eq!(media[2].path, expected.to_string_lossy().replace('\\', "/"));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_audio_sources() {
+        let md = "<audio src=\"song.MP3\"></audio>\n";
+        let media = media_references(md, "/notes/doc.md");
+        assert_eq!(media.len(), 1);
+        assert_eq!(media[0].kind, MediaKind::Audio);
+        assert_eq!(media[0].path, "/notes/song.MP3");
+        assert_eq!(media[0].mime_type.as_deref(), Some("audio/mpeg"));
+        assert!(!media[0].exists);
+    }
+}
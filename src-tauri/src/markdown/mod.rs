@@ -1,9 +1,110 @@
+pub mod abbreviations;
+pub mod alt_text;
+pub mod anchors;
+pub mod block_ids;
+pub mod code_lint;
+pub mod code_tabs;
+pub mod color_swatches;
+pub mod criticmarkup;
+pub mod dates;
+pub mod delimited;
+pub mod document_style;
+pub mod fallback;
+pub mod fence_handlers;
+pub mod folding;
+pub mod footnotes;
+pub mod front_matter;
+pub mod front_matter_schema;
+pub mod github_refs;
+pub mod heading_anchors;
+pub mod heading_numbers;
+pub mod headings;
 pub mod highlighter;
+pub mod html_to_markdown;
 pub mod images;
+pub mod includes;
+pub mod kbd;
+pub mod keywords;
+pub mod language;
+pub mod lines;
+pub mod links;
+pub mod lint;
+pub mod math;
+pub mod media;
+pub mod notebook;
 pub mod parser;
+pub mod patch;
+pub mod raw_html;
+pub mod remote;
+pub mod rtf;
+pub mod sanitize;
+pub mod search_hits;
+pub mod sections;
+pub mod short_urls;
+pub mod slides;
+pub mod slug;
+pub mod snippets;
+pub mod source_map;
 pub mod special_blocks;
+pub mod summary;
+pub mod tables;
+pub mod task_counts;
+pub mod tasks;
 
-pub use highlighter::highlight_code;
-pub use images::resolve_image_paths;
-pub use parser::render_markdown_html;
-pub use special_blocks::{extract_special_blocks, SpecialBlock};
+/// A document demonstrating every syntax feature MarkViewer renders
+pub const SYNTAX_SAMPLE: &str = include_str!("syntax_sample.md");
+
+pub use alt_text::{audit_image_alt_text, AltTextIssue};
+pub use anchors::{
+    duplicate_headings, find_broken_anchors, heading_anchor_map, AnchorIssue, DuplicateHeading,
+};
+pub use code_lint::{lint_fenced_code, CodeLint};
+pub use color_swatches::insert_color_swatches;
+pub use dates::relative_time;
+pub use document_style::document_style_block;
+pub use fallback::special_block_fallback;
+pub use fence_handlers::client_block_types;
+pub use folding::{find_fold_regions, FoldRegion};
+pub use front_matter::{
+    extract_front_matter, front_matter_html, inherited_front_matter, merge_front_matter,
+    FrontMatterMode,
+};
+pub use front_matter_schema::{validate_front_matter_schema, SchemaError};
+pub use github_refs::link_github_refs;
+pub use heading_anchors::{style_heading_anchors, AnchorPlacement, DEFAULT_ANCHOR_SYMBOL};
+pub use heading_numbers::number_headings;
+pub use headings::{normalize_heading_levels, shift_heading_levels};
+pub use highlighter::{
+    get_highlight_css_light, highlight_code, highlight_tokens, list_themes, preview_theme_html,
+    Token,
+};
+pub use html_to_markdown::html_to_markdown;
+pub use images::{constrain_images, rebase_markdown_image_paths, resolve_image_paths_with};
+pub use includes::expand_code_includes;
+pub use keywords::{default_stopwords, top_keywords};
+pub use language::detect_language;
+pub use links::mark_local_links;
+pub use lint::{lint_markdown, LintReport};
+pub use math::tex_to_mathml;
+pub use media::{media_references, MediaKind, MediaRef};
+pub use notebook::notebook_to_markdown;
+pub use parser::{escape_html, render_markdown_html_with, HtmlOptions, TocEntry};
+pub use patch::{diff_rendered_blocks, BlockPatch};
+pub use remote::resolve_remote_paths;
+pub use rtf::markdown_to_rtf;
+pub use sanitize::sanitize_html;
+pub use search_hits::mark_search_hits;
+pub use sections::filter_sections;
+pub use short_urls::shorten_autolinks;
+pub use slides::{split_slides, DEFAULT_SLIDE_SEPARATOR};
+pub use slug::EmojiPolicy;
+pub use snippets::{search_snippets, Snippet};
+pub use source_map::{map_blocks_to_source, SourceMapEntry};
+pub use special_blocks::{
+    extract_special_blocks_labeled, extract_special_blocks_with, offset_placeholder_ids,
+    placeholder_number, replace_placeholder, SpecialBlock,
+};
+pub use summary::summarize_sections;
+pub use tables::{normalize_tables, NormalizedTables};
+pub use task_counts::add_heading_task_counts;
+pub use tasks::{parse_tasks, summarize_tasks, Task, TaskProgress};
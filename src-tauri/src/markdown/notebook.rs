@@ -0,0 +1,192 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::Value;
+
+use super::parser::escape_html;
+
+/// Converts a Jupyter notebook (.ipynb JSON) into markdown.
+///
+/// - Markdown cells are copied as-is
+/// - Code cells become fenced code blocks in the notebook's language
+/// - Text outputs are appended as `<pre>` blocks, image outputs as data URI images
+pub fn notebook_to_markdown(json: &str) -> Result<String, String> {
+    let notebook: Value =
+        serde_json::from_str(json).map_err(|e| format!("Invalid notebook JSON: {}", e))?;
+
+    let cells = notebook
+        .get("cells")
+        .and_then(Value::as_array)
+        .ok_or_else(|| "Invalid notebook: missing 'cells' array".to_string())?;
+
+    let language = notebook_language(&notebook);
+    let mut parts = Vec::new();
+
+    for (index, cell) in cells.iter().enumerate() {
+        let cell_type = cell
+            .get("cell_type")
+            .and_then(Value::as_str)
+            .ok_or_else(|| format!("Invalid notebook: cell {} has no 'cell_type'", index))?;
+        let source = join_text(cell.get("source"));
+
+        match cell_type {
+            "markdown" => parts.push(source),
+            "code" => {
+                if !source.trim().is_empty() {
+                    let fence = code_fence_for(&source);
+                    parts.push(format!(
+                        "{}{}\n{}\n{}",
+                        fence,
+                        language,
+                        source.trim_end_matches('\n'),
+                        fence
+                    ));
+                }
+                if let Some(outputs) = cell.get("outputs").and_then(Value::as_array) {
+                    parts.extend(outputs.iter().filter_map(render_output));
+                }
+            }
+            // Raw cells and unknown types are shown verbatim
+            _ => parts.push(format!("<pre>{}</pre>", escape_html(&source))),
+        }
+    }
+
+    Ok(parts.join("\n\n") + "\n")
+}
+
+/// Reads the notebook language from its metadata (defaults to python)
+fn notebook_language(notebook: &Value) -> String {
+    let metadata = notebook.get("metadata");
+    metadata
+        .and_then(|m| m.pointer("/language_info/name"))
+        .or_else(|| metadata.and_then(|m| m.pointer("/kernelspec/language")))
+        .and_then(Value::as_str)
+        .unwrap_or("python")
+        .to_string()
+}
+
+/// Notebook text fields are either a string or an array of lines
+fn join_text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Picks a backtick fence longer than any backtick run inside the code
+fn code_fence_for(code: &str) -> String {
+    let mut longest = 0;
+    let mut current = 0;
+    for c in code.chars() {
+        if c == '`' {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    "`".repeat(longest.max(2) + 1)
+}
+
+/// Renders a single code cell output as markdown/HTML, if it has a displayable form
+fn render_output(output: &Value) -> Option<String> {
+    let output_type = output.get("output_type").and_then(Value::as_str)?;
+
+    let text = match output_type {
+        "stream" => join_text(output.get("text")),
+        "execute_result" | "display_data" => {
+            let data = output.get("data")?;
+            for mime in ["image/png", "image/jpeg", "image/gif"] {
+                let image = join_text(data.get(mime));
+                if !image.is_empty() {
+                    let image: String = image.split_whitespace().collect();
+                    return Some(format!("![output](data:{};base64,{})", mime, image));
+                }
+            }
+            // Inline SVG can contain blank lines, which would end an HTML
+            // block, so it goes in as a data URI image like the rasters
+            let svg = join_text(data.get("image/svg+xml"));
+            if !svg.trim().is_empty() {
+                let svg = STANDARD.encode(svg.trim());
+                return Some(format!("![output](data:image/svg+xml;base64,{})", svg));
+            }
+            join_text(data.get("text/plain"))
+        }
+        "error" => {
+            let name = output.get("ename").and_then(Value::as_str).unwrap_or("Error");
+            let value = output.get("evalue").and_then(Value::as_str).unwrap_or("");
+            format!("{}: {}", name, value)
+        }
+        _ => return None,
+    };
+
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    Some(format!(
+        "<pre class=\"notebook-output\">{}</pre>",
+        escape_html(text.trim_end_matches('\n'))
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTEBOOK: &str = r##"{
+        "metadata": {"language_info": {"name": "python"}},
+        "nbformat": 4,
+        "cells": [
+            {"cell_type": "markdown", "source": ["# Analysis\n", "Some **notes**."]},
+            {"cell_type": "code", "source": "print(1 < 2)", "outputs": [
+                {"output_type": "stream", "name": "stdout", "text": ["True\n"]}
+            ]},
+            {"cell_type": "code", "source": [], "outputs": [
+                {"output_type": "display_data", "data": {"image/png": "iVBORw0KGgo=\n"}}
+            ]}
+        ]
+    }"##;
+
+    #[test]
+    fn test_markdown_and_code_cells() {
+        let md = notebook_to_markdown(NOTEBOOK).unwrap();
+        assert!(md.contains("# Analysis\nSome **notes**."));
+        assert!(md.contains("```python\nprint(1 < 2)\n```"));
+    }
+
+    #[test]
+    fn test_outputs() {
+        let md = notebook_to_markdown(NOTEBOOK).unwrap();
+        assert!(md.contains("<pre class=\"notebook-output\">True</pre>"));
+        assert!(md.contains("![output](data:image/png;base64,iVBORw0KGgo=)"));
+    }
+
+    #[test]
+    fn test_svg_output_with_blank_lines() {
+        let svg = "<svg xmlns=\"http://www.w3.org/2000/svg\">\n<g>\n\n<text>a</text>\n\n</g>\n</svg>\n";
+        let notebook = serde_json::json!({
+            "cells": [{"cell_type": "code", "source": "plot()", "outputs": [
+                {"output_type": "display_data", "data": {"image/svg+xml": svg}}
+            ]}]
+        });
+        let md = notebook_to_markdown(&notebook.to_string()).unwrap();
+        let expected = format!("![output](data:image/svg+xml;base64,{})", STANDARD.encode(svg.trim()));
+        assert!(md.contains(&expected));
+        assert!(!md.contains("<svg"));
+    }
+
+    #[test]
+    fn test_fence_longer_than_code_backticks() {
+        assert_eq!(code_fence_for("let s = \"```\";"), "````");
+        assert_eq!(code_fence_for("x = 1"), "```");
+    }
+
+    #[test]
+    fn test_malformed_notebook() {
+        assert!(notebook_to_markdown("not json").unwrap_err().contains("Invalid notebook JSON"));
+        assert!(notebook_to_markdown("{}").unwrap_err().contains("missing 'cells'"));
+        assert!(notebook_to_markdown(r#"{"cells": [{}]}"#)
+            .unwrap_err()
+            .contains("cell 0"));
+    }
+}
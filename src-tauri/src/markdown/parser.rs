@@ -1,9 +1,27 @@
-use comrak::Options;
+use comrak::nodes::{AstNode, NodeHtmlBlock, NodeValue};
+use comrak::{Arena, Options};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-use super::highlighter::highlight_code;
+use super::abbreviations::expand_abbreviations;
+use super::block_ids::{block_anchor_ids, placed_block_lines, tag_blocks, top_level_block_ids};
+use super::code_tabs::group_code_tabs;
+use super::criticmarkup::expand_criticmarkup;
+use super::fence_handlers::{fence_registry, FenceRegistry};
+use super::footnotes::expand_inline_footnotes;
+use super::front_matter::extract_front_matter;
+use super::highlighter::{code_theme_class, highlight_code_block};
+use super::images::expand_image_embeds;
+use super::kbd::expand_kbd_shortcuts;
+use super::raw_html::{escape_unknown_tags, keep_raw_tables_whole};
+use super::slug::{apply_heading_ids, EmojiPolicy, Slugger, HEADING_ID_PREFIX};
+use super::tables::expand_cell_line_breaks;
 
 /// Creates comrak options with GFM extensions enabled
-fn get_options() -> Options {
+pub(super) fn get_options() -> Options {
     let mut options = Options::default();
 
     // Extension options (GFM features)
@@ -13,7 +31,7 @@ fn get_options() -> Options {
     options.extension.autolink = true;
     options.extension.tasklist = true;
     options.extension.superscript = true;
-    options.extension.header_ids = Some("heading-".to_string());
+    options.extension.header_ids = Some(HEADING_ID_PREFIX.to_string());
     options.extension.footnotes = true;
     options.extension.description_lists = true;
     options.extension.front_matter_delimiter = Some("---".to_string());
@@ -28,7 +46,22 @@ fn get_options() -> Options {
 }
 
 /// Custom syntax highlighter adapter for comrak
-pub struct SyntectAdapter;
+///
+/// comrak writes the `<pre>` and `<code>` tags before the code itself, so they
+/// are held back until the code is highlighted and its line count is known.
+#[derive(Default)]
+pub struct SyntectAdapter {
+    /// Blocks with more lines than this get the `collapsible` class
+    pub collapse_lines: Option<usize>,
+    /// Give blocks the dark code theme's class instead of the light one's
+    pub dark_theme: bool,
+    /// Record time spent highlighting
+    pub timed: bool,
+    pre_attributes: Mutex<HashMap<String, String>>,
+    code_attributes: Mutex<HashMap<String, String>>,
+    /// Time spent highlighting and the number of blocks highlighted
+    highlighting: Mutex<(Duration, usize)>,
+}
 
 impl comrak::adapters::SyntaxHighlighterAdapter for SyntectAdapter {
     fn write_highlighted(
@@ -38,45 +71,430 @@ impl comrak::adapters::SyntaxHighlighterAdapter for SyntectAdapter {
         code: &str,
     ) -> std::io::Result<()> {
         let lang = lang.unwrap_or("text");
-        let highlighted = highlight_code(code, lang);
-        write!(output, "{}", highlighted)
+        let started = self.timed.then(Instant::now);
+        let highlighted = highlight_code_block(code, lang);
+        if let Some(started) = started {
+            let mut highlighting = self.highlighting.lock().unwrap();
+            highlighting.0 += started.elapsed();
+            highlighting.1 += 1;
+        }
+
+        let mut pre_attributes = take(&self.pre_attributes);
+        pre_attributes.insert("data-lines".to_string(), highlighted.lines.to_string());
+        let mut classes = vec![code_theme_class(self.dark_theme)];
+        if self.collapse_lines.is_some_and(|max| highlighted.lines > max) {
+            classes.push("collapsible");
+        }
+        // A file name after the language is shown in a header above the code
+        let filename = pre_attributes
+            .get("data-meta")
+            .and_then(|meta| fence_filename(meta))
+            .map(escape_html);
+        if let Some(filename) = filename {
+            pre_attributes.insert("data-filename".to_string(), filename);
+            pre_attributes.insert("data-lang".to_string(), escape_html(lang));
+            classes.push("has-filename");
+        }
+        let class = pre_attributes.entry("class".to_string()).or_default();
+        for name in classes {
+            if !class.is_empty() {
+                class.push(' ');
+            }
+            class.push_str(name);
+        }
+
+        write!(
+            output,
+            "<pre{}><code{}>{}",
+            attributes_html(&pre_attributes),
+            attributes_html(&take(&self.code_attributes)),
+            highlighted.html
+        )
     }
 
     fn write_pre_tag(
         &self,
-        output: &mut dyn std::io::Write,
-        attributes: std::collections::HashMap<String, String>,
+        _output: &mut dyn std::io::Write,
+        attributes: HashMap<String, String>,
     ) -> std::io::Result<()> {
-        let mut attrs_str = String::new();
-        for (key, value) in attributes {
-            attrs_str.push_str(&format!(" {}=\"{}\"", key, value));
-        }
-        write!(output, "<pre{}>", attrs_str)
+        *self.pre_attributes.lock().unwrap() = attributes;
+        Ok(())
     }
 
     fn write_code_tag(
         &self,
-        output: &mut dyn std::io::Write,
-        attributes: std::collections::HashMap<String, String>,
+        _output: &mut dyn std::io::Write,
+        attributes: HashMap<String, String>,
     ) -> std::io::Result<()> {
-        let mut attrs_str = String::new();
-        for (key, value) in attributes {
-            attrs_str.push_str(&format!(" {}=\"{}\"", key, value));
+        *self.code_attributes.lock().unwrap() = attributes;
+        Ok(())
+    }
+}
+
+/// The file name in a fence's info string after the language
+/// (` ```rust src/main.rs {1,3} `), skipping `{...}` line emphasis and
+/// `key=value` attributes
+fn fence_filename(meta: &str) -> Option<&str> {
+    let mut depth = 0usize;
+    for token in meta.split_whitespace() {
+        if depth > 0 || token.starts_with('{') {
+            depth += token.matches('{').count();
+            depth = depth.saturating_sub(token.matches('}').count());
+            continue;
+        }
+        if !token.contains('=') {
+            return Some(token);
         }
-        write!(output, "<code{}>", attrs_str)
     }
+    None
+}
+
+fn take(attributes: &Mutex<HashMap<String, String>>) -> HashMap<String, String> {
+    std::mem::take(&mut *attributes.lock().unwrap())
+}
+
+/// Attributes in name order, so the same block always renders the same HTML
+fn attributes_html(attributes: &HashMap<String, String>) -> String {
+    let mut names: Vec<&String> = attributes.keys().collect();
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| format!(" {}=\"{}\"", name, attributes[name]))
+        .collect()
+}
+
+/// Escapes text for safe inclusion in HTML content and attribute values
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Options for the markdown to HTML step
+#[derive(Debug, Clone)]
+pub struct HtmlOptions {
+    /// How emoji in headings appear in generated heading ids
+    pub emoji_policy: EmojiPolicy,
+    /// Give headings the ids GitHub would (no prefix; the emoji policy is
+    /// ignored)
+    pub github_ids: bool,
+    /// Tag each top-level block with a content-derived `data-block-id`
+    pub block_ids: bool,
+    /// Give list items and tables content-derived `block-...` ids for deep links
+    pub block_anchors: bool,
+    /// Render literal `\n` in table cells as line breaks
+    pub table_line_breaks: bool,
+    /// Curly quotes and dashes for `"`, `'`, `--` and `---` outside code
+    pub smart_punctuation: bool,
+    /// Render `[[kbd:Cmd+S]]` as `<kbd>` elements
+    pub kbd_shortcuts: bool,
+    /// Turn image embeds like `![[chart.png]]` into markdown images
+    pub image_embeds: bool,
+    /// Turn Pandoc-style `^[note]` into numbered footnotes
+    pub inline_footnotes: bool,
+    /// Wrap terms defined with `*[HTML]: ...` in `<abbr>` elements
+    pub abbreviations: bool,
+    /// Code blocks with more lines than this get a `collapsible` class
+    pub collapse_code_lines: Option<usize>,
+    /// Style code blocks for a dark document
+    pub dark_theme: bool,
+    /// Render every newline inside a paragraph as `<br>`
+    pub hard_line_breaks: bool,
+    /// Expand CriticMarkup edits into `<ins>`, `<del>` and `<mark>`
+    pub criticmarkup: bool,
+    /// Show `<placeholder>`-style text that isn't an HTML tag as written
+    /// (on by default, as in `render_markdown`)
+    pub escape_unknown_tags: bool,
+    /// Measure each stage into [`RenderedHtml::timings`]
+    pub timings: bool,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            emoji_policy: EmojiPolicy::default(),
+            github_ids: false,
+            block_ids: false,
+            block_anchors: false,
+            table_line_breaks: false,
+            smart_punctuation: false,
+            kbd_shortcuts: false,
+            image_embeds: false,
+            inline_footnotes: false,
+            abbreviations: false,
+            collapse_code_lines: None,
+            dark_theme: false,
+            hard_line_breaks: false,
+            criticmarkup: false,
+            escape_unknown_tags: true,
+            timings: false,
+        }
+    }
+}
+
+/// HTML produced by [`render_markdown_html_with`]
+#[derive(Debug, Clone)]
+pub struct RenderedHtml {
+    pub html: String,
+    /// Ids of the tagged top-level blocks, in document order
+    pub block_ids: Vec<String>,
+    /// 1-based source line range of each tagged block, parallel to `block_ids`
+    pub block_lines: Vec<(usize, usize)>,
+    /// Every heading, at all levels, with the id it was given
+    pub toc: Vec<TocEntry>,
+    /// Where the time went, if [`HtmlOptions::timings`] was set (zero otherwise)
+    pub timings: HtmlTimings,
+}
+
+/// Time spent in each stage of [`render_markdown_html_with`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HtmlTimings {
+    /// Preprocessing and comrak parsing
+    pub parse: Duration,
+    /// HTML output and post-processing, excluding highlighting
+    pub format: Duration,
+    /// Syntax highlighting of code blocks
+    pub highlight: Duration,
+    /// Number of code blocks highlighted
+    pub code_blocks: usize,
 }
 
 /// Renders markdown to HTML with syntax highlighting
 pub fn render_markdown_html(markdown: &str) -> String {
-    let options = get_options();
+    render_markdown_html_with(markdown, &HtmlOptions::default()).html
+}
+
+/// Renders markdown to HTML with syntax highlighting and custom options
+pub fn render_markdown_html_with(markdown: &str, html_options: &HtmlOptions) -> RenderedHtml {
+    render_markdown_html_with_registry(markdown, html_options, &fence_registry())
+}
+
+/// Like [`render_markdown_html_with`], rendering fences with the handlers in
+/// `registry` instead of the app's
+pub fn render_markdown_html_with_registry(
+    markdown: &str,
+    html_options: &HtmlOptions,
+    registry: &FenceRegistry,
+) -> RenderedHtml {
+    let mut options = get_options();
+    options.render.sourcepos = html_options.block_ids || html_options.block_anchors;
+    options.parse.smart = html_options.smart_punctuation;
+    options.render.hardbreaks = html_options.hard_line_breaks;
+
+    let timed = html_options.timings;
+    let started = timed.then(Instant::now);
 
     // Set up syntax highlighting plugin
-    let adapter = SyntectAdapter;
+    let adapter = SyntectAdapter {
+        collapse_lines: html_options.collapse_code_lines,
+        dark_theme: html_options.dark_theme,
+        timed,
+        ..Default::default()
+    };
     let mut plugins = comrak::Plugins::default();
     plugins.render.codefence_syntax_highlighter = Some(&adapter);
 
-    comrak::markdown_to_html_with_plugins(markdown, &options, &plugins)
+    let markdown = preprocess(markdown, html_options);
+
+    let arena = Arena::new();
+    let root = comrak::parse_document(&arena, &markdown, &options);
+    let toc = collect_toc(root, html_options);
+    if registry.has_server_handlers() {
+        apply_fence_handlers(root, registry);
+    }
+    let parsed = timed.then(Instant::now);
+    let mut output = Vec::new();
+    comrak::format_html_with_plugins(root, &options, &mut output, &plugins).unwrap();
+    let html = String::from_utf8(output).unwrap();
+    let slugger = if html_options.github_ids {
+        Slugger::github()
+    } else {
+        Slugger::new(html_options.emoji_policy)
+    };
+    let html = apply_heading_ids(&html, slugger);
+    let html = group_code_tabs(&html);
+
+    let (highlight, code_blocks) = *adapter.highlighting.lock().unwrap();
+    let mut timings = HtmlTimings {
+        parse: started.zip(parsed).map_or(Duration::ZERO, |(started, parsed)| parsed - started),
+        highlight,
+        code_blocks,
+        ..Default::default()
+    };
+
+    if !options.render.sourcepos {
+        timings.format = elapsed_since(parsed).saturating_sub(highlight);
+        return RenderedHtml {
+            html,
+            block_ids: Vec::new(),
+            block_lines: Vec::new(),
+            toc,
+            timings,
+        };
+    }
+
+    let blocks = if html_options.block_ids {
+        top_level_block_ids(root, &markdown)
+    } else {
+        Vec::new()
+    };
+    let anchors = if html_options.block_anchors {
+        block_anchor_ids(root, &markdown)
+    } else {
+        Vec::new()
+    };
+    let (html, block_ids) = tag_blocks(&html, &blocks, &anchors);
+    let block_lines = placed_block_lines(&blocks, &block_ids);
+    timings.format = elapsed_since(parsed).saturating_sub(highlight);
+    RenderedHtml {
+        html,
+        block_ids,
+        block_lines,
+        toc,
+        timings,
+    }
+}
+
+/// Time since `started`, or zero when untimed
+fn elapsed_since(started: Option<Instant>) -> Duration {
+    started.map_or(Duration::ZERO, |started| started.elapsed())
+}
+
+/// Applies the source rewrites enabled in `html_options` before parsing
+fn preprocess<'a>(markdown: &'a str, html_options: &HtmlOptions) -> Cow<'a, str> {
+    let mut markdown = keep_raw_tables_whole(markdown);
+    if html_options.escape_unknown_tags {
+        markdown = Cow::Owned(escape_unknown_tags(&markdown).into_owned());
+    }
+    if html_options.table_line_breaks {
+        markdown = Cow::Owned(expand_cell_line_breaks(&markdown));
+    }
+    if html_options.inline_footnotes {
+        markdown = Cow::Owned(expand_inline_footnotes(&markdown));
+    }
+    if html_options.abbreviations {
+        markdown = Cow::Owned(expand_abbreviations(&markdown));
+    }
+    if html_options.kbd_shortcuts {
+        markdown = Cow::Owned(expand_kbd_shortcuts(&markdown));
+    }
+    if html_options.image_embeds {
+        markdown = Cow::Owned(expand_image_embeds(&markdown));
+    }
+    if html_options.criticmarkup {
+        markdown = Cow::Owned(expand_criticmarkup(&markdown));
+    }
+    markdown
+}
+
+/// A heading in a document's table of contents
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TocEntry {
+    /// Heading level, 1-6
+    pub level: u8,
+    /// Plain text of the heading
+    pub text: String,
+    /// Id of the rendered heading (`heading-intro`, `heading-intro-1`…),
+    /// without `#`
+    pub anchor_id: String,
+}
+
+/// Lists the headings of a document down to `max_depth`, with the ids the
+/// renderer gives them.
+pub fn generate_toc(markdown: &str, max_depth: u8) -> Vec<TocEntry> {
+    generate_toc_with(markdown, max_depth, &HtmlOptions::default())
+}
+
+/// [`generate_toc`] for a document rendered with `html_options`.
+///
+/// Renders the document; when the HTML is needed too, use the `toc` of
+/// [`render_markdown_html_with`] instead of parsing twice.
+pub fn generate_toc_with(
+    markdown: &str,
+    max_depth: u8,
+    html_options: &HtmlOptions,
+) -> Vec<TocEntry> {
+    let (_, body) = extract_front_matter(markdown);
+    let mut toc = render_markdown_html_with(body, html_options).toc;
+    toc.retain(|entry| entry.level <= max_depth);
+    toc
+}
+
+/// The headings of a parsed document with the ids [`apply_heading_ids`]
+/// gives them.
+///
+/// Headings come from the parsed document, so `#` lines in code blocks and
+/// front matter are skipped and headings in quotes and lists are included.
+fn collect_toc<'a>(root: &'a AstNode<'a>, html_options: &HtmlOptions) -> Vec<TocEntry> {
+    let mut slugger = if html_options.github_ids {
+        Slugger::github()
+    } else {
+        Slugger::new(html_options.emoji_policy)
+    };
+
+    let mut toc = Vec::new();
+    for node in root.descendants() {
+        let level = match node.data.borrow().value {
+            NodeValue::Heading(ref heading) => heading.level,
+            _ => continue,
+        };
+        let mut text = String::new();
+        push_heading_text(node, &mut text);
+        toc.push(TocEntry {
+            level,
+            anchor_id: slugger.heading_id(&text),
+            text: text.split_whitespace().collect::<Vec<_>>().join(" "),
+        });
+    }
+    toc
+}
+
+/// Appends the text a heading's rendered HTML shows, which is what its id
+/// is made from: image alt text and raw HTML tags don't count
+fn push_heading_text<'a>(node: &'a AstNode<'a>, text: &mut String) {
+    for child in node.children() {
+        match &child.data.borrow().value {
+            NodeValue::Text(literal) => text.push_str(literal),
+            NodeValue::Code(code) => text.push_str(&code.literal),
+            NodeValue::ShortCode(code) => text.push_str(&code.emoji),
+            NodeValue::FootnoteReference(reference) => text.push_str(&reference.ix.to_string()),
+            NodeValue::SoftBreak | NodeValue::LineBreak => text.push('\n'),
+            NodeValue::Image(_) | NodeValue::HtmlInline(_) => {}
+            _ => push_heading_text(child, text),
+        }
+    }
+}
+
+/// Replaces fenced code blocks that have a registered backend handler with
+/// the HTML it renders; blocks it declines stay code and are highlighted
+fn apply_fence_handlers<'a>(root: &'a AstNode<'a>, registry: &FenceRegistry) {
+    for node in root.descendants() {
+        let html = match &node.data.borrow().value {
+            NodeValue::CodeBlock(block) if block.fenced => {
+                let lang = block.info.split_whitespace().next().unwrap_or("");
+                registry.render(lang, &block.literal)
+            }
+            _ => None,
+        };
+        if let Some(mut literal) = html {
+            if !literal.ends_with('\n') {
+                literal.push('\n');
+            }
+            node.data.borrow_mut().value = NodeValue::HtmlBlock(NodeHtmlBlock {
+                block_type: 6,
+                literal,
+            });
+        }
+    }
 }
 
 #[cfg(test)]
@@ -100,6 +518,108 @@ mod tests {
         assert!(html.contains("<th>"));
     }
 
+    #[test]
+    fn test_table_line_breaks() {
+        let md = "| Step | Detail |\n|---|---|\n| 1 | **Build**\\n`cargo build` |";
+        let options = HtmlOptions {
+            table_line_breaks: true,
+            ..Default::default()
+        };
+
+        let html = render_markdown_html_with(md, &options).html;
+        assert!(html.contains("<td><strong>Build</strong><br><code>cargo build</code></td>"));
+
+        let html = render_markdown_html(md);
+        assert!(!html.contains("<br>"));
+    }
+
+    #[test]
+    fn test_smart_punctuation() {
+        let md = "\"Quoted\" -- it's `\"code\" --`\n\n```\n\"fenced\" --\n```";
+        let options = HtmlOptions {
+            smart_punctuation: true,
+            ..Default::default()
+        };
+
+        let html = render_markdown_html_with(md, &options).html;
+        assert!(html.contains("\u{201c}Quoted\u{201d} \u{2013} it\u{2019}s"));
+        assert!(html.contains("<code>&quot;code&quot; --</code>"));
+        assert!(!html.contains("\u{201c}fenced"));
+
+        let html = render_markdown_html(md);
+        assert!(html.contains("&quot;Quoted&quot; -- it's"));
+    }
+
+    #[test]
+    fn test_kbd_shortcuts() {
+        let md = "Press [[kbd:Ctrl+Alt+Del]] or `[[kbd:Esc]]`";
+        let options = HtmlOptions {
+            kbd_shortcuts: true,
+            ..Default::default()
+        };
+
+        let html = render_markdown_html_with(md, &options).html;
+        assert!(html.contains("<kbd>Ctrl</kbd>+<kbd>Alt</kbd>+<kbd>Del</kbd>"));
+        assert!(html.contains("<code>[[kbd:Esc]]</code>"));
+
+        assert!(render_markdown_html(md).contains("[[kbd:Ctrl+Alt+Del]]"));
+    }
+
+    #[test]
+    fn test_inline_footnotes() {
+        let md = "Aside^[Inline *note*.] and a reference[^1].\n\n[^1]: Defined.";
+        let options = HtmlOptions {
+            inline_footnotes: true,
+            ..Default::default()
+        };
+
+        let html = render_markdown_html_with(md, &options).html;
+        assert!(html.contains("<section class=\"footnotes\""));
+        assert!(html.contains("href=\"#fn-inline-1\""));
+        assert!(html.contains("Inline <em>note</em>."));
+        assert!(html.contains("Defined."));
+
+        assert!(render_markdown_html(md).contains("^[Inline"));
+    }
+
+    #[test]
+    fn test_fence_handlers() {
+        use crate::markdown::fence_handlers::{FenceHandler, FenceRegistry};
+        use std::sync::Arc;
+
+        let mut registry = FenceRegistry::default();
+        registry.register(
+            "upper",
+            FenceHandler::Server(Arc::new(|content| {
+                let content = content.trim();
+                (!content.is_empty())
+                    .then(|| format!("<div class=\"upper\">{}</div>", content.to_uppercase()))
+            })),
+        );
+
+        let md = "```upper\nhello\n```\n\n```upper\n```\n\n```rust\nfn a() {}\n```";
+        let html = render_markdown_html_with_registry(md, &HtmlOptions::default(), &registry).html;
+        assert!(html.contains("<div class=\"upper\">HELLO</div>"));
+        assert!(!html.contains("hello"));
+        // Declined and unregistered blocks are highlighted as usual
+        assert_eq!(html.matches("<pre").count(), 2);
+    }
+
+    #[test]
+    fn test_abbreviations() {
+        let md = "The HTML spec.\n\n*[HTML]: HyperText Markup Language";
+        let options = HtmlOptions {
+            abbreviations: true,
+            ..Default::default()
+        };
+
+        let html = render_markdown_html_with(md, &options).html;
+        assert!(html.contains("<abbr title=\"HyperText Markup Language\">HTML</abbr> spec."));
+        assert!(!html.contains("*[HTML]") && !html.contains("[HTML]:"));
+
+        assert!(render_markdown_html(md).contains("[HTML]:"));
+    }
+
     #[test]
     fn test_tasklist() {
         let md = "- [ ] Todo\n- [x] Done";
@@ -107,6 +627,95 @@ mod tests {
         assert!(html.contains("type=\"checkbox\""));
     }
 
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html("<a href=\"x\">Tom & 'Jerry'</a>"),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#39;Jerry&#39;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_generate_toc() {
+        let md = "---\ntitle: Guide\n---\n# Intro\n\n\
+                  ```sh\n# not a heading\n```\n\n\
+                  > ## Intro\n\n\
+                  - ### `Setup` ![logo](logo.png) [[kbd:Ctrl+S]]\n\n\
+                  #### Deep\n\n\
+                  ## Intro\n";
+        let options = HtmlOptions {
+            kbd_shortcuts: true,
+            ..Default::default()
+        };
+        let toc = generate_toc_with(md, 3, &options);
+
+        let entries: Vec<(u8, &str, &str)> = toc
+            .iter()
+            .map(|e| (e.level, e.text.as_str(), e.anchor_id.as_str()))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (1, "Intro", "heading-intro"),
+                (2, "Intro", "heading-intro-1"),
+                (3, "Setup Ctrl+S", "heading-setup-ctrls"),
+                (2, "Intro", "heading-intro-2"),
+            ]
+        );
+
+        assert_eq!(generate_toc(md, 1).len(), 1);
+
+        // Ids match the rendered headings, including ones past max_depth
+        let (_, body) = extract_front_matter(md);
+        let html = render_markdown_html_with(body, &options).html;
+        for entry in generate_toc_with(md, 6, &options) {
+            assert!(html.contains(&format!("id=\"{}\"", entry.anchor_id)));
+        }
+    }
+
+    #[test]
+    fn test_heading_ids_follow_emoji_policy() {
+        let md = "## 🚀 Launch";
+
+        let strip = render_markdown_html(md);
+        assert!(strip.contains("id=\"heading-launch\""));
+
+        let keep = render_markdown_html_with(
+            md,
+            &HtmlOptions {
+                emoji_policy: EmojiPolicy::Keep,
+                ..Default::default()
+            },
+        )
+        .html;
+        assert!(keep.contains("id=\"heading-%F0%9F%9A%80-launch\""));
+
+        let shortcode = render_markdown_html_with(
+            md,
+            &HtmlOptions {
+                emoji_policy: EmojiPolicy::Shortcode,
+                ..Default::default()
+            },
+        )
+        .html;
+        assert!(shortcode.contains("href=\"#heading-rocket-launch\""));
+        assert!(shortcode.contains("id=\"heading-rocket-launch\""));
+
+        let github = render_markdown_html_with(
+            "## 🚀 Launch\n\n## C++ & Rust\n\n## C++ & Rust",
+            &HtmlOptions {
+                github_ids: true,
+                ..Default::default()
+            },
+        )
+        .html;
+        assert!(github.contains(
+            "<a href=\"#-launch\" aria-hidden=\"true\" class=\"anchor\" id=\"-launch\">"
+        ));
+        assert!(github.contains("id=\"c--rust\""));
+        assert!(github.contains("id=\"c--rust-1\""));
+    }
+
     #[test]
     fn test_code_block() {
         let md = "```rust\nfn main() {}\n```";
@@ -114,4 +723,83 @@ mod tests {
         assert!(html.contains("<pre"));
         assert!(html.contains("<code"));
     }
+
+    #[test]
+    fn test_timings_count_code_blocks() {
+        let md = "```rust\nfn main() {}\n```\n\n```\nplain\n```\n\nText";
+        let options = HtmlOptions {
+            timings: true,
+            ..Default::default()
+        };
+        let timings = render_markdown_html_with(md, &options).timings;
+        assert_eq!(timings.code_blocks, 2);
+
+        let timings = render_markdown_html_with("Text", &options).timings;
+        assert_eq!(timings.code_blocks, 0);
+        assert_eq!(timings.highlight, Duration::ZERO);
+
+        // Nothing is measured unless asked for
+        let timings = render_markdown_html_with(md, &HtmlOptions::default()).timings;
+        assert_eq!(timings.code_blocks, 0);
+        assert_eq!(timings.parse, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_code_theme_class_on_pre() {
+        let html = render_markdown_html("```rust\nfn main() {}\n```\n\n~~~\nplain\n~~~\n");
+        let light_pre = "<pre class=\"code-theme-github-light\"";
+        assert_eq!(html.matches(light_pre).count(), 2);
+
+        let options = HtmlOptions {
+            dark_theme: true,
+            ..Default::default()
+        };
+        let html = render_markdown_html_with("```rust\nfn main() {}\n```", &options).html;
+        assert!(html.contains("<pre class=\"code-theme-github-dark\""));
+        assert!(!html.contains("code-theme-github-light"));
+    }
+
+    #[test]
+    fn test_collapse_code_lines() {
+        let md = "```\none\ntwo\nthree\n```\n\n```rust\nfn main() {}\n```";
+        let options = HtmlOptions {
+            collapse_code_lines: Some(2),
+            ..Default::default()
+        };
+
+        let html = render_markdown_html_with(md, &options).html;
+        assert!(
+            html.contains("<pre class=\"code-theme-github-light collapsible\" data-lines=\"3\">")
+        );
+        assert!(html.contains("data-lines=\"1\""));
+        assert_eq!(html.matches("collapsible").count(), 1);
+
+        let html = render_markdown_html(md);
+        assert!(html.contains("<pre class=\"code-theme-github-light\" data-lines=\"3\">"));
+        assert!(!html.contains("collapsible"));
+    }
+
+    #[test]
+    fn test_code_block_filename() {
+        let html = render_markdown_html("```rust main.rs\nfn main() {}\n```");
+        assert!(html.contains("data-lang=\"rust\""));
+        assert!(html.contains("data-filename=\"main.rs\""));
+        assert!(html.contains("<pre class=\"code-theme-github-light has-filename\""));
+
+        let html = render_markdown_html("```rust {1, 2} src/main.rs\nfn main() {}\n```");
+        assert!(html.contains("data-filename=\"src/main.rs\""));
+
+        let html = render_markdown_html("```rust {1,2}\nfn main() {}\n```");
+        assert!(!html.contains("data-filename"));
+        assert!(!html.contains("has-filename"));
+    }
+
+    #[test]
+    fn test_fence_filename() {
+        assert_eq!(fence_filename("main.rs"), Some("main.rs"));
+        assert_eq!(fence_filename("src/lib.rs {3-5}"), Some("src/lib.rs"));
+        assert_eq!(fence_filename("{ 1, 3 } title=x app.py"), Some("app.py"));
+        assert_eq!(fence_filename("{1,3}"), None);
+        assert_eq!(fence_filename(""), None);
+    }
 }
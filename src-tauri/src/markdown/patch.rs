@@ -0,0 +1,291 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+
+/// Start tag of a top-level block tagged with `data-block-id`
+static BLOCK_START: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"<(\w+)[^<>]*?\sdata-block-id="([^"]*)"[^<>]*>"#).unwrap());
+
+/// Elements without a closing tag
+const VOID_TAGS: &[&str] = &["hr", "img", "br", "input"];
+
+/// A block whose HTML changed, in place
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangedBlock {
+    /// `data-block-id` of the block in the previous render
+    pub id: String,
+    /// Its id now (differs from `id` when its source changed)
+    pub new_id: String,
+    /// The block's new HTML
+    pub html: String,
+}
+
+/// A block that is new in this render
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AddedBlock {
+    pub id: String,
+    pub html: String,
+    /// The block it follows in the new render; None to insert it first
+    pub after: Option<String>,
+}
+
+/// Block-level difference between two renders of a document
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BlockPatch {
+    /// Block ids of the new render, in document order
+    pub block_ids: Vec<String>,
+    pub changed: Vec<ChangedBlock>,
+    pub added: Vec<AddedBlock>,
+    /// Ids of blocks that are gone
+    pub removed: Vec<String>,
+    /// The whole new HTML, set instead of block changes when content outside
+    /// tagged blocks (front matter, special block placeholders) changed
+    pub html: Option<String>,
+}
+
+/// A top-level block of rendered HTML
+#[derive(Debug, Clone, PartialEq)]
+struct Block<'a> {
+    id: &'a str,
+    html: &'a str,
+}
+
+/// Compares two renders made with block ids and lists the blocks to add,
+/// remove or replace to turn the previous DOM into the new one.
+///
+/// Blocks are matched by id (ids hash the source, so an unchanged block keeps
+/// its id); a removed block directly replaced by a new one is reported as
+/// changed. Content outside tagged blocks can't be patched, so if it differs
+/// the patch carries the full HTML instead.
+pub fn diff_rendered_blocks(previous_html: &str, html: &str) -> BlockPatch {
+    let (previous, previous_rest) = split_blocks(previous_html);
+    let (current, rest) = split_blocks(html);
+    let block_ids = current.iter().map(|block| block.id.to_string()).collect();
+    if previous_rest != rest {
+        return BlockPatch {
+            block_ids,
+            html: Some(html.to_string()),
+            ..Default::default()
+        };
+    }
+
+    let mut patch = BlockPatch {
+        block_ids,
+        ..Default::default()
+    };
+    let matches = matching_blocks(&previous, &current);
+    let (mut old, mut new) = (0, 0);
+    for (matched_old, matched_new) in matches.into_iter().chain([(previous.len(), current.len())]) {
+        let replaced = (matched_old - old).min(matched_new - new);
+        for offset in 0..replaced {
+            patch.changed.push(ChangedBlock {
+                id: previous[old + offset].id.to_string(),
+                new_id: current[new + offset].id.to_string(),
+                html: current[new + offset].html.to_string(),
+            });
+        }
+        patch.removed.extend(
+            previous[old + replaced..matched_old]
+                .iter()
+                .map(|block| block.id.to_string()),
+        );
+        for index in new + replaced..matched_new {
+            patch.added.push(AddedBlock {
+                id: current[index].id.to_string(),
+                html: current[index].html.to_string(),
+                after: index.checked_sub(1).map(|i| current[i].id.to_string()),
+            });
+        }
+
+        if let (Some(before), Some(after)) = (previous.get(matched_old), current.get(matched_new)) {
+            if before.html != after.html {
+                patch.changed.push(ChangedBlock {
+                    id: before.id.to_string(),
+                    new_id: after.id.to_string(),
+                    html: after.html.to_string(),
+                });
+            }
+        }
+        (old, new) = (matched_old + 1, matched_new + 1);
+    }
+
+    patch
+}
+
+/// Splits HTML into its tagged top-level blocks and the untagged content
+/// between them, keyed by the id of the block it follows
+fn split_blocks(html: &str) -> (Vec<Block<'_>>, Vec<(Option<&str>, &str)>) {
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut rest = Vec::new();
+    let mut pos = 0;
+
+    while let Some(caps) = BLOCK_START.captures_at(html, pos) {
+        let start = caps.get(0).unwrap();
+        let tag = caps.get(1).unwrap().as_str();
+        let end = if VOID_TAGS.contains(&tag) {
+            start.end()
+        } else {
+            element_end(html, start.end(), tag)
+        };
+
+        let between = html[pos..start.start()].trim();
+        if !between.is_empty() {
+            rest.push((blocks.last().map(|block| block.id), between));
+        }
+        blocks.push(Block {
+            id: caps.get(2).unwrap().as_str(),
+            html: &html[start.start()..end],
+        });
+        pos = end;
+    }
+    let after = html[pos..].trim();
+    if !after.is_empty() {
+        rest.push((blocks.last().map(|block| block.id), after));
+    }
+
+    (blocks, rest)
+}
+
+/// End of the element whose start tag ends at `from`, counting nested
+/// elements of the same name; the end of `html` if it is never closed
+fn element_end(html: &str, from: usize, tag: &str) -> usize {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut depth = 1;
+    let mut pos = from;
+
+    while let Some(offset) = html[pos..].find('<') {
+        let at = pos + offset;
+        let rest = &html[at..];
+        if rest.starts_with(&close) {
+            depth -= 1;
+            if depth == 0 {
+                return at + close.len();
+            }
+        } else if rest.starts_with(&open)
+            && rest[open.len()..].starts_with(|c: char| c == '>' || c.is_whitespace())
+        {
+            depth += 1;
+        }
+        pos = at + 1;
+    }
+    html.len()
+}
+
+/// Index pairs of blocks with the same id, in order (longest common
+/// subsequence); the unchanged prefix and suffix are matched directly
+fn matching_blocks(previous: &[Block], current: &[Block]) -> Vec<(usize, usize)> {
+    let prefix = previous
+        .iter()
+        .zip(current)
+        .take_while(|(a, b)| a.id == b.id)
+        .count();
+    let suffix = previous[prefix..]
+        .iter()
+        .rev()
+        .zip(current[prefix..].iter().rev())
+        .take_while(|(a, b)| a.id == b.id)
+        .count();
+    let old = &previous[prefix..previous.len() - suffix];
+    let new = &current[prefix..current.len() - suffix];
+
+    // lengths[i][j]: common subsequence length of old[i..] and new[j..]
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i].id == new[j].id {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut matches: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i].id == new[j].id {
+            matches.push((prefix + i, prefix + j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    matches.extend((0..suffix).map(|k| (previous.len() - suffix + k, current.len() - suffix + k)));
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn html(blocks: &[(&str, &str)]) -> String {
+        blocks
+            .iter()
+            .map(|(id, text)| format!("<p data-block-id=\"{}\">{}</p>\n", id, text))
+            .collect()
+    }
+
+    #[test]
+    fn test_one_edited_paragraph() {
+        let previous = html(&[("b-1", "One"), ("b-2", "Two"), ("b-3", "Three")]);
+        let current = html(&[("b-1", "One"), ("b-4", "Two!"), ("b-3", "Three")]);
+        let patch = diff_rendered_blocks(&previous, &current);
+
+        assert_eq!(
+            patch.changed,
+            vec![ChangedBlock {
+                id: "b-2".to_string(),
+                new_id: "b-4".to_string(),
+                html: "<p data-block-id=\"b-4\">Two!</p>".to_string(),
+            }]
+        );
+        assert!(patch.added.is_empty() && patch.removed.is_empty() && patch.html.is_none());
+        assert_eq!(patch.block_ids, vec!["b-1", "b-4", "b-3"]);
+    }
+
+    #[test]
+    fn test_added_and_removed_blocks() {
+        let previous = html(&[("b-1", "One"), ("b-2", "Two"), ("b-3", "Three")]);
+        let current = html(&[("b-5", "Zero"), ("b-1", "One"), ("b-3", "Three")]);
+        let patch = diff_rendered_blocks(&previous, &current);
+
+        assert!(patch.changed.is_empty());
+        assert_eq!(patch.removed, vec!["b-2"]);
+        assert_eq!(patch.added.len(), 1);
+        assert_eq!(patch.added[0].id, "b-5");
+        assert_eq!(patch.added[0].after, None);
+    }
+
+    #[test]
+    fn test_nested_elements_stay_in_their_block() {
+        let previous = "<ul data-block-id=\"b-1\">\n<li>a<ul>\n<li>b</li>\n</ul></li>\n</ul>\n\
+                        <hr data-block-id=\"b-2\" />\n";
+        let current = previous.replace(">b<", ">c<").replace("\"b-1\"", "\"b-3\"");
+        let patch = diff_rendered_blocks(previous, &current);
+
+        assert_eq!(patch.changed.len(), 1);
+        assert!(patch.changed[0].html.ends_with("</li>\n</ul></li>\n</ul>"));
+        assert_eq!(patch.block_ids, vec!["b-3", "b-2"]);
+    }
+
+    #[test]
+    fn test_untagged_change_sends_full_html() {
+        let previous = format!(
+            "{}<div class=\"special-block\" id=\"sb-0\"></div>\n",
+            html(&[("b-1", "A")])
+        );
+        let current = format!(
+            "{}<div class=\"special-block\" id=\"sb-1\"></div>\n",
+            html(&[("b-1", "A")])
+        );
+        let patch = diff_rendered_blocks(&previous, &current);
+
+        assert_eq!(patch.html.as_deref(), Some(current.as_str()));
+        assert!(patch.changed.is_empty());
+        assert!(diff_rendered_blocks(&previous, &previous).html.is_none());
+    }
+}
@@ -0,0 +1,306 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::borrow::Cow;
+
+use super::lines::{next_code_span, source_lines};
+
+/// Stands in for a blank line inside a raw table; an empty comment keeps the
+/// HTML block open and renders as nothing
+const BLANK_LINE_FILLER: &str = "<!-- -->";
+
+/// Something shaped like an HTML start or end tag: `<name ...>`, `</name>`
+static TAG_LIKE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"</?([A-Za-z][A-Za-z0-9-]*)(?:\s[^<>]*)?/?>").unwrap());
+
+/// Where `<...>` is a link destination or reference definition, not a tag
+static LINK_DESTINATION_BEFORE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:\]\(\s*|^\s{0,3}\[[^\]]+\]:\s*)$").unwrap());
+
+/// Element names of HTML, SVG and MathML, lowercase and separated by
+/// whitespace
+const KNOWN_ELEMENTS: &str =
+    "a abbr acronym address applet area article aside audio b base basefont bdi \
+     bdo bgsound big blink blockquote body br button canvas caption center cite \
+     code col colgroup data datalist dd del details dfn dialog dir div dl dt em \
+     embed fieldset figcaption figure font footer form frame frameset h1 h2 h3 \
+     h4 h5 h6 head header hgroup hr html i iframe img input ins isindex kbd \
+     keygen label legend li link listing main map mark marquee menu menuitem \
+     meta meter multicol nav nextid nobr noembed noframes noscript object ol \
+     optgroup option output p param picture plaintext pre progress q rb rp rt \
+     rtc ruby s samp script search section select slot small source spacer span \
+     strike strong style sub summary sup table tbody td template textarea tfoot \
+     th thead time title tr track tt u ul var video wbr xmp altglyph \
+     altglyphdef altglyphitem animate animatecolor animatemotion \
+     animatetransform circle clippath cursor defs desc discard ellipse feblend \
+     fecolormatrix fecomponenttransfer fecomposite feconvolvematrix \
+     fediffuselighting fedisplacementmap fedistantlight fedropshadow feflood \
+     fefunca fefuncb fefuncg fefuncr fegaussianblur feimage femerge femergenode \
+     femorphology feoffset fepointlight fespecularlighting fespotlight fetile \
+     feturbulence filter foreignobject g glyph glyphref hkern image line \
+     lineargradient marker mask metadata mpath path pattern polygon polyline \
+     radialgradient rect set stop svg switch symbol text textpath tref tspan \
+     use view vkern annotation maction maligngroup malignmark math menclose \
+     merror mfenced mfrac mglyph mi mlabeledtr mlongdiv mmultiscripts mn mo \
+     mover mpadded mphantom mprescripts mroot mrow ms mscarries mscarry msgroup \
+     msline mspace msqrt msrow mstack mstyle msub msubsup msup mtable mtd mtext \
+     mtr munder munderover none semantics";
+
+/// Escapes angle-bracket text that isn't a real HTML tag, so `<placeholder>`
+/// or `<T>` shows up as written instead of becoming an unknown element the
+/// browser hides.
+///
+/// HTML, SVG and MathML elements and custom elements (names with a `-`)
+/// are kept, as are autolinks, comments and `<...>` link destinations. Code
+/// spans, fenced code, raw `<pre>` blocks and lines that look like indented
+/// code are left alone.
+pub fn escape_unknown_tags(markdown: &str) -> Cow<'_, str> {
+    if !TAG_LIKE
+        .captures_iter(markdown)
+        .any(|caps| !is_known_element(&caps[1]))
+    {
+        return Cow::Borrowed(markdown);
+    }
+
+    let mut result = String::with_capacity(markdown.len() + 16);
+    let mut indented_code = false;
+    let mut previous_blank = true;
+    for line in source_lines(markdown) {
+        let blank = line.text.trim().is_empty();
+        indented_code = !blank && indent_width(line.text) >= 4 && (previous_blank || indented_code);
+        previous_blank = blank;
+        if line.in_code || indented_code {
+            result.push_str(line.text);
+        } else {
+            let mut pos = 0;
+            while let Some((start, end)) = next_code_span(line.text, pos) {
+                result.push_str(&escape_in(line.text, pos, start));
+                result.push_str(&line.text[start..end]);
+                pos = end;
+            }
+            result.push_str(&escape_in(line.text, pos, line.text.len()));
+        }
+        result.push('\n');
+    }
+    if !markdown.ends_with('\n') {
+        result.pop();
+    }
+    Cow::Owned(result)
+}
+
+/// Escapes the unknown tags in `line[from..to]`; the whole line is needed to
+/// tell link destinations apart
+fn escape_in(line: &str, from: usize, to: usize) -> String {
+    let text = &line[from..to];
+    TAG_LIKE
+        .replace_all(text, |caps: &Captures| {
+            let tag = caps.get(0).unwrap();
+            if is_known_element(&caps[1])
+                || LINK_DESTINATION_BEFORE.is_match(&line[..from + tag.start()])
+            {
+                tag.as_str().to_string()
+            } else {
+                format!("&lt;{}", &tag.as_str()[1..])
+            }
+        })
+        .into_owned()
+}
+
+fn is_known_element(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.contains('-') || KNOWN_ELEMENTS.split_whitespace().any(|known| known == name)
+}
+
+/// Keeps hand-written `<table>` blocks in one piece.
+///
+/// A blank line ends a raw HTML block, so in
+///
+/// ```text
+/// <table>
+///   <tr>
+///
+///     <td>a</td>
+/// ```
+///
+/// the indented `<td>` line after the gap would become an indented code
+/// block showing the tags as text. Blank lines inside a raw table that are
+/// followed by such a line are replaced with an empty comment instead, so
+/// line numbers stay the same. Blank lines before markdown content (e.g.
+/// `**bold**` in a cell) are kept.
+pub fn keep_raw_tables_whole(markdown: &str) -> Cow<'_, str> {
+    if !markdown.contains("<table") && !markdown.contains("<TABLE") {
+        return Cow::Borrowed(markdown);
+    }
+
+    let lines = source_lines(markdown);
+    let mut fill = vec![false; lines.len()];
+    let mut depth = 0usize;
+    for (index, line) in lines.iter().enumerate() {
+        if line.in_code {
+            continue;
+        }
+        let lower = line.text.to_ascii_lowercase();
+        if depth == 0 && !(indent_width(line.text) <= 3 && opens_table(lower.trim_start())) {
+            continue;
+        }
+
+        if line.text.trim().is_empty() {
+            let next = lines[index + 1..]
+                .iter()
+                .find(|next| !next.text.trim().is_empty());
+            fill[index] = next.is_some_and(|next| {
+                indent_width(next.text) >= 4 && next.text.trim_start().starts_with('<')
+            });
+            continue;
+        }
+        depth += lower.matches("<table").count();
+        depth = depth.saturating_sub(lower.matches("</table").count());
+    }
+
+    if !fill.contains(&true) {
+        return Cow::Borrowed(markdown);
+    }
+    let mut result = String::with_capacity(markdown.len() + BLANK_LINE_FILLER.len());
+    for (line, fill) in lines.iter().zip(fill) {
+        result.push_str(if fill { BLANK_LINE_FILLER } else { line.text });
+        result.push('\n');
+    }
+    if !markdown.ends_with('\n') {
+        result.pop();
+    }
+    Cow::Owned(result)
+}
+
+/// `<table` followed by whitespace, `>` or the end of the line
+fn opens_table(lower: &str) -> bool {
+    lower.strip_prefix("<table").is_some_and(
+        |rest| !matches!(rest.chars().next(), Some(c) if c != '>' && !c.is_whitespace()),
+    )
+}
+
+/// Width of a line's indentation, with tabs to the next multiple of four
+fn indent_width(line: &str) -> usize {
+    let mut width = 0;
+    for c in line.chars() {
+        match c {
+            ' ' => width += 1,
+            '\t' => width += 4 - width % 4,
+            _ => break,
+        }
+    }
+    width
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::parser::{render_markdown_html, render_markdown_html_with, HtmlOptions};
+
+    const ASCII_ART: &str = "<pre class=\"diagram\">\n\
+                             +--------+       +--------+\n\
+                             | client | ----> | server |\n\
+                             +--------+       +--------+\n\
+                             \n\
+                             \x20   {--retry--}   ^[x]   [[kbd:Ctrl]]\n\
+                             \n\
+                             \x20       *not emphasis*\n\
+                             </pre>";
+
+    #[test]
+    fn test_ascii_art_pre_unchanged() {
+        let md = format!("Before\n\n{}\n\nAfter", ASCII_ART);
+        let options = HtmlOptions {
+            smart_punctuation: true,
+            kbd_shortcuts: true,
+            inline_footnotes: true,
+            abbreviations: true,
+            criticmarkup: true,
+            ..Default::default()
+        };
+        let html = render_markdown_html_with(&md, &options).html;
+        assert!(html.contains(&format!("{}\n", ASCII_ART)));
+        assert!(html.contains("<p>After</p>"));
+    }
+
+    #[test]
+    fn test_table_with_blank_lines() {
+        let md =
+            "<table>\n  <tr>\n\n    <td>a</td>\n\n    <td>b</td>\n  </tr>\n</table>\n\nAfter\n";
+        let kept = keep_raw_tables_whole(md);
+        assert_eq!(
+            kept,
+            "<table>\n  <tr>\n<!-- -->\n    <td>a</td>\n<!-- -->\n    <td>b</td>\n  </tr>\n</table>\n\nAfter\n"
+        );
+
+        let html = render_markdown_html(md);
+        assert!(!html.contains("<pre"));
+        assert!(!html.contains("&lt;td&gt;"));
+        assert!(html.contains("    <td>a</td>\n"));
+        assert!(html.contains("</table>\n<p>After</p>"));
+    }
+
+    #[test]
+    fn test_markdown_in_cells_is_kept() {
+        let md = "<table><tr><td>\n\n**bold**\n\n</td></tr></table>\n";
+        assert_eq!(keep_raw_tables_whole(md), md);
+        assert!(render_markdown_html(md).contains("<strong>bold</strong>"));
+    }
+
+    #[test]
+    fn test_escape_unknown_tags() {
+        let md = "Fill in <placeholder> and <b>bold</b>, then <https://example.com>.\n\
+                  <foo>\n\n\
+                  `Vec<T>` and <my-widget></my-widget> [doc](<my file.md>)\n\n\
+                  ```rust\nlet v: Vec<T>;\n```\n\n    <indented>\n";
+        let escaped = escape_unknown_tags(md);
+        assert_eq!(
+            escaped,
+            "Fill in &lt;placeholder> and <b>bold</b>, then <https://example.com>.\n\
+             &lt;foo>\n\n\
+             `Vec<T>` and <my-widget></my-widget> [doc](<my file.md>)\n\n\
+             ```rust\nlet v: Vec<T>;\n```\n\n    <indented>\n"
+        );
+
+        let plain = "<div>\n<B>ok</B> <svg><path d=\"M0\"/></svg>\n</div>";
+        assert!(matches!(escape_unknown_tags(plain), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_svg_filter_and_rare_elements_kept() {
+        let svg = "<svg width=\"100\" height=\"100\">\n\
+                   <filter id=\"wobble\">\n\
+                   <feTurbulence baseFrequency=\"0.05\" result=\"noise\"/>\n\
+                   <feDisplacementMap in=\"SourceGraphic\" in2=\"noise\" scale=\"8\"/>\n\
+                   <feMorphology operator=\"dilate\" radius=\"1\"/>\n\
+                   <feComponentTransfer><feFuncA type=\"linear\" slope=\"0.5\"/></feComponentTransfer>\n\
+                   </filter>\n\
+                   <switch><metadata>x</metadata><rect filter=\"url(#wobble)\">\n\
+                   <animate attributeName=\"x\" to=\"10\"/><set attributeName=\"y\" to=\"5\"/>\n\
+                   <animateTransform type=\"rotate\"/></rect></switch>\n\
+                   </svg>\n";
+        assert!(matches!(escape_unknown_tags(svg), Cow::Borrowed(_)));
+
+        let rare = "<big>a</big> <nobr>b</nobr> <marquee>c</marquee> \
+                    <ruby>d<rb>e</rb><rtc>f</rtc></ruby> \
+                    <math><mmultiscripts><mi>X</mi><mprescripts/><none/><mi>a</mi></mmultiscripts></math>";
+        assert!(matches!(escape_unknown_tags(rare), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_unknown_tags_render_as_text() {
+        let html = render_markdown_html("Use <foo> with <b>bold</b>");
+        assert!(html.contains("<p>Use &lt;foo&gt; with <b>bold</b></p>"));
+
+        let options = HtmlOptions {
+            escape_unknown_tags: false,
+            ..Default::default()
+        };
+        let html = render_markdown_html_with("Use <foo> here", &options).html;
+        assert!(html.contains("<foo>"));
+    }
+
+    #[test]
+    fn test_indented_code_outside_tables() {
+        let md = "Text\n\n    <td>code</td>\n";
+        assert_eq!(keep_raw_tables_whole(md), md);
+    }
+}
@@ -0,0 +1,106 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+/// `src`/`href` attributes of images, media sources and links
+static URL_ATTR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(<(?:img|a|source|video|audio)\s[^>]*?\b(?:src|href)=")([^"]*)""#).unwrap()
+});
+
+/// Resolves relative image and link targets against a remote base URL.
+///
+/// `base_url` is the address of the folder the document was published from
+/// (e.g. `https://raw.githubusercontent.com/user/repo/main/docs/`), so
+/// `img/a.png` becomes `.../docs/img/a.png`, `../a.png` goes up a folder and
+/// `/a.png` resolves against the site root. URLs, anchors and other schemes
+/// (`mailto:`, `data:`) are untouched, as is everything when `base_url` isn't
+/// an absolute URL.
+pub fn resolve_remote_paths(html: &str, base_url: &str) -> String {
+    URL_ATTR
+        .replace_all(html, |caps: &Captures| {
+            let url = join_url(base_url, &caps[2]).unwrap_or_else(|| caps[2].to_string());
+            format!("{}{}\"", &caps[1], url)
+        })
+        .into_owned()
+}
+
+/// Joins a relative target onto a base folder URL
+fn join_url(base_url: &str, target: &str) -> Option<String> {
+    let has_scheme = target.find(':').is_some_and(|i| !target[..i].contains('/'));
+    if target.is_empty() || has_scheme || target.starts_with(['#', '?']) || target.starts_with("//")
+    {
+        return None;
+    }
+
+    let authority = base_url.find("://")? + 3;
+    let origin_end = base_url[authority..]
+        .find(['/', '?', '#'])
+        .map_or(base_url.len(), |i| authority + i);
+    let origin = &base_url[..origin_end];
+    let base_dir = base_url[origin_end..].split(['?', '#']).next().unwrap_or("");
+
+    let (path, suffix) = target.split_at(target.find(['?', '#']).unwrap_or(target.len()));
+    let mut segments: Vec<&str> = if path.starts_with('/') {
+        Vec::new()
+    } else {
+        base_dir.split('/').filter(|s| !s.is_empty()).collect()
+    };
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    let mut url = format!("{}/{}", origin, segments.join("/"));
+    if path.ends_with('/') && !segments.is_empty() {
+        url.push('/');
+    }
+    url.push_str(suffix);
+    Some(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "https://raw.githubusercontent.com/user/repo/main/docs/";
+
+    #[test]
+    fn test_relative_paths_resolve_against_base() {
+        let html = r#"<p><img src="img/logo.png" alt="x" /> <a href="../README.md#usage">readme</a> <img src="./a b.png?raw=1"></p>"#;
+        assert_eq!(
+            resolve_remote_paths(html, BASE),
+            "<p><img src=\"https://raw.githubusercontent.com/user/repo/main/docs/img/logo.png\" alt=\"x\" /> \
+             <a href=\"https://raw.githubusercontent.com/user/repo/main/README.md#usage\">readme</a> \
+             <img src=\"https://raw.githubusercontent.com/user/repo/main/docs/a b.png?raw=1\"></p>"
+        );
+    }
+
+    #[test]
+    fn test_root_relative_and_base_without_slash() {
+        assert_eq!(
+            join_url("https://example.com/site/docs", "/assets/a.png").as_deref(),
+            Some("https://example.com/assets/a.png")
+        );
+        assert_eq!(
+            join_url("https://example.com", "a.png").as_deref(),
+            Some("https://example.com/a.png")
+        );
+        assert_eq!(
+            join_url("https://example.com/docs", "../../x/").as_deref(),
+            Some("https://example.com/x/")
+        );
+    }
+
+    #[test]
+    fn test_urls_and_anchors_untouched() {
+        let html = "<a href=\"#top\">t</a><a href=\"mailto:a@b.c\">m</a>\
+                    <img src=\"https://cdn.dev/a.png\"><img src=\"data:image/png;base64,AA\">\
+                    <a href=\"//cdn.dev/x\">p</a>";
+        assert_eq!(resolve_remote_paths(html, BASE), html);
+        assert_eq!(resolve_remote_paths("<img src=\"a.png\">", "docs/"), "<img src=\"a.png\">");
+    }
+}
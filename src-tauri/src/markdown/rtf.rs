@@ -0,0 +1,324 @@
+use comrak::nodes::{AstNode, ListType, NodeValue};
+use comrak::Arena;
+use std::borrow::Cow;
+
+use super::footnotes::expand_inline_footnotes;
+use super::parser::{get_options, HtmlOptions};
+
+/// Document header: a sans-serif body font, a monospace font for code and
+/// a link color
+const RTF_HEADER: &str = "{\\rtf1\\ansi\\ansicpg1252\\deff0\n\
+                          {\\fonttbl{\\f0\\fswiss Helvetica;}{\\f1\\fmodern Courier New;}}\n\
+                          {\\colortbl;\\red3\\green102\\blue214;}\n\
+                          \\f0\\fs24\n";
+
+/// Heading font sizes in half-points, H1 to H6 (body text is 12pt)
+const HEADING_SIZES: [u32; 6] = [36, 32, 28, 26, 24, 24];
+
+/// Indentation per list or quote level, in twips (half an inch)
+const INDENT_STEP: u32 = 720;
+
+/// Width of each table column, in twips
+const TABLE_COLUMN_WIDTH: u32 = 2400;
+
+/// Renders markdown as Rich Text Format for pasting into word processors.
+///
+/// Covers headings, paragraphs, bold/italic/strikethrough, inline and block
+/// code (in a monospace font), links, lists, task lists, quotes, rules,
+/// footnotes and tables (as simple fixed-width rows). Special blocks like
+/// mermaid diagrams stay as their source in a code block; raw HTML and front
+/// matter are dropped, and images are replaced by their alt text.
+pub fn markdown_to_rtf(markdown: &str, html_options: &HtmlOptions) -> String {
+    let mut options = get_options();
+    options.parse.smart = html_options.smart_punctuation;
+
+    let markdown = if html_options.inline_footnotes {
+        Cow::Owned(expand_inline_footnotes(markdown))
+    } else {
+        Cow::Borrowed(markdown)
+    };
+
+    let arena = Arena::new();
+    let root = comrak::parse_document(&arena, &markdown, &options);
+    let mut writer = RtfWriter {
+        out: String::from(RTF_HEADER),
+        hard_line_breaks: html_options.hard_line_breaks,
+        ..Default::default()
+    };
+    writer.children(root);
+    writer.out.push('}');
+    writer.out
+}
+
+/// Walks the document tree, writing RTF
+#[derive(Default)]
+struct RtfWriter {
+    out: String,
+    hard_line_breaks: bool,
+    /// Current left indent, in twips
+    indent: u32,
+    /// Next number of each enclosing list (None for bullet lists)
+    lists: Vec<Option<usize>>,
+    /// List marker for the first paragraph of the current item
+    marker: Option<String>,
+    /// Inside a table cell, where paragraphs are written inline
+    in_table: bool,
+}
+
+impl RtfWriter {
+    fn children<'a>(&mut self, node: &'a AstNode<'a>) {
+        for child in node.children() {
+            self.node(child);
+        }
+    }
+
+    fn node<'a>(&mut self, node: &'a AstNode<'a>) {
+        let data = node.data.borrow();
+        match &data.value {
+            NodeValue::FrontMatter(_) | NodeValue::HtmlBlock(_) | NodeValue::HtmlInline(_) => {}
+            NodeValue::Heading(heading) => {
+                let size = HEADING_SIZES[(heading.level as usize).clamp(1, 6) - 1];
+                self.out
+                    .push_str(&format!("{{\\pard\\sb240\\sa120\\keepn\\b\\fs{} ", size));
+                self.children(node);
+                self.out.push_str("\\par}\n");
+            }
+            NodeValue::Paragraph if self.in_table => self.children(node),
+            NodeValue::Paragraph => {
+                self.start_paragraph("");
+                self.children(node);
+                self.out.push_str("\\par}\n");
+            }
+            NodeValue::CodeBlock(block) => {
+                self.start_paragraph("\\f1\\fs20");
+                let lines: Vec<String> = block
+                    .literal
+                    .trim_end_matches('\n')
+                    .lines()
+                    .map(escape_rtf)
+                    .collect();
+                self.out.push_str(&lines.join("\\line "));
+                self.out.push_str("\\par}\n");
+            }
+            NodeValue::BlockQuote => {
+                self.indent += INDENT_STEP;
+                self.children(node);
+                self.indent -= INDENT_STEP;
+            }
+            NodeValue::List(list) => {
+                self.lists.push(match list.list_type {
+                    ListType::Bullet => None,
+                    ListType::Ordered => Some(list.start),
+                });
+                self.indent += INDENT_STEP;
+                self.children(node);
+                self.indent -= INDENT_STEP;
+                self.lists.pop();
+            }
+            NodeValue::Item(_) => {
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}.", *number - 1)
+                    }
+                    _ => "\\u8226?".to_string(),
+                };
+                self.list_item(node, marker);
+            }
+            NodeValue::TaskItem(checked) => {
+                let marker = if checked.is_some() {
+                    "\\u9745?"
+                } else {
+                    "\\u9744?"
+                };
+                self.list_item(node, marker.to_string());
+            }
+            NodeValue::FootnoteDefinition(definition) => {
+                let marker = format!("[{}]", escape_rtf(&definition.name));
+                self.list_item(node, marker);
+            }
+            NodeValue::ThematicBreak => {
+                self.out
+                    .push_str("{\\pard\\sa120\\brdrb\\brdrs\\brdrw10\\brsp20 \\par}\n");
+            }
+            NodeValue::Table(_) => {
+                self.in_table = true;
+                self.children(node);
+                self.in_table = false;
+                self.out.push_str("\\pard\n");
+            }
+            NodeValue::TableRow(header) => {
+                self.out.push_str("\\trowd\\trgaph108");
+                for column in 1..=node.children().count() as u32 {
+                    self.out
+                        .push_str(&format!("\\cellx{}", column * TABLE_COLUMN_WIDTH));
+                }
+                self.out.push('\n');
+                for cell in node.children() {
+                    self.out.push_str(if *header {
+                        "\\pard\\intbl{\\b "
+                    } else {
+                        "\\pard\\intbl{"
+                    });
+                    self.children(cell);
+                    self.out.push_str("}\\cell\n");
+                }
+                self.out.push_str("\\row\n");
+            }
+            NodeValue::Text(text) => self.out.push_str(&escape_rtf(text)),
+            NodeValue::Code(code) => {
+                self.out.push_str("{\\f1 ");
+                self.out.push_str(&escape_rtf(&code.literal));
+                self.out.push('}');
+            }
+            NodeValue::ShortCode(code) => self.out.push_str(&escape_rtf(&code.emoji)),
+            NodeValue::SoftBreak if self.hard_line_breaks => self.out.push_str("\\line "),
+            NodeValue::SoftBreak => self.out.push(' '),
+            NodeValue::LineBreak => self.out.push_str("\\line "),
+            NodeValue::Emph => self.group(node, "\\i"),
+            NodeValue::Strong => self.group(node, "\\b"),
+            NodeValue::Strikethrough => self.group(node, "\\strike"),
+            NodeValue::Superscript => self.group(node, "\\super"),
+            NodeValue::Link(link) => {
+                self.out.push_str(&format!(
+                    "{{\\field{{\\*\\fldinst{{HYPERLINK \"{}\"}}}}{{\\fldrslt{{\\ul\\cf1 ",
+                    escape_rtf(&link.url.replace('"', "%22"))
+                ));
+                self.children(node);
+                self.out.push_str("}}}");
+            }
+            NodeValue::FootnoteReference(reference) => {
+                self.out
+                    .push_str(&format!("{{\\super [{}]}}", escape_rtf(&reference.name)));
+            }
+            // Images become their alt text; anything else its content
+            _ => self.children(node),
+        }
+    }
+
+    /// Opens a paragraph at the current indent with the given formatting,
+    /// with the pending list marker hanging in front of it
+    fn start_paragraph(&mut self, controls: &str) {
+        self.out.push_str("{\\pard\\sa120");
+        self.out.push_str(controls);
+        match self.marker.take() {
+            Some(marker) => self.out.push_str(&format!(
+                "\\li{}\\fi-360\\tx{} {}\\tab ",
+                self.indent, self.indent, marker
+            )),
+            None if self.indent > 0 => self.out.push_str(&format!("\\li{} ", self.indent)),
+            None => self.out.push(' '),
+        }
+    }
+
+    fn list_item<'a>(&mut self, node: &'a AstNode<'a>, marker: String) {
+        self.marker = Some(marker);
+        self.children(node);
+        self.marker = None;
+    }
+
+    /// Writes the node's content in a group with the given formatting
+    fn group<'a>(&mut self, node: &'a AstNode<'a>, control: &str) {
+        self.out.push('{');
+        self.out.push_str(control);
+        self.out.push(' ');
+        self.children(node);
+        self.out.push('}');
+    }
+}
+
+/// Escapes text for RTF: braces and backslashes get a backslash, and
+/// non-ASCII characters become `\uN?` escapes (UTF-16, so emoji take two)
+fn escape_rtf(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\t' => escaped.push_str("\\tab "),
+            '\n' | '\r' => escaped.push(' '),
+            c if c.is_ascii() => escaped.push(c),
+            c => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    escaped.push_str(&format!("\\u{}?", *unit as i16));
+                }
+            }
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether every `{` is closed, ignoring escaped braces
+    fn balanced(rtf: &str) -> bool {
+        let mut depth = 0i32;
+        let mut chars = rtf.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            if depth < 0 {
+                return false;
+            }
+        }
+        depth == 0
+    }
+
+    #[test]
+    fn test_heading_and_bold() {
+        let rtf = markdown_to_rtf("# Title\n\nSome **bold** text.", &HtmlOptions::default());
+
+        assert!(rtf.starts_with("{\\rtf1\\ansi"));
+        assert!(rtf.contains("{\\pard\\sb240\\sa120\\keepn\\b\\fs36 Title\\par}"));
+        assert!(rtf.contains("{\\pard\\sa120 Some {\\b bold} text.\\par}"));
+        assert!(rtf.ends_with('}'));
+        assert!(balanced(&rtf));
+    }
+
+    #[test]
+    fn test_lists_code_and_links() {
+        let md = "1. First\n2. Second\n\n- [x] Done\n\n\
+                  Run `cargo {x}` or see [docs](https://example.com).\n\n\
+                  ```mermaid\ngraph TD\n  A-->B\n```\n";
+        let rtf = markdown_to_rtf(md, &HtmlOptions::default());
+
+        assert!(rtf.contains("\\li720\\fi-360\\tx720 1.\\tab First\\par}"));
+        assert!(rtf.contains("2.\\tab Second"));
+        assert!(rtf.contains("\\u9745?\\tab Done"));
+        assert!(rtf.contains("{\\f1 cargo \\{x\\}}"));
+        assert!(rtf.contains(
+            "{\\field{\\*\\fldinst{HYPERLINK \"https://example.com\"}}{\\fldrslt{\\ul\\cf1 docs}}}"
+        ));
+        assert!(rtf.contains("\\f1\\fs20 graph TD\\line   A-->B\\par}"));
+        assert!(balanced(&rtf));
+    }
+
+    #[test]
+    fn test_table_rows() {
+        let rtf = markdown_to_rtf("| A | B |\n|---|---|\n| 1 | 2 |\n", &HtmlOptions::default());
+
+        assert_eq!(rtf.matches("\\row").count(), 2);
+        assert!(rtf.contains("\\trowd\\trgaph108\\cellx2400\\cellx4800"));
+        assert!(rtf.contains("\\pard\\intbl{\\b A}\\cell"));
+        assert!(rtf.contains("\\pard\\intbl{1}\\cell"));
+        assert!(balanced(&rtf));
+    }
+
+    #[test]
+    fn test_escape_rtf() {
+        assert_eq!(escape_rtf("a\\b {c}"), "a\\\\b \\{c\\}");
+        assert_eq!(escape_rtf("café"), "caf\\u233?");
+        assert_eq!(escape_rtf("😀"), "\\u-10179?\\u-8704?");
+    }
+}
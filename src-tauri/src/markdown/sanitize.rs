@@ -0,0 +1,135 @@
+use ammonia::Builder;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::borrow::Cow;
+
+use super::images::CONSTRAINED_IMAGE_STYLE;
+
+/// Elements MarkViewer emits on top of ammonia's defaults
+const EXTRA_TAGS: &[&str] = &[
+    "div", "span", "input", "details", "summary", "section", "mark", "kbd", "abbr", "math",
+    "mi", "mn", "mo", "ms", "mtext", "mspace", "mrow", "msup", "msub", "msubsup", "mfrac",
+    "msqrt", "mroot", "mover", "munder", "munderover", "mtable", "mtr", "mtd", "mstyle",
+    "mpadded", "mphantom", "menclose", "semantics", "annotation", "button",
+];
+
+/// Attributes allowed on any element
+const GENERIC_ATTRIBUTES: &[&str] = &[
+    "class", "id", "role", "display", "mathvariant", "stretchy", "fence", "separator",
+    "accent", "columnalign",
+];
+
+/// The only inline style allowed: a color swatch's background
+static SWATCH_STYLE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^background:#[0-9a-fA-F]{3,8}$").unwrap());
+
+/// Sanitizer allowing MarkViewer's own markup (special-block placeholders,
+/// data attributes, tasklist checkboxes, MathML) while removing scripts,
+/// event handlers and dangerous URLs.
+static SANITIZER: Lazy<Builder<'static>> = Lazy::new(|| {
+    let mut builder = Builder::default();
+    builder
+        .add_tags(EXTRA_TAGS)
+        .add_generic_attributes(GENERIC_ATTRIBUTES)
+        .add_generic_attribute_prefixes(&["data-", "aria-"])
+        .add_tag_attributes("input", &["type", "checked", "disabled"])
+        .add_tag_attributes("pre", &["lang"])
+        .add_tag_attributes("details", &["open"])
+        .add_tag_attributes("button", &["type"])
+        .add_tag_attributes("div", &["hidden"])
+        .add_tag_attributes("span", &["style"])
+        .add_tag_attributes("img", &["style"])
+        .add_url_schemes(&["asset", "file", "data"])
+        .link_rel(None)
+        .attribute_filter(|element, attribute, value| {
+            // data: URLs are only allowed for images
+            let is_url = matches!(attribute, "href" | "src");
+            if is_url && value.trim_start().to_lowercase().starts_with("data:") {
+                let image = element == "img" && value.trim_start().starts_with("data:image/");
+                if !image || value.contains("image/svg") {
+                    return None;
+                }
+            }
+            // Inline styles are only kept for color swatches and constrained images
+            let constrained = element == "img" && value == CONSTRAINED_IMAGE_STYLE;
+            if attribute == "style" && !SWATCH_STYLE.is_match(value) && !constrained {
+                return None;
+            }
+            Some(Cow::Borrowed(value))
+        });
+    builder
+});
+
+/// Removes dangerous HTML (scripts, `on*` handlers, `javascript:` URLs) from
+/// rendered output, keeping the elements MarkViewer itself generates.
+pub fn sanitize_html(html: &str) -> String {
+    SANITIZER.clean(html).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_removes_scripts_and_handlers() {
+        let html = r#"<p onclick="evil()">Hi<script>alert(1)</script></p><a href="javascript:alert(1)">x</a>"#;
+        let clean = sanitize_html(html);
+        assert!(!clean.contains("script"));
+        assert!(!clean.contains("onclick"));
+        assert!(!clean.contains("javascript:"));
+        assert!(clean.contains("<p>Hi</p>"));
+    }
+
+    #[test]
+    fn test_keeps_generated_markup() {
+        let html = concat!(
+            r#"<div class="special-block mermaid" id="special-block-0" data-block-type="mermaid"></div>"#,
+            r#"<ul><li><input type="checkbox" checked="" disabled="" /> Done</li></ul>"#,
+            r#"<pre lang="rust"><code><span class="source rust">fn</span></code></pre>"#,
+            r##"<h1><a href="#heading-x" aria-hidden="true" class="anchor" id="heading-x"></a>X</h1>"##,
+        );
+        let clean = sanitize_html(html);
+        assert!(clean.contains(r#"data-block-type="mermaid""#));
+        assert!(clean.contains(r#"id="special-block-0""#));
+        assert!(clean.contains(r#"class="special-block mermaid""#));
+        assert!(clean.contains(r#"type="checkbox""#));
+        assert!(clean.contains(r#"<span class="source rust">"#));
+        assert!(clean.contains(r#"aria-hidden="true""#));
+    }
+
+    #[test]
+    fn test_keeps_code_tabs() {
+        let html = concat!(
+            r#"<div class="code-tabs"><div class="code-tab-list" role="tablist">"#,
+            r#"<button type="button" class="code-tab" role="tab" data-tab="1">sh</button></div>"#,
+            r#"<div class="code-tab-panel" role="tabpanel" data-tab="1" hidden=""></div></div>"#,
+        );
+        let clean = sanitize_html(html);
+        assert!(clean.contains(r#"<button type="button" class="code-tab" role="tab""#));
+        assert!(clean.contains(r#"data-tab="1" hidden="""#));
+    }
+
+    #[test]
+    fn test_data_urls_only_for_images() {
+        let html = r#"<img src="data:image/png;base64,AA=="><a href="data:text/html,<b>x</b>">x</a>"#;
+        let clean = sanitize_html(html);
+        assert!(clean.contains("data:image/png;base64,AA=="));
+        assert!(!clean.contains("data:text/html"));
+    }
+
+    #[test]
+    fn test_only_swatch_styles_kept() {
+        let html = r#"<span class="color-swatch" style="background:#ff0000"></span><span style="background:url(x)">a</span><p style="color:red">b</p>"#;
+        let clean = sanitize_html(html);
+        assert!(clean.contains(r#"style="background:#ff0000""#));
+        assert!(!clean.contains("url(x)"));
+        assert!(!clean.contains("color:red"));
+
+        let image = sanitize_html(concat!(
+            r#"<img src="a.png" style="max-width:100%;height:auto">"#,
+            r#"<img src="b.png" style="position:fixed">"#
+        ));
+        assert!(image.contains(r#"style="max-width:100%;height:auto""#));
+        assert!(!image.contains("position:fixed"));
+    }
+}
@@ -0,0 +1,155 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+use super::parser::escape_html;
+
+/// Tags and comments; everything between them is text
+pub(super) static TAG: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<!--.*?-->|<[^>]*>").unwrap());
+
+/// Elements whose text is never highlighted
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "textarea", "svg", "math"];
+
+/// Elements skipped unless code highlighting is requested
+const CODE_ELEMENTS: &[&str] = &["pre", "code"];
+
+/// Wraps occurrences of the search terms in rendered HTML with
+/// `<mark class="search-hit">`.
+///
+/// Matching is case-insensitive and only touches text between tags, so tag
+/// names, attributes and entities are left intact. Text inside `<pre>`/`<code>`
+/// is skipped unless `include_code` is set. Longer terms win where terms overlap.
+pub fn mark_search_hits(html: &str, terms: &[String], include_code: bool) -> String {
+    let Some(pattern) = terms_pattern(terms) else {
+        return html.to_string();
+    };
+
+    let mut result = String::with_capacity(html.len());
+    let mut skip_depth = 0usize;
+    let mut last = 0;
+
+    for tag in TAG.find_iter(html) {
+        push_text(&mut result, &html[last..tag.start()], &pattern, skip_depth == 0);
+        result.push_str(tag.as_str());
+        last = tag.end();
+
+        let (name, closing) = tag_name(tag.as_str());
+        let skipped = SKIPPED_ELEMENTS.contains(&name.as_str())
+            || (!include_code && CODE_ELEMENTS.contains(&name.as_str()));
+        if skipped && !tag.as_str().ends_with("/>") {
+            if closing {
+                skip_depth = skip_depth.saturating_sub(1);
+            } else {
+                skip_depth += 1;
+            }
+        }
+    }
+    push_text(&mut result, &html[last..], &pattern, skip_depth == 0);
+
+    result
+}
+
+/// One case-insensitive alternation of the escaped terms, longest first. An
+/// entity alternative keeps matches from landing inside `&amp;` and friends.
+fn terms_pattern(terms: &[String]) -> Option<Regex> {
+    let mut terms: Vec<String> = terms
+        .iter()
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .map(|t| regex::escape(&escape_html(t)))
+        .collect();
+    if terms.is_empty() {
+        return None;
+    }
+    terms.sort_by_key(|t| std::cmp::Reverse(t.len()));
+    terms.dedup();
+
+    Regex::new(&format!("(?i)({})|&#?[[:alnum:]]+;", terms.join("|"))).ok()
+}
+
+fn push_text(result: &mut String, text: &str, pattern: &Regex, highlight: bool) {
+    if !highlight || text.is_empty() {
+        result.push_str(text);
+        return;
+    }
+    let marked = pattern.replace_all(text, |caps: &Captures| match caps.get(1) {
+        Some(hit) => format!("<mark class=\"search-hit\">{}</mark>", hit.as_str()),
+        None => caps[0].to_string(),
+    });
+    result.push_str(&marked);
+}
+
+/// Lowercase element name and whether the tag closes it (`</x>`)
+pub(super) fn tag_name(tag: &str) -> (String, bool) {
+    let inner = tag.trim_start_matches('<');
+    let closing = inner.starts_with('/');
+    let name = inner
+        .trim_start_matches('/')
+        .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    (name, closing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(terms: &[&str]) -> Vec<String> {
+        terms.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_marks_terms_case_insensitively() {
+        let html = "<p>Install the CLI, then install plugins.</p>";
+        let marked = mark_search_hits(html, &terms(&["install", "plugin"]), false);
+
+        assert_eq!(
+            marked,
+            "<p><mark class=\"search-hit\">Install</mark> the CLI, then \
+             <mark class=\"search-hit\">install</mark> <mark class=\"search-hit\">plugin</mark>s.</p>"
+        );
+    }
+
+    #[test]
+    fn test_skips_tags_attributes_and_entities() {
+        let html = "<a href=\"#amp\" title=\"amp\">amp &amp; co</a>";
+        let marked = mark_search_hits(html, &terms(&["amp"]), false);
+
+        assert_eq!(
+            marked,
+            "<a href=\"#amp\" title=\"amp\"><mark class=\"search-hit\">amp</mark> &amp; co</a>"
+        );
+    }
+
+    #[test]
+    fn test_terms_with_special_characters() {
+        let html = "<p>R&amp;D (beta)</p>";
+        let marked = mark_search_hits(html, &terms(&["r&d", "(beta)"]), false);
+
+        assert_eq!(
+            marked,
+            "<p><mark class=\"search-hit\">R&amp;D</mark> <mark class=\"search-hit\">(beta)</mark></p>"
+        );
+    }
+
+    #[test]
+    fn test_code_is_skipped_unless_included() {
+        let html = "<p>run <code>run</code></p><pre><code><span>run</span></code></pre><p>run</p>";
+
+        let marked = mark_search_hits(html, &terms(&["run"]), false);
+        assert_eq!(marked.matches("search-hit").count(), 2);
+        assert!(marked.contains("<code>run</code>"));
+        assert!(marked.ends_with("<p><mark class=\"search-hit\">run</mark></p>"));
+
+        let marked = mark_search_hits(html, &terms(&["run"]), true);
+        assert_eq!(marked.matches("search-hit").count(), 4);
+    }
+
+    #[test]
+    fn test_no_terms_leaves_html_unchanged() {
+        let html = "<p>text</p>";
+        assert_eq!(mark_search_hits(html, &terms(&["", "  "]), false), html);
+    }
+}
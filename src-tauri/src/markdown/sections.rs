@@ -0,0 +1,104 @@
+use super::lines::{atx_heading, source_lines};
+
+/// A heading section: the heading line and everything up to the next heading
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    /// Heading level (0 for content before the first heading)
+    pub level: u8,
+    /// Heading text (empty for content before the first heading)
+    pub title: String,
+    /// 1-based first line (the heading line)
+    pub start_line: usize,
+    /// 1-based last line
+    pub end_line: usize,
+    /// Source text of the section, including the heading line
+    pub content: String,
+}
+
+/// Splits markdown into sections at every ATX heading outside fenced code.
+///
+/// Content before the first heading becomes a level-0 section (omitted when blank).
+pub fn split_sections(markdown: &str) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut current = Section {
+        level: 0,
+        title: String::new(),
+        start_line: 1,
+        end_line: 0,
+        content: String::new(),
+    };
+
+    for line in source_lines(markdown) {
+        let heading = if line.in_code { None } else { atx_heading(line.text) };
+
+        if let Some((level, title)) = heading {
+            if current.level > 0 || !current.content.trim().is_empty() {
+                sections.push(current);
+            }
+            current = Section {
+                level,
+                title: title.to_string(),
+                start_line: line.number,
+                end_line: line.number,
+                content: String::new(),
+            };
+        }
+
+        current.content.push_str(line.text);
+        current.content.push('\n');
+        current.end_line = line.number;
+    }
+
+    if current.level > 0 || !current.content.trim().is_empty() {
+        sections.push(current);
+    }
+
+    sections
+}
+
+/// Keeps only the sections whose text (heading included) contains `query`,
+/// compared case-insensitively. Returns the joined source of the kept sections.
+pub fn filter_sections(markdown: &str, query: &str) -> String {
+    let query = query.to_lowercase();
+    split_sections(markdown)
+        .into_iter()
+        .filter(|section| section.content.to_lowercase().contains(&query))
+        .map(|section| section.content)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_sections() {
+        let md = "Intro\n\n# One\nText\n```\n# not a heading\n```\n## Two\nMore";
+        let sections = split_sections(md);
+
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[0].level, 0);
+        assert_eq!(sections[0].content, "Intro\n\n");
+        assert_eq!(sections[1].title, "One");
+        assert_eq!((sections[1].start_line, sections[1].end_line), (3, 7));
+        assert!(sections[1].content.contains("# not a heading"));
+        assert_eq!(sections[2].level, 2);
+        assert_eq!(sections[2].content, "## Two\nMore\n");
+    }
+
+    #[test]
+    fn test_filter_sections() {
+        let md = "# Install\nRun cargo\n# Usage\nOpen a FILE\n# License\nMIT";
+        assert_eq!(filter_sections(md, "file"), "# Usage\nOpen a FILE\n");
+        assert_eq!(filter_sections(md, "install"), "# Install\nRun cargo\n");
+        assert_eq!(filter_sections(md, "missing"), "");
+    }
+
+    #[test]
+    fn test_no_preamble() {
+        let sections = split_sections("\n# Only\n");
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].title, "Only");
+    }
+}
@@ -0,0 +1,97 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+/// A link with only an `href`, as emitted for autolinks
+static LINK: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<a href="([^"]*)">([^<]*)</a>"#).unwrap());
+
+/// Autolinks whose text is longer than this get a shortened label
+pub const SHORT_URL_LENGTH: usize = 40;
+
+/// Shortens the label of long autolinked URLs to the domain and the start
+/// of the path, e.g. `github.com/rust-lang/rust/issues/1…`.
+///
+/// The `href` keeps the full URL, which also goes into the `title` for hover.
+/// Only links whose text is their own URL (`https://…` or `www.…`) are
+/// touched, so `[text](url)` links keep their text.
+pub fn shorten_autolinks(html: &str) -> String {
+    if !html.contains("<a href=") {
+        return html.to_string();
+    }
+
+    LINK.replace_all(html, |caps: &Captures| {
+        let (href, text) = (&caps[1], &caps[2]);
+        let is_autolink =
+            text == href || (text.starts_with("www.") && href == format!("http://{}", text));
+        if !is_autolink || text.chars().count() <= SHORT_URL_LENGTH {
+            return caps[0].to_string();
+        }
+        format!(
+            "<a href=\"{}\" title=\"{}\">{}</a>",
+            href,
+            href,
+            short_label(text)
+        )
+    })
+    .into_owned()
+}
+
+/// The URL without its scheme and `www.`, cut to [`SHORT_URL_LENGTH`] with
+/// an ellipsis. `text` is HTML-escaped, so entities are never cut in half.
+fn short_label(text: &str) -> String {
+    let without_scheme = text.split_once("://").map_or(text, |(_, rest)| rest);
+    let label = without_scheme
+        .strip_prefix("www.")
+        .unwrap_or(without_scheme);
+    if label.chars().count() <= SHORT_URL_LENGTH {
+        return label.to_string();
+    }
+
+    let cut = label
+        .char_indices()
+        .nth(SHORT_URL_LENGTH)
+        .map_or(label.len(), |(index, _)| index);
+    let mut short = &label[..cut];
+    if let Some(amp) = short.rfind('&') {
+        if !short[amp..].contains(';') {
+            short = &short[..amp];
+        }
+    }
+    format!("{}…", short)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortens_long_autolinks() {
+        let url = "https://www.example.com/docs/guides/getting-started/installation?tab=linux";
+        let html = format!("<p>See <a href=\"{}\">{}</a>.</p>", url, url);
+        assert_eq!(
+            shorten_autolinks(&html),
+            format!(
+                "<p>See <a href=\"{}\" title=\"{}\">example.com/docs/guides/getting-started/…</a>.</p>",
+                url, url
+            )
+        );
+
+        let www = "www.example.com/a/very/long/path/that/keeps/going/on";
+        let html = format!("<a href=\"http://{}\">{}</a>", www, www);
+        assert!(shorten_autolinks(&html).contains(">example.com/a/very/long/path/that/keeps/…</a>"));
+    }
+
+    #[test]
+    fn test_leaves_short_and_labelled_links() {
+        let html = "<a href=\"https://example.com/a\">https://example.com/a</a> \
+                    <a href=\"https://example.com/docs/guides/getting-started/installation\">the guide</a>";
+        assert_eq!(shorten_autolinks(html), html);
+    }
+
+    #[test]
+    fn test_never_cuts_entities() {
+        assert_eq!(
+            short_label("https://example.com/search?query=rust-language&amp;page=2"),
+            "example.com/search?query=rust-language…"
+        );
+    }
+}
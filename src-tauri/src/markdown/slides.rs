@@ -0,0 +1,66 @@
+use super::front_matter::extract_front_matter;
+use super::lines::source_lines;
+
+/// Slide separator used when none is configured: a `---` thematic break
+pub const DEFAULT_SLIDE_SEPARATOR: &str = "---";
+
+/// Splits a presentation into the markdown of each slide.
+///
+/// A slide ends at a line that is exactly `separator` (trailing whitespace
+/// aside) starting at column 0, outside fenced code. The front matter is never
+/// a separator; it stays at the top of the first slide. Blank slides, e.g.
+/// from a trailing separator, are dropped.
+pub fn split_slides(markdown: &str, separator: &str) -> Vec<String> {
+    let (_, body) = extract_front_matter(markdown);
+    let front = &markdown[..markdown.len() - body.len()];
+
+    let mut slides = Vec::new();
+    let mut current = front.to_string();
+    for line in source_lines(body) {
+        if !line.in_code && line.text.trim_end() == separator {
+            slides.push(std::mem::take(&mut current));
+            continue;
+        }
+        current.push_str(line.text);
+        current.push('\n');
+    }
+    slides.push(current);
+
+    slides.retain(|slide| !extract_front_matter(slide).1.trim().is_empty());
+    slides
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_splits_on_column_zero_rules() {
+        let md = "# One\n\n---\n\n# Two\n\n```\n---\n```\n  ---\n\n---\n# Three\n---\n";
+        assert_eq!(
+            split_slides(md, DEFAULT_SLIDE_SEPARATOR),
+            vec![
+                "# One\n\n",
+                "\n# Two\n\n```\n---\n```\n  ---\n\n",
+                "# Three\n"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_front_matter_stays_on_first_slide() {
+        let md = "---\ntitle: Talk\n---\n# Intro\n---\n# End";
+        assert_eq!(
+            split_slides(md, DEFAULT_SLIDE_SEPARATOR),
+            vec!["---\ntitle: Talk\n---\n# Intro\n", "# End\n"]
+        );
+    }
+
+    #[test]
+    fn test_custom_separator() {
+        assert_eq!(
+            split_slides("A\n---\nstill A\n***\nB", "***"),
+            vec!["A\n---\nstill A\n", "B\n"]
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Prefix for every generated heading id
+pub const HEADING_ID_PREFIX: &str = "heading-";
+
+/// Prefixes of the ids given to special block placeholders and to list
+/// items and tables; unprefixed GitHub ids starting with one of these get
+/// the heading prefix instead so they cannot collide
+const RESERVED_ID_PREFIXES: [&str; 2] = ["special-block-", "block-"];
+
+/// Characters dropped from slugs (same set as comrak's anchorizer)
+static REJECTED_CHARS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[^\p{L}\p{M}\p{Nd}\p{Nl}\p{Pc} -]").unwrap());
+
+/// Characters GitHub drops from heading anchors: punctuation and symbols
+/// (emoji included) other than `-` and `_`
+static GITHUB_REJECTED_CHARS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[^\p{L}\p{M}\p{N}_ -]").unwrap());
+
+/// Heading anchors emitted by comrak's header_ids extension (the prefix is
+/// only on the id, not the href)
+static HEADING_ANCHOR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r##"(?s)<a href="#[^"]*" aria-hidden="true" class="anchor" id="heading-[^"]*"></a>(.*?)(</h[1-6]>)"##,
+    )
+    .unwrap()
+});
+
+static HTML_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]*>").unwrap());
+
+/// Longest emoji sequence tried (ZWJ families run to 11 code points)
+const MAX_EMOJI_CHARS: usize = 11;
+
+/// How emoji in heading text are represented in generated ids
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmojiPolicy {
+    /// Drop emoji ("🚀 Launch" -> "launch")
+    #[default]
+    Strip,
+    /// Keep emoji, percent-encoded ("🚀 Launch" -> "%F0%9F%9A%80-launch")
+    Keep,
+    /// Replace emoji with their shortcode ("🚀 Launch" -> "rocket-launch")
+    Shortcode,
+}
+
+/// Generates unique heading slugs for one document.
+///
+/// Repeated slugs get a numeric suffix ("intro", "intro-1", ...), matching
+/// comrak. Every consumer of heading ids should go through this type so links
+/// resolve regardless of the emoji policy.
+#[derive(Debug, Default)]
+pub struct Slugger {
+    policy: EmojiPolicy,
+    /// Slug like GitHub and leave out the heading prefix
+    github: bool,
+    used: HashSet<String>,
+}
+
+impl Slugger {
+    pub fn new(policy: EmojiPolicy) -> Self {
+        Self {
+            policy,
+            github: false,
+            used: HashSet::new(),
+        }
+    }
+
+    /// A slugger producing the ids GitHub gives headings (see
+    /// [`github_slug`]), so `#my-heading` links work on both
+    pub fn github() -> Self {
+        Self {
+            github: true,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the next unique slug for `text` (without the heading prefix)
+    pub fn slug(&mut self, text: &str) -> String {
+        let base = if self.github {
+            github_slug(text)
+        } else {
+            slugify(text, self.policy)
+        };
+        let mut candidate = base.clone();
+        let mut suffix = 0;
+        while self.used.contains(&candidate) {
+            suffix += 1;
+            candidate = format!("{}-{}", base, suffix);
+        }
+        self.used.insert(candidate.clone());
+        candidate
+    }
+
+    /// Returns the next unique heading id (with the heading prefix, except
+    /// for GitHub ids that do not look like generated block ids)
+    pub fn heading_id(&mut self, text: &str) -> String {
+        let slug = self.slug(text);
+        let reserved = RESERVED_ID_PREFIXES
+            .iter()
+            .any(|prefix| slug.starts_with(prefix));
+        if self.github && !reserved {
+            return slug;
+        }
+        format!("{}{}", HEADING_ID_PREFIX, slug)
+    }
+}
+
+/// Converts heading text to a slug: lowercase, punctuation removed, spaces
+/// turned into hyphens, emoji handled according to `policy`.
+pub fn slugify(text: &str, policy: EmojiPolicy) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut slug = String::with_capacity(text.len());
+    let mut plain = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let Some((len, emoji)) = match_emoji(&chars[i..]) else {
+            plain.push(chars[i]);
+            i += 1;
+            continue;
+        };
+
+        push_plain(&mut slug, &plain);
+        plain.clear();
+        match policy {
+            EmojiPolicy::Strip => {}
+            EmojiPolicy::Keep => {
+                for byte in emoji.as_str().bytes() {
+                    slug.push_str(&format!("%{:02X}", byte));
+                }
+            }
+            EmojiPolicy::Shortcode => {
+                if let Some(shortcode) = emoji.shortcode() {
+                    slug.push(' ');
+                    push_plain(&mut slug, shortcode);
+                    slug.push(' ');
+                }
+            }
+        }
+        i += len;
+    }
+    push_plain(&mut slug, &plain);
+
+    slug.split_whitespace().collect::<Vec<_>>().join("-")
+}
+
+/// Converts heading text to a slug the way GitHub does: lowercase,
+/// punctuation and emoji removed except `-` and `_`, and every space turned
+/// into a hyphen (so "A  B" gives "a--b")
+pub fn github_slug(text: &str) -> String {
+    GITHUB_REJECTED_CHARS
+        .replace_all(&text.trim().to_lowercase(), "")
+        .replace(' ', "-")
+}
+
+fn push_plain(slug: &mut String, text: &str) {
+    slug.push_str(&REJECTED_CHARS.replace_all(&text.to_lowercase(), ""));
+}
+
+/// Finds the longest emoji sequence at the start of `chars`
+fn match_emoji(chars: &[char]) -> Option<(usize, &'static emojis::Emoji)> {
+    let first = *chars.first()?;
+    // ASCII only starts an emoji as a keycap ("1️⃣", "#️⃣")
+    if first.is_ascii() && !matches!(chars.get(1), Some('\u{FE0F}' | '\u{20E3}')) {
+        return None;
+    }
+
+    let max = chars.len().min(MAX_EMOJI_CHARS);
+    (1..=max).rev().find_map(|len| {
+        let candidate: String = chars[..len].iter().collect();
+        emojis::get(&candidate).map(|emoji| (len, emoji))
+    })
+}
+
+/// Rewrites comrak's heading anchors to use ids from `slugger`, which should
+/// be fresh for the document.
+pub fn apply_heading_ids(html: &str, mut slugger: Slugger) -> String {
+    HEADING_ANCHOR
+        .replace_all(html, |caps: &Captures| {
+            let id = slugger.heading_id(&heading_text(&caps[1]));
+            format!(
+                "<a href=\"#{}\" aria-hidden=\"true\" class=\"anchor\" id=\"{}\"></a>{}{}",
+                id, id, &caps[1], &caps[2]
+            )
+        })
+        .into_owned()
+}
+
+/// Plain text of a rendered heading's inner HTML
+pub(super) fn heading_text(inner_html: &str) -> String {
+    HTML_TAG
+        .replace_all(inner_html, "")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify_plain() {
+        assert_eq!(slugify("Hello, World!", EmojiPolicy::Strip), "hello-world");
+        assert_eq!(slugify("snake_case and-dash", EmojiPolicy::Strip), "snake_case-and-dash");
+        assert_eq!(slugify("Café über", EmojiPolicy::Strip), "café-über");
+    }
+
+    #[test]
+    fn test_emoji_policies() {
+        let heading = "🚀 Launch";
+        assert_eq!(slugify(heading, EmojiPolicy::default()), "launch");
+        assert_eq!(slugify(heading, EmojiPolicy::Keep), "%F0%9F%9A%80-launch");
+        assert_eq!(slugify(heading, EmojiPolicy::Shortcode), "rocket-launch");
+        assert_eq!(slugify("Go😀now", EmojiPolicy::Shortcode), "go-grinning-now");
+    }
+
+    #[test]
+    fn test_slugger_dedupes() {
+        let mut slugger = Slugger::new(EmojiPolicy::Strip);
+        assert_eq!(slugger.slug("Intro"), "intro");
+        assert_eq!(slugger.slug("Intro"), "intro-1");
+        assert_eq!(slugger.heading_id("🚀 Intro"), "heading-intro-2");
+    }
+
+    #[test]
+    fn test_apply_heading_ids() {
+        let html = concat!(
+            r##"<h2><a href="#-launch" aria-hidden="true" class="anchor" "##,
+            r#"id="heading--launch"></a>🚀 Launch &amp; <em>Go</em></h2>"#
+        );
+
+        let result = apply_heading_ids(html, Slugger::new(EmojiPolicy::Shortcode));
+
+        assert!(result.contains(r##"href="#heading-rocket-launch-go""##));
+        assert!(result.contains(r#"id="heading-rocket-launch-go""#));
+        assert!(result.ends_with("🚀 Launch &amp; <em>Go</em></h2>"));
+    }
+
+    #[test]
+    fn test_github_ids() {
+        let mut slugger = Slugger::github();
+        let ids: Vec<String> = [
+            "Hello World",
+            "Hello World",
+            "Hello World",
+            "C++ & Rust!",
+            "Foo  Bar",
+            "API_v2 – Notes",
+            "🚀 Launch",
+            "Über Café",
+            "1. Setup (macOS)",
+            "hello-world",
+        ]
+        .iter()
+        .map(|text| slugger.heading_id(text))
+        .collect();
+
+        assert_eq!(
+            ids,
+            vec![
+                "hello-world",
+                "hello-world-1",
+                "hello-world-2",
+                "c--rust",
+                "foo--bar",
+                "api_v2--notes",
+                "-launch",
+                "über-café",
+                "1-setup-macos",
+                "hello-world-3",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_github_ids_avoid_generated_ids() {
+        let mut slugger = Slugger::github();
+        assert_eq!(
+            slugger.heading_id("Special Block 0"),
+            "heading-special-block-0"
+        );
+        assert_eq!(slugger.heading_id("Block 3f2a"), "heading-block-3f2a");
+        assert_eq!(slugger.heading_id("Blocks"), "blocks");
+    }
+}
@@ -0,0 +1,164 @@
+use serde::Serialize;
+
+use super::front_matter::extract_front_matter;
+use super::lines::{fenced_blocks, source_lines};
+use super::parser::{render_markdown_html_with, HtmlOptions};
+use super::search_hits::mark_search_hits;
+
+/// A search match rendered with the lines around it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Snippet {
+    /// 1-based line of the first match in the snippet
+    pub line: usize,
+    /// 1-based first and last source lines rendered
+    pub start_line: usize,
+    pub end_line: usize,
+    /// The lines rendered as HTML, matches in `<mark class="search-hit">`
+    pub html: String,
+}
+
+/// Finds the lines mentioning `query` (case-insensitive) and renders each
+/// with `context_lines` lines either side, for search results.
+///
+/// Matches whose context overlaps share a snippet. A snippet starting inside
+/// a fenced code block gets the block's opening fence, so code still renders
+/// as code; front matter is never matched or shown.
+pub fn search_snippets(markdown: &str, query: &str, context_lines: usize) -> Vec<Snippet> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let (_, body) = extract_front_matter(markdown);
+    let first_line = markdown[..markdown.len() - body.len()]
+        .matches('\n')
+        .count()
+        + 1;
+    let lines = source_lines(markdown);
+    let last_line = lines.len();
+
+    // (first match, start, end) of each snippet
+    let mut windows: Vec<(usize, usize, usize)> = Vec::new();
+    for line in &lines[first_line - 1..] {
+        if !line.text.to_lowercase().contains(&query) {
+            continue;
+        }
+        let start = line.number.saturating_sub(context_lines).max(first_line);
+        let end = (line.number + context_lines).min(last_line);
+        match windows.last_mut() {
+            Some((_, _, last_end)) if start <= *last_end + 1 => *last_end = end,
+            _ => windows.push((line.number, start, end)),
+        }
+    }
+    if windows.is_empty() {
+        return Vec::new();
+    }
+
+    let blocks = fenced_blocks(markdown);
+    let terms = [query];
+    windows
+        .into_iter()
+        .map(|(line, start_line, end_line)| {
+            let mut source = String::new();
+            // The opening fence of a code block the snippet starts inside
+            let opening = blocks.iter().find(|block| {
+                block.start_line < start_line && start_line <= block.start_line + block.lines.len()
+            });
+            if let Some(block) = opening {
+                source.push_str(lines[block.start_line - 1].text);
+                source.push('\n');
+            }
+            // A closing fence on the first line would open a new block
+            let on_closing_fence = blocks
+                .iter()
+                .any(|block| start_line == block.start_line + block.lines.len() + 1);
+            let shown = &lines[start_line - 1..end_line];
+            for line in shown.iter().skip(usize::from(on_closing_fence)) {
+                source.push_str(line.text);
+                source.push('\n');
+            }
+
+            let html = render_markdown_html_with(&source, &HtmlOptions::default()).html;
+            Snippet {
+                line,
+                start_line,
+                end_line,
+                html: mark_search_hits(&html, &terms, true),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "---\ntitle: Setup\n---\n# Setup\n\n\
+                       Install the **CLI** first.\n\n\
+                       Then run it:\n\n\
+                       ```text\nmarkviewer --init\nmarkviewer open notes.md\nexit 0\n```\n\n\
+                       That's all.\n";
+
+    #[test]
+    fn test_two_lines_of_context() {
+        let snippets = search_snippets(DOC, "cli", 2);
+
+        assert_eq!(snippets.len(), 1);
+        assert_eq!(
+            (
+                snippets[0].line,
+                snippets[0].start_line,
+                snippets[0].end_line
+            ),
+            (6, 4, 8)
+        );
+        let html = &snippets[0].html;
+        assert!(html.contains("<h1>"));
+        assert!(html.contains(
+            "<p>Install the <strong><mark class=\"search-hit\">CLI</mark></strong> first.</p>"
+        ));
+        assert!(html.contains("<p>Then run it:</p>"));
+        assert!(!html.contains("markviewer"));
+    }
+
+    #[test]
+    fn test_match_in_code_block() {
+        let snippets = search_snippets(DOC, "open notes", 1);
+
+        assert_eq!(snippets.len(), 1);
+        assert_eq!((snippets[0].start_line, snippets[0].end_line), (11, 13));
+        let html = &snippets[0].html;
+        assert!(html.contains("<pre"));
+        assert!(html.contains("<mark class=\"search-hit\">open notes</mark>"));
+        assert!(!html.contains("<p>"));
+    }
+
+    #[test]
+    fn test_nearby_matches_share_a_snippet() {
+        let snippets = search_snippets(DOC, "markviewer", 1);
+        assert_eq!(snippets.len(), 1);
+        assert_eq!(
+            (
+                snippets[0].line,
+                snippets[0].start_line,
+                snippets[0].end_line
+            ),
+            (11, 10, 13)
+        );
+
+        assert!(search_snippets(DOC, "setup", 0)
+            .iter()
+            .all(|snippet| snippet.line > 3));
+        assert!(search_snippets(DOC, "  ", 2).is_empty());
+    }
+
+    #[test]
+    fn test_snippet_starting_on_closing_fence() {
+        let snippets = search_snippets(DOC, "all.", 2);
+
+        assert_eq!(snippets.len(), 1);
+        assert_eq!((snippets[0].start_line, snippets[0].end_line), (14, 16));
+        let html = &snippets[0].html;
+        assert!(!html.contains("<pre"));
+        assert!(html.contains("<mark class=\"search-hit\">all.</mark></p>"));
+    }
+}
@@ -0,0 +1,120 @@
+use serde::Serialize;
+
+/// A rendered block and the source lines it came from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SourceMapEntry {
+    /// `data-block-id` of the rendered element
+    pub block_id: String,
+    /// 1-based first source line
+    pub start_line: usize,
+    /// 1-based last source line
+    pub end_line: usize,
+}
+
+/// Maps rendered blocks back to lines of the original document.
+///
+/// `block_lines` are 1-based line ranges in `rendered_source`, the markdown
+/// handed to the renderer once front matter was split off and special blocks
+/// replaced by placeholders. Its non-blank lines are matched with `source`
+/// in order; a line that was rewritten (a placeholder standing in for a
+/// whole fence, a shifted heading) maps to the gap between its matched
+/// neighbours, so the result is approximate around such lines.
+pub fn map_blocks_to_source(
+    source: &str,
+    rendered_source: &str,
+    block_ids: &[String],
+    block_lines: &[(usize, usize)],
+) -> Vec<SourceMapEntry> {
+    let source: Vec<&str> = source.lines().collect();
+    let rendered: Vec<&str> = rendered_source.lines().collect();
+    let matches = align_lines(&source, &rendered);
+    let last_line = source.len().saturating_sub(1);
+
+    block_ids
+        .iter()
+        .zip(block_lines)
+        .filter_map(|(id, &(start, end))| {
+            let (start, end) = (start.checked_sub(1)?, end.checked_sub(1)?);
+            let first = matches.get(start).copied().flatten().unwrap_or_else(|| {
+                // Just after the previous matched line, skipping blank lines
+                let mut line = matches[..start.min(matches.len())]
+                    .iter()
+                    .rev()
+                    .find_map(|m| m.map(|line| line + 1))
+                    .unwrap_or(0);
+                while line < last_line && source[line].trim().is_empty() {
+                    line += 1;
+                }
+                line
+            });
+            let last = matches.get(end).copied().flatten().unwrap_or_else(|| {
+                // Just before the next matched line, skipping blank lines
+                let mut line = matches
+                    .get(end + 1..)
+                    .unwrap_or_default()
+                    .iter()
+                    .find_map(|m| m.map(|line| line.saturating_sub(1)))
+                    .unwrap_or(last_line);
+                while line > first && source[line].trim().is_empty() {
+                    line -= 1;
+                }
+                line
+            });
+
+            Some(SourceMapEntry {
+                block_id: id.clone(),
+                start_line: first + 1,
+                end_line: last.max(first) + 1,
+            })
+        })
+        .collect()
+}
+
+/// For each rendered line, the index of the same line in `source`, matching
+/// non-blank lines in order; None for blank and unmatched lines
+fn align_lines(source: &[&str], rendered: &[&str]) -> Vec<Option<usize>> {
+    let mut next = 0;
+    rendered
+        .iter()
+        .map(|line| {
+            if line.trim().is_empty() {
+                return None;
+            }
+            let found = source[next.min(source.len())..]
+                .iter()
+                .position(|candidate| candidate == line)?;
+            next += found + 1;
+            Some(next - 1)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("b-{}", i)).collect()
+    }
+
+    fn lines(entries: &[SourceMapEntry]) -> Vec<(usize, usize)> {
+        entries.iter().map(|e| (e.start_line, e.end_line)).collect()
+    }
+
+    #[test]
+    fn test_offsets_front_matter() {
+        let source = "---\ntitle: x\n---\n# Heading\n\nSome\ntext\n";
+        let rendered = "# Heading\n\nSome\ntext\n";
+        let entries = map_blocks_to_source(source, rendered, &ids(2), &[(1, 1), (3, 4)]);
+        assert_eq!(lines(&entries), vec![(4, 4), (6, 7)]);
+        assert_eq!(entries[0].block_id, "b-0");
+    }
+
+    #[test]
+    fn test_placeholder_maps_to_replaced_fence() {
+        let source = "Intro\n\n```mermaid\ngraph TD\n\nA-->B\n```\n\nAfter\n";
+        let rendered = "Intro\n\n<div class=\"special-block\"></div>\n\nAfter\n";
+        let entries = map_blocks_to_source(source, rendered, &ids(3), &[(1, 1), (3, 3), (5, 5)]);
+        assert_eq!(lines(&entries), vec![(1, 1), (3, 7), (9, 9)]);
+    }
+}
@@ -1,9 +1,31 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::delimited::{delimiter_for, render_delimited_table};
+use super::parser::escape_html;
+
+/// Fence languages extracted by default: those the preview renders.
+/// "plantuml" and "abc" are opt-in, since the preview has no renderer for them
+pub const DEFAULT_SPECIAL_BLOCK_TYPES: &[&str] = &["mermaid", "chart"];
+
+/// Screen reader labels for placeholders by block type; other types are
+/// announced as "<type> block"
+pub const DEFAULT_SPECIAL_BLOCK_LABELS: &[(&str, &str)] = &[
+    ("abc", "Music notation"),
+    ("chart", "Chart"),
+    ("math", "Math formula"),
+    ("mermaid", "Mermaid diagram"),
+    ("plantuml", "PlantUML diagram"),
+];
+
+/// Largest block source (in bytes) copied into a placeholder's `data-source`;
+/// bigger blocks only carry their source in the `SpecialBlock`
+pub const MAX_SOURCE_ATTRIBUTE_BYTES: usize = 16 * 1024;
 
 /// Represents a special block (mermaid diagram or chart) extracted from markdown
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpecialBlock {
-    /// Type of block: "mermaid" or "chart"
+    /// Type of block: "mermaid", "chart", "plantuml", "abc", ...
     pub block_type: String,
     /// The content inside the code fence
     pub content: String,
@@ -17,47 +39,103 @@ pub struct SpecialBlock {
 /// The modified markdown has the special blocks replaced with placeholder divs
 /// that will be filled in by JavaScript on the frontend.
 pub fn extract_special_blocks(markdown: &str) -> (String, Vec<SpecialBlock>) {
+    extract_special_blocks_with(markdown, DEFAULT_SPECIAL_BLOCK_TYPES)
+}
+
+/// Like [`extract_special_blocks`], but only handles the given fence languages.
+///
+/// "csv" and "tsv" are opt-in: they are rendered directly as HTML tables
+/// instead of becoming placeholders. "math" also extracts `$$` display blocks.
+/// Empty "abc" (music notation) fences stay ordinary code blocks.
+pub fn extract_special_blocks_with(
+    markdown: &str,
+    block_types: &[&str],
+) -> (String, Vec<SpecialBlock>) {
+    extract_special_blocks_labeled(markdown, block_types, &HashMap::new())
+}
+
+/// Like [`extract_special_blocks_with`], with screen reader labels for the
+/// placeholders by block type (e.g. `"mermaid" => "Diagramme Mermaid"`).
+///
+/// Placeholders are announced as images (`role="img"`) with the label as
+/// their `aria-label`, falling back to [`DEFAULT_SPECIAL_BLOCK_LABELS`].
+pub fn extract_special_blocks_labeled(
+    markdown: &str,
+    block_types: &[&str],
+    labels: &HashMap<String, String>,
+) -> (String, Vec<SpecialBlock>) {
     let mut blocks = Vec::new();
     let mut result = String::new();
     let mut in_code_block = false;
     let mut code_fence = String::new(); // Store the fence type (``` or ~~~)
     let mut code_lang = String::new();
     let mut code_content = String::new();
+    // Leading whitespace of the opening fence, e.g. inside list items
+    let mut code_indent = "";
     let mut block_counter = 0;
+    let math_enabled = block_types.iter().any(|t| t.eq_ignore_ascii_case("math"));
 
     for line in markdown.lines() {
         let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
 
-        // Check for code fence start/end (``` or ~~~)
+        // Check for code fence start/end (``` or ~~~, or $$ for display math)
         let is_backtick_fence = trimmed.starts_with("```");
         let is_tilde_fence = trimmed.starts_with("~~~");
+        let is_math_fence = math_enabled
+            && trimmed.trim_end() == "$$"
+            && (!in_code_block || code_fence == "$$");
 
-        if is_backtick_fence || is_tilde_fence {
-            let fence = if is_backtick_fence { "```" } else { "~~~" };
+        if !in_code_block && math_enabled && is_single_line_math(trimmed) {
+            let tex = trimmed.trim_end();
+            push_placeholder(
+                &mut result,
+                &mut blocks,
+                &mut block_counter,
+                "math",
+                &tex[2..tex.len() - 2],
+                indent,
+                labels,
+            );
+        } else if is_backtick_fence || is_tilde_fence || is_math_fence {
+            let fence = if is_backtick_fence {
+                "```"
+            } else if is_tilde_fence {
+                "~~~"
+            } else {
+                "$$"
+            };
 
             if in_code_block && trimmed.starts_with(&code_fence) {
                 // End of code block (matching fence type)
                 let lang_lower = code_lang.to_lowercase();
-                if lang_lower == "mermaid" || lang_lower == "chart" {
-                    let placeholder_id = format!("special-block-{}", block_counter);
-                    block_counter += 1;
-
-                    blocks.push(SpecialBlock {
-                        block_type: lang_lower.clone(),
-                        content: code_content.trim().to_string(),
-                        placeholder_id: placeholder_id.clone(),
-                    });
-
-                    // Insert a placeholder div that will be found and rendered by JS
-                    result.push_str(&format!(
-                        "<div class=\"special-block {}\" id=\"{}\" data-block-type=\"{}\"></div>\n",
-                        lang_lower, placeholder_id, lang_lower
-                    ));
+                let content = dedent(&code_content, code_indent);
+                let enabled = block_types.iter().any(|t| t.eq_ignore_ascii_case(&lang_lower));
+                let table = delimiter_for(&lang_lower)
+                    .filter(|_| enabled)
+                    .and_then(|delimiter| render_delimited_table(&content, delimiter).ok());
+
+                if let Some(table) = table {
+                    result.push_str(&table);
+                    result.push_str("\n\n");
+                } else if enabled
+                    && delimiter_for(&lang_lower).is_none()
+                    && has_content(&lang_lower, &content)
+                {
+                    push_placeholder(
+                        &mut result,
+                        &mut blocks,
+                        &mut block_counter,
+                        &lang_lower,
+                        &content,
+                        code_indent,
+                        labels,
+                    );
                 } else {
                     // Regular code block - keep for comrak to process
-                    result.push_str(&format!("{}{}\n", code_fence, code_lang));
+                    result.push_str(&format!("{}{}{}\n", code_indent, code_fence, code_lang));
                     result.push_str(&code_content);
-                    result.push_str(&format!("{}\n", code_fence));
+                    result.push_str(&format!("{}{}\n", code_indent, code_fence));
                 }
                 in_code_block = false;
                 code_fence.clear();
@@ -67,8 +145,13 @@ pub fn extract_special_blocks(markdown: &str) -> (String, Vec<SpecialBlock>) {
                 // Start of code block
                 in_code_block = true;
                 code_fence = fence.to_string();
-                // Extract language after the fence
-                code_lang = trimmed[3..].trim().to_string();
+                code_indent = indent;
+                // Extract language after the fence ($$ blocks are always math)
+                code_lang = if fence == "$$" {
+                    "math".to_string()
+                } else {
+                    trimmed[3..].trim().to_string()
+                };
             } else {
                 // Inside a code block but different fence type - treat as content
                 code_content.push_str(line);
@@ -85,13 +168,151 @@ pub fn extract_special_blocks(markdown: &str) -> (String, Vec<SpecialBlock>) {
 
     // Handle unclosed code block (shouldn't happen in valid markdown)
     if in_code_block {
-        result.push_str(&format!("{}{}\n", code_fence, code_lang));
+        if code_fence == "$$" {
+            result.push_str("$$\n");
+        } else {
+            result.push_str(&format!("{}{}{}\n", code_indent, code_fence, code_lang));
+        }
         result.push_str(&code_content);
     }
 
     (result, blocks)
 }
 
+/// Records a special block and inserts its placeholder div
+fn push_placeholder(
+    result: &mut String,
+    blocks: &mut Vec<SpecialBlock>,
+    block_counter: &mut usize,
+    block_type: &str,
+    content: &str,
+    indent: &str,
+    labels: &HashMap<String, String>,
+) {
+    let placeholder_id = format!("special-block-{}", block_counter);
+    *block_counter += 1;
+    let content = content.trim();
+
+    // The source lets the frontend show it inline when rendering fails
+    let source = if content.len() <= MAX_SOURCE_ATTRIBUTE_BYTES {
+        format!(" data-source=\"{}\"", source_attribute(content))
+    } else {
+        String::new()
+    };
+    let label = escape_html(&block_label(block_type, labels));
+
+    blocks.push(SpecialBlock {
+        block_type: block_type.to_string(),
+        content: content.to_string(),
+        placeholder_id: placeholder_id.clone(),
+    });
+
+    // Insert a placeholder div that will be found and rendered by JS
+    result.push_str(&format!(
+        "{}<div class=\"special-block {}\" id=\"{}\" data-block-type=\"{}\" role=\"img\" \
+         aria-label=\"{}\"{}></div>\n",
+        indent, block_type, placeholder_id, block_type, label, source
+    ));
+}
+
+/// The screen reader label for a block type: the caller's, the default or
+/// "<type> block"
+fn block_label(block_type: &str, labels: &HashMap<String, String>) -> String {
+    if let Some(label) = labels.get(block_type) {
+        return label.clone();
+    }
+    DEFAULT_SPECIAL_BLOCK_LABELS
+        .iter()
+        .find(|(known, _)| *known == block_type)
+        .map_or_else(|| format!("{} block", block_type), |(_, label)| label.to_string())
+}
+
+/// Removes the opening fence's indentation from each content line; lines
+/// indented less lose only the whitespace they have
+fn dedent(content: &str, indent: &str) -> String {
+    if indent.is_empty() {
+        return content.to_string();
+    }
+    content
+        .split_inclusive('\n')
+        .map(|line| {
+            let width = line
+                .bytes()
+                .take(indent.len())
+                .take_while(|b| *b == b' ' || *b == b'\t')
+                .count();
+            &line[width..]
+        })
+        .collect()
+}
+
+/// Escapes block source for an attribute, encoding line breaks so the
+/// placeholder stays a single line of raw HTML
+fn source_attribute(content: &str) -> String {
+    escape_html(content)
+        .replace('\r', "&#13;")
+        .replace('\n', "&#10;")
+}
+
+/// Whether a fence holds enough to become a placeholder; only ABC tunes are
+/// checked here, parsing them is the frontend's job
+fn has_content(block_type: &str, content: &str) -> bool {
+    block_type != "abc" || !content.trim().is_empty()
+}
+
+/// A display math block written on one line: `$$ x^2 $$`
+fn is_single_line_math(trimmed: &str) -> bool {
+    let trimmed = trimmed.trim_end();
+    trimmed.len() > 4 && trimmed.starts_with("$$") && trimmed.ends_with("$$")
+}
+
+/// Replaces the placeholder line for `placeholder_id` with the given HTML,
+/// indented like the placeholder
+pub fn replace_placeholder(markdown: &str, placeholder_id: &str, html: &str) -> String {
+    let marker = format!("id=\"{}\"", placeholder_id);
+    let mut result = String::with_capacity(markdown.len() + html.len());
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("<div class=\"special-block") && line.contains(&marker) {
+            // Keep the indentation of a placeholder nested in a list
+            result.push_str(&line[..line.len() - trimmed.len()]);
+            result.push_str(html);
+        } else {
+            result.push_str(line);
+        }
+        result.push('\n');
+    }
+    result
+}
+
+/// Adds `offset` to the number of each `special-block-N` placeholder id, in
+/// both the blocks and the HTML holding their placeholders, so separately
+/// rendered parts of a document (e.g. slides) don't reuse ids
+pub fn offset_placeholder_ids(html: &mut String, blocks: &mut [SpecialBlock], offset: usize) {
+    if offset == 0 {
+        return;
+    }
+    let mut numbered: Vec<(usize, &mut SpecialBlock)> = blocks
+        .iter_mut()
+        .filter_map(|block| Some((placeholder_number(&block.placeholder_id)?, block)))
+        .collect();
+    // Highest first, so a renamed id never matches a block still to be renamed
+    numbered.sort_by_key(|(number, _)| std::cmp::Reverse(*number));
+    for (number, block) in numbered {
+        let new_id = format!("special-block-{}", number + offset);
+        *html = html.replace(
+            &format!("id=\"{}\"", block.placeholder_id),
+            &format!("id=\"{}\"", new_id),
+        );
+        block.placeholder_id = new_id;
+    }
+}
+
+/// The `N` of a `special-block-N` placeholder id
+pub fn placeholder_number(placeholder_id: &str) -> Option<usize> {
+    placeholder_id.strip_prefix("special-block-")?.parse().ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -118,12 +339,52 @@ More text.
         assert!(result.contains("data-block-type=\"mermaid\""));
     }
 
+    #[test]
+    fn test_extract_plantuml() {
+        let md = "```plantuml\n@startuml\nBob -> Alice : hello\n@enduml\n```";
+        let (unchanged, none) = extract_special_blocks(md);
+        assert!(none.is_empty());
+        assert_eq!(unchanged, format!("{}\n", md));
+
+        let (result, blocks) = extract_special_blocks_with(md, &["plantuml"]);
+
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].block_type, "plantuml");
+        assert!(blocks[0].content.contains("Bob -> Alice"));
+        assert!(result.contains("data-block-type=\"plantuml\""));
+    }
+
+    #[test]
+    fn test_extract_abc() {
+        let md = "```mermaid\ngraph\n```\n\n```abc\nX:1\nK:G\nGABc|\n```\n\n```abc\n\n```\n\n```chart\n{}\n```";
+        let (_, defaults) = extract_special_blocks(md);
+        assert!(defaults.iter().all(|b| b.block_type != "abc"));
+
+        let (result, blocks) = extract_special_blocks_with(md, &["mermaid", "abc", "chart"]);
+
+        let ids: Vec<(&str, &str)> = blocks
+            .iter()
+            .map(|b| (b.block_type.as_str(), b.placeholder_id.as_str()))
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                ("mermaid", "special-block-0"),
+                ("abc", "special-block-1"),
+                ("chart", "special-block-2"),
+            ]
+        );
+        assert_eq!(blocks[1].content, "X:1\nK:G\nGABc|");
+        // An empty tune stays a code block
+        assert!(result.contains("```abc\n\n```"));
+    }
+
     #[test]
     fn test_extract_chart() {
         let md = r#"```chart
 {"type": "bar", "data": {}}
 ```"#;
-        let (result, blocks) = extract_special_blocks(md);
+        let (_, blocks) = extract_special_blocks(md);
 
         assert_eq!(blocks.len(), 1);
         assert_eq!(blocks[0].block_type, "chart");
@@ -142,6 +403,33 @@ fn main() {}
         assert!(result.contains("fn main()"));
     }
 
+    #[test]
+    fn test_nested_block_is_dedented() {
+        let md = "- a\n  - b\n\n    ```mermaid\n    graph TD\n      A-->B\n    ```\n";
+        let (result, blocks) = extract_special_blocks(md);
+
+        assert_eq!(blocks[0].content, "graph TD\n  A-->B");
+        // The placeholder stays inside the list item
+        assert!(result.contains("\n    <div class=\"special-block mermaid\""));
+    }
+
+    #[test]
+    fn test_replace_nested_placeholder() {
+        let md = "- a\n\n  ```mermaid\n  graph\n  ```\n";
+        let (result, blocks) = extract_special_blocks(md);
+        let replaced = replace_placeholder(&result, &blocks[0].placeholder_id, "<p>static</p>");
+        assert!(replaced.contains("\n  <p>static</p>\n"));
+        assert!(!replaced.contains("special-block-0"));
+    }
+
+    #[test]
+    fn test_nested_regular_code_keeps_indent() {
+        let md = "- a\n\n  ```rust\n  fn main() {}\n  ```\n";
+        let (result, _) = extract_special_blocks(md);
+
+        assert_eq!(result, md);
+    }
+
     #[test]
     fn test_multiple_special_blocks() {
         let md = r#"```mermaid
@@ -155,7 +443,7 @@ graph LR
 ```mermaid
 pie
 ```"#;
-        let (result, blocks) = extract_special_blocks(md);
+        let (_, blocks) = extract_special_blocks(md);
 
         assert_eq!(blocks.len(), 3);
         assert_eq!(blocks[0].placeholder_id, "special-block-0");
@@ -178,4 +466,126 @@ graph
         assert_eq!(blocks[0].block_type, "mermaid");
         assert_eq!(blocks[1].block_type, "chart");
     }
+
+    #[test]
+    fn test_csv_is_opt_in() {
+        let md = "```csv\na,b\n1,2\n```";
+
+        let (result, _) = extract_special_blocks(md);
+        assert!(result.contains("```csv"));
+
+        let (result, blocks) = extract_special_blocks_with(md, &["mermaid", "chart", "csv"]);
+        assert!(blocks.is_empty());
+        assert!(result.contains("<table class=\"delimited-table\">"));
+        assert!(result.contains("<th>a</th>"));
+    }
+
+    #[test]
+    fn test_custom_block_types() {
+        let md = "```mermaid\ngraph\n```\n\n```chart\n{}\n```";
+        let (result, blocks) = extract_special_blocks_with(md, &["chart"]);
+
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].block_type, "chart");
+        assert!(result.contains("```mermaid"));
+    }
+
+    #[test]
+    fn test_math_blocks() {
+        let md = "$$\nE = mc^2\n$$\n\n$$ a^2 + b^2 $$\n\n```math\n\\frac{1}{2}\n```";
+
+        let (result, blocks) = extract_special_blocks(md);
+        assert!(blocks.is_empty());
+        assert!(result.contains("$$\nE = mc^2"));
+
+        let (result, blocks) = extract_special_blocks_with(md, &["math"]);
+        assert_eq!(blocks.len(), 3);
+        assert!(blocks.iter().all(|b| b.block_type == "math"));
+        assert_eq!(blocks[0].content, "E = mc^2");
+        assert_eq!(blocks[1].content, "a^2 + b^2");
+        assert_eq!(blocks[2].content, "\\frac{1}{2}");
+        assert!(!result.contains("$$"));
+    }
+
+    #[test]
+    fn test_dollars_inside_code_are_not_math() {
+        let md = "```bash\n$$\necho $$\n```";
+        let (result, blocks) = extract_special_blocks_with(md, &["math"]);
+        assert!(blocks.is_empty());
+        assert!(result.contains("echo $$"));
+    }
+
+    #[test]
+    fn test_replace_placeholder() {
+        let (result, blocks) = extract_special_blocks("a\n\n```mermaid\ngraph\n```\n\nb");
+        let replaced = replace_placeholder(&result, &blocks[0].placeholder_id, "<p>static</p>");
+        assert!(replaced.contains("<p>static</p>"));
+        assert!(!replaced.contains("special-block-0"));
+        assert!(replaced.starts_with("a\n"));
+    }
+
+    #[test]
+    fn test_offset_placeholder_ids() {
+        let (mut html, mut blocks) =
+            extract_special_blocks("```mermaid\ngraph TD\n```\n\n```chart\n{}\n```\n");
+        offset_placeholder_ids(&mut html, &mut blocks, 1);
+        let ids: Vec<&str> = blocks.iter().map(|b| b.placeholder_id.as_str()).collect();
+        assert_eq!(ids, vec!["special-block-1", "special-block-2"]);
+        assert!(html.contains("mermaid\" id=\"special-block-1\""));
+        assert!(html.contains("chart\" id=\"special-block-2\""));
+        assert!(!html.contains("special-block-0"));
+    }
+
+    #[test]
+    fn test_placeholder_carries_source() {
+        let md = "```mermaid\ngraph TD\n  A-->B[\"<b>\"]\n\n  B-->C\n```\n\n```chart\n{\"type\": \"bar\"}\n```\n";
+        let (result, blocks) = extract_special_blocks(md);
+
+        assert!(result.contains(
+            "data-source=\"graph TD&#10;  A--&gt;B[&quot;&lt;b&gt;&quot;]&#10;&#10;  B--&gt;C\"></div>\n"
+        ));
+        assert!(result.contains("data-source=\"{&quot;type&quot;: &quot;bar&quot;}\""));
+        assert_eq!(result.lines().count(), 3);
+        assert_eq!(blocks[0].content, "graph TD\n  A-->B[\"<b>\"]\n\n  B-->C");
+    }
+
+    #[test]
+    fn test_large_source_not_copied() {
+        let large = "A-->B\n".repeat(MAX_SOURCE_ATTRIBUTE_BYTES);
+        let (result, blocks) = extract_special_blocks(&format!("```mermaid\n{}```\n", large));
+        assert!(!result.contains("data-source"));
+        assert_eq!(blocks[0].content, large.trim());
+    }
+
+    #[test]
+    fn test_placeholder_aria_attributes() {
+        let md = "```mermaid
+graph
+```
+
+```chart
+{}
+```
+
+```gantt
+x
+```
+";
+        let (result, _) = extract_special_blocks_with(md, &["mermaid", "chart", "gantt"]);
+
+        assert!(result.contains(
+            "data-block-type=\"mermaid\" role=\"img\" aria-label=\"Mermaid diagram\" \
+             data-source=\"graph\"></div>"
+        ));
+        assert!(result.contains("role=\"img\" aria-label=\"Chart\""));
+        assert!(result.contains("role=\"img\" aria-label=\"gantt block\""));
+
+        let labels = HashMap::from([
+            ("mermaid".to_string(), "Diagramme Mermaid".to_string()),
+            ("chart".to_string(), "Graphique <données>".to_string()),
+        ]);
+        let (result, _) = extract_special_blocks_labeled(md, &["mermaid", "chart"], &labels);
+        assert!(result.contains("aria-label=\"Diagramme Mermaid\""));
+        assert!(result.contains("aria-label=\"Graphique &lt;données&gt;\""));
+    }
 }
@@ -0,0 +1,204 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::front_matter::extract_front_matter;
+use super::lines::source_lines;
+use super::sections::split_sections;
+
+/// Bullet or ordered list item
+static LIST_ITEM: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*(?:[-*+]|\d+[.)])\s").unwrap());
+
+/// Words ending in a period that don't end a sentence (compared lowercased)
+const ABBREVIATIONS: &[&str] = &[
+    "e.g.", "i.e.", "etc.", "vs.", "cf.", "mr.", "mrs.", "ms.", "dr.", "prof.", "st.", "jr.",
+    "sr.", "fig.", "no.", "approx.", "inc.", "ltd.",
+];
+
+/// A block of a section's body
+enum Block {
+    /// Paragraph text, joined onto one line
+    Prose(String),
+    /// Lines kept verbatim: fenced code, lists, tables and HTML
+    Other(String),
+}
+
+/// Condenses a document to its headings and the first `sentences_per_section`
+/// sentences of each section's prose.
+///
+/// An extractive summary: sentences are copied, never rewritten. Fenced code,
+/// lists, tables and HTML are dropped unless `keep_code_and_lists` is set, in
+/// which case they're kept as written. Front matter is dropped.
+pub fn summarize_sections(
+    markdown: &str,
+    sentences_per_section: usize,
+    keep_code_and_lists: bool,
+) -> String {
+    let (_, body) = extract_front_matter(markdown);
+
+    let mut parts: Vec<String> = Vec::new();
+    for section in split_sections(body) {
+        let mut content = section.content.as_str();
+        if section.level > 0 {
+            let (heading, rest) = content.split_once('\n').unwrap_or((content, ""));
+            parts.push(heading.trim_end().to_string());
+            content = rest;
+        }
+
+        let mut budget = sentences_per_section;
+        for block in section_blocks(content) {
+            match block {
+                Block::Prose(text) if budget > 0 => {
+                    let sentences = split_sentences(&text);
+                    let kept: Vec<&str> = sentences.into_iter().take(budget).collect();
+                    budget -= kept.len();
+                    parts.push(kept.join(" "));
+                }
+                Block::Other(text) if keep_code_and_lists => parts.push(text),
+                _ => {}
+            }
+        }
+    }
+
+    let mut summary = parts.join("\n\n");
+    if !summary.is_empty() {
+        summary.push('\n');
+    }
+    summary
+}
+
+/// Splits a section body into paragraphs and other blocks
+fn section_blocks(content: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut prose: Vec<&str> = Vec::new();
+    let mut other: Vec<&str> = Vec::new();
+
+    let flush = |prose: &mut Vec<&str>, other: &mut Vec<&str>, blocks: &mut Vec<Block>| {
+        if !prose.is_empty() {
+            blocks.push(Block::Prose(prose.join(" ")));
+            prose.clear();
+        }
+        if !other.is_empty() {
+            blocks.push(Block::Other(other.join("\n")));
+            other.clear();
+        }
+    };
+
+    for line in source_lines(content) {
+        let text = line.text.trim();
+        let is_other = line.in_code
+            || LIST_ITEM.is_match(line.text)
+            || text.starts_with(['|', '<'])
+            // A list item's continuation lines stay with the list
+            || (!other.is_empty() && line.text.starts_with([' ', '\t']) && !text.is_empty());
+
+        if text.is_empty() && !line.in_code {
+            flush(&mut prose, &mut other, &mut blocks);
+        } else if is_other {
+            if !prose.is_empty() {
+                flush(&mut prose, &mut Vec::new(), &mut blocks);
+            }
+            other.push(line.text);
+        } else {
+            if !other.is_empty() {
+                flush(&mut Vec::new(), &mut other, &mut blocks);
+            }
+            prose.push(text.trim_start_matches(['>', ' ']));
+        }
+    }
+    flush(&mut prose, &mut other, &mut blocks);
+
+    blocks
+}
+
+/// Splits text after `.`, `!` or `?` (and any closing quotes or brackets)
+/// when followed by whitespace, except after common abbreviations and
+/// initials like "J."
+fn split_sentences(text: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (index, c) = chars[i];
+        i += 1;
+        if !matches!(c, '.' | '!' | '?') {
+            continue;
+        }
+        while i < chars.len() && matches!(chars[i].1, '.' | '!' | '?' | '"' | '\'' | ')') {
+            i += 1;
+        }
+        let Some(&(end, next)) = chars.get(i) else {
+            break;
+        };
+        if !next.is_whitespace() || is_abbreviation(&text[start..index + c.len_utf8()]) {
+            continue;
+        }
+        sentences.push(text[start..end].trim());
+        start = end;
+    }
+
+    let rest = text[start..].trim();
+    if !rest.is_empty() {
+        sentences.push(rest);
+    }
+    sentences
+}
+
+/// Whether the word ending `text` (at its period) is an abbreviation or an
+/// initial, so the period doesn't end the sentence
+fn is_abbreviation(text: &str) -> bool {
+    let word = text
+        .rsplit(char::is_whitespace)
+        .next()
+        .unwrap_or("")
+        .trim_start_matches(['(', '"', '\'']);
+    let lower = word.to_lowercase();
+    let initial = word.chars().count() == 2 && word.starts_with(char::is_uppercase);
+    initial || ABBREVIATIONS.contains(&lower.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_headings_and_first_sentences() {
+        let md = "---\ntitle: Notes\n---\nIntro one. Intro two.\n\n\
+                  # Setup\n\nInstall it first, e.g. with cargo. Then run it! Finally, rest.\n\n\
+                  ```sh\ncargo install x\n```\n\n- a list item\n\n## Usage\n\nOpen a file? Yes.\n\n\
+                  A second paragraph.\n";
+
+        assert_eq!(
+            summarize_sections(md, 1, false),
+            "Intro one.\n\n# Setup\n\nInstall it first, e.g. with cargo.\n\n## Usage\n\nOpen a file?\n"
+        );
+        assert_eq!(
+            summarize_sections(md, 2, false),
+            "Intro one. Intro two.\n\n# Setup\n\nInstall it first, e.g. with cargo. Then run it!\n\n\
+             ## Usage\n\nOpen a file? Yes.\n"
+        );
+        assert_eq!(
+            summarize_sections(md, 3, true),
+            "Intro one. Intro two.\n\n# Setup\n\nInstall it first, e.g. with cargo. Then run it! Finally, rest.\n\n\
+             ```sh\ncargo install x\n```\n\n- a list item\n\n## Usage\n\nOpen a file? Yes.\n\nA second paragraph.\n"
+        );
+    }
+
+    #[test]
+    fn test_split_sentences() {
+        assert_eq!(
+            split_sentences("Dr. Smith met J. Doe at 3.5 km. \"Really?\" she said. End"),
+            vec![
+                "Dr. Smith met J. Doe at 3.5 km.",
+                "\"Really?\"",
+                "she said.",
+                "End"
+            ]
+        );
+        assert_eq!(
+            split_sentences("Wait... what?! Ok."),
+            vec!["Wait...", "what?!", "Ok."]
+        );
+    }
+}
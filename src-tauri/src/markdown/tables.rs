@@ -0,0 +1,354 @@
+use serde::Serialize;
+
+use super::lines::{source_lines, SourceLine};
+
+/// Replaces literal `\n` sequences inside pipe-table rows with `<br>`, so a
+/// cell can hold several lines.
+///
+/// Code spans and escaped backslashes (`\\n`) are left alone, as are lines
+/// outside tables and inside fenced code. Line numbers are preserved.
+pub fn expand_cell_line_breaks(markdown: &str) -> String {
+    let lines = source_lines(markdown);
+    let mut in_table = false;
+    let mut output: Vec<String> = Vec::with_capacity(lines.len());
+
+    for (index, line) in lines.iter().enumerate() {
+        if line.in_code || line.text.trim().is_empty() {
+            in_table = false;
+        } else if !in_table {
+            // A table starts at a header row followed by a delimiter row
+            in_table = line.text.contains('|')
+                && lines.get(index + 1).is_some_and(|next| {
+                    !next.in_code && is_delimiter_row(next.text)
+                });
+        }
+
+        if in_table && !is_delimiter_row(line.text) {
+            output.push(replace_line_breaks(line.text));
+        } else {
+            output.push(line.text.to_string());
+        }
+    }
+
+    let mut result = output.join("\n");
+    if markdown.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+/// Markdown with its tables reformatted, plus tables that were skipped
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NormalizedTables {
+    pub markdown: String,
+    /// One message per table left untouched
+    pub warnings: Vec<String>,
+}
+
+/// Column alignment from a delimiter row
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+/// Reformats pipe tables: rows are padded to the same number of columns,
+/// a missing delimiter row is added under the header, and cells are padded so
+/// the pipes line up. Column alignments are kept.
+///
+/// A run of pipe rows without a delimiter row is only treated as a table when
+/// every row starts with `|`. A table whose delimiter row isn't the second
+/// row can't be interpreted and is left as-is with a warning. Fenced code is
+/// never touched.
+pub fn normalize_tables(markdown: &str) -> NormalizedTables {
+    let lines = source_lines(markdown);
+    let mut output: Vec<String> = Vec::with_capacity(lines.len());
+    let mut warnings = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let end = index
+            + lines[index..]
+                .iter()
+                .take_while(|line| {
+                    !line.in_code && !line.text.trim().is_empty() && line.text.contains('|')
+                })
+                .count();
+        if end == index {
+            output.push(lines[index].text.to_string());
+            index += 1;
+            continue;
+        }
+
+        let rows = &lines[index..end];
+        match format_table(rows) {
+            Ok(Some(table)) => output.extend(table),
+            Ok(None) => output.extend(rows.iter().map(|line| line.text.to_string())),
+            Err(message) => {
+                warnings.push(format!("Line {}: {}", rows[0].number, message));
+                output.extend(rows.iter().map(|line| line.text.to_string()));
+            }
+        }
+        index = end;
+    }
+
+    let mut result = output.join("\n");
+    if markdown.ends_with('\n') {
+        result.push('\n');
+    }
+    NormalizedTables {
+        markdown: result,
+        warnings,
+    }
+}
+
+/// Formats a run of pipe rows; None when they aren't a table
+fn format_table(rows: &[SourceLine]) -> Result<Option<Vec<String>>, String> {
+    let delimiters: Vec<usize> = rows
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| is_delimiter_row(line.text))
+        .map(|(i, _)| i)
+        .collect();
+
+    // Without a delimiter row, only rows that all start with a pipe count
+    let all_piped = rows.len() > 1
+        && rows
+            .iter()
+            .all(|line| line.text.trim_start().starts_with('|'));
+    let aligns: Vec<Align> = match delimiters.as_slice() {
+        [1] => split_cells(rows[1].text)
+            .iter()
+            .map(|cell| alignment(cell))
+            .collect(),
+        [] if all_piped => Vec::new(),
+        [] => return Ok(None),
+        _ => {
+            return Err("delimiter row must directly follow the header; left unchanged".to_string())
+        }
+    };
+
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| delimiters.first() != Some(i))
+        .map(|(_, line)| split_cells(line.text))
+        .collect();
+    let columns = cells
+        .iter()
+        .map(Vec::len)
+        .chain([aligns.len()])
+        .max()
+        .unwrap_or(0);
+    let aligns: Vec<Align> = (0..columns)
+        .map(|column| aligns.get(column).copied().unwrap_or(Align::None))
+        .collect();
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            cells
+                .iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+                .max(3)
+        })
+        .collect();
+
+    let indent: String = rows[0]
+        .text
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .collect();
+    let format_row = |row: &[String]| {
+        let padded: Vec<String> = (0..columns)
+            .map(|column| {
+                let cell = row.get(column).map(String::as_str).unwrap_or("");
+                pad_cell(cell, widths[column], aligns[column])
+            })
+            .collect();
+        format!("{}| {} |", indent, padded.join(" | "))
+    };
+
+    let mut table = vec![format_row(&cells[0])];
+    let separators: Vec<String> = aligns
+        .iter()
+        .zip(&widths)
+        .map(|(&align, &width)| delimiter_cell(align, width))
+        .collect();
+    table.push(format!("{}| {} |", indent, separators.join(" | ")));
+    table.extend(cells[1..].iter().map(|row| format_row(row)));
+
+    Ok(Some(table))
+}
+
+/// Splits a row on unescaped pipes, dropping the outer ones
+fn split_cells(line: &str) -> Vec<String> {
+    let trimmed = line.trim();
+    let trimmed = trimmed.strip_prefix('|').unwrap_or(trimmed);
+    let trimmed = match trimmed.strip_suffix('|') {
+        Some(rest) if !rest.ends_with('\\') => rest,
+        _ => trimmed,
+    };
+
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = trimmed.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                cell.push(c);
+                if let Some(next) = chars.next() {
+                    cell.push(next);
+                }
+            }
+            '|' => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+fn alignment(cell: &str) -> Align {
+    match (cell.starts_with(':'), cell.ends_with(':') && cell.len() > 1) {
+        (true, true) => Align::Center,
+        (true, false) => Align::Left,
+        (false, true) => Align::Right,
+        (false, false) => Align::None,
+    }
+}
+
+fn delimiter_cell(align: Align, width: usize) -> String {
+    match align {
+        Align::None => "-".repeat(width),
+        Align::Left => format!(":{}", "-".repeat(width - 1)),
+        Align::Center => format!(":{}:", "-".repeat(width - 2)),
+        Align::Right => format!("{}:", "-".repeat(width - 1)),
+    }
+}
+
+fn pad_cell(cell: &str, width: usize, align: Align) -> String {
+    let padding = width.saturating_sub(cell.chars().count());
+    let (before, after) = match align {
+        Align::Right => (padding, 0),
+        Align::Center => (padding / 2, padding - padding / 2),
+        Align::None | Align::Left => (0, padding),
+    };
+    format!("{}{}{}", " ".repeat(before), cell, " ".repeat(after))
+}
+
+/// True for a table delimiter row like `| --- | :-: |`
+fn is_delimiter_row(line: &str) -> bool {
+    let trimmed = line.trim().trim_start_matches('|').trim_end_matches('|');
+    line.contains('|')
+        && !trimmed.is_empty()
+        && trimmed.split('|').all(|cell| {
+            let cell = cell.trim().trim_start_matches(':').trim_end_matches(':');
+            !cell.is_empty() && cell.chars().all(|c| c == '-')
+        })
+}
+
+/// Replaces unescaped `\n` outside code spans with `<br>`
+fn replace_line_breaks(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    let mut code_ticks = 0;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '`' => {
+                let mut ticks = 1;
+                while chars.peek() == Some(&'`') {
+                    chars.next();
+                    ticks += 1;
+                }
+                // A span closes on a run of the same length
+                code_ticks = match code_ticks {
+                    0 => ticks,
+                    open if open == ticks => 0,
+                    open => open,
+                };
+                result.push_str(&"`".repeat(ticks));
+            }
+            '\\' if code_ticks == 0 => match chars.next() {
+                Some('n') => result.push_str("<br>"),
+                Some(next) => {
+                    result.push('\\');
+                    result.push(next);
+                }
+                None => result.push('\\'),
+            },
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_cell_line_breaks() {
+        let md = "| Step | Value |\n|---|:-:|\n| 1 | first\\nsecond |\n| 2 | `a\\nb` and \\\\n |\n\nText with \\n stays\n";
+        let expanded = expand_cell_line_breaks(md);
+
+        assert_eq!(
+            expanded,
+            "| Step | Value |\n|---|:-:|\n| 1 | first<br>second |\n| 2 | `a\\nb` and \\\\n |\n\nText with \\n stays\n"
+        );
+    }
+
+    #[test]
+    fn test_tables_in_code_are_untouched() {
+        let md = "```\n| a | b |\n|---|---|\n| x\\ny | z |\n```";
+        assert_eq!(expand_cell_line_breaks(md), md);
+    }
+
+    #[test]
+    fn test_normalize_ragged_table() {
+        let md = "Intro\n\n| Name | Qty |\n|:--|--:|\n| apple | 3 | fresh |\n|pear|12|\n\nAfter\n";
+        let normalized = normalize_tables(md);
+
+        assert_eq!(
+            normalized.markdown,
+            "Intro\n\n\
+             | Name  | Qty |       |\n\
+             | :---- | --: | ----- |\n\
+             | apple |   3 | fresh |\n\
+             | pear  |  12 |       |\n\
+             \nAfter\n"
+        );
+        assert!(normalized.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_normalize_adds_missing_delimiter() {
+        let md = "| a | b |\n| c |\n";
+        assert_eq!(
+            normalize_tables(md).markdown,
+            "| a   | b   |\n| --- | --- |\n| c   |     |\n"
+        );
+    }
+
+    #[test]
+    fn test_normalize_leaves_malformed_and_code() {
+        let md = "| a | b |\n| c | d |\n|---|---|\n\n```\n|x|y|\n|-|\n```\n\na | b\n";
+        let normalized = normalize_tables(md);
+        assert_eq!(normalized.markdown, md);
+        assert_eq!(normalized.warnings.len(), 1);
+        assert!(normalized.warnings[0].starts_with("Line 1:"));
+    }
+
+    #[test]
+    fn test_is_delimiter_row() {
+        assert!(is_delimiter_row("|---|---|"));
+        assert!(is_delimiter_row(" :-- | --: "));
+        assert!(!is_delimiter_row("| a | b |"));
+        assert!(!is_delimiter_row("---"));
+    }
+}
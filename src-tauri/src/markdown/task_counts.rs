@@ -0,0 +1,74 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Closing heading tags and rendered task checkboxes, in document order
+static HEADING_END_OR_TASK: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"</h[1-6]>|<input\b[^>]*\stype="checkbox"[^>]*>"#).unwrap());
+
+/// Whether a checkbox tag is ticked
+static CHECKED: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\schecked\b"#).unwrap());
+
+/// Appends a `<span class="task-count">(done/total)</span>` to each rendered
+/// heading whose section has tasklist items.
+///
+/// A section runs from a heading to the next heading of any level, as in
+/// `summarize_tasks`, and nested tasks count individually. Headings without
+/// tasks are left alone.
+pub fn add_heading_task_counts(html: &str) -> String {
+    // Closing tag position of each heading with its (done, total) counts
+    let mut sections: Vec<(usize, usize, usize)> = Vec::new();
+    let mut in_section = false;
+    for found in HEADING_END_OR_TASK.find_iter(html) {
+        if found.as_str().starts_with("</h") {
+            sections.push((found.start(), 0, 0));
+            in_section = true;
+        } else if in_section {
+            let (_, done, total) = sections.last_mut().unwrap();
+            *total += 1;
+            if CHECKED.is_match(found.as_str()) {
+                *done += 1;
+            }
+        }
+    }
+
+    let mut result = String::with_capacity(html.len());
+    let mut last = 0;
+    for (end, done, total) in sections.into_iter().filter(|&(_, _, total)| total > 0) {
+        result.push_str(&html[last..end]);
+        result.push_str(&format!(
+            " <span class=\"task-count\">({}/{})</span>",
+            done, total
+        ));
+        last = end;
+    }
+    result.push_str(&html[last..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_per_section() {
+        let html = "<ul><li><input type=\"checkbox\" disabled=\"\" /> Before</li></ul>\n\
+                    <h2>Backend</h2>\n<ul>\n\
+                    <li><input type=\"checkbox\" checked=\"\" disabled=\"\" /> API</li>\n\
+                    <li><input type=\"checkbox\" disabled=\"\" /> Tests</li>\n\
+                    <li><input type=\"checkbox\" disabled=\"\" /> Docs</li>\n</ul>\n\
+                    <h2>Notes</h2>\n<p>No tasks here</p>\n\
+                    <h3>Later</h3>\n<ul><li><input type=\"checkbox\" checked=\"\" disabled=\"\" /> Done</li></ul>\n";
+        let counted = add_heading_task_counts(html);
+
+        assert!(counted.contains("<h2>Backend <span class=\"task-count\">(1/3)</span></h2>"));
+        assert!(counted.contains("<h2>Notes</h2>"));
+        assert!(counted.contains("<h3>Later <span class=\"task-count\">(1/1)</span></h3>"));
+        assert_eq!(counted.matches("task-count").count(), 2);
+    }
+
+    #[test]
+    fn test_no_tasks_unchanged() {
+        let html = "<h1>Title</h1>\n<p>Text <input type=\"text\"></p>\n";
+        assert_eq!(add_heading_task_counts(html), html);
+    }
+}
@@ -0,0 +1,266 @@
+use chrono::NaiveDate;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::Serialize;
+
+use super::lines::{atx_heading, source_lines};
+
+/// Matches a tasklist item: indentation, list marker, and the checkbox state
+static TASK_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\s*)(?:[-*+]|\d+[.)])\s+\[([ xX])\](?:\s|$)").unwrap());
+
+/// Due dates: `📅 2024-06-01` (Obsidian Tasks), `@due(2024-06-01)` and
+/// `due:2024-06-01` (todo.txt)
+static DUE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:📅\u{FE0F}?\s*|@due\(\s*|\bdue:)(\d{4}-\d{2}-\d{2})\s*\)?").unwrap()
+});
+
+/// Priorities: Obsidian Tasks emoji (`🔺 ⏫ 🔼 🔽 ⏬`) and `!high`-style words
+static PRIORITY_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)([🔺⏫🔼🔽⏬])\u{FE0F}?|(?:^|\s)!(highest|high|medium|low|lowest)\b").unwrap()
+});
+
+/// Task priority, highest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Highest,
+    High,
+    Medium,
+    Low,
+    Lowest,
+}
+
+impl Priority {
+    fn from_annotation(annotation: &str) -> Option<Self> {
+        match annotation.to_lowercase().as_str() {
+            "🔺" | "highest" => Some(Self::Highest),
+            "⏫" | "high" => Some(Self::High),
+            "🔼" | "medium" => Some(Self::Medium),
+            "🔽" | "low" => Some(Self::Low),
+            "⏬" | "lowest" => Some(Self::Lowest),
+            _ => None,
+        }
+    }
+}
+
+/// A tasklist item with its annotations
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Task {
+    /// 1-based source line
+    pub line: usize,
+    /// Item text with the due date and priority annotations removed
+    pub text: String,
+    pub done: bool,
+    /// Due date as `YYYY-MM-DD`
+    pub due: Option<String>,
+    pub priority: Option<Priority>,
+    /// Nearest preceding heading text
+    pub heading: Option<String>,
+}
+
+/// Task completion for a single heading section
+#[derive(Debug, Clone, Serialize)]
+pub struct SectionProgress {
+    /// Nearest preceding heading text (None for tasks before the first heading)
+    pub heading: Option<String>,
+    /// 1-based line of the heading (0 when there is no heading)
+    pub line: usize,
+    pub total: usize,
+    pub completed: usize,
+}
+
+/// Task completion summary for a document
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TaskProgress {
+    pub total: usize,
+    pub completed: usize,
+    /// Per-section breakdown, in document order (only sections with tasks)
+    pub sections: Vec<SectionProgress>,
+}
+
+/// Counts tasklist items (`- [ ]` / `- [x]`), ignoring fenced code blocks.
+///
+/// Nested tasks are counted individually unless `top_level_only` is set.
+pub fn summarize_tasks(markdown: &str, top_level_only: bool) -> TaskProgress {
+    let mut progress = TaskProgress::default();
+    let mut heading: Option<(String, usize)> = None;
+    let mut section_index: Option<usize> = None;
+
+    for line in source_lines(markdown) {
+        if line.in_code {
+            continue;
+        }
+
+        if let Some((_, text)) = atx_heading(line.text) {
+            heading = Some((text.to_string(), line.number));
+            section_index = None;
+            continue;
+        }
+
+        let Some(caps) = TASK_REGEX.captures(line.text) else {
+            continue;
+        };
+        if top_level_only && !caps[1].is_empty() {
+            continue;
+        }
+
+        let index = *section_index.get_or_insert_with(|| {
+            progress.sections.push(SectionProgress {
+                heading: heading.as_ref().map(|(text, _)| text.clone()),
+                line: heading.as_ref().map_or(0, |(_, number)| *number),
+                total: 0,
+                completed: 0,
+            });
+            progress.sections.len() - 1
+        });
+
+        let done = &caps[2] != " ";
+        let section = &mut progress.sections[index];
+        section.total += 1;
+        progress.total += 1;
+        if done {
+            section.completed += 1;
+            progress.completed += 1;
+        }
+    }
+
+    progress
+}
+
+/// Lists tasklist items with their due dates and priorities, for agenda views.
+///
+/// Understands Obsidian Tasks emoji (`📅 2024-06-01 ⏫`) and inline words
+/// (`@due(2024-06-01) !high`, `due:2024-06-01`). Dates that aren't valid
+/// calendar days are left in the text. Fenced code is ignored.
+pub fn parse_tasks(markdown: &str) -> Vec<Task> {
+    let mut tasks = Vec::new();
+    let mut heading: Option<String> = None;
+
+    for line in source_lines(markdown) {
+        if line.in_code {
+            continue;
+        }
+        if let Some((_, text)) = atx_heading(line.text) {
+            heading = Some(text.to_string());
+            continue;
+        }
+        let Some(caps) = TASK_REGEX.captures(line.text) else {
+            continue;
+        };
+
+        let mut due = None;
+        let text = DUE_REGEX.replace_all(&line.text[caps[0].len()..], |c: &Captures| {
+            match NaiveDate::parse_from_str(&c[1], "%Y-%m-%d") {
+                Ok(date) if due.is_none() => {
+                    due = Some(date.to_string());
+                    String::new()
+                }
+                Ok(_) => String::new(),
+                Err(_) => c[0].to_string(),
+            }
+        });
+        let mut priority = None;
+        let text = PRIORITY_REGEX.replace_all(&text, |c: &Captures| {
+            let annotation = c.get(1).or_else(|| c.get(2)).unwrap().as_str();
+            priority = priority.or(Priority::from_annotation(annotation));
+            " "
+        });
+
+        tasks.push(Task {
+            line: line.number,
+            text: text.split_whitespace().collect::<Vec<_>>().join(" "),
+            done: &caps[2] != " ",
+            due,
+            priority,
+            heading: heading.clone(),
+        });
+    }
+
+    tasks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "- [x] Intro task
+
+# Backend
+- [ ] API
+  - [x] Nested done
+- [X] Database
+
+```markdown
+- [ ] Not a task
+```
+
+# Frontend
+* [ ] Layout
+1. [x] Routing
+";
+
+    #[test]
+    fn test_totals_and_sections() {
+        let progress = summarize_tasks(DOC, false);
+        assert_eq!(progress.total, 6);
+        assert_eq!(progress.completed, 4);
+
+        assert_eq!(progress.sections.len(), 3);
+        assert_eq!(progress.sections[0].heading, None);
+        assert_eq!(progress.sections[1].heading.as_deref(), Some("Backend"));
+        assert_eq!(progress.sections[1].total, 3);
+        assert_eq!(progress.sections[1].completed, 2);
+        assert_eq!(progress.sections[2].heading.as_deref(), Some("Frontend"));
+        assert_eq!(progress.sections[2].completed, 1);
+    }
+
+    #[test]
+    fn test_top_level_only() {
+        let progress = summarize_tasks(DOC, true);
+        assert_eq!(progress.total, 5);
+        assert_eq!(progress.sections[1].total, 2);
+    }
+
+    #[test]
+    fn test_parse_task_annotations() {
+        let md = "# Work\n\
+                  - [ ] Write report 📅 2024-06-01 ⏫\n\
+                  - [x] Call Ana @due(2024-05-20) !low\n\
+                  - [ ] Renew due:2024-07-15 🔺\n\
+                  - [ ] Plain task\n\
+                  - [ ] Bad date 📅 2024-02-30 !HIGH\n\
+                  - not a task 📅 2024-06-01";
+        let tasks = parse_tasks(md);
+
+        assert_eq!(tasks.len(), 5);
+        assert_eq!(
+            tasks[0],
+            Task {
+                line: 2,
+                text: "Write report".to_string(),
+                done: false,
+                due: Some("2024-06-01".to_string()),
+                priority: Some(Priority::High),
+                heading: Some("Work".to_string()),
+            }
+        );
+        assert!(tasks[1].done);
+        assert_eq!(tasks[1].text, "Call Ana");
+        assert_eq!(tasks[1].due.as_deref(), Some("2024-05-20"));
+        assert_eq!(tasks[1].priority, Some(Priority::Low));
+        assert_eq!(tasks[2].due.as_deref(), Some("2024-07-15"));
+        assert_eq!(tasks[2].priority, Some(Priority::Highest));
+        assert_eq!((tasks[3].due.clone(), tasks[3].priority), (None, None));
+        assert_eq!(tasks[4].text, "Bad date 📅 2024-02-30");
+        assert_eq!(tasks[4].due, None);
+        assert_eq!(tasks[4].priority, Some(Priority::High));
+    }
+
+    #[test]
+    fn test_no_tasks() {
+        let progress = summarize_tasks("# Title\n\n- plain item", false);
+        assert_eq!(progress.total, 0);
+        assert!(progress.sections.is_empty());
+    }
+}
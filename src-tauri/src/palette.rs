@@ -0,0 +1,129 @@
+use color_thief::ColorFormat;
+use image::imageops::FilterType;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::markdown::images::local_image_paths;
+
+/// Number of colors returned
+pub const PALETTE_SIZE: u8 = 5;
+
+/// Images are downscaled to fit this edge before sampling
+const SAMPLE_EDGE: u32 = 256;
+
+/// color_thief sampling step: 1 checks every pixel, 10 is the fastest
+const SAMPLE_QUALITY: u8 = 5;
+
+/// An image's palette and the modification time it was read at
+type CachedPalette = (SystemTime, Vec<String>);
+
+/// Palettes per image, keyed by path and invalidated by modification time
+static PALETTE_CACHE: Lazy<Mutex<HashMap<PathBuf, CachedPalette>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Extracts the dominant colors of a document's first local image as `#rrggbb`
+/// strings, most dominant first.
+///
+/// Images that are missing or can't be decoded are skipped; without any local
+/// image the palette is empty.
+pub fn document_palette(markdown: &str, base_path: &str) -> Vec<String> {
+    local_image_paths(markdown, base_path)
+        .iter()
+        .find_map(|path| cached_palette(path))
+        .unwrap_or_default()
+}
+
+fn cached_palette(path: &Path) -> Option<Vec<String>> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+
+    if let Ok(cache) = PALETTE_CACHE.lock() {
+        if let Some((cached_at, palette)) = cache.get(path) {
+            if *cached_at == modified {
+                return Some(palette.clone());
+            }
+        }
+    }
+
+    let palette = image_palette(path)?;
+
+    if let Ok(mut cache) = PALETTE_CACHE.lock() {
+        cache.insert(path.to_path_buf(), (modified, palette.clone()));
+    }
+
+    Some(palette)
+}
+
+fn image_palette(path: &Path) -> Option<Vec<String>> {
+    let image = image::open(path).ok()?;
+    let image = if image.width() > SAMPLE_EDGE || image.height() > SAMPLE_EDGE {
+        image.resize(SAMPLE_EDGE, SAMPLE_EDGE, FilterType::Triangle)
+    } else {
+        image
+    };
+    let pixels = image.to_rgba8();
+
+    let colors = color_thief::get_palette(
+        pixels.as_raw(),
+        ColorFormat::Rgba,
+        SAMPLE_QUALITY,
+        PALETTE_SIZE,
+    )
+    .unwrap_or_default();
+    let mut palette: Vec<String> = Vec::new();
+    for color in colors {
+        let hex = format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b);
+        if !palette.contains(&hex) {
+            palette.push(hex);
+        }
+    }
+    palette.truncate(PALETTE_SIZE as usize);
+
+    // A single-color image has nothing to quantize; report that color
+    if palette.is_empty() {
+        let [r, g, b, _] = pixels.pixels().next()?.0;
+        palette.push(format!("#{:02x}{:02x}{:02x}", r, g, b));
+    }
+    Some(palette)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn test_palette_from_first_local_image() {
+        let dir = std::env::temp_dir().join("markviewer-palette-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut hero = RgbImage::from_pixel(40, 40, Rgb([200, 20, 20]));
+        for x in 0..40 {
+            for y in 0..10 {
+                hero.put_pixel(x, y, Rgb([20, 20, 200]));
+            }
+        }
+        hero.save(dir.join("hero.png")).unwrap();
+        let doc = dir.join("doc.md").display().to_string();
+
+        let md = "![missing](gone.png)\n![hero](hero.png)";
+        let palette = document_palette(md, &doc);
+
+        assert!(!palette.is_empty() && palette.len() <= PALETTE_SIZE as usize);
+        assert!(palette.iter().all(|c| c.len() == 7 && c.starts_with('#')));
+        // Cached results are identical
+        assert_eq!(document_palette(md, &doc), palette);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_no_local_images() {
+        let md = "# Text\n\n![web](https://example.com/a.png)";
+        assert!(document_palette(md, "/x/doc.md").is_empty());
+    }
+}
@@ -0,0 +1,182 @@
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Environment variable pointing at a local plantuml.jar
+pub const PLANTUML_JAR_ENV: &str = "PLANTUML_JAR";
+
+/// Places a packaged plantuml.jar is commonly installed
+const JAR_LOCATIONS: &[&str] = &[
+    "/usr/share/plantuml/plantuml.jar",
+    "/usr/local/share/plantuml/plantuml.jar",
+    "/opt/homebrew/share/plantuml/plantuml.jar",
+];
+
+/// Renders a PlantUML diagram to SVG.
+///
+/// With a `server_url` the diagram is fetched from that PlantUML server.
+/// Otherwise a local renderer is used: the jar named by `PLANTUML_JAR` (or a
+/// packaged plantuml.jar) run with `java`, or a `plantuml` executable on PATH.
+pub fn render_plantuml_svg(content: &str, server_url: Option<&str>) -> Result<String, String> {
+    match server_url.map(str::trim).filter(|url| !url.is_empty()) {
+        Some(server) => render_with_server(content, server),
+        None => render_locally(content),
+    }
+}
+
+fn render_with_server(content: &str, server: &str) -> Result<String, String> {
+    let url = format!("{}/svg/{}", server.trim_end_matches('/'), encode_diagram(content));
+    let svg = ureq::get(&url)
+        .call()
+        .map_err(|e| format!("PlantUML server request failed: {}", e))?
+        .into_string()
+        .map_err(|e| format!("Failed to read PlantUML response: {}", e))?;
+    check_svg(svg)
+}
+
+fn render_locally(content: &str) -> Result<String, String> {
+    let jar = std::env::var_os(PLANTUML_JAR_ENV)
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(JAR_LOCATIONS.iter().map(PathBuf::from))
+        .find(|path| path.is_file());
+
+    let mut command = match jar {
+        Some(jar) => {
+            let mut command = Command::new("java");
+            command.arg("-jar").arg(jar);
+            command
+        }
+        None => Command::new("plantuml"),
+    };
+    command
+        .args(["-tsvg", "-pipe", "-charset", "UTF-8"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = command.spawn().map_err(|_| {
+        format!(
+            "No PlantUML renderer configured: set a server URL, point {} at plantuml.jar \
+             or install the plantuml command",
+            PLANTUML_JAR_ENV
+        )
+    })?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(content.as_bytes())
+            .map_err(|e| format!("Failed to send diagram to PlantUML: {}", e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("PlantUML failed: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "PlantUML failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    check_svg(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn check_svg(svg: String) -> Result<String, String> {
+    if svg.contains("<svg") {
+        Ok(svg)
+    } else {
+        Err("PlantUML did not return an SVG".to_string())
+    }
+}
+
+/// Encodes diagram source for PlantUML server URLs: raw deflate followed by
+/// PlantUML's base64 variant (`0-9A-Za-z-_`).
+pub fn encode_diagram(content: &str) -> String {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    // Writing to a Vec cannot fail
+    encoder.write_all(content.as_bytes()).unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let mut encoded = String::with_capacity(compressed.len() * 4 / 3 + 4);
+    for chunk in compressed.chunks(3) {
+        let b1 = chunk[0];
+        let b2 = chunk.get(1).copied().unwrap_or(0);
+        let b3 = chunk.get(2).copied().unwrap_or(0);
+        for value in [
+            b1 >> 2,
+            ((b1 & 0x3) << 4) | (b2 >> 4),
+            ((b2 & 0xF) << 2) | (b3 >> 6),
+            b3 & 0x3F,
+        ] {
+            encoded.push(encode_6bit(value));
+        }
+    }
+    encoded
+}
+
+fn encode_6bit(value: u8) -> char {
+    match value {
+        0..=9 => (b'0' + value) as char,
+        10..=35 => (b'A' + value - 10) as char,
+        36..=61 => (b'a' + value - 36) as char,
+        62 => '-',
+        _ => '_',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::DeflateDecoder;
+
+    fn decode_diagram(encoded: &str) -> String {
+        let values: Vec<u8> = encoded
+            .chars()
+            .map(|c| match c {
+                '0'..='9' => c as u8 - b'0',
+                'A'..='Z' => c as u8 - b'A' + 10,
+                'a'..='z' => c as u8 - b'a' + 36,
+                '-' => 62,
+                _ => 63,
+            })
+            .collect();
+        let mut bytes = Vec::new();
+        for group in values.chunks(4) {
+            bytes.push((group[0] << 2) | (group[1] >> 4));
+            bytes.push((group[1] << 4) | (group[2] >> 2));
+            bytes.push((group[2] << 6) | group[3]);
+        }
+
+        let mut decoder = DeflateDecoder::new(Vec::new());
+        // Trailing padding bytes after the deflate stream are ignored
+        let _ = decoder.write_all(&bytes);
+        String::from_utf8(decoder.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_encode_diagram_round_trip() {
+        let source = "@startuml\nBob -> Alice : hello\n@enduml";
+        let encoded = encode_diagram(source);
+
+        assert!(encoded
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(decode_diagram(&encoded), source);
+    }
+
+    #[test]
+    fn test_encode_6bit_alphabet() {
+        let alphabet: String = (0..64).map(encode_6bit).collect();
+        assert_eq!(
+            alphabet,
+            "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz-_"
+        );
+    }
+
+    #[test]
+    fn test_check_svg() {
+        assert!(check_svg("<?xml?><svg></svg>".to_string()).is_ok());
+        assert!(check_svg("Bad request".to_string()).is_err());
+    }
+}
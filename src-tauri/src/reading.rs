@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Store file holding where each document was last read
+pub const READING_STORE: &str = "markviewer-reading.json";
+
+/// Key holding the positions object inside the store
+pub const POSITIONS_KEY: &str = "positions";
+
+/// The topmost visible block of each document, by file path.
+///
+/// Blocks are identified by the `data-block-id` the renderer gives top-level
+/// blocks, which stays the same while the block's text doesn't change.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ReadingPositions(BTreeMap<String, String>);
+
+impl ReadingPositions {
+    /// Loads positions from the stored JSON value, starting empty if it is
+    /// missing or malformed
+    pub fn from_stored(value: Option<Value>) -> Self {
+        value
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_default()
+    }
+
+    /// The block a document was last read at
+    pub fn get(&self, path: &str) -> Option<&str> {
+        self.0.get(path).map(String::as_str)
+    }
+
+    /// Records a document's position; returns whether it changed
+    pub fn set(&mut self, path: &str, block_id: &str) -> bool {
+        if self.get(path) == Some(block_id) {
+            return false;
+        }
+        self.0.insert(path.to_string(), block_id.to_string());
+        true
+    }
+
+    /// Forgets documents that no longer exist; returns how many were removed
+    pub fn prune_missing(&mut self) -> usize {
+        let before = self.0.len();
+        self.0.retain(|path, _| Path::new(path).exists());
+        before - self.0.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_position_round_trips() {
+        let mut positions = ReadingPositions::default();
+        assert!(positions.set("/docs/guide.md", "block-3f2a"));
+        assert!(!positions.set("/docs/guide.md", "block-3f2a"));
+        assert!(positions.set("/docs/notes.md", "block-91c0"));
+
+        let stored = serde_json::to_value(&positions).unwrap();
+        assert_eq!(stored["/docs/guide.md"], "block-3f2a");
+
+        let loaded = ReadingPositions::from_stored(Some(stored));
+        assert_eq!(loaded.get("/docs/guide.md"), Some("block-3f2a"));
+        assert_eq!(loaded.get("/docs/notes.md"), Some("block-91c0"));
+        assert_eq!(loaded.get("/docs/other.md"), None);
+
+        let malformed = ReadingPositions::from_stored(Some(Value::from("nope")));
+        assert_eq!(malformed, ReadingPositions::default());
+    }
+
+    #[test]
+    fn test_prune_missing() {
+        let dir = std::env::temp_dir().join("markviewer-reading-positions");
+        fs::create_dir_all(&dir).unwrap();
+        let kept = dir.join("kept.md");
+        fs::write(&kept, "# Kept").unwrap();
+        let kept = kept.to_str().unwrap();
+
+        let mut positions = ReadingPositions::default();
+        positions.set(kept, "block-1");
+        positions.set(dir.join("deleted.md").to_str().unwrap(), "block-2");
+
+        assert_eq!(positions.prune_missing(), 1);
+        assert_eq!(positions.get(kept), Some("block-1"));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
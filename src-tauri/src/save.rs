@@ -0,0 +1,168 @@
+use serde::Deserialize;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use tempfile::Builder;
+
+/// Line-ending style used when a document is written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    /// Keep the dominant ending of the file being overwritten
+    #[default]
+    Preserve,
+}
+
+/// Rewrites every line ending (`\n`, `\r\n` or a lone `\r`) as LF or CRLF.
+/// `Preserve` returns the content unchanged.
+pub fn normalize_line_endings(content: &str, ending: LineEnding) -> String {
+    let newline = match ending {
+        LineEnding::Lf => "\n",
+        LineEnding::Crlf => "\r\n",
+        LineEnding::Preserve => return content.to_string(),
+    };
+
+    let mut result = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' => {
+                chars.next_if_eq(&'\n');
+                result.push_str(newline);
+            }
+            '\n' => result.push_str(newline),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// The dominant line ending of `content`: CRLF when most line breaks are
+/// `\r\n`, LF otherwise. None when there are no line breaks.
+pub fn detect_line_ending(content: &str) -> Option<LineEnding> {
+    let crlf = content.matches("\r\n").count();
+    let lf = content.matches('\n').count() - crlf;
+    match (crlf, lf) {
+        (0, 0) => None,
+        _ if crlf > lf => Some(LineEnding::Crlf),
+        _ => Some(LineEnding::Lf),
+    }
+}
+
+/// Writes a document with the requested line endings.
+///
+/// `Preserve` uses the dominant ending of the existing file; new files and
+/// files without line breaks are written as given. The content goes to a
+/// uniquely named temporary file next to the document that is then renamed
+/// over it, so a failed save never leaves a truncated document behind.
+/// Symlinks are followed (the file they point at is replaced, not the link)
+/// and the file's permissions are kept.
+pub fn save_document(path: &Path, content: &str, ending: LineEnding) -> Result<(), String> {
+    let path = &fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let ending = match ending {
+        LineEnding::Preserve => fs::read_to_string(path)
+            .ok()
+            .and_then(|existing| detect_line_ending(&existing))
+            .unwrap_or(LineEnding::Preserve),
+        ending => ending,
+    };
+    let content = normalize_line_endings(content, ending);
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("Invalid file path: {}", path.display()))?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let mut temp = Builder::new()
+        .prefix(&format!(".{}.", file_name.to_string_lossy()))
+        .suffix(".tmp")
+        .tempfile_in(dir)
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+    temp.write_all(content.as_bytes())
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+    if let Ok(metadata) = fs::metadata(path) {
+        temp.as_file()
+            .set_permissions(metadata.permissions())
+            .map_err(|e| format!("Failed to keep file permissions: {}", e))?;
+    }
+    // The temporary file is removed if this fails
+    temp.persist(path)
+        .map_err(|e| format!("Failed to save file: {}", e.error))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crlf_mode_uses_crlf_throughout() {
+        let content = "# Title\nmixed\r\nold mac\rend\n";
+        let result = normalize_line_endings(content, LineEnding::Crlf);
+        assert_eq!(result, "# Title\r\nmixed\r\nold mac\r\nend\r\n");
+        assert_eq!(result.matches('\n').count(), result.matches("\r\n").count());
+    }
+
+    #[test]
+    fn test_lf_mode_strips_carriage_returns() {
+        let result = normalize_line_endings("a\r\nb\rc\n", LineEnding::Lf);
+        assert_eq!(result, "a\nb\nc\n");
+        assert!(!result.contains('\r'));
+    }
+
+    #[test]
+    fn test_detect_dominant_ending() {
+        assert_eq!(detect_line_ending("a\r\nb\r\nc\n"), Some(LineEnding::Crlf));
+        assert_eq!(detect_line_ending("a\r\nb\nc\n"), Some(LineEnding::Lf));
+        assert_eq!(detect_line_ending("single line"), None);
+    }
+
+    #[test]
+    fn test_save_preserves_existing_ending() {
+        let dir = std::env::temp_dir().join("markviewer-save-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("note.md");
+
+        save_document(&path, "a\nb\n", LineEnding::Preserve).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a\nb\n");
+
+        save_document(&path, "a\nb\n", LineEnding::Crlf).unwrap();
+        save_document(&path, "a\nb\nc\n", LineEnding::Preserve).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a\r\nb\r\nc\r\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_keeps_symlinks_and_permissions() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let dir = std::env::temp_dir().join("markviewer-save-link-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("real.md");
+        let link = dir.join("link.md");
+        fs::write(&target, "old\n").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o640)).unwrap();
+        symlink(&target, &link).unwrap();
+
+        save_document(&link, "new\n", LineEnding::Preserve).unwrap();
+
+        let link_type = fs::symlink_metadata(&link).unwrap().file_type();
+        assert!(link_type.is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new\n");
+        let mode = fs::metadata(&target).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,248 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::daily_notes::is_vault_relative;
+
+/// Store file shared with the frontend's store-service
+pub const SETTINGS_STORE: &str = "markviewer-settings.json";
+
+/// Key holding the settings object inside the store
+pub const SETTINGS_KEY: &str = "settings";
+
+const MIN_FONT_SIZE: u32 = 10;
+const MAX_FONT_SIZE: u32 = 24;
+const EXTERNAL_EDITORS: &[&str] = &["vscode", "cursor", "neovide", "terminal", "custom"];
+
+/// A named external editor command
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditorProfile {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// Typed view of the persisted app settings.
+///
+/// Field names match the frontend's `AppSettings` (camelCase). Keys this struct
+/// doesn't know about are kept in `other` so saving never drops them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    /// "light" or "dark"
+    pub theme: String,
+    /// Reading font size in px (zoom level)
+    pub font_size: u32,
+    /// Font family for the preview
+    pub reading_font: Option<String>,
+    /// Custom CSS applied to the preview
+    pub user_css: Option<String>,
+    /// Preferred external editor
+    pub external_editor: String,
+    /// Custom command when `external_editor` is "custom"
+    pub custom_editor_command: Option<String>,
+    /// Additional named editor commands
+    pub editor_profiles: Vec<EditorProfile>,
+    /// Maximum number of recent files to remember
+    pub recent_files_limit: u32,
+    /// Folders searched for images linked by bare file name when they aren't
+    /// next to the document; relative folders are relative to the document
+    pub attachment_folders: Vec<String>,
+    /// Folder for daily notes, relative to the vault root
+    pub daily_notes_folder: String,
+    /// Daily note name format, e.g. `YYYY/MM/YYYY-MM-DD`
+    pub daily_note_format: String,
+    /// Template for new daily notes, relative to the vault root
+    pub daily_note_template: Option<String>,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: "dark".to_string(),
+            font_size: 15,
+            reading_font: None,
+            user_css: None,
+            external_editor: "vscode".to_string(),
+            custom_editor_command: None,
+            editor_profiles: Vec::new(),
+            recent_files_limit: 10,
+            attachment_folders: Vec::new(),
+            daily_notes_folder: String::new(),
+            daily_note_format: "YYYY-MM-DD".to_string(),
+            daily_note_template: None,
+            other: Map::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// Loads settings from the stored JSON value, falling back to defaults.
+    ///
+    /// A stored field of the wrong type falls back on its own, so one bad
+    /// value doesn't reset (and on the next save, erase) the others.
+    pub fn from_stored(value: Option<Value>) -> Self {
+        let stored = match value {
+            Some(Value::Object(map)) => map,
+            _ => return Self::default(),
+        };
+        if let Ok(settings) = serde_json::from_value(Value::Object(stored.clone())) {
+            return settings;
+        }
+
+        let mut merged = match serde_json::to_value(Self::default()) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        };
+        for (key, value) in stored {
+            let mut candidate = merged.clone();
+            candidate.insert(key, value);
+            if serde_json::from_value::<Settings>(Value::Object(candidate.clone())).is_ok() {
+                merged = candidate;
+            }
+        }
+        serde_json::from_value(Value::Object(merged)).unwrap_or_default()
+    }
+
+    /// Applies a partial update (only the provided keys) and validates the result
+    pub fn merged(&self, partial: &Value) -> Result<Settings, String> {
+        let updates = partial
+            .as_object()
+            .ok_or_else(|| "Settings update must be an object".to_string())?;
+
+        let mut current = match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        };
+        for (key, value) in updates {
+            current.insert(key.clone(), value.clone());
+        }
+
+        let settings: Settings = serde_json::from_value(Value::Object(current))
+            .map_err(|e| format!("Invalid settings: {}", e))?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Checks each field, returning the first problem found
+    pub fn validate(&self) -> Result<(), String> {
+        if self.theme != "light" && self.theme != "dark" {
+            return Err(format!("Invalid theme '{}': expected 'light' or 'dark'", self.theme));
+        }
+        if !(MIN_FONT_SIZE..=MAX_FONT_SIZE).contains(&self.font_size) {
+            return Err(format!(
+                "Invalid fontSize {}: must be between {} and {}",
+                self.font_size, MIN_FONT_SIZE, MAX_FONT_SIZE
+            ));
+        }
+        if let Some(font) = &self.reading_font {
+            if font.trim().is_empty() || font.contains([';', '{', '}']) {
+                return Err(format!("Invalid readingFont '{}'", font));
+            }
+        }
+        if let Some(css) = &self.user_css {
+            if css.to_lowercase().contains("</style") {
+                return Err("Invalid userCss: must not contain </style>".to_string());
+            }
+        }
+        if !EXTERNAL_EDITORS.contains(&self.external_editor.as_str()) {
+            return Err(format!("Invalid externalEditor '{}'", self.external_editor));
+        }
+        if self.external_editor == "custom"
+            && self.custom_editor_command.as_deref().unwrap_or("").trim().is_empty()
+        {
+            return Err("customEditorCommand is required when externalEditor is 'custom'".to_string());
+        }
+        for profile in &self.editor_profiles {
+            if profile.name.trim().is_empty() || profile.command.trim().is_empty() {
+                return Err("Editor profiles need a name and a command".to_string());
+            }
+        }
+        if !(1..=100).contains(&self.recent_files_limit) {
+            return Err(format!(
+                "Invalid recentFilesLimit {}: must be between 1 and 100",
+                self.recent_files_limit
+            ));
+        }
+        if self.attachment_folders.iter().any(|folder| folder.trim().is_empty()) {
+            return Err("Invalid attachmentFolders: folders must not be empty".to_string());
+        }
+        let format = &self.daily_note_format;
+        if format.trim().is_empty() || !is_vault_relative(format) {
+            return Err(format!("Invalid dailyNoteFormat '{}'", format));
+        }
+        let folder = &self.daily_notes_folder;
+        if !is_vault_relative(folder) {
+            return Err(format!("Invalid dailyNotesFolder '{}'", folder));
+        }
+        if let Some(template) = self.daily_note_template.as_deref() {
+            if !is_vault_relative(template) {
+                return Err(format!("Invalid dailyNoteTemplate '{}'", template));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_stored_keeps_unknown_keys() {
+        let stored = json!({"theme": "light", "fontSize": 18, "sidebarWidth": 300});
+        let settings = Settings::from_stored(Some(stored));
+
+        assert_eq!(settings.theme, "light");
+        assert_eq!(settings.font_size, 18);
+        assert_eq!(settings.recent_files_limit, 10);
+        assert_eq!(settings.other["sidebarWidth"], 300);
+
+        let value = serde_json::to_value(&settings).unwrap();
+        assert_eq!(value["sidebarWidth"], 300);
+    }
+
+    #[test]
+    fn test_from_stored_falls_back_per_field() {
+        let stored = json!({"theme": "light", "fontSize": "large", "recentFilesLimit": 25});
+        let settings = Settings::from_stored(Some(stored));
+
+        assert_eq!(settings.theme, "light");
+        assert_eq!(settings.font_size, Settings::default().font_size);
+        assert_eq!(settings.recent_files_limit, 25);
+        assert_eq!(Settings::from_stored(Some(json!([1, 2]))), Settings::default());
+    }
+
+    #[test]
+    fn test_merged_updates_only_given_fields() {
+        let settings = Settings::default();
+        let updated = settings
+            .merged(&json!({"fontSize": 20, "readingFont": "Georgia"}))
+            .unwrap();
+
+        assert_eq!(updated.font_size, 20);
+        assert_eq!(updated.reading_font.as_deref(), Some("Georgia"));
+        assert_eq!(updated.theme, settings.theme);
+    }
+
+    #[test]
+    fn test_merged_validates() {
+        let settings = Settings::default();
+        assert!(settings.merged(&json!({"theme": "blue"})).is_err());
+        assert!(settings.merged(&json!({"fontSize": 99})).is_err());
+        assert!(settings.merged(&json!({"externalEditor": "custom"})).is_err());
+        assert!(settings.merged(&json!({"userCss": "</style><script>"})).is_err());
+        assert!(settings.merged(&json!({"recentFilesLimit": 0})).is_err());
+        assert!(settings.merged(&json!({"attachmentFolders": [" "]})).is_err());
+        assert!(settings.merged(&json!({"dailyNoteFormat": ""})).is_err());
+        assert!(settings.merged(&json!({"dailyNotesFolder": "../out"})).is_err());
+        assert!(settings.merged(&json!({"dailyNotesFolder": "/tmp/out"})).is_err());
+        assert!(settings.merged(&json!({"dailyNoteFormat": "/YYYY-MM-DD"})).is_err());
+        assert!(settings.merged(&json!({"dailyNoteTemplate": "../t.md"})).is_err());
+        assert!(settings.merged(&json!({"dailyNoteTemplate": "templates/t.md"})).is_ok());
+        assert!(settings.merged(&json!("not an object")).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::imageops::FilterType;
+use image::ImageFormat;
+use std::io::Cursor;
+
+use crate::file_index::document_title;
+use crate::markdown::escape_html;
+use crate::markdown::images::local_image_paths;
+
+/// Largest thumbnail edge accepted, in pixels
+pub const MAX_THUMBNAIL_SIZE: u32 = 1024;
+
+/// Builds a square thumbnail for a document as a data URI.
+///
+/// The first local image that can be decoded is center-cropped and resized to
+/// `size` x `size` (PNG). Without one, an SVG placeholder shows the initials
+/// of the document title on a color derived from it.
+pub fn document_thumbnail_uri(
+    markdown: &str,
+    base_path: &str,
+    size: u32,
+) -> Result<String, String> {
+    if size == 0 || size > MAX_THUMBNAIL_SIZE {
+        return Err(format!("Thumbnail size must be between 1 and {}", MAX_THUMBNAIL_SIZE));
+    }
+
+    for path in local_image_paths(markdown, base_path) {
+        let Ok(image) = image::open(&path) else {
+            continue;
+        };
+        let thumbnail = image.resize_to_fill(size, size, FilterType::Lanczos3);
+        let mut png = Vec::new();
+        thumbnail
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .map_err(|e| format!("Failed to encode thumbnail: {}", e))?;
+        return Ok(format!("data:image/png;base64,{}", STANDARD.encode(png)));
+    }
+
+    let title = document_title(markdown).unwrap_or_default();
+    let svg = placeholder_svg(&title, size);
+    Ok(format!("data:image/svg+xml;base64,{}", STANDARD.encode(svg)))
+}
+
+/// A colored square with up to two initials from the title
+fn placeholder_svg(title: &str, size: u32) -> String {
+    let initials: String = title
+        .split_whitespace()
+        .filter_map(|word| word.chars().find(|c| c.is_alphanumeric()))
+        .take(2)
+        .flat_map(char::to_uppercase)
+        .collect();
+    let initials = if initials.is_empty() { "?".to_string() } else { initials };
+    let hue = title
+        .bytes()
+        .fold(0u32, |hash, b| hash.wrapping_mul(31).wrapping_add(b as u32))
+        % 360;
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" \
+         viewBox=\"0 0 100 100\">\
+         <rect width=\"100\" height=\"100\" fill=\"hsl({hue}, 55%, 45%)\"/>\
+         <text x=\"50\" y=\"50\" dy=\"0.35em\" text-anchor=\"middle\" font-family=\"sans-serif\" \
+         font-size=\"40\" fill=\"#fff\">{}</text></svg>",
+        escape_html(&initials)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, Rgb, RgbImage};
+    use std::fs;
+
+    fn decode(uri: &str, prefix: &str) -> Vec<u8> {
+        STANDARD.decode(uri.strip_prefix(prefix).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_thumbnail_from_first_local_image() {
+        let dir = std::env::temp_dir().join("markviewer-thumbnail-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        RgbImage::from_pixel(40, 20, Rgb([200, 10, 10])).save(dir.join("wide.png")).unwrap();
+        let doc = dir.join("doc.md");
+
+        let md = "# Trip\n\n![gone](nope.png)\n![web](https://x.com/a.png)\n![wide](wide.png)";
+        let uri = document_thumbnail_uri(md, &doc.display().to_string(), 16).unwrap();
+
+        let png = decode(&uri, "data:image/png;base64,");
+        let thumbnail = image::load_from_memory(&png).unwrap();
+        assert_eq!(thumbnail.dimensions(), (16, 16));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_placeholder_from_title_initials() {
+        let uri = document_thumbnail_uri("# release notes 2024\n\nNo images.", "/x/doc.md", 64)
+            .unwrap();
+
+        let svg = String::from_utf8(decode(&uri, "data:image/svg+xml;base64,")).unwrap();
+        assert!(svg.contains("width=\"64\""));
+        assert!(svg.contains(">RN</text>"));
+
+        let untitled = document_thumbnail_uri("plain", "/x/doc.md", 64).unwrap();
+        let svg = String::from_utf8(decode(&untitled, "data:image/svg+xml;base64,")).unwrap();
+        assert!(svg.contains(">?</text>"));
+    }
+
+    #[test]
+    fn test_invalid_size() {
+        assert!(document_thumbnail_uri("", "/x/doc.md", 0).is_err());
+        assert!(document_thumbnail_uri("", "/x/doc.md", 4096).is_err());
+    }
+}
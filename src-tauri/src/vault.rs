@@ -0,0 +1,166 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File extensions treated as markdown (matches the frontend's file-service)
+pub const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown"];
+
+/// File extensions treated as images
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "svg", "bmp", "avif"];
+
+/// True if the path has a markdown extension
+pub fn is_markdown_file(path: &Path) -> bool {
+    has_extension(path, MARKDOWN_EXTENSIONS)
+}
+
+/// True if the path has an image extension
+pub fn is_image_file(path: &Path) -> bool {
+    has_extension(path, IMAGE_EXTENSIONS)
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| extensions.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Recursively collects markdown files under `root`, sorted by path.
+///
+/// Hidden files and directories (starting with '.') are skipped.
+pub fn markdown_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_files(root, is_markdown_file, &mut files);
+    files.sort();
+    files
+}
+
+/// Recursively collects image files under `root`, sorted by path, skipping
+/// hidden files and directories like [`markdown_files`].
+pub fn image_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_files(root, is_image_file, &mut files);
+    files.sort();
+    files
+}
+
+fn collect_files(dir: &Path, wanted: fn(&Path) -> bool, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if hidden {
+            continue;
+        }
+
+        if path.is_dir() {
+            collect_files(&path, wanted, files);
+        } else if wanted(&path) {
+            files.push(path);
+        }
+    }
+}
+
+/// One clickable step of a breadcrumb from the vault root to a file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BreadcrumbSegment {
+    /// Folder or file name
+    pub name: String,
+    /// Absolute path of the folder or file
+    pub path: String,
+}
+
+/// Splits `file` into breadcrumb segments starting at `root`, e.g.
+/// `Vault / Projects / today.md`.
+///
+/// Paths are compared as written, without touching the disk. A file outside
+/// `root` gets a single segment with its file name.
+pub fn breadcrumb_segments(root: &Path, file: &Path) -> Vec<BreadcrumbSegment> {
+    let segment = |path: &Path| BreadcrumbSegment {
+        name: path.file_name().map_or_else(
+            || path.to_string_lossy().into_owned(),
+            |name| name.to_string_lossy().into_owned(),
+        ),
+        path: path.to_string_lossy().into_owned(),
+    };
+
+    let Ok(relative) = file.strip_prefix(root) else {
+        return vec![segment(file)];
+    };
+
+    let mut segments = vec![segment(root)];
+    let mut current = root.to_path_buf();
+    for component in relative.components() {
+        current.push(component);
+        segments.push(segment(&current));
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_markdown_file() {
+        assert!(is_markdown_file(Path::new("/a/notes.md")));
+        assert!(is_markdown_file(Path::new("README.MARKDOWN")));
+        assert!(!is_markdown_file(Path::new("/a/image.png")));
+        assert!(!is_markdown_file(Path::new("/a/md")));
+    }
+
+    #[test]
+    fn test_is_image_file() {
+        assert!(is_image_file(Path::new("/a/Photo.JPG")));
+        assert!(is_image_file(Path::new("diagram.svg")));
+        assert!(!is_image_file(Path::new("/a/notes.md")));
+    }
+
+    #[test]
+    fn test_breadcrumb_segments() {
+        let names = |root: &str, file: &str| -> Vec<(String, String)> {
+            breadcrumb_segments(Path::new(root), Path::new(file))
+                .into_iter()
+                .map(|s| (s.name, s.path))
+                .collect()
+        };
+        let pair = |name: &str, path: &str| (name.to_string(), path.to_string());
+
+        assert_eq!(
+            names("/home/me/Vault", "/home/me/Vault/Projects/today.md"),
+            vec![
+                pair("Vault", "/home/me/Vault"),
+                pair("Projects", "/home/me/Vault/Projects"),
+                pair("today.md", "/home/me/Vault/Projects/today.md"),
+            ]
+        );
+        assert_eq!(
+            names("/home/me/Vault", "/tmp/other.md"),
+            vec![pair("other.md", "/tmp/other.md")]
+        );
+        assert_eq!(
+            names("/home/me/Vault", "/home/me/VaultOld/a.md"),
+            vec![pair("a.md", "/home/me/VaultOld/a.md")]
+        );
+    }
+
+    #[test]
+    fn test_markdown_files() {
+        let root = std::env::temp_dir().join("markviewer-vault-test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::create_dir_all(root.join(".hidden")).unwrap();
+        fs::write(root.join("a.md"), "# A").unwrap();
+        fs::write(root.join("sub/b.markdown"), "# B").unwrap();
+        fs::write(root.join("sub/c.txt"), "C").unwrap();
+        fs::write(root.join(".hidden/d.md"), "# D").unwrap();
+
+        let files = markdown_files(&root);
+        assert_eq!(files, vec![root.join("a.md"), root.join("sub/b.markdown")]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
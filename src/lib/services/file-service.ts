@@ -1,5 +1,6 @@
+import { invoke } from '@tauri-apps/api/core';
 import { open, save } from '@tauri-apps/plugin-dialog';
-import { readTextFile, writeTextFile, readDir, watch, exists, type WatchEvent } from '@tauri-apps/plugin-fs';
+import { readTextFile, readDir, watch, exists, type WatchEvent } from '@tauri-apps/plugin-fs';
 import type { FileTreeNode, CodeLanguage } from '$lib/types';
 
 type WatchCallback = (event: WatchEvent) => void;
@@ -95,8 +96,12 @@ export const fileService = {
 		return await readTextFile(path);
 	},
 
-	async saveFile(path: string, content: string): Promise<void> {
-		await writeTextFile(path, content);
+	async saveFile(
+		path: string,
+		content: string,
+		lineEnding: 'lf' | 'crlf' | 'preserve' = 'preserve'
+	): Promise<void> {
+		await invoke('save_file', { path, content, lineEnding });
 	},
 
 	async saveFileDialog(defaultPath?: string): Promise<string | null> {
@@ -193,6 +198,10 @@ export const fileService = {
 		return await watch(path, callback, { recursive: false });
 	},
 
+	async fileChanged(path: string): Promise<void> {
+		await invoke('file_changed', { path });
+	},
+
 	async fileExists(path: string): Promise<boolean> {
 		try {
 			return await exists(path);
@@ -81,6 +81,7 @@ class FilesStore {
 		// 			'modify' in event.type;
 		//
 		// 		if (isModify) {
+		// 			fileService.fileChanged(path);
 		// 			const buffer = this.buffers.find((b) => b.id === bufferId);
 		// 			if (buffer && !buffer.isDirty) {
 		// 				this.reloadBuffer(bufferId);
@@ -108,6 +109,7 @@ class FilesStore {
 		if (!buffer?.path) return;
 
 		try {
+			await fileService.fileChanged(buffer.path);
 			const content = await fileService.readFile(buffer.path);
 			buffer.content = content;
 			buffer.originalContent = content;
@@ -130,7 +130,10 @@ class SettingsStore {
 	}
 
 	private async persist() {
+		// Keep settings only the backend uses (attachment and daily note folders)
+		const stored = await storeService.get<Record<string, unknown>>('settings');
 		await storeService.set<AppSettings>('settings', {
+			...stored,
 			theme: this.theme,
 			sidebarVisible: this.sidebarVisible,
 			sidebarWidth: this.sidebarWidth,